@@ -1276,10 +1276,28 @@ pub struct LLMContext {
 	#[dynamic(rename = "totalTokens")]
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub total_tokens: Option<u64>,
+	/// The number of images generated, for image generation requests.
+	#[dynamic(rename = "outputImages")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub output_images: Option<u64>,
+	/// The length of the input audio in seconds, rounded up, for transcription requests.
+	#[dynamic(rename = "inputAudioSeconds")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub input_audio_seconds: Option<u64>,
 	/// The service tier the provider served the request under.
 	#[dynamic(rename = "serviceTier")]
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub service_tier: Option<Strng>,
+	/// Whether the response was served from the gateway response cache, either `hit` or `miss`.
+	/// Unset when response caching is not enabled or was bypassed for the request.
+	#[dynamic(rename = "responseCache")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub response_cache: Option<Strng>,
+	/// Cosine similarity of the closest request in the semantic response cache, between 0 and 1.
+	/// Unset when semantic caching is not enabled, or no similar request was cached.
+	#[dynamic(rename = "responseCacheSimilarity")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub response_cache_similarity: Option<f64>,
 	// For now, not exposed to CEL; only used to piggy-back this field for metrics.
 	#[serde(skip)]
 	#[dynamic(skip)]
@@ -1311,8 +1329,8 @@ pub struct LLMContext {
 	/// Unset when the model could not be priced.
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub cost: Option<llm::cost::Breakdown>,
-	/// Effective model catalog rates in USD per 1M tokens after tier selection.
-	/// Unset when the model could not be priced.
+	/// Effective model catalog rates in USD per 1M tokens after tier selection; `perImage` and
+	/// `perAudioSecond` are per unit. Unset when the model could not be priced.
 	#[dynamic(rename = "costRates")]
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub cost_rates: Option<llm::cost::CostRates>,
@@ -1333,6 +1351,8 @@ impl LLMContext {
 			output_audio_tokens: resp.output_audio_tokens,
 			count_tokens: resp.count_tokens,
 			total_tokens: resp.total_tokens,
+			output_images: resp.output_images,
+			input_audio_seconds: resp.input_audio_seconds,
 			first_token: resp.first_token,
 			time_to_first_token: None,
 			time_per_output_token: None,
@@ -1343,6 +1363,8 @@ impl LLMContext {
 			cached_input_tokens: resp.cached_input_tokens,
 			cache_creation_input_tokens: resp.cache_creation_input_tokens,
 			service_tier: resp.service_tier.clone(),
+			response_cache: resp.response_cache.map(|s| strng::new(s.as_str())),
+			response_cache_similarity: resp.response_cache_similarity.map(f64::from),
 			response_model: resp.provider_model.clone(),
 			// Not always set
 			completion: resp.completion.clone(),
@@ -1416,6 +1438,8 @@ impl From<llm::LLMRequest> for LLMContext {
 			output_text_tokens: None,
 			output_audio_tokens: None,
 			total_tokens: None,
+			output_images: None,
+			input_audio_seconds: None,
 			completion: None,
 			reasoning_tokens: None,
 			input_image_tokens: None,
@@ -1424,6 +1448,8 @@ impl From<llm::LLMRequest> for LLMContext {
 			cached_input_tokens: None,
 			cache_creation_input_tokens: None,
 			service_tier: None,
+			response_cache: None,
+			response_cache_similarity: None,
 			cost: None,
 			cost_rates: None,
 			cost_status: None,
@@ -2092,7 +2118,11 @@ pub fn full_example_executor() -> ExecutorSerde {
 			output_audio_tokens: Some(3),
 			reasoning_tokens: Some(30),
 			total_tokens: Some(150),
+			output_images: None,
+			input_audio_seconds: None,
 			service_tier: Some("default".into()),
+			response_cache: None,
+			response_cache_similarity: None,
 			first_token: None,
 			time_to_first_token: Some(chrono::Duration::milliseconds(123).into()),
 			time_per_output_token: Some(chrono::Duration::milliseconds(7).into()),
@@ -2109,6 +2139,8 @@ pub fn full_example_executor() -> ExecutorSerde {
 				max_tokens: Some(1024),
 				encoding_format: None,
 				dimensions: None,
+				batch_requests: None,
+				batch_output_file: None,
 			},
 			cost: None,
 			cost_rates: None,
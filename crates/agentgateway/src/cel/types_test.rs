@@ -83,7 +83,11 @@ fn build_test_request() -> crate::http::Request {
 		output_text_tokens: None,
 		output_audio_tokens: None,
 		total_tokens: Some(150),
+		output_images: None,
+		input_audio_seconds: None,
 		service_tier: None,
+		response_cache: None,
+		response_cache_similarity: None,
 		first_token: None,
 		time_to_first_token: Some(chrono::Duration::milliseconds(123).into()),
 		time_per_output_token: Some(chrono::Duration::milliseconds(7).into()),
@@ -145,6 +149,8 @@ fn llm_cost_is_exposed_to_cel_as_floats() {
 		reasoning: dec("0"),
 		input_audio: dec("0"),
 		output_audio: dec("0"),
+		images: dec("0"),
+		audio_seconds: dec("0"),
 	});
 	let executor = Executor::new_request(&req);
 
@@ -1,7 +1,15 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use prometheus_client::metrics::gauge::Gauge;
+use quick_cache::sync::Cache;
 use serde::de::Error;
 
+use crate::cel::{Executor, Expression};
 use crate::llm::LLMRequest;
 use crate::proxy::ProxyError;
+use crate::telemetry::metrics::Metrics;
 use crate::*;
 
 #[derive(Debug, Clone)]
@@ -10,7 +18,7 @@ use crate::*;
 #[derive(serde::Serialize)]
 pub struct RateLimit {
 	#[serde(skip_serializing)]
-	ratelimit: Arc<ratelimit::Ratelimiter>,
+	buckets: Arc<Buckets>,
 	#[serde(flatten)]
 	pub spec: RateLimitSpec,
 }
@@ -41,6 +49,27 @@ pub struct RateLimitSpec {
 	#[serde(default)]
 	#[serde(rename = "type")]
 	pub limit_type: RateLimitType,
+	/// CEL expression used to split the limit into independent buckets, one per distinct value.
+	/// For example, `jwt.sub` limits each user separately.
+	/// If unset, all requests share a single bucket.
+	/// If the expression fails to evaluate, the rate limit is skipped for the request.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub key: Option<Arc<cel::Expression>>,
+	/// Maximum number of keyed buckets to keep. When exceeded, rarely used buckets are evicted
+	/// first; an evicted key starts again with a full bucket. Buckets that are idle and have
+	/// refilled completely are dropped regardless, as they are no different from a new bucket.
+	/// Only used when `key` is set.
+	#[serde(default = "default_max_keys")]
+	pub max_keys: usize,
+}
+
+pub const DEFAULT_MAX_KEYS: usize = 10_000;
+
+// How often keyed buckets are scanned for idle buckets to drop.
+const IDLE_SWEEP_INTERVAL: Duration = Duration::from_secs(10);
+
+fn default_max_keys() -> usize {
+	DEFAULT_MAX_KEYS
 }
 
 #[apply(schema!)]
@@ -55,28 +84,193 @@ pub enum RateLimitType {
 	Tokens,
 }
 
+#[derive(Debug)]
+enum Buckets {
+	Shared(Arc<ratelimit::Ratelimiter>),
+	Keyed(KeyedBuckets),
+}
+
+#[derive(Debug)]
+struct KeyedBuckets {
+	buckets: Cache<String, Arc<ratelimit::Ratelimiter>>,
+	// The number of live keys last added to the `local_rate_limit_keys` gauge, so the gauge can be
+	// kept as a sum across all keyed rate limits.
+	reported: AtomicI64,
+	gauge: OnceLock<Gauge>,
+	next_sweep: Mutex<Instant>,
+}
+
+impl KeyedBuckets {
+	fn new(max_keys: usize) -> Self {
+		KeyedBuckets {
+			buckets: Cache::new(max_keys.max(1)),
+			reported: AtomicI64::new(0),
+			gauge: OnceLock::new(),
+			next_sweep: Mutex::new(Instant::now() + IDLE_SWEEP_INTERVAL),
+		}
+	}
+
+	/// Drops idle buckets, at most once per sweep interval.
+	fn maybe_sweep(&self) {
+		let now = Instant::now();
+		let Ok(mut next_sweep) = self.next_sweep.try_lock() else {
+			// Another request is already sweeping.
+			return;
+		};
+		if now < *next_sweep {
+			return;
+		}
+		*next_sweep = now + IDLE_SWEEP_INTERVAL;
+		self.evict_idle();
+	}
+
+	/// Drops the buckets that are full and not held by an in-flight request. Such a bucket is
+	/// no different from the one a new request for its key would get, so dropping it is lossless.
+	fn evict_idle(&self) {
+		self
+			.buckets
+			.retain(|_, rl| Arc::strong_count(rl) > 1 || rl.available_refill() < rl.max_tokens());
+	}
+
+	fn report(&self, metrics: &Metrics) {
+		let gauge = self
+			.gauge
+			.get_or_init(|| metrics.local_rate_limit_keys.clone());
+		let live = self.buckets.len() as i64;
+		let prev = self.reported.swap(live, Ordering::Relaxed);
+		if live != prev {
+			gauge.inc_by(live - prev);
+		}
+	}
+}
+
+impl Drop for KeyedBuckets {
+	fn drop(&mut self) {
+		if let Some(gauge) = self.gauge.get() {
+			gauge.dec_by(self.reported.load(Ordering::Relaxed));
+		}
+	}
+}
+
+fn build_ratelimiter(spec: &RateLimitSpec) -> Result<ratelimit::Ratelimiter, ratelimit::Error> {
+	ratelimit::Ratelimiter::builder(spec.tokens_per_fill, spec.fill_interval)
+		.initial_available(spec.max_tokens)
+		.max_tokens(spec.max_tokens)
+		.build()
+}
+
 impl TryFrom<RateLimitSpec> for RateLimit {
 	type Error = ratelimit::Error;
 	fn try_from(value: RateLimitSpec) -> Result<Self, Self::Error> {
-		let rl = ratelimit::Ratelimiter::builder(value.tokens_per_fill, value.fill_interval)
-			.initial_available(value.max_tokens)
-			.max_tokens(value.max_tokens)
-			.build()?;
-		Ok(RateLimit {
-			ratelimit: Arc::new(rl),
-			spec: value,
-		})
+		let keyed = value.key.is_some();
+		RateLimit::build(value, keyed)
 	}
 }
 
 impl RateLimit {
-	pub fn check_request(&self) -> Result<(), ProxyError> {
+	/// Build a rate limit whose buckets are selected by a key computed by the caller, with
+	/// [`RateLimit::bucket_for_key`], rather than by the `key` expression of the spec.
+	pub fn new_keyed(spec: RateLimitSpec) -> Result<Self, ratelimit::Error> {
+		RateLimit::build(spec, true)
+	}
+
+	fn build(spec: RateLimitSpec, keyed: bool) -> Result<Self, ratelimit::Error> {
+		// Always build one limiter up front so invalid parameters are rejected at config time,
+		// even for keyed limits that only create buckets on demand.
+		let rl = build_ratelimiter(&spec)?;
+		let buckets = if keyed {
+			Buckets::Keyed(KeyedBuckets::new(spec.max_keys))
+		} else {
+			Buckets::Shared(Arc::new(rl))
+		};
+		Ok(RateLimit {
+			buckets: Arc::new(buckets),
+			spec,
+		})
+	}
+
+	/// Resolve the bucket that applies to this request.
+	/// Returns `None` if the rate limit is keyed and the key could not be evaluated.
+	pub fn bucket(&self, req: &http::Request, metrics: &Metrics) -> Option<Bucket> {
+		if let Buckets::Shared(rl) = self.buckets.as_ref() {
+			return Some(Bucket(rl.clone()));
+		}
+		let expr = self.spec.key.as_deref()?;
+		let exec = Executor::new_request(req);
+		let key = match exec
+			.eval(expr)
+			.and_then(|v| v.as_string().map_err(Into::into))
+		{
+			Ok(key) => key,
+			Err(e) => {
+				trace!(
+					"local rate limit key evaluation failed, skipping: expr={:?}, error={}",
+					expr, e
+				);
+				return None;
+			},
+		};
+		self.bucket_for_key(key.as_str(), metrics)
+	}
+
+	/// Resolve the bucket for a key. Limits that are not keyed always use their shared bucket.
+	pub fn bucket_for_key(&self, key: &str, metrics: &Metrics) -> Option<Bucket> {
+		let keyed = match self.buckets.as_ref() {
+			Buckets::Shared(rl) => return Some(Bucket(rl.clone())),
+			Buckets::Keyed(keyed) => keyed,
+		};
+		let rl = keyed
+			.buckets
+			.get_or_insert_with(key, || build_ratelimiter(&self.spec).map(Arc::new))
+			.inspect_err(|e| warn!("failed to build local rate limit bucket: {e}"))
+			.ok()?;
+		keyed.maybe_sweep();
+		keyed.report(metrics);
+		Some(Bucket(rl))
+	}
+
+	pub fn check_request(&self, req: &http::Request, metrics: &Metrics) -> Result<(), ProxyError> {
 		if self.spec.limit_type != RateLimitType::Requests {
 			return Ok(());
 		}
+		match self.bucket(req, metrics) {
+			Some(bucket) => bucket.check_request(),
+			None => Ok(()),
+		}
+	}
+
+	/// Check the token limit for an LLM request. On success, returns the bucket that was charged so
+	/// the actual usage can be amended once the response is known.
+	pub fn check_llm_request(
+		&self,
+		req: &http::Request,
+		llm_req: &LLMRequest,
+		metrics: &Metrics,
+	) -> Result<Option<Bucket>, ProxyError> {
+		if self.spec.limit_type != RateLimitType::Tokens {
+			return Ok(None);
+		}
+		let Some(bucket) = self.bucket(req, metrics) else {
+			return Ok(None);
+		};
+		bucket.check_llm_request(llm_req)?;
+		Ok(Some(bucket))
+	}
+
+	pub fn expressions(&self) -> impl Iterator<Item = &Expression> {
+		self.spec.key.iter().map(|k| k.as_ref())
+	}
+}
+
+/// A single token bucket selected for a request. Without a `key`, all requests share one bucket.
+#[derive(Debug, Clone)]
+pub struct Bucket(Arc<ratelimit::Ratelimiter>);
+
+impl Bucket {
+	pub fn check_request(&self) -> Result<(), ProxyError> {
 		// TODO: return headers on success, not just failure
 		self
-			.ratelimit
+			.0
 			.try_wait()
 			.map_err(|(limit, remaining, reset)| ProxyError::RateLimitExceeded {
 				limit,
@@ -86,14 +280,11 @@ impl RateLimit {
 	}
 
 	pub fn check_llm_request(&self, req: &LLMRequest) -> Result<(), ProxyError> {
-		if self.spec.limit_type != RateLimitType::Tokens {
-			return Ok(());
-		}
 		if let Some(it) = req.input_tokens {
 			// If we tokenized the request, check to make sure we permit that many tokens
 			// We will add the response tokens in `amend_tokens`
 			self
-				.ratelimit
+				.0
 				.try_wait_n(it)
 				.map_err(|(limit, remaining, reset)| ProxyError::RateLimitExceeded {
 					limit,
@@ -103,15 +294,14 @@ impl RateLimit {
 		} else {
 			// Otherwise, make sure at least 1 token is allowed.
 			// Note this may lead to large over-allowance, especially with fast fill_intervals.
-			let avail = self.ratelimit.available_refill();
+			let avail = self.0.available_refill();
 			if avail > 0 {
 				Ok(())
 			} else {
 				Err(ProxyError::RateLimitExceeded {
-					limit: self.ratelimit.max_tokens(),
+					limit: self.0.max_tokens(),
 					remaining: avail,
-					reset_seconds: (self.ratelimit.next_refill() - clocksource::precise::Instant::now())
-						.as_secs(),
+					reset_seconds: (self.0.next_refill() - clocksource::precise::Instant::now()).as_secs(),
 				})
 			}
 		}
@@ -123,24 +313,32 @@ impl RateLimit {
 	/// If there are fewer tokens available than requested to remove, the bucket
 	/// will be set to 0.
 	pub fn amend_tokens(&self, tokens_to_remove: i64) {
-		self.ratelimit.amend_tokens(tokens_to_remove);
+		self.0.amend_tokens(tokens_to_remove);
 	}
 }
 
 impl crate::store::RequestPolicyTrait for Vec<RateLimit> {
 	async fn apply(
 		&self,
-		_client: &crate::proxy::httpproxy::PolicyClient,
+		client: &crate::proxy::httpproxy::PolicyClient,
 		_log: &mut crate::telemetry::log::RequestLog,
-		_req: &mut http::Request,
+		req: &mut http::Request,
 	) -> Result<http::PolicyResponse, crate::proxy::ProxyResponse> {
 		for rate_limit in self {
-			rate_limit.check_request()?;
+			rate_limit.check_request(req, &client.inputs.metrics)?;
 		}
 		Ok(http::PolicyResponse::default())
 	}
+
+	fn expressions(&self) -> impl Iterator<Item = &Expression> {
+		self.iter().flat_map(RateLimit::expressions)
+	}
 }
 
+#[cfg(test)]
+#[path = "localratelimit_tests.rs"]
+mod tests;
+
 // Forked from https://github.com/pelikan-io/rustcommon/tree/main/ratelimit to provide some additional functions
 mod ratelimit {
 	use core::sync::atomic::{AtomicU64, Ordering};
@@ -0,0 +1,168 @@
+use prometheus_client::registry::Registry;
+use serde_json::json;
+
+use super::*;
+
+fn metrics() -> Metrics {
+	Metrics::new(&mut Registry::default(), Default::default())
+}
+
+fn request_for(user: Option<&str>) -> http::Request {
+	let mut rb = ::http::Request::builder().uri("http://example.com/");
+	if let Some(user) = user {
+		rb = rb.header("x-user", user);
+	}
+	rb.body(http::Body::empty()).unwrap()
+}
+
+fn keyed_limit(max_tokens: u64, max_keys: usize) -> RateLimit {
+	serde_json::from_value(json!({
+		"maxTokens": max_tokens,
+		"tokensPerFill": 1,
+		"fillInterval": "1h",
+		"key": "request.headers['x-user']",
+		"maxKeys": max_keys,
+	}))
+	.unwrap()
+}
+
+#[test]
+fn keyed_buckets_are_independent() {
+	let metrics = metrics();
+	let rl = keyed_limit(2, 100);
+	let alice = request_for(Some("alice"));
+	let bob = request_for(Some("bob"));
+
+	assert!(rl.check_request(&alice, &metrics).is_ok());
+	assert!(rl.check_request(&alice, &metrics).is_ok());
+	assert!(rl.check_request(&alice, &metrics).is_err());
+
+	// Bob has his own bucket, unaffected by alice
+	assert!(rl.check_request(&bob, &metrics).is_ok());
+	assert!(rl.check_request(&bob, &metrics).is_ok());
+	assert!(rl.check_request(&bob, &metrics).is_err());
+
+	assert_eq!(metrics.local_rate_limit_keys.get(), 2);
+}
+
+#[test]
+fn keyed_bucket_skipped_when_key_fails() {
+	let metrics = metrics();
+	let rl = keyed_limit(1, 100);
+	let anonymous = request_for(None);
+
+	for _ in 0..5 {
+		assert!(rl.check_request(&anonymous, &metrics).is_ok());
+	}
+	assert!(rl.bucket(&anonymous, &metrics).is_none());
+	assert_eq!(metrics.local_rate_limit_keys.get(), 0);
+}
+
+#[test]
+fn unkeyed_buckets_are_shared() {
+	let metrics = metrics();
+	let rl: RateLimit = serde_json::from_value(json!({
+		"maxTokens": 1,
+		"tokensPerFill": 1,
+		"fillInterval": "1h",
+	}))
+	.unwrap();
+
+	assert!(
+		rl.check_request(&request_for(Some("alice")), &metrics)
+			.is_ok()
+	);
+	assert!(
+		rl.check_request(&request_for(Some("bob")), &metrics)
+			.is_err()
+	);
+	assert_eq!(metrics.local_rate_limit_keys.get(), 0);
+}
+
+#[test]
+fn keyed_buckets_are_bounded() {
+	let metrics = metrics();
+	let rl = keyed_limit(1, 4);
+	for i in 0..100 {
+		let req = request_for(Some(&format!("user-{i}")));
+		assert!(rl.check_request(&req, &metrics).is_ok());
+	}
+	let live = metrics.local_rate_limit_keys.get();
+	assert!(
+		(1..=4).contains(&live),
+		"expected at most 4 live keys, got {live}"
+	);
+
+	drop(rl);
+	assert_eq!(metrics.local_rate_limit_keys.get(), 0);
+}
+
+#[test]
+fn idle_keyed_buckets_are_dropped() {
+	let metrics = metrics();
+	let rl: RateLimit = serde_json::from_value(json!({
+		"maxTokens": 2,
+		"tokensPerFill": 2,
+		"fillInterval": "1h",
+		"key": "request.headers['x-user']",
+	}))
+	.unwrap();
+	let Buckets::Keyed(keyed) = rl.buckets.as_ref() else {
+		panic!("expected keyed buckets");
+	};
+	let alice = request_for(Some("alice"));
+	let bob = request_for(Some("bob"));
+	assert!(rl.check_request(&alice, &metrics).is_ok());
+	rl.bucket(&bob, &metrics).unwrap();
+	let in_flight = rl.bucket(&request_for(Some("carol")), &metrics).unwrap();
+
+	// Alice's bucket still has to refill; bob's is full and unused.
+	keyed.evict_idle();
+	assert!(keyed.buckets.get("alice").is_some());
+	assert!(keyed.buckets.get("bob").is_none());
+	assert!(keyed.buckets.get("carol").is_some());
+
+	drop(in_flight);
+	keyed.evict_idle();
+	assert!(keyed.buckets.get("carol").is_none());
+
+	// The drained bucket is kept, so the limit still applies.
+	assert!(rl.check_request(&alice, &metrics).is_ok());
+	assert!(rl.check_request(&alice, &metrics).is_err());
+}
+
+#[test]
+fn keyed_token_limit_amends_matching_bucket() {
+	let metrics = metrics();
+	let rl: RateLimit = serde_json::from_value(json!({
+		"maxTokens": 10,
+		"tokensPerFill": 1,
+		"fillInterval": "1h",
+		"type": "tokens",
+		"key": "request.headers['x-user']",
+	}))
+	.unwrap();
+	let alice = request_for(Some("alice"));
+	let bob = request_for(Some("bob"));
+
+	let mut llm_req = crate::llm::LLMRequest {
+		input_tokens: None,
+		input_format: crate::llm::InputFormat::Completions,
+		native_format: None,
+		cache_convention: crate::llm::CacheTokenConvention::pending(),
+		request_model: "test-model".into(),
+		provider: "test-provider".into(),
+		streaming: false,
+		params: Default::default(),
+		prompt: None,
+	};
+	let bucket = rl
+		.check_llm_request(&alice, &llm_req, &metrics)
+		.unwrap()
+		.expect("token limits return a bucket");
+	bucket.amend_tokens(10);
+
+	llm_req.input_tokens = Some(1);
+	assert!(rl.check_llm_request(&alice, &llm_req, &metrics).is_err());
+	assert!(rl.check_llm_request(&bob, &llm_req, &metrics).is_ok());
+}
@@ -126,3 +126,81 @@ fn test_process_rate_limit_headers() {
 	assert(&[("x-ratelimit-reset-tokens", "abc")], None);
 	assert(&[("x-ratelimit-reset-requests", "-1m")], None);
 }
+
+#[test]
+fn test_rate_limit_headroom() {
+	let now = SystemTime::now();
+	let get = |convention: RateLimitHeaders, headers: &[(&str, &str)]| {
+		let mut h = HeaderMap::new();
+		for (k, v) in headers.iter() {
+			h.insert(HeaderName::from_str(k).unwrap(), v.parse().unwrap());
+		}
+		rate_limit_headroom(&h, convention, now)
+	};
+
+	// The most constrained limit wins.
+	assert_eq!(
+		get(
+			RateLimitHeaders::OpenAI,
+			&[
+				("x-ratelimit-limit-requests", "100"),
+				("x-ratelimit-remaining-requests", "90"),
+				("x-ratelimit-reset-requests", "6s"),
+				("x-ratelimit-limit-tokens", "1000"),
+				("x-ratelimit-remaining-tokens", "250"),
+				("x-ratelimit-reset-tokens", "1m30s"),
+			]
+		),
+		Some(RateLimitHeadroom {
+			remaining: 0.25,
+			reset: Some(Duration::from_secs(90)),
+		})
+	);
+	// Azure omits the limit on some deployments; without it there is no ratio to compute.
+	assert_eq!(
+		get(
+			RateLimitHeaders::OpenAI,
+			&[("x-ratelimit-remaining-tokens", "250")]
+		),
+		None
+	);
+	assert_eq!(
+		get(
+			RateLimitHeaders::OpenAI,
+			&[
+				("x-ratelimit-limit-requests", "100"),
+				("x-ratelimit-remaining-requests", "0"),
+			]
+		),
+		Some(RateLimitHeadroom {
+			remaining: 0.0,
+			reset: None,
+		})
+	);
+
+	let reset = chrono::DateTime::<chrono::Utc>::from(now + Duration::from_secs(30)).to_rfc3339();
+	let got = get(
+		RateLimitHeaders::Anthropic,
+		&[
+			("anthropic-ratelimit-requests-limit", "50"),
+			("anthropic-ratelimit-requests-remaining", "49"),
+			("anthropic-ratelimit-input-tokens-limit", "10000"),
+			("anthropic-ratelimit-input-tokens-remaining", "1000"),
+			("anthropic-ratelimit-input-tokens-reset", &reset),
+		],
+	)
+	.unwrap();
+	assert_eq!(got.remaining, 0.1);
+	assert!((29..=30).contains(&got.reset.unwrap().as_secs()));
+	// Headers of another provider are ignored.
+	assert_eq!(
+		get(
+			RateLimitHeaders::Anthropic,
+			&[
+				("x-ratelimit-limit-requests", "100"),
+				("x-ratelimit-remaining-requests", "0"),
+			]
+		),
+		None
+	);
+}
@@ -72,30 +72,121 @@ fn process_rate_limit_headers(h: &HeaderMap, now: SystemTime) -> Option<std::tim
 		x_headers::X_RATELIMIT_RESET_TOKENS_MINUTE,
 	]
 	.iter()
-	.filter_map(|hn| {
-		get_header(h, hn).and_then(|v| {
-			if let Ok(d) = durfmt::parse(v) {
-				Some(d)
-			} else if v
-				.chars()
-				.last()
-				.map(|c| c.is_ascii_digit())
-				.unwrap_or(false)
-			{
-				// Treat as seconds
-				durfmt::parse(&(v.to_string() + "s")).ok()
-			} else {
-				None
-			}
-		})
-	})
+	.filter_map(|hn| get_header(h, hn).and_then(parse_reset_duration))
 	.min();
 	if let Some(smallest) = smallest {
-		return Some(*smallest);
+		return Some(smallest);
 	}
 	None
 }
 
+/// Parse a duration like `6m0s`, or a bare number of seconds.
+fn parse_reset_duration(v: &str) -> Option<Duration> {
+	if let Ok(d) = durfmt::parse(v) {
+		Some(d)
+	} else if v
+		.chars()
+		.last()
+		.map(|c| c.is_ascii_digit())
+		.unwrap_or(false)
+	{
+		// Treat as seconds
+		durfmt::parse(&(v.to_string() + "s")).ok()
+	} else {
+		None
+	}
+}
+
+/// The rate limit headers a provider reports on every response, not only once it is limited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitHeaders {
+	/// `x-ratelimit-{limit,remaining,reset}-{requests,tokens}`, where the reset is a duration.
+	/// Used by OpenAI and Azure OpenAI.
+	OpenAI,
+	/// `anthropic-ratelimit-{requests,tokens,input-tokens,output-tokens}-{limit,remaining,reset}`,
+	/// where the reset is an RFC 3339 timestamp.
+	Anthropic,
+}
+
+/// How much of its rate limit an upstream has left.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitHeadroom {
+	/// Fraction (0.0–1.0) remaining of the most constrained limit.
+	pub remaining: f64,
+	/// Time until that limit resets, if reported.
+	pub reset: Option<Duration>,
+}
+
+const OPENAI_RATE_LIMITS: &[(&str, &str, &str)] = &[
+	(
+		"x-ratelimit-limit-requests",
+		"x-ratelimit-remaining-requests",
+		"x-ratelimit-reset-requests",
+	),
+	(
+		"x-ratelimit-limit-tokens",
+		"x-ratelimit-remaining-tokens",
+		"x-ratelimit-reset-tokens",
+	),
+];
+
+const ANTHROPIC_RATE_LIMITS: &[(&str, &str, &str)] = &[
+	(
+		"anthropic-ratelimit-requests-limit",
+		"anthropic-ratelimit-requests-remaining",
+		"anthropic-ratelimit-requests-reset",
+	),
+	(
+		"anthropic-ratelimit-tokens-limit",
+		"anthropic-ratelimit-tokens-remaining",
+		"anthropic-ratelimit-tokens-reset",
+	),
+	(
+		"anthropic-ratelimit-input-tokens-limit",
+		"anthropic-ratelimit-input-tokens-remaining",
+		"anthropic-ratelimit-input-tokens-reset",
+	),
+	(
+		"anthropic-ratelimit-output-tokens-limit",
+		"anthropic-ratelimit-output-tokens-remaining",
+		"anthropic-ratelimit-output-tokens-reset",
+	),
+];
+
+/// Read the remaining rate limit reported by an upstream, following the provider's `convention`.
+/// Returns the limit with the smallest fraction remaining, or None if no limit is reported.
+pub fn rate_limit_headroom(
+	h: &HeaderMap,
+	convention: RateLimitHeaders,
+	now: SystemTime,
+) -> Option<RateLimitHeadroom> {
+	let limits = match convention {
+		RateLimitHeaders::OpenAI => OPENAI_RATE_LIMITS,
+		RateLimitHeaders::Anthropic => ANTHROPIC_RATE_LIMITS,
+	};
+	limits
+		.iter()
+		.filter_map(|(limit, remaining, reset)| {
+			let limit = h.get(*limit)?.to_str().ok()?.parse::<f64>().ok()?;
+			let remaining = h.get(*remaining)?.to_str().ok()?.parse::<f64>().ok()?;
+			if limit <= 0.0 {
+				return None;
+			}
+			let reset = h.get(*reset).and_then(|v| v.to_str().ok());
+			let reset = match convention {
+				RateLimitHeaders::OpenAI => reset.and_then(parse_reset_duration),
+				RateLimitHeaders::Anthropic => reset
+					.and_then(|v| chrono::DateTime::parse_from_rfc3339(v).ok())
+					.and_then(|t| SystemTime::from(t).duration_since(now).ok()),
+			};
+			Some(RateLimitHeadroom {
+				remaining: (remaining / limit).clamp(0.0, 1.0),
+				reset,
+			})
+		})
+		.min_by(|a, b| a.remaining.total_cmp(&b.remaining))
+}
+
 #[cfg(test)]
 #[path = "outlierdetction_tests.rs"]
 mod tests;
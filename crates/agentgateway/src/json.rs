@@ -87,3 +87,150 @@ pub fn convert<I: Serialize, O: DeserializeOwned>(input: &I) -> Result<O, serde_
 	let o = serde_json::from_value::<O>(v)?;
 	Ok(o)
 }
+
+/// Validate `value` against a JSON schema. Only the keywords tool and function definitions use in
+/// practice are checked (types, `enum`/`const`, object properties, array items, string and number
+/// bounds, `pattern`, and `allOf`/`anyOf`/`oneOf`); other keywords, including `$ref`, are ignored.
+pub fn validate_schema(schema: &Value, value: &Value) -> anyhow::Result<()> {
+	validate_at(schema, value, "")
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str) -> anyhow::Result<()> {
+	let Some(schema) = schema.as_object() else {
+		// `true` and `{}` accept anything; `false` accepts nothing.
+		anyhow::ensure!(schema != &Value::Bool(false), "{path}: no value is allowed");
+		return Ok(());
+	};
+	let at = if path.is_empty() { "/" } else { path };
+	if let Some(types) = schema.get("type") {
+		let allowed: Vec<&str> = match types {
+			Value::String(t) => vec![t.as_str()],
+			Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+			_ => Vec::new(),
+		};
+		if !allowed.is_empty() && !allowed.iter().any(|t| is_type(value, t)) {
+			anyhow::bail!("{at}: expected {}", allowed.join(" or "));
+		}
+	}
+	if let Some(Value::Array(options)) = schema.get("enum") {
+		anyhow::ensure!(
+			options.contains(value),
+			"{at}: value is not one of the allowed values"
+		);
+	}
+	if let Some(expected) = schema.get("const") {
+		anyhow::ensure!(
+			expected == value,
+			"{at}: value does not match the expected constant"
+		);
+	}
+	match value {
+		Value::Object(fields) => {
+			if let Some(Value::Array(required)) = schema.get("required") {
+				for name in required.iter().filter_map(Value::as_str) {
+					anyhow::ensure!(
+						fields.contains_key(name),
+						"{at}: missing required property {name}"
+					);
+				}
+			}
+			let properties = schema.get("properties").and_then(Value::as_object);
+			for (name, v) in fields {
+				let child = format!("{path}/{name}");
+				match properties.and_then(|p| p.get(name)) {
+					Some(s) => validate_at(s, v, &child)?,
+					None => match schema.get("additionalProperties") {
+						Some(Value::Bool(false)) => anyhow::bail!("{at}: unexpected property {name}"),
+						Some(s) => validate_at(s, v, &child)?,
+						None => {},
+					},
+				}
+			}
+		},
+		Value::Array(items) => {
+			if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+				anyhow::ensure!(
+					items.len() as u64 >= min,
+					"{at}: expected at least {min} items"
+				);
+			}
+			if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+				anyhow::ensure!(
+					items.len() as u64 <= max,
+					"{at}: expected at most {max} items"
+				);
+			}
+			if let Some(s) = schema.get("items") {
+				for (i, v) in items.iter().enumerate() {
+					validate_at(s, v, &format!("{path}/{i}"))?;
+				}
+			}
+		},
+		Value::String(s) => {
+			let len = s.chars().count() as u64;
+			if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+				anyhow::ensure!(len >= min, "{at}: expected at least {min} characters");
+			}
+			if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+				anyhow::ensure!(len <= max, "{at}: expected at most {max} characters");
+			}
+			if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+				let re = regex::Regex::new(pattern)?;
+				anyhow::ensure!(re.is_match(s), "{at}: value does not match {pattern}");
+			}
+		},
+		Value::Number(n) => {
+			let n = n.as_f64().unwrap_or_default();
+			let bound = |k: &str| schema.get(k).and_then(Value::as_f64);
+			if let Some(min) = bound("minimum") {
+				anyhow::ensure!(n >= min, "{at}: expected at least {min}");
+			}
+			if let Some(max) = bound("maximum") {
+				anyhow::ensure!(n <= max, "{at}: expected at most {max}");
+			}
+			if let Some(min) = bound("exclusiveMinimum") {
+				anyhow::ensure!(n > min, "{at}: expected more than {min}");
+			}
+			if let Some(max) = bound("exclusiveMaximum") {
+				anyhow::ensure!(n < max, "{at}: expected less than {max}");
+			}
+		},
+		_ => {},
+	}
+	if let Some(Value::Array(all)) = schema.get("allOf") {
+		for s in all {
+			validate_at(s, value, path)?;
+		}
+	}
+	if let Some(Value::Array(any)) = schema.get("anyOf") {
+		anyhow::ensure!(
+			any.iter().any(|s| validate_at(s, value, path).is_ok()),
+			"{at}: value does not match any allowed schema"
+		);
+	}
+	if let Some(Value::Array(one)) = schema.get("oneOf") {
+		let matched = one
+			.iter()
+			.filter(|s| validate_at(s, value, path).is_ok())
+			.count();
+		anyhow::ensure!(matched == 1, "{at}: value must match exactly one schema");
+	}
+	Ok(())
+}
+
+fn is_type(value: &Value, t: &str) -> bool {
+	match t {
+		"object" => value.is_object(),
+		"array" => value.is_array(),
+		"string" => value.is_string(),
+		"boolean" => value.is_boolean(),
+		"null" => value.is_null(),
+		"number" => value.is_number(),
+		"integer" => {
+			value.as_i64().is_some()
+				|| value.as_u64().is_some()
+				|| value.as_f64().is_some_and(|f| f.fract() == 0.0)
+		},
+		_ => true,
+	}
+}
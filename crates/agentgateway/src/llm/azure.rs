@@ -63,13 +63,7 @@ impl Provider {
 					return strng::literal!("/anthropic/v1/messages/count_tokens");
 				}
 			}
-			let t = if route == RouteType::Embeddings {
-				strng::literal!("embeddings")
-			} else if route == RouteType::Responses {
-				strng::literal!("responses")
-			} else {
-				strng::literal!("chat/completions")
-			};
+			let t = openai_path_segment(route);
 			let project = self
 				.project_name
 				.as_deref()
@@ -77,13 +71,7 @@ impl Provider {
 			return strng::format!("/api/projects/{project}/openai/v1/{t}");
 		}
 
-		let t = if route == RouteType::Embeddings {
-			strng::literal!("embeddings")
-		} else if route == RouteType::Responses {
-			strng::literal!("responses")
-		} else {
-			strng::literal!("chat/completions")
-		};
+		let t = openai_path_segment(route);
 
 		let api_version = self.api_version();
 		if api_version == "v1" {
@@ -117,6 +105,19 @@ impl Provider {
 	}
 }
 
+/// The path of an OpenAI-compatible endpoint, relative to the API root. Routes without a dedicated
+/// endpoint are translated down to chat completions.
+fn openai_path_segment(route: RouteType) -> Strng {
+	match route {
+		RouteType::Embeddings => strng::literal!("embeddings"),
+		RouteType::Responses => strng::literal!("responses"),
+		RouteType::ImageGeneration => strng::literal!("images/generations"),
+		RouteType::AudioTranscription => strng::literal!("audio/transcriptions"),
+		RouteType::AudioSpeech => strng::literal!("audio/speech"),
+		_ => strng::literal!("chat/completions"),
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -217,6 +218,20 @@ mod tests {
 		"gpt-4o-mini",
 		"/openai/v1/chat/completions"
 	)]
+	#[case::openai_images(
+		AzureResourceType::OpenAI,
+		RouteType::ImageGeneration,
+		None,
+		"gpt-image-1",
+		"/openai/v1/images/generations"
+	)]
+	#[case::foundry_transcriptions(
+		AzureResourceType::Foundry,
+		RouteType::AudioTranscription,
+		None,
+		"gpt-4o-transcribe",
+		"/api/projects/my-resource/openai/v1/audio/transcriptions"
+	)]
 	fn test_get_path_for_model(
 		#[case] resource_type: AzureResourceType,
 		#[case] route: RouteType,
@@ -0,0 +1,312 @@
+//! OpenAI Batch and Files API support.
+//!
+//! Batch input files are uploaded to `/v1/files` as JSONL, one request per line. Uploads are
+//! rewritten so model aliases and the model allowlist apply to every request in the file, and the
+//! number of requests and the models they use are recorded. Usage is read from batch output files
+//! as they are downloaded, and priced per model at batch rates by the cost catalog. Each output file
+//! is only billed the first time it is downloaded.
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use agent_core::prelude::Strng;
+use agent_core::strng;
+use bytes::Bytes;
+use headers::{ContentEncoding, HeaderMapExt};
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use quick_cache::sync::{Cache, GuardResult};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio_util::codec::LinesCodec;
+use tracing::debug;
+
+use super::policy::Policy;
+use super::types::ResponseType;
+use super::types::multipart::Form;
+use super::{
+	AIError, AIProvider, AmendOnDrop, CacheTokenConvention, InputFormat, LLMInfo, LLMRequest,
+	LLMRequestParams, LLMResponse, RequestResult, cost, map_compression_error, types,
+};
+use crate::http::{self, Body, Request, Response, header};
+use crate::llm::model_router::llm_error_response;
+use crate::parse;
+use crate::store::LLMResponsePolicies;
+use crate::telemetry::log::AsyncLog;
+use crate::telemetry::trc::RequestSnapshot;
+
+/// Maximum number of downloaded files remembered as billed. Once exceeded, the least used files are
+/// forgotten, and downloading them again bills them again.
+const MAX_BILLED_FILES: usize = 100_000;
+
+/// Files whose usage was already recorded, so downloading the same output file again is free.
+static BILLED_FILES: Lazy<Cache<Strng, ()>> = Lazy::new(|| Cache::new(MAX_BILLED_FILES));
+
+/// Token usage of one model in a batch output file.
+#[derive(Debug, Clone)]
+pub struct BatchModelUsage {
+	pub model: Strng,
+	pub usage: LLMResponse,
+}
+
+#[derive(Debug, Default)]
+struct InputSummary {
+	requests: u64,
+	models: BTreeSet<String>,
+}
+
+pub(super) async fn process_request(
+	provider: &AIProvider,
+	policies: Option<&Policy>,
+	req: Request,
+) -> Result<RequestResult, AIError> {
+	let buffer = http::buffer_limit(&req);
+	let boundary = if req.method() == ::http::Method::POST {
+		Form::boundary(req.headers())
+	} else {
+		None
+	};
+	let (mut parts, body) = req.into_parts();
+
+	// Batch input files are uploaded as multipart form data; everything else is forwarded as-is.
+	let (body, summary) = match boundary {
+		Some(boundary) => {
+			let Ok(bytes) = http::read_body_with_limit(body, buffer).await else {
+				return Err(AIError::RequestTooLarge);
+			};
+			let override_model = provider.override_model();
+			match rewrite_upload(override_model.as_deref(), policies, boundary, bytes.clone()).await {
+				Ok(Some((rewritten, summary))) => {
+					parts.headers.remove(header::CONTENT_LENGTH);
+					(Body::from(rewritten), Some(summary))
+				},
+				Ok(None) => (Body::from(bytes), None),
+				Err(rejection) => return Ok(RequestResult::Rejected(rejection)),
+			}
+		},
+		None => (body, None),
+	};
+
+	let llm_info = LLMRequest {
+		input_tokens: None,
+		input_format: InputFormat::Batch,
+		native_format: None,
+		cache_convention: CacheTokenConvention::InputIncludesCache,
+		request_model: summary
+			.as_ref()
+			.map(|s| s.models.iter().join(","))
+			.unwrap_or_default()
+			.into(),
+		provider: provider.provider(),
+		streaming: false,
+		params: LLMRequestParams {
+			batch_requests: summary.as_ref().map(|s| s.requests),
+			batch_output_file: downloaded_file(&parts),
+			..Default::default()
+		},
+		prompt: None,
+	};
+	parts.extensions.insert(llm_info.clone());
+	Ok(RequestResult::Success(
+		Request::from_parts(parts, body),
+		llm_info,
+	))
+}
+
+/// Id of the file downloaded by a `GET .../files/{id}/content` request.
+fn downloaded_file(parts: &::http::request::Parts) -> Option<Strng> {
+	if parts.method != ::http::Method::GET {
+		return None;
+	}
+	let mut segments = parts.uri.path().trim_end_matches('/').rsplit('/');
+	match (segments.next(), segments.next(), segments.next()) {
+		(Some("content"), Some(id), Some("files")) if !id.is_empty() => Some(strng::new(id)),
+		_ => None,
+	}
+}
+
+/// Returns whether a downloaded file is billed, which is only the case the first time it is
+/// downloaded.
+fn first_download(file: &Strng) -> bool {
+	match BILLED_FILES.get_value_or_guard(file, Some(Duration::ZERO)) {
+		GuardResult::Guard(guard) => {
+			let _ = guard.insert(());
+			true
+		},
+		GuardResult::Value(_) | GuardResult::Timeout => false,
+	}
+}
+
+/// Rewrite a file upload with `purpose=batch`. Returns `None` for other uploads.
+async fn rewrite_upload(
+	override_model: Option<&str>,
+	policies: Option<&Policy>,
+	boundary: String,
+	body: Bytes,
+) -> Result<Option<(Bytes, InputSummary)>, Response> {
+	let mut form = Form::parse(boundary, body).await.map_err(invalid_upload)?;
+	if form.text("purpose").map(str::trim) != Some("batch") {
+		return Ok(None);
+	}
+	let mut summary = InputSummary::default();
+	if let Some(file) = form.get_mut("file") {
+		file.data = rewrite_input(override_model, policies, &file.data, &mut summary)?;
+	}
+	Ok(Some((form.to_bytes(), summary)))
+}
+
+/// Apply the provider model override, model aliases and the model allowlist to each request of a
+/// batch input file.
+fn rewrite_input(
+	override_model: Option<&str>,
+	policies: Option<&Policy>,
+	input: &[u8],
+	summary: &mut InputSummary,
+) -> Result<Bytes, Response> {
+	let mut out = Vec::with_capacity(input.len());
+	for (i, line) in input.split(|b| *b == b'\n').enumerate() {
+		if line.trim_ascii().is_empty() {
+			continue;
+		}
+		let invalid = |reason: &str| {
+			llm_error_response(
+				::http::StatusCode::BAD_REQUEST,
+				&format!("batch input line {}: {reason}", i + 1),
+				"invalid_batch_input",
+			)
+		};
+		let mut request: Value =
+			serde_json::from_slice(line).map_err(|_| invalid("not a JSON object"))?;
+		let Some(body) = request.get_mut("body").and_then(Value::as_object_mut) else {
+			return Err(invalid("missing request body"));
+		};
+		let requested = body.get("model").and_then(Value::as_str);
+		let Some(model) = override_model.or(requested).map(str::to_string) else {
+			return Err(invalid("missing model"));
+		};
+		let model = match policies {
+			Some(p) => p
+				.resolve_allowed_model(&model)?
+				.map(|aliased| aliased.to_string())
+				.unwrap_or(model),
+			None => model,
+		};
+		body.insert("model".to_string(), Value::String(model.clone()));
+		serde_json::to_writer(&mut out, &request).map_err(|_| invalid("not a JSON object"))?;
+		out.push(b'\n');
+		summary.requests += 1;
+		summary.models.insert(model);
+	}
+	Ok(Bytes::from(out))
+}
+
+fn invalid_upload(err: multer::Error) -> Response {
+	debug!(%err, "failed to parse batch file upload");
+	llm_error_response(
+		::http::StatusCode::BAD_REQUEST,
+		"file upload must be valid multipart/form-data",
+		"invalid_request_body",
+	)
+}
+
+/// Record usage from batch output files as they are downloaded. Other batch and file responses
+/// carry no usage, so they are free, as are later downloads of an output file.
+pub(super) fn process_response(
+	req: LLMRequest,
+	response_policies: LLMResponsePolicies,
+	req_snapshot: Option<Arc<RequestSnapshot>>,
+	log: AsyncLog<LLMInfo>,
+	model_catalog: Option<Arc<cost::ModelCatalog>>,
+	resp: Response,
+) -> Result<Response, AIError> {
+	let downloaded_file = req.params.batch_output_file.clone();
+	log.store(Some(LLMInfo::new(req, LLMResponse::default())));
+	let logger = AmendOnDrop::new(log, response_policies, req_snapshot, model_catalog);
+	if !resp.status().is_success() {
+		return Ok(resp);
+	}
+	if let Some(file) = downloaded_file
+		&& !first_download(&file)
+	{
+		debug!(%file, "batch file was already billed, skipping usage");
+		return Ok(resp);
+	}
+
+	let buffer = http::response_buffer_limit(&resp);
+	let (mut parts, body) = resp.into_parts();
+	let ce = parts.headers.typed_get::<ContentEncoding>();
+	let (body, decompressed_encoding) = http::compression::decompress_body(body, ce.as_ref())
+		.map_err(|e| map_compression_error(e, &parts.headers))?;
+	if decompressed_encoding.is_some() {
+		parts.headers.remove(header::CONTENT_ENCODING);
+		parts.headers.remove(header::CONTENT_LENGTH);
+		parts.headers.remove(header::TRANSFER_ENCODING);
+	}
+
+	let body = parse::passthrough::full_passthrough_parser(
+		body,
+		LinesCodec::new_with_max_length(buffer),
+		move |line: String| {
+			if let Some((model, usage)) = result_usage(&line) {
+				logger.non_atomic_mutate(|info| record_usage(&mut info.response, model, usage));
+			}
+		},
+	);
+	Ok(Response::from_parts(parts, body))
+}
+
+/// Usage of one line of a batch output file.
+fn result_usage(line: &str) -> Option<(Strng, LLMResponse)> {
+	#[derive(Deserialize)]
+	struct ResultLine {
+		response: Option<ResultResponse>,
+	}
+	#[derive(Deserialize)]
+	struct ResultResponse {
+		body: Value,
+	}
+
+	let body = serde_json::from_str::<ResultLine>(line)
+		.ok()?
+		.response?
+		.body;
+	let usage = types::detect::Response::Json(body).to_llm_response(false);
+	let model = usage.provider_model.clone()?;
+	Some((model, usage))
+}
+
+fn record_usage(total: &mut LLMResponse, model: Strng, usage: LLMResponse) {
+	add_usage(total, &usage);
+	match total.batch_usage.iter_mut().find(|u| u.model == model) {
+		Some(existing) => add_usage(&mut existing.usage, &usage),
+		None => total.batch_usage.push(BatchModelUsage { model, usage }),
+	}
+}
+
+fn add_usage(total: &mut LLMResponse, usage: &LLMResponse) {
+	fn add(total: &mut Option<u64>, value: Option<u64>) {
+		if let Some(value) = value {
+			*total = Some(total.unwrap_or_default() + value);
+		}
+	}
+	add(&mut total.input_tokens, usage.input_tokens);
+	add(&mut total.input_image_tokens, usage.input_image_tokens);
+	add(&mut total.input_text_tokens, usage.input_text_tokens);
+	add(&mut total.input_audio_tokens, usage.input_audio_tokens);
+	add(&mut total.output_tokens, usage.output_tokens);
+	add(&mut total.output_image_tokens, usage.output_image_tokens);
+	add(&mut total.output_text_tokens, usage.output_text_tokens);
+	add(&mut total.output_audio_tokens, usage.output_audio_tokens);
+	add(&mut total.total_tokens, usage.total_tokens);
+	add(&mut total.reasoning_tokens, usage.reasoning_tokens);
+	add(
+		&mut total.cache_creation_input_tokens,
+		usage.cache_creation_input_tokens,
+	);
+	add(&mut total.cached_input_tokens, usage.cached_input_tokens);
+}
+
+#[cfg(test)]
+#[path = "batch_tests.rs"]
+mod tests;
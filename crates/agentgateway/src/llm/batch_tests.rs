@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use agent_core::strng;
+use serde_json::json;
+
+use super::*;
+
+const BOUNDARY: &str = "batch-boundary";
+
+fn upload(purpose: &str, file: &str) -> Bytes {
+	Bytes::from(format!(
+		"--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"purpose\"\r\n\r\n{purpose}\r\n\
+		 --{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"input.jsonl\"\r\n\
+		 Content-Type: application/jsonl\r\n\r\n{file}\r\n--{BOUNDARY}--\r\n"
+	))
+}
+
+fn input_line(id: &str, model: &str) -> String {
+	json!({
+		"custom_id": id,
+		"method": "POST",
+		"url": "/v1/chat/completions",
+		"body": {"model": model, "messages": [{"role": "user", "content": "hi"}]},
+	})
+	.to_string()
+}
+
+fn policy() -> Policy {
+	let mut policy = Policy {
+		model_aliases: HashMap::from([(strng::new("fast"), strng::new("gpt-4o-mini"))]),
+		allowed_models: vec![strng::new("gpt-4o*")],
+		..Default::default()
+	};
+	policy.compile_model_alias_patterns();
+	policy
+}
+
+fn models(input: &[u8]) -> Vec<String> {
+	input
+		.split(|b| *b == b'\n')
+		.filter(|l| !l.is_empty())
+		.map(|l| {
+			let v: Value = serde_json::from_slice(l).unwrap();
+			v["body"]["model"].as_str().unwrap().to_string()
+		})
+		.collect()
+}
+
+#[test]
+fn rewrite_input_applies_aliases_and_counts_requests() {
+	let input = format!(
+		"{}\n\n{}\n{}\n",
+		input_line("a", "fast"),
+		input_line("b", "gpt-4o"),
+		input_line("c", "fast"),
+	);
+	let mut summary = InputSummary::default();
+	let out = rewrite_input(None, Some(&policy()), input.as_bytes(), &mut summary).unwrap();
+	assert_eq!(models(&out), ["gpt-4o-mini", "gpt-4o", "gpt-4o-mini"]);
+	assert_eq!(summary.requests, 3);
+	assert_eq!(
+		summary.models.into_iter().collect::<Vec<_>>(),
+		["gpt-4o", "gpt-4o-mini"]
+	);
+}
+
+#[test]
+fn rewrite_input_applies_provider_model_override() {
+	let input = input_line("a", "anything");
+	let mut summary = InputSummary::default();
+	let out = rewrite_input(Some("gpt-4o"), None, input.as_bytes(), &mut summary).unwrap();
+	assert_eq!(models(&out), ["gpt-4o"]);
+}
+
+#[test]
+fn rewrite_input_rejects_disallowed_models() {
+	let input = format!("{}\n{}\n", input_line("a", "gpt-4o"), input_line("b", "o3"));
+	let mut summary = InputSummary::default();
+	let rejected = rewrite_input(None, Some(&policy()), input.as_bytes(), &mut summary).unwrap_err();
+	assert_eq!(rejected.status(), ::http::StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn rewrite_input_rejects_invalid_lines() {
+	for input in ["not json", r#"{"custom_id":"a"}"#, r#"{"body":{}}"#] {
+		let mut summary = InputSummary::default();
+		let rejected = rewrite_input(None, None, input.as_bytes(), &mut summary).unwrap_err();
+		assert_eq!(
+			rejected.status(),
+			::http::StatusCode::BAD_REQUEST,
+			"{input}"
+		);
+	}
+}
+
+#[tokio::test]
+async fn rewrite_upload_only_rewrites_batch_files() {
+	let file = input_line("a", "fast");
+	let (out, summary) = rewrite_upload(
+		None,
+		Some(&policy()),
+		BOUNDARY.to_string(),
+		upload("batch", &file),
+	)
+	.await
+	.unwrap()
+	.expect("batch uploads are rewritten");
+	assert_eq!(summary.requests, 1);
+	let out = String::from_utf8(out.to_vec()).unwrap();
+	assert!(out.contains("name=\"purpose\"\r\n\r\nbatch\r\n"));
+	assert!(out.contains("\"model\":\"gpt-4o-mini\""));
+	assert!(out.ends_with(&format!("--{BOUNDARY}--\r\n")));
+
+	let other = rewrite_upload(
+		None,
+		Some(&policy()),
+		BOUNDARY.to_string(),
+		upload("assistants", &file),
+	)
+	.await
+	.unwrap();
+	assert!(other.is_none());
+}
+
+#[test]
+fn result_usage_is_summed_per_model() {
+	let line = |model: &str, input: u64, output: u64| {
+		json!({
+			"id": "batch_req_1",
+			"custom_id": "a",
+			"response": {
+				"status_code": 200,
+				"body": {
+					"object": "chat.completion",
+					"model": model,
+					"choices": [],
+					"usage": {
+						"prompt_tokens": input,
+						"completion_tokens": output,
+						"total_tokens": input + output,
+					},
+				},
+			},
+		})
+		.to_string()
+	};
+
+	let mut total = LLMResponse::default();
+	for l in [
+		line("gpt-4o", 10, 5),
+		line("gpt-4o-mini", 100, 50),
+		line("gpt-4o", 20, 10),
+	] {
+		let (model, usage) = result_usage(&l).unwrap();
+		record_usage(&mut total, model, usage);
+	}
+	assert_eq!(total.input_tokens, Some(130));
+	assert_eq!(total.output_tokens, Some(65));
+	assert_eq!(total.batch_usage.len(), 2);
+	assert_eq!(total.batch_usage[0].model.as_str(), "gpt-4o");
+	assert_eq!(total.batch_usage[0].usage.input_tokens, Some(30));
+	assert_eq!(total.batch_usage[0].usage.output_tokens, Some(15));
+	assert_eq!(total.batch_usage[1].usage.input_tokens, Some(100));
+
+	// Failed requests and non-result content carry no usage
+	let failed = json!({"id": "batch_req_2", "response": null, "error": {"code": "x"}});
+	assert!(result_usage(&failed.to_string()).is_none());
+	assert!(result_usage("{\"object\":\"file\"}").is_none());
+}
+
+#[tokio::test]
+async fn output_files_are_billed_once() {
+	let output = format!(
+		"{}\n",
+		json!({
+			"id": "batch_req_1",
+			"custom_id": "a",
+			"response": {
+				"status_code": 200,
+				"body": {
+					"object": "chat.completion",
+					"model": "gpt-4o",
+					"choices": [],
+					"usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15},
+				},
+			},
+		})
+	);
+	let download = |uri: &str| {
+		let (parts, _) = ::http::Request::get(uri).body(()).unwrap().into_parts();
+		let req = LLMRequest {
+			input_tokens: None,
+			input_format: InputFormat::Batch,
+			native_format: None,
+			cache_convention: CacheTokenConvention::InputIncludesCache,
+			request_model: "".into(),
+			provider: "openai".into(),
+			streaming: false,
+			params: LLMRequestParams {
+				batch_output_file: downloaded_file(&parts),
+				..Default::default()
+			},
+			prompt: None,
+		};
+		let output = output.clone();
+		async move {
+			let log = AsyncLog::default();
+			let resp = process_response(
+				req,
+				LLMResponsePolicies::default(),
+				None,
+				log.clone(),
+				None,
+				Response::new(Body::from(output)),
+			)
+			.unwrap();
+			http::read_body_with_limit(resp.into_body(), 1 << 20)
+				.await
+				.unwrap();
+			log.take().unwrap().response.input_tokens
+		}
+	};
+
+	let file = "/v1/files/file-billed-once/content";
+	assert_eq!(download(file).await, Some(10));
+	assert_eq!(download(file).await, None, "downloading again is free");
+	assert_eq!(
+		download("/v1/files/file-billed-other/content").await,
+		Some(10)
+	);
+	// Other file requests are not tracked.
+	let (parts, _) = ::http::Request::get("/v1/files/file-1")
+		.body(())
+		.unwrap()
+		.into_parts();
+	assert!(downloaded_file(&parts).is_none());
+}
@@ -8,6 +8,10 @@ use crate::http::Response;
 use crate::llm::{AmendOnDrop, logged_response_parsing, types};
 use crate::{llm, parse};
 
+#[cfg(test)]
+#[path = "completions_tests.rs"]
+mod tests;
+
 /// Parse a Google error response, handling both single object and array-wrapped formats.
 /// Google's OpenAI-compatible endpoints consistently return `[{"error": {...}}]`
 /// rather than `{"error": {...}}` when using the Vertex AI shim.
@@ -960,6 +964,534 @@ pub mod from_messages {
 	}
 }
 
+pub mod from_generate_content {
+	use std::collections::{BTreeMap, HashMap, VecDeque};
+
+	use bytes::Bytes;
+	use serde_json::Value;
+	use tracing::debug;
+	use types::completions::typed as completions;
+	use types::gemini;
+
+	use crate::json;
+	use crate::llm::types::ResponseType;
+	use crate::llm::{AIError, logged_response_parsing, types};
+	use crate::parse::sse::SseJsonEvent;
+
+	/// Translate a Google GenAI generateContent request into an OpenAI completions request.
+	/// Every other provider format is reached through the completions conversions.
+	pub fn translate(req: &gemini::Request) -> Result<types::completions::Request, AIError> {
+		let xlated = translate_internal(req.clone());
+		json::convert(&xlated).map_err(AIError::RequestMarshal)
+	}
+
+	#[allow(deprecated)]
+	fn translate_internal(req: gemini::Request) -> completions::Request {
+		let gemini::Request {
+			contents,
+			system_instruction,
+			generation_config,
+			tools,
+			tool_config,
+			model,
+			stream,
+			rest: _,
+		} = req;
+
+		let mut messages: Vec<completions::RequestMessage> = Vec::new();
+		if let Some(system) = system_instruction {
+			let text = part_text(&system.parts);
+			if !text.is_empty() {
+				messages.push(completions::RequestMessage::System(
+					completions::RequestSystemMessage {
+						content: completions::RequestSystemMessageContent::Text(text),
+						name: None,
+					},
+				));
+			}
+		}
+
+		// Gemini only requires ids on function calls when there are several calls to the same function,
+		// and otherwise pairs responses with calls by name and order. Completions requires ids.
+		let mut call_ids = CallIds::default();
+		for content in contents {
+			if content.role.as_deref() == Some("model") {
+				messages.extend(assistant_message(content.parts, &mut call_ids));
+			} else {
+				user_messages(content.parts, &mut call_ids, &mut messages);
+			}
+		}
+
+		let tools: Vec<completions::Tool> = tools
+			.into_iter()
+			.flatten()
+			.flat_map(|t| t.function_declarations)
+			.map(|f| {
+				completions::Tool::Function(completions::FunctionTool {
+					function: completions::FunctionObject {
+						name: f.name,
+						description: f.description,
+						parameters: f
+							.parameters_json_schema
+							.or(f.parameters.map(to_json_schema)),
+						strict: None,
+					},
+				})
+			})
+			.collect();
+
+		let tool_choice = tool_config
+			.and_then(|c| c.function_calling_config)
+			.and_then(|c| match (c.mode, c.allowed_function_names.as_deref()) {
+				(Some(gemini::FunctionCallingMode::Any), Some([name])) => Some(
+					completions::ToolChoiceOption::Function(completions::NamedToolChoice {
+						function: completions::FunctionName { name: name.clone() },
+					}),
+				),
+				(Some(gemini::FunctionCallingMode::Any | gemini::FunctionCallingMode::Validated), _) => {
+					Some(completions::ToolChoiceOption::Mode(
+						completions::ToolChoiceOptions::Required,
+					))
+				},
+				(Some(gemini::FunctionCallingMode::Auto), _) => Some(completions::ToolChoiceOption::Mode(
+					completions::ToolChoiceOptions::Auto,
+				)),
+				(Some(gemini::FunctionCallingMode::None), _) => Some(completions::ToolChoiceOption::Mode(
+					completions::ToolChoiceOptions::None,
+				)),
+				_ => None,
+			});
+
+		let config = generation_config.unwrap_or_default();
+		let response_format = match (
+			config.response_mime_type.as_deref(),
+			config
+				.response_json_schema
+				.or(config.response_schema.map(to_json_schema)),
+		) {
+			(Some("application/json"), Some(schema)) => Some(completions::ResponseFormat::JsonSchema {
+				json_schema: completions::ResponseFormatJsonSchema {
+					description: None,
+					name: "structured_output".to_string(),
+					schema,
+					strict: None,
+				},
+			}),
+			(Some("application/json"), None) => Some(completions::ResponseFormat::JsonObject),
+			_ => None,
+		};
+
+		completions::Request {
+			messages,
+			model,
+			stream: Some(stream),
+			stream_options: if stream {
+				Some(completions::StreamOptions {
+					include_usage: Some(true),
+					include_obfuscation: None,
+				})
+			} else {
+				None
+			},
+			temperature: config.temperature,
+			top_p: config.top_p,
+			max_completion_tokens: config.max_output_tokens,
+			max_tokens: None,
+			stop: config.stop_sequences.map(completions::Stop::StringArray),
+			n: config.candidate_count,
+			seed: config.seed,
+			presence_penalty: config.presence_penalty,
+			frequency_penalty: config.frequency_penalty,
+			tools: if tools.is_empty() { None } else { Some(tools) },
+			tool_choice,
+			response_format,
+			vendor_extensions: completions::RequestVendorExtensions::default(),
+			parallel_tool_calls: None,
+			reasoning_effort: None,
+			user: None,
+			store: None,
+			metadata: None,
+			logit_bias: None,
+			logprobs: None,
+			top_logprobs: None,
+			modalities: None,
+			prediction: None,
+			audio: None,
+			function_call: None,
+			functions: None,
+			service_tier: None,
+			web_search_options: None,
+		}
+	}
+
+	#[derive(Default)]
+	struct CallIds {
+		next: usize,
+		pending: HashMap<String, VecDeque<String>>,
+	}
+
+	impl CallIds {
+		fn call(&mut self, name: &str, id: Option<String>) -> String {
+			let id = id.unwrap_or_else(|| {
+				self.next += 1;
+				format!("call_{}", self.next)
+			});
+			self
+				.pending
+				.entry(name.to_string())
+				.or_default()
+				.push_back(id.clone());
+			id
+		}
+
+		fn response(&mut self, name: &str, id: Option<String>) -> String {
+			let pending = self.pending.get_mut(name);
+			match id {
+				Some(id) => {
+					if let Some(pending) = pending {
+						pending.retain(|p| p != &id);
+					}
+					id
+				},
+				None => pending
+					.and_then(VecDeque::pop_front)
+					.unwrap_or_else(|| name.to_string()),
+			}
+		}
+	}
+
+	fn part_text(parts: &[gemini::Part]) -> String {
+		parts
+			.iter()
+			.filter(|p| !p.thought.unwrap_or_default())
+			.filter_map(|p| p.text.as_deref())
+			.collect::<Vec<_>>()
+			.join("\n")
+	}
+
+	fn user_messages(
+		parts: Vec<gemini::Part>,
+		call_ids: &mut CallIds,
+		messages: &mut Vec<completions::RequestMessage>,
+	) {
+		let mut content: Vec<completions::RequestUserMessageContentPart> = Vec::new();
+		for part in parts {
+			if let Some(resp) = part.function_response {
+				// Tool results must directly follow the assistant turn that requested them.
+				messages.push(completions::RequestMessage::Tool(
+					completions::RequestToolMessage {
+						tool_call_id: call_ids.response(&resp.name, resp.id),
+						content: completions::RequestToolMessageContent::Text(resp.response.to_string()),
+					},
+				));
+			} else if let Some(text) = part.text {
+				content.push(completions::RequestUserMessageContentPart::Text(
+					completions::RequestMessageContentPartText { text },
+				));
+			} else if let Some(url) = image_url(&part) {
+				content.push(completions::RequestUserMessageContentPart::ImageUrl(
+					completions::RequestMessageContentPartImage {
+						image_url: completions::ImageUrl { url, detail: None },
+					},
+				));
+			}
+		}
+		if !content.is_empty() {
+			messages.push(completions::RequestMessage::User(
+				completions::RequestUserMessage {
+					content: completions::RequestUserMessageContent::Array(content),
+					name: None,
+				},
+			));
+		}
+	}
+
+	fn image_url(part: &gemini::Part) -> Option<String> {
+		if let Some(blob) = &part.inline_data
+			&& blob.mime_type.starts_with("image/")
+		{
+			return Some(format!("data:{};base64,{}", blob.mime_type, blob.data));
+		}
+		part
+			.file_data
+			.as_ref()
+			.filter(|f| {
+				f.mime_type
+					.as_deref()
+					.is_none_or(|m| m.starts_with("image/"))
+			})
+			.map(|f| f.file_uri.clone())
+	}
+
+	fn assistant_message(
+		parts: Vec<gemini::Part>,
+		call_ids: &mut CallIds,
+	) -> Option<completions::RequestMessage> {
+		let text = part_text(&parts);
+		let tool_calls: Vec<completions::MessageToolCalls> = parts
+			.into_iter()
+			.filter_map(|p| p.function_call)
+			.map(|call| {
+				completions::MessageToolCalls::Function(completions::MessageToolCall {
+					id: call_ids.call(&call.name, call.id),
+					function: completions::FunctionCall {
+						arguments: call.args.to_string(),
+						name: call.name,
+					},
+				})
+			})
+			.collect();
+		if text.is_empty() && tool_calls.is_empty() {
+			return None;
+		}
+		Some(completions::RequestMessage::Assistant(
+			completions::RequestAssistantMessage {
+				content: (!text.is_empty())
+					.then_some(completions::RequestAssistantMessageContent::Text(text)),
+				tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+				..Default::default()
+			},
+		))
+	}
+
+	/// Gemini's OpenAPI-style schemas spell types in upper case (`OBJECT`, `STRING`, ...), which JSON
+	/// Schema does not accept.
+	fn to_json_schema(mut schema: Value) -> Value {
+		fn lowercase_types(v: &mut Value) {
+			match v {
+				Value::Object(map) => {
+					for (k, v) in map.iter_mut() {
+						match v {
+							Value::String(s) if k == "type" => *s = s.to_ascii_lowercase(),
+							_ => lowercase_types(v),
+						}
+					}
+				},
+				Value::Array(items) => items.iter_mut().for_each(lowercase_types),
+				_ => {},
+			}
+		}
+		lowercase_types(&mut schema);
+		schema
+	}
+
+	/// Translate a chat completions response, already converted from the provider's format, into a
+	/// generateContent response.
+	pub fn translate_response(resp: &dyn ResponseType) -> Result<Box<dyn ResponseType>, AIError> {
+		let bytes = resp.serialize().map_err(AIError::ResponseMarshal)?;
+		let resp = serde_json::from_slice::<completions::Response>(&bytes)
+			.map_err(logged_response_parsing(&bytes))?;
+		Ok(Box::new(translate_response_internal(resp)))
+	}
+
+	fn translate_response_internal(resp: completions::Response) -> gemini::Response {
+		let candidates = resp
+			.choices
+			.into_iter()
+			.map(|choice| {
+				let mut parts = Vec::new();
+				if let Some(reasoning) = choice.message.reasoning_content {
+					parts.push(gemini::Part {
+						thought: Some(true),
+						..gemini::Part::from_text(reasoning)
+					});
+				}
+				if let Some(text) = choice.message.content {
+					parts.push(gemini::Part::from_text(text));
+				}
+				parts.extend(
+					choice
+						.message
+						.tool_calls
+						.into_iter()
+						.flatten()
+						.filter_map(|tc| match tc {
+							completions::MessageToolCalls::Function(f) => Some(function_call_part(
+								Some(f.id),
+								f.function.name,
+								&f.function.arguments,
+							)),
+							completions::MessageToolCalls::Custom(_) => None,
+						}),
+				);
+				gemini::Candidate {
+					content: gemini::Content {
+						role: Some("model".to_string()),
+						parts,
+					},
+					finish_reason: choice.finish_reason.map(finish_reason),
+					index: choice.index,
+				}
+			})
+			.collect();
+		gemini::Response {
+			candidates,
+			usage_metadata: resp.usage.as_ref().map(usage_metadata),
+			model_version: Some(resp.model),
+			response_id: Some(resp.id),
+		}
+	}
+
+	fn function_call_part(id: Option<String>, name: String, arguments: &str) -> gemini::Part {
+		gemini::Part {
+			function_call: Some(gemini::FunctionCall {
+				id,
+				name,
+				args: serde_json::from_str(arguments).unwrap_or_default(),
+			}),
+			..Default::default()
+		}
+	}
+
+	fn finish_reason(reason: completions::FinishReason) -> gemini::FinishReason {
+		match reason {
+			completions::FinishReason::Stop
+			| completions::FinishReason::ToolCalls
+			| completions::FinishReason::FunctionCall => gemini::FinishReason::Stop,
+			completions::FinishReason::Length => gemini::FinishReason::MaxTokens,
+			completions::FinishReason::ContentFilter => gemini::FinishReason::Safety,
+		}
+	}
+
+	fn usage_metadata(usage: &completions::Usage) -> gemini::UsageMetadata {
+		gemini::UsageMetadata {
+			prompt_token_count: usage.prompt_tokens,
+			candidates_token_count: usage.completion_tokens,
+			total_token_count: usage.total_tokens,
+			cached_content_token_count: usage
+				.prompt_tokens_details
+				.as_ref()
+				.and_then(|d| d.cached_tokens)
+				.map(|t| t as u32),
+			thoughts_token_count: usage
+				.completion_tokens_details
+				.as_ref()
+				.and_then(|d| d.reasoning_tokens)
+				.map(|t| t as u32),
+		}
+	}
+
+	/// Translate a chat completions SSE stream into a streamGenerateContent SSE stream (`alt=sse`).
+	/// Telemetry is recorded by the completions stream this wraps.
+	pub fn translate_stream(b: crate::http::Body, buffer_limit: usize) -> crate::http::Body {
+		// Tool call arguments arrive in fragments, but Gemini sends each call whole.
+		// Keyed by (choice index, tool call index).
+		let mut tool_calls: BTreeMap<(u32, u32), (Option<String>, String, String)> = BTreeMap::new();
+		crate::parse::sse::json_transform_multi::<completions::StreamResponse, gemini::Response, _>(
+			b,
+			buffer_limit,
+			move |evt| {
+				let chunk = match evt {
+					SseJsonEvent::Done => return None,
+					SseJsonEvent::Data(Err(e)) => {
+						debug!("failed to parse streaming response: {e}");
+						return None;
+					},
+					SseJsonEvent::Data(Ok(chunk)) => chunk,
+				};
+				let mut candidates = Vec::new();
+				for choice in chunk.choices {
+					let mut parts = Vec::new();
+					if let Some(reasoning) = choice.delta.reasoning_content.filter(|r| !r.is_empty()) {
+						parts.push(gemini::Part {
+							thought: Some(true),
+							..gemini::Part::from_text(reasoning)
+						});
+					}
+					if let Some(text) = choice.delta.content.filter(|t| !t.is_empty()) {
+						parts.push(gemini::Part::from_text(text));
+					}
+					for tc in choice.delta.tool_calls.into_iter().flatten() {
+						let (id, name, args) = tool_calls.entry((choice.index, tc.index)).or_default();
+						if tc.id.is_some() {
+							*id = tc.id;
+						}
+						if let Some(f) = tc.function {
+							name.push_str(f.name.as_deref().unwrap_or_default());
+							args.push_str(f.arguments.as_deref().unwrap_or_default());
+						}
+					}
+					if choice.finish_reason.is_some() {
+						let done: Vec<_> = tool_calls
+							.keys()
+							.filter(|(c, _)| *c == choice.index)
+							.copied()
+							.collect();
+						for key in done {
+							if let Some((id, name, args)) = tool_calls.remove(&key) {
+								parts.push(function_call_part(id, name, &args));
+							}
+						}
+					}
+					if parts.is_empty() && choice.finish_reason.is_none() {
+						continue;
+					}
+					candidates.push(gemini::Candidate {
+						content: gemini::Content {
+							role: Some("model".to_string()),
+							parts,
+						},
+						finish_reason: choice.finish_reason.map(finish_reason),
+						index: choice.index,
+					});
+				}
+				let usage_metadata = chunk.usage.as_ref().map(usage_metadata);
+				if candidates.is_empty() && usage_metadata.is_none() {
+					return None;
+				}
+				Some((
+					"",
+					gemini::Response {
+						candidates,
+						usage_metadata,
+						model_version: Some(chunk.model),
+						response_id: Some(chunk.id),
+					},
+				))
+			},
+		)
+	}
+
+	/// Translate an error response, already converted to the chat completions error format where
+	/// possible, into the Google error format.
+	pub fn translate_error(bytes: &Bytes, status: ::http::StatusCode) -> Result<Bytes, AIError> {
+		let message = serde_json::from_slice::<Value>(bytes)
+			.ok()
+			.and_then(|v| {
+				v.pointer("/error/message")
+					.and_then(Value::as_str)
+					.map(str::to_string)
+			})
+			.unwrap_or_else(|| String::from_utf8_lossy(bytes).into_owned());
+		let m = completions::GoogleErrorResponse {
+			error: completions::GoogleError {
+				code: status.as_u16() as i32,
+				message,
+				status: Some(google_status(status).to_string()),
+			},
+		};
+		Ok(Bytes::from(
+			serde_json::to_vec(&m).map_err(AIError::ResponseMarshal)?,
+		))
+	}
+
+	fn google_status(status: ::http::StatusCode) -> &'static str {
+		match status {
+			::http::StatusCode::BAD_REQUEST => "INVALID_ARGUMENT",
+			::http::StatusCode::UNAUTHORIZED => "UNAUTHENTICATED",
+			::http::StatusCode::FORBIDDEN => "PERMISSION_DENIED",
+			::http::StatusCode::NOT_FOUND => "NOT_FOUND",
+			::http::StatusCode::CONFLICT => "ABORTED",
+			::http::StatusCode::TOO_MANY_REQUESTS => "RESOURCE_EXHAUSTED",
+			::http::StatusCode::NOT_IMPLEMENTED => "UNIMPLEMENTED",
+			::http::StatusCode::SERVICE_UNAVAILABLE => "UNAVAILABLE",
+			::http::StatusCode::GATEWAY_TIMEOUT => "DEADLINE_EXCEEDED",
+			s if s.is_client_error() => "FAILED_PRECONDITION",
+			_ => "INTERNAL",
+		}
+	}
+}
+
 pub fn passthrough_stream(
 	mut log: AmendOnDrop,
 	include_completion_in_log: bool,
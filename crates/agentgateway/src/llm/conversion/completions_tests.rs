@@ -0,0 +1,100 @@
+use serde_json::json;
+
+use super::*;
+
+fn generate_content_request(body: serde_json::Value) -> types::gemini::Request {
+	let mut req: types::gemini::Request = serde_json::from_value(body).unwrap();
+	req.model = Some("gemini-2.5-flash".to_string());
+	req
+}
+
+#[test]
+fn test_generate_content_translation() {
+	let req = generate_content_request(json!({
+		"systemInstruction": {"parts": [{"text": "be brief"}]},
+		"contents": [
+			{"role": "user", "parts": [{"text": "weather in Paris?"}]},
+			{"role": "model", "parts": [{"functionCall": {"name": "get_weather", "args": {"city": "Paris"}}}]},
+			{"role": "user", "parts": [{"functionResponse": {"name": "get_weather", "response": {"temp": 21}}}]}
+		],
+		"tools": [{"functionDeclarations": [{
+			"name": "get_weather",
+			"parameters": {"type": "OBJECT", "properties": {"city": {"type": "STRING"}}}
+		}]}],
+		"toolConfig": {"functionCallingConfig": {"mode": "ANY", "allowedFunctionNames": ["get_weather"]}},
+		"generationConfig": {"maxOutputTokens": 64, "temperature": 0.5, "stopSequences": ["END"]}
+	}));
+
+	let translated = from_generate_content::translate(&req).unwrap();
+	let raw = serde_json::to_value(&translated).unwrap();
+
+	assert_eq!(raw["model"], "gemini-2.5-flash");
+	assert_eq!(raw["max_completion_tokens"], 64);
+	assert_eq!(raw["temperature"], 0.5);
+	assert_eq!(raw["stop"], json!(["END"]));
+	let messages = raw["messages"].as_array().unwrap();
+	assert_eq!(messages.len(), 4);
+	assert_eq!(messages[0]["role"], "system");
+	assert_eq!(messages[1]["role"], "user");
+	assert_eq!(messages[2]["role"], "assistant");
+	let call_id = &messages[2]["tool_calls"][0]["id"];
+	assert_eq!(
+		messages[2]["tool_calls"][0]["function"]["name"],
+		"get_weather"
+	);
+	assert_eq!(messages[3]["role"], "tool");
+	assert_eq!(&messages[3]["tool_call_id"], call_id);
+	assert_eq!(
+		raw["tools"][0]["function"]["parameters"]["properties"]["city"]["type"],
+		"string"
+	);
+	assert_eq!(raw["tool_choice"]["function"]["name"], "get_weather");
+}
+
+#[test]
+fn test_generate_content_response_translation() {
+	let resp: types::completions::Response = serde_json::from_value(json!({
+		"id": "chatcmpl-1",
+		"object": "chat.completion",
+		"created": 1,
+		"model": "gpt-4o",
+		"choices": [{
+			"index": 0,
+			"message": {
+				"role": "assistant",
+				"content": "Paris",
+				"tool_calls": [{"id": "call_1", "type": "function", "function": {"name": "lookup", "arguments": "{\"q\":1}"}}]
+			},
+			"finish_reason": "length"
+		}],
+		"usage": {"prompt_tokens": 5, "completion_tokens": 1, "total_tokens": 6}
+	}))
+	.unwrap();
+
+	let translated = from_generate_content::translate_response(&resp).unwrap();
+	let raw: serde_json::Value = serde_json::from_slice(&translated.serialize().unwrap()).unwrap();
+
+	let candidate = &raw["candidates"][0];
+	assert_eq!(candidate["content"]["role"], "model");
+	assert_eq!(candidate["content"]["parts"][0]["text"], "Paris");
+	assert_eq!(
+		candidate["content"]["parts"][1]["functionCall"],
+		json!({"id": "call_1", "name": "lookup", "args": {"q": 1}})
+	);
+	assert_eq!(candidate["finishReason"], "MAX_TOKENS");
+	assert_eq!(raw["usageMetadata"]["promptTokenCount"], 5);
+	assert_eq!(raw["usageMetadata"]["candidatesTokenCount"], 1);
+	assert_eq!(translated.to_llm_response(false).output_tokens, Some(1));
+}
+
+#[test]
+fn test_generate_content_error_translation() {
+	let body = Bytes::from_static(br#"{"error":{"message":"slow down","type":"rate_limit"}}"#);
+	let translated =
+		from_generate_content::translate_error(&body, ::http::StatusCode::TOO_MANY_REQUESTS).unwrap();
+	let raw: serde_json::Value = serde_json::from_slice(&translated).unwrap();
+	assert_eq!(
+		raw,
+		json!({"error": {"code": 429, "message": "slow down", "status": "RESOURCE_EXHAUSTED"}})
+	);
+}
@@ -162,3 +162,62 @@ pub mod from_embeddings {
 		Ok(Box::new(openai_resp))
 	}
 }
+
+pub mod from_images {
+	use super::*;
+
+	/// The aspect ratios Imagen can generate, as (name, width / height).
+	const ASPECT_RATIOS: [(&str, f64); 5] = [
+		("1:1", 1.0),
+		("3:4", 0.75),
+		("4:3", 4.0 / 3.0),
+		("9:16", 9.0 / 16.0),
+		("16:9", 16.0 / 9.0),
+	];
+
+	pub fn translate(req: &types::images::Request) -> Result<Vec<u8>, AIError> {
+		let vertex_req = types::vertex::ImagePredictRequest {
+			instances: vec![types::vertex::ImageInstance {
+				prompt: req.prompt.clone(),
+			}],
+			parameters: types::vertex::ImageParameters {
+				sample_count: req.n.unwrap_or(1),
+				aspect_ratio: req.size.as_deref().and_then(aspect_ratio),
+			},
+		};
+		serde_json::to_vec(&vertex_req).map_err(AIError::RequestMarshal)
+	}
+
+	/// Imagen takes an aspect ratio rather than a size, so pick the supported ratio closest to the
+	/// requested `WIDTHxHEIGHT`. Unparseable sizes (such as `auto`) use the model default.
+	pub(super) fn aspect_ratio(size: &str) -> Option<&'static str> {
+		let (w, h) = size.split_once('x')?;
+		let (w, h) = (w.trim().parse::<f64>().ok()?, h.trim().parse::<f64>().ok()?);
+		if w <= 0.0 || h <= 0.0 {
+			return None;
+		}
+		let ratio = w / h;
+		ASPECT_RATIOS
+			.iter()
+			.min_by(|(_, a), (_, b)| (a - ratio).abs().total_cmp(&(b - ratio).abs()))
+			.map(|(name, _)| *name)
+	}
+
+	/// Translate an Imagen prediction into the OpenAI images response, with base64 image data.
+	pub fn translate_response(bytes: &[u8]) -> Result<Box<dyn ResponseType>, AIError> {
+		let resp: types::vertex::ImagePredictResponse =
+			serde_json::from_slice(bytes).map_err(logged_response_parsing(bytes))?;
+		let data = resp
+			.predictions
+			.into_iter()
+			// Predictions filtered by responsible AI settings carry no image.
+			.filter_map(|p| p.bytes_base64_encoded)
+			.map(|b64| serde_json::json!({ "b64_json": b64 }))
+			.collect();
+		Ok(Box::new(types::images::Response {
+			data,
+			usage: None,
+			rest: serde_json::json!({ "created": chrono::Utc::now().timestamp() }),
+		}))
+	}
+}
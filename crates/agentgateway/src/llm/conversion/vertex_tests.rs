@@ -121,3 +121,46 @@ fn test_embeddings_response_missing_statistics() {
 
 	assert_eq!(resp.usage.prompt_tokens, 0);
 }
+
+#[test]
+fn test_images_translation() {
+	let req: types::images::Request = serde_json::from_value(json!({
+		"model": "imagen-3.0-generate-002",
+		"prompt": "a lighthouse at dusk",
+		"n": 2,
+		"size": "1792x1024",
+		"quality": "hd"
+	}))
+	.unwrap();
+
+	let translated = from_images::translate(&req).unwrap();
+	let raw: serde_json::Value = serde_json::from_slice(&translated).unwrap();
+	assert_eq!(
+		raw,
+		json!({
+			"instances": [{"prompt": "a lighthouse at dusk"}],
+			"parameters": {"sampleCount": 2, "aspectRatio": "16:9"}
+		})
+	);
+
+	assert_eq!(from_images::aspect_ratio("1024x1024"), Some("1:1"));
+	assert_eq!(from_images::aspect_ratio("1024x1536"), Some("3:4"));
+	assert_eq!(from_images::aspect_ratio("auto"), None);
+}
+
+#[test]
+fn test_images_response_translation() {
+	let body = json!({
+		"predictions": [
+			{"bytesBase64Encoded": "aW1hZ2U=", "mimeType": "image/png"},
+			{"raiFilteredReason": "filtered"}
+		]
+	});
+	let resp = from_images::translate_response(&serde_json::to_vec(&body).unwrap()).unwrap();
+	let llm = resp.to_llm_response(false);
+	assert_eq!(llm.output_images, Some(1));
+
+	let out: serde_json::Value = serde_json::from_slice(&resp.serialize().unwrap()).unwrap();
+	assert_eq!(out["data"], json!([{"b64_json": "aW1hZ2U="}]));
+	assert!(out["created"].is_i64());
+}
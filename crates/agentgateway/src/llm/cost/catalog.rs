@@ -71,6 +71,13 @@ pub struct Model {
 	pub rates: Rates,
 	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	pub tiers: Vec<Tier>,
+	/// Rates for batch API requests, overlaid on the standard rates. Without any, batch requests
+	/// are billed at the usual 50% batch discount.
+	#[serde(default, skip_serializing_if = "Rates::is_empty")]
+	pub batch: Rates,
+	/// Maximum tokens the model accepts in one request, input and output combined.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub context_window: Option<u64>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -91,6 +98,12 @@ pub struct Rates {
 	pub input_audio: Option<Money>,
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	pub output_audio: Option<Money>,
+	/// Price per generated image, rather than per million tokens.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub per_image: Option<Money>,
+	/// Price per second of input audio, rather than per million tokens.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub per_audio_second: Option<Money>,
 }
 
 impl Rates {
@@ -108,6 +121,23 @@ impl Rates {
 			reasoning: pick(&self.reasoning, &delta.reasoning),
 			input_audio: pick(&self.input_audio, &delta.input_audio),
 			output_audio: pick(&self.output_audio, &delta.output_audio),
+			per_image: pick(&self.per_image, &delta.per_image),
+			per_audio_second: pick(&self.per_audio_second, &delta.per_audio_second),
+		}
+	}
+
+	pub fn scale(&self, factor: Decimal) -> Rates {
+		let scale = |m: &Option<Money>| m.as_ref().map(|m| Money(m.0 * factor));
+		Rates {
+			input: scale(&self.input),
+			output: scale(&self.output),
+			cache_read: scale(&self.cache_read),
+			cache_write: scale(&self.cache_write),
+			reasoning: scale(&self.reasoning),
+			input_audio: scale(&self.input_audio),
+			output_audio: scale(&self.output_audio),
+			per_image: scale(&self.per_image),
+			per_audio_second: scale(&self.per_audio_second),
 		}
 	}
 }
@@ -178,6 +208,8 @@ pub struct Usage {
 	pub reasoning: u64,
 	pub input_audio: u64,
 	pub output_audio: u64,
+	pub images: u64,
+	pub audio_seconds: u64,
 }
 
 impl Usage {
@@ -199,9 +231,23 @@ pub struct Breakdown {
 	pub reasoning: Decimal,
 	pub input_audio: Decimal,
 	pub output_audio: Decimal,
+	pub images: Decimal,
+	pub audio_seconds: Decimal,
 }
 
 impl Breakdown {
+	pub fn add(&mut self, other: &Breakdown) {
+		self.input += other.input;
+		self.cache_read += other.cache_read;
+		self.cache_write += other.cache_write;
+		self.output += other.output;
+		self.reasoning += other.reasoning;
+		self.input_audio += other.input_audio;
+		self.output_audio += other.output_audio;
+		self.images += other.images;
+		self.audio_seconds += other.audio_seconds;
+	}
+
 	pub fn total(&self) -> Decimal {
 		self.input
 			+ self.cache_read
@@ -210,6 +256,8 @@ impl Breakdown {
 			+ self.reasoning
 			+ self.input_audio
 			+ self.output_audio
+			+ self.images
+			+ self.audio_seconds
 	}
 }
 
@@ -229,6 +277,9 @@ impl Rates {
 			reasoning: line(usage.reasoning, reasoning_rate) / unit,
 			input_audio: line(usage.input_audio, input_audio_rate) / unit,
 			output_audio: line(usage.output_audio, output_audio_rate) / unit,
+			// Images and audio seconds are priced per unit.
+			images: line(usage.images, self.per_image.as_ref()),
+			audio_seconds: line(usage.audio_seconds, self.per_audio_second.as_ref()),
 		}
 	}
 }
@@ -257,6 +308,15 @@ impl Model {
 			None => self.rates.clone(),
 		}
 	}
+
+	pub(super) fn effective_batch_rates(&self, context_tokens: u64) -> Rates {
+		let rates = self.effective_rates(context_tokens);
+		if self.batch.is_empty() {
+			rates.scale(Decimal::new(5, 1))
+		} else {
+			rates.overlay(&self.batch)
+		}
+	}
 }
 
 fn line(tokens: u64, rate: Option<&Money>) -> Decimal {
@@ -279,7 +339,12 @@ mod tests {
 	}
 
 	fn entry(rates: Rates, tiers: Vec<Tier>) -> Model {
-		Model { rates, tiers }
+		Model {
+			rates,
+			tiers,
+			batch: Rates::default(),
+			context_window: None,
+		}
 	}
 
 	fn tier(context_over: u64, rates: Rates) -> Tier {
@@ -622,4 +687,37 @@ mod tests {
 		};
 		assert_eq!(e.price(&u), d("0.000024975"));
 	}
+
+	#[test]
+	fn per_unit_rates_are_not_per_million() {
+		let rates: Rates = serde_json::from_str(
+			r#"{"input":"5","output":"40","perImage":"0.04","perAudioSecond":"0.0001"}"#,
+		)
+		.unwrap();
+		let e = entry(rates, vec![]);
+		let u = Usage {
+			input: 1000,
+			output: 4160,
+			images: 2,
+			audio_seconds: 90,
+			..Default::default()
+		};
+		let b = e.breakdown(&u);
+		assert_eq!(b.images, d("0.08"));
+		assert_eq!(b.audio_seconds, d("0.009"));
+		assert_eq!(b.total(), d("0.2554"));
+	}
+
+	#[test]
+	fn batch_rates_default_to_half_price() {
+		let mut e = entry(rates("2.5", "10"), vec![tier(100_000, rates("5", "20"))]);
+		assert_eq!(e.effective_batch_rates(0), rates("1.25", "5"));
+		assert_eq!(e.effective_batch_rates(100_001), rates("2.5", "10"));
+
+		e.batch = Rates {
+			output: Some(m("4")),
+			..Default::default()
+		};
+		assert_eq!(e.effective_batch_rates(0), rates("2.5", "4"));
+	}
 }
@@ -13,7 +13,9 @@ use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, warn};
 
-use super::{CacheTokenConvention, LLMInfo, LLMResponse};
+use super::batch::BatchModelUsage;
+use super::policy::response_cache::ResponseCacheStatus;
+use super::{CacheTokenConvention, InputFormat, LLMInfo, LLMResponse};
 use crate::ModelCatalogSource;
 
 mod catalog;
@@ -92,9 +94,61 @@ impl ModelCatalog {
 		self.snapshot.store(Arc::new(snapshot));
 	}
 
+	/// The context window of a model, when the catalog records one.
+	pub fn context_window(&self, provider: &str, model: &str) -> Option<u64> {
+		self
+			.snapshot
+			.load()
+			.catalog
+			.as_ref()?
+			.resolve(provider, model)?
+			.context_window
+	}
+
+	/// Estimate the cost of a request before it is sent, e.g. to compare candidate models.
+	pub fn estimate(
+		&self,
+		provider: &str,
+		model: &str,
+		input_tokens: u64,
+		output_tokens: u64,
+	) -> Option<Decimal> {
+		let resp = LLMResponse {
+			input_tokens: Some(input_tokens),
+			output_tokens: Some(output_tokens),
+			..Default::default()
+		};
+		self
+			.snapshot
+			.load()
+			.project(
+				provider,
+				model,
+				&resp,
+				CacheTokenConvention::InputIncludesCache,
+			)
+			.cost
+			.map(|c| c.total())
+	}
+
 	pub fn project(&self, info: &LLMInfo) -> CostProjection {
+		if info.response.response_cache == Some(ResponseCacheStatus::Hit) {
+			// Responses served from the gateway cache never reach the provider, so they are free.
+			return CostProjection {
+				status: CostLookupStatus::Exact,
+				cost: Some(Breakdown::default()),
+				cost_rates: None,
+			};
+		}
 		let provider = info.request.provider.as_str();
 		let snapshot = self.snapshot.load();
+		if info.request.input_format == InputFormat::Batch {
+			return snapshot.project_batch(
+				provider,
+				&info.response.batch_usage,
+				info.request.cache_convention,
+			);
+		}
 		if let Some(provider_model) = &info.response.provider_model {
 			let projection = snapshot.project_with_missing_trace(
 				provider,
@@ -102,6 +156,7 @@ impl ModelCatalog {
 				&info.response,
 				info.request.cache_convention,
 				false,
+				false,
 			);
 			if projection.status != CostLookupStatus::Missing {
 				return projection;
@@ -174,7 +229,40 @@ impl CatalogSnapshot {
 		resp: &LLMResponse,
 		convention: CacheTokenConvention,
 	) -> CostProjection {
-		self.project_with_missing_trace(provider, model, resp, convention, true)
+		self.project_with_missing_trace(provider, model, resp, convention, true, false)
+	}
+
+	/// Batch results may mix models, so each model's usage is priced separately at batch rates.
+	/// Submitting and polling batches is free; usage is billed when the results are fetched.
+	fn project_batch(
+		&self,
+		provider: &str,
+		usage: &[BatchModelUsage],
+		convention: CacheTokenConvention,
+	) -> CostProjection {
+		if self.catalog.is_none() {
+			return CostProjection::unpriced(CostLookupStatus::NoCatalog);
+		}
+		let mut cost = Breakdown::default();
+		for u in usage {
+			let projection = self.project_with_missing_trace(
+				provider,
+				u.model.as_str(),
+				&u.usage,
+				convention,
+				true,
+				true,
+			);
+			match projection.cost {
+				Some(c) => cost.add(&c),
+				None => return CostProjection::unpriced(projection.status),
+			}
+		}
+		CostProjection {
+			status: CostLookupStatus::Exact,
+			cost: Some(cost),
+			cost_rates: None,
+		}
 	}
 
 	fn project_with_missing_trace(
@@ -184,6 +272,7 @@ impl CatalogSnapshot {
 		resp: &LLMResponse,
 		convention: CacheTokenConvention,
 		trace_missing: bool,
+		batch: bool,
 	) -> CostProjection {
 		let Some(catalog) = self.catalog.as_ref() else {
 			crate::proxy::dtrace::pol_event!(
@@ -219,7 +308,11 @@ impl CatalogSnapshot {
 		// Tier selection must be invariant to cache-read repricing below: the
 		// cache tokens may move between input/cache_read, but their sum is stable.
 		let context_tokens = provisional_usage.context_tokens();
-		let rates = entry.effective_rates(context_tokens);
+		let rates = if batch {
+			entry.effective_batch_rates(context_tokens)
+		} else {
+			entry.effective_rates(context_tokens)
+		};
 		if rates.is_empty() {
 			crate::proxy::dtrace::pol_event!(
 				TRACE_POLICY_KIND,
@@ -256,6 +349,7 @@ impl CatalogSnapshot {
 				"cacheTokenConvention": cache_convention_name(convention),
 				"contextTokens": context_tokens,
 				"pricesCacheRead": prices_cache_read,
+				"batch": batch,
 				"usage": &usage,
 				"rates": cost_rates,
 				"cost": cost,
@@ -322,6 +416,12 @@ pub struct CostRates {
 	#[serde(skip_serializing_if = "Option::is_none")]
 	#[dynamic(rename = "outputAudio")]
 	pub output_audio: Option<f64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[dynamic(rename = "perImage")]
+	pub per_image: Option<f64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[dynamic(rename = "perAudioSecond")]
+	pub per_audio_second: Option<f64>,
 }
 
 impl From<&Rates> for CostRates {
@@ -335,6 +435,8 @@ impl From<&Rates> for CostRates {
 			reasoning: f(&r.reasoning),
 			input_audio: f(&r.input_audio),
 			output_audio: f(&r.output_audio),
+			per_image: f(&r.per_image),
+			per_audio_second: f(&r.per_audio_second),
 		}
 	}
 }
@@ -344,8 +446,13 @@ fn breakdown_f64(d: Decimal) -> f64 {
 }
 
 impl Breakdown {
+	/// The total cost in USD.
+	pub fn total_usd(&self) -> f64 {
+		breakdown_f64(self.total())
+	}
+
 	// (CEL field name, value) pairs. `total` is computed, the rest are stored.
-	fn components(&self) -> [(&'static str, Decimal); 8] {
+	fn components(&self) -> [(&'static str, Decimal); 10] {
 		[
 			("total", self.total()),
 			("input", self.input),
@@ -355,6 +462,8 @@ impl Breakdown {
 			("reasoning", self.reasoning),
 			("inputAudio", self.input_audio),
 			("outputAudio", self.output_audio),
+			("images", self.images),
+			("audioSeconds", self.audio_seconds),
 		]
 	}
 }
@@ -375,6 +484,11 @@ pub struct CostBreakdown {
 	pub input_audio: f64,
 	#[dynamic(rename = "outputAudio")]
 	pub output_audio: f64,
+	#[serde(default)]
+	pub images: f64,
+	#[serde(default)]
+	#[dynamic(rename = "audioSeconds")]
+	pub audio_seconds: f64,
 }
 
 impl From<&Breakdown> for CostBreakdown {
@@ -388,6 +502,8 @@ impl From<&Breakdown> for CostBreakdown {
 			reasoning: breakdown_f64(b.reasoning),
 			input_audio: breakdown_f64(b.input_audio),
 			output_audio: breakdown_f64(b.output_audio),
+			images: breakdown_f64(b.images),
+			audio_seconds: breakdown_f64(b.audio_seconds),
 		}
 	}
 }
@@ -403,13 +519,15 @@ impl From<CostBreakdown> for Breakdown {
 			reasoning: d(b.reasoning),
 			input_audio: d(b.input_audio),
 			output_audio: d(b.output_audio),
+			images: d(b.images),
+			audio_seconds: d(b.audio_seconds),
 		}
 	}
 }
 
 impl ::cel::types::dynamic::DynamicType for Breakdown {
 	fn materialize(&self) -> ::cel::Value<'_> {
-		let mut map = vector_map::VecMap::with_capacity(8);
+		let mut map = vector_map::VecMap::with_capacity(10);
 		for (name, value) in self.components() {
 			map.insert(
 				::cel::objects::KeyRef::from(name),
@@ -619,6 +737,8 @@ fn usage_for(
 		reasoning,
 		input_audio,
 		output_audio,
+		images: resp.output_images.unwrap_or(0),
+		audio_seconds: resp.input_audio_seconds.unwrap_or(0),
 	}
 }
 
@@ -789,6 +909,48 @@ mod tests {
 		assert_eq!(cost, Some(2.0));
 	}
 
+	#[test]
+	fn batch_results_are_priced_per_model_at_batch_rates() {
+		let catalog = model_catalog(&test_catalog("1"));
+		let mut info = test_llm_info("", None);
+		info.request.input_format = crate::llm::InputFormat::Batch;
+		info.response = LLMResponse::default();
+		let p = catalog.project(&info);
+		assert_eq!(p.status, CostLookupStatus::Exact);
+		assert_eq!(p.cost.and_then(|c| c.total().to_f64()), Some(0.0));
+
+		let usage = LLMResponse {
+			input_tokens: Some(1_000_000),
+			output_tokens: Some(500_000),
+			..Default::default()
+		};
+		info.response.batch_usage = vec![
+			BatchModelUsage {
+				model: "my-model".into(),
+				usage: usage.clone(),
+			},
+			BatchModelUsage {
+				model: "my-model".into(),
+				usage: usage.clone(),
+			},
+		];
+		let p = catalog.project(&info);
+		assert_eq!(p.status, CostLookupStatus::Exact);
+		assert_eq!(
+			p.cost.and_then(|c| c.total().to_f64()),
+			Some(2.0),
+			"two requests at half of the standard $2"
+		);
+
+		info.response.batch_usage.push(BatchModelUsage {
+			model: "unknown-model".into(),
+			usage,
+		});
+		let p = catalog.project(&info);
+		assert_eq!(p.status, CostLookupStatus::Missing);
+		assert!(p.cost.is_none());
+	}
+
 	#[test]
 	fn empty_model_catalog_reports_no_catalog() {
 		let catalog = ModelCatalog::default();
@@ -836,6 +998,20 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn project_response_cache_hit_is_free() {
+		let catalog = model_catalog(&test_catalog("1"));
+		let mut info = test_llm_info("my-model", None);
+		info.response.response_cache = Some(ResponseCacheStatus::Hit);
+		let projection = catalog.project(&info);
+		assert_eq!(projection.status, CostLookupStatus::Exact);
+		assert_eq!(projection.cost.and_then(|c| c.total().to_f64()), Some(0.0));
+
+		info.response.response_cache = Some(ResponseCacheStatus::Miss);
+		let projection = catalog.project(&info);
+		assert_eq!(projection.cost.and_then(|c| c.total().to_f64()), Some(2.0));
+	}
+
 	#[test]
 	fn project_keeps_unpriced_provider_model_result() {
 		let catalog = model_catalog(
@@ -68,6 +68,8 @@ fn models_dev_transform(
 			let entry = catalog::Model {
 				rates: models_dev_rates(&cost.rates).with_context(|| format!("{gateway_id}/{model_id}"))?,
 				tiers: models_dev_tiers(&cost.tiers).with_context(|| format!("{gateway_id}/{model_id}"))?,
+				batch: Default::default(),
+				context_window: model.limit.as_ref().and_then(|l| l.context),
 			};
 			if entry.rates.is_empty() && entry.tiers.is_empty() {
 				continue;
@@ -98,6 +100,12 @@ struct ModelsDevModel {
 	#[serde(default)]
 	status: String,
 	cost: Option<ModelsDevCost>,
+	limit: Option<ModelsDevLimit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelsDevLimit {
+	context: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -157,6 +165,8 @@ fn models_dev_rates(rates: &ModelsDevRates) -> anyhow::Result<catalog::Rates> {
 		reasoning: models_dev_money(&rates.reasoning)?,
 		input_audio: models_dev_money(&rates.input_audio)?,
 		output_audio: models_dev_money(&rates.output_audio)?,
+		per_image: None,
+		per_audio_second: None,
 	})
 }
 
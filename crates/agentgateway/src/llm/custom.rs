@@ -63,6 +63,9 @@ pub enum ProviderFormat {
 	AnthropicTokenCount,
 	Realtime,
 	Rerank,
+	ImageGeneration,
+	AudioTranscription,
+	AudioSpeech,
 }
 
 impl ProviderFormat {
@@ -75,6 +78,9 @@ impl ProviderFormat {
 			Self::AnthropicTokenCount => InputFormat::CountTokens,
 			Self::Realtime => InputFormat::Realtime,
 			Self::Rerank => InputFormat::Rerank,
+			Self::ImageGeneration => InputFormat::ImageGeneration,
+			Self::AudioTranscription => InputFormat::AudioTranscription,
+			Self::AudioSpeech => InputFormat::AudioSpeech,
 		}
 	}
 
@@ -87,6 +93,9 @@ impl ProviderFormat {
 			Self::AnthropicTokenCount => RouteType::AnthropicTokenCount,
 			Self::Realtime => RouteType::Realtime,
 			Self::Rerank => RouteType::Rerank,
+			Self::ImageGeneration => RouteType::ImageGeneration,
+			Self::AudioTranscription => RouteType::AudioTranscription,
+			Self::AudioSpeech => RouteType::AudioSpeech,
 		}
 	}
 }
@@ -12,21 +12,24 @@ pub use policy::Policy;
 use rand::RngExt;
 use serde::de::DeserializeOwned;
 use tiktoken_rs::CoreBPE;
-use tiktoken_rs::tokenizer::{Tokenizer, get_tokenizer};
+use tiktoken_rs::tokenizer::Tokenizer;
 
 use crate::http::auth::{AppliedBackendAuthLocation, AwsAuth, AzureAuth, BackendAuth, GcpAuth};
 use crate::http::jwt::Claims;
+use crate::http::outlierdetection::RateLimitHeaders;
 use crate::http::{Body, Request, Response};
 pub use crate::llm::types::{RequestType, ResponseType};
 use crate::proxy::httpproxy::PolicyClient;
 use crate::store::{BackendPolicies, LLMResponsePolicies};
 use crate::telemetry::log::{AsyncLog, RequestLog};
+use crate::telemetry::metrics::PromptTemplateLabels;
 use crate::types::agent::{BackendTrafficPolicy, SimpleBackendReference, Target};
 use crate::types::loadbalancer::{ActiveHandle, EndpointWithInfo};
 use crate::*;
 
 pub mod anthropic;
 pub mod azure;
+pub mod batch;
 pub mod bedrock;
 pub mod copilot;
 pub mod custom;
@@ -38,6 +41,7 @@ pub mod vertex;
 mod conversion;
 pub mod cost;
 pub mod policy;
+pub mod tokenizer;
 mod types;
 
 use policy::streaming_guardrails::GuardedSseBody;
@@ -63,9 +67,68 @@ fn normalize_sse_response_headers(mut resp: Response) -> Response {
 #[serde(rename_all = "camelCase")]
 pub struct AIBackend {
 	pub providers: crate::types::loadbalancer::EndpointSet<NamedAIProvider>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub failover: Option<Failover>,
+}
+
+/// Failover retries a failed request against the next provider of an AI backend, in priority order.
+/// The request is re-translated for each provider, so providers of different kinds can be mixed.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Failover {
+	/// HTTP response status codes that move the request on to the next provider. Defaults to 429
+	/// and all 5xx codes. Connection errors always fail over.
+	#[serde(
+		default,
+		skip_serializing_if = "<[_]>::is_empty",
+		serialize_with = "ser_display_iter",
+		deserialize_with = "crate::http::retry::de_codes"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Vec<std::num::NonZeroU16>"))]
+	pub codes: Box<[::http::StatusCode]>,
+	/// Total number of attempts, including the original request. Defaults to one attempt per provider.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_attempts: Option<std::num::NonZeroU8>,
+}
+
+impl Failover {
+	pub fn matches(&self, status: ::http::StatusCode) -> bool {
+		if self.codes.is_empty() {
+			status == ::http::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+		} else {
+			self.codes.contains(&status)
+		}
+	}
 }
 
 impl AIBackend {
+	/// Select the provider for a failover attempt, skipping providers that were already tried.
+	/// The first attempt uses the regular selection; later attempts take the next untried provider
+	/// in priority order, preferring healthy providers over evicted ones.
+	pub fn select_failover_provider(
+		&self,
+		tried: &[Strng],
+	) -> Option<(Arc<NamedAIProvider>, ActiveHandle)> {
+		if tried.is_empty() {
+			return self.select_provider();
+		}
+		let Some((ep, info)) = self
+			.providers
+			.find_endpoint(|ep, info| (!tried.contains(&ep.name)).then(|| (ep.clone(), info.clone())))
+		else {
+			// Every provider was tried; fall back to the regular selection.
+			return self.select_provider();
+		};
+		let handle = self.providers.start_request(ep.name.clone(), &info);
+		Some((ep, handle))
+	}
+
+	/// Whether there is a provider that was not yet tried.
+	pub fn has_untried_provider(&self, tried: &[Strng]) -> bool {
+		self.providers.any(|ep| !tried.contains(&ep.name))
+	}
+
 	pub fn select_provider(&self) -> Option<(Arc<NamedAIProvider>, ActiveHandle)> {
 		let iter = self.providers.iter();
 		let index = iter.index();
@@ -105,10 +168,20 @@ pub struct NamedAIProvider {
 	/// This comes with the cost of an expensive operation.
 	#[serde(default)]
 	pub tokenize: bool,
+	/// How request tokens are counted for each model, when `tokenize` is set.
+	#[serde(default, skip_serializing_if = "tokenizer::Tokenizers::is_empty")]
+	pub tokenizers: tokenizer::Tokenizers,
 	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	pub inline_policies: Vec<BackendTrafficPolicy>,
 }
 
+impl NamedAIProvider {
+	/// The tokenizers to count request tokens with, if requests are tokenized at all.
+	pub fn tokenizers(&self) -> Option<&tokenizer::Tokenizers> {
+		self.tokenize.then_some(&self.tokenizers)
+	}
+}
+
 #[apply(schema!)]
 #[derive(Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum RouteType {
@@ -133,6 +206,18 @@ pub enum RouteType {
 	AnthropicTokenCount,
 	/// Cohere /v2/rerank (document reranking)
 	Rerank,
+	/// Google /v1beta/models/{model}:generateContent (and :streamGenerateContent)
+	GenerateContent,
+	/// OpenAI /v1/images/generations
+	ImageGeneration,
+	/// OpenAI /v1/audio/transcriptions (multipart audio upload)
+	AudioTranscription,
+	/// OpenAI /v1/audio/speech
+	AudioSpeech,
+	/// OpenAI /v1/batches (batch submission and status)
+	Batches,
+	/// OpenAI /v1/files (batch input uploads and result downloads)
+	Files,
 }
 
 #[apply(schema!)]
@@ -227,26 +312,47 @@ pub enum InputFormat {
 	CountTokens,
 	Detect,
 	Rerank,
+	GenerateContent,
+	Batch,
+	ImageGeneration,
+	AudioTranscription,
+	AudioSpeech,
 }
 
 impl InputFormat {
+	/// The format requests are converted through on their way to and from the provider. Formats
+	/// only accepted at the edge are translated to and from chat completions, and everything
+	/// in between treats them as completions.
+	fn base(self) -> InputFormat {
+		match self {
+			InputFormat::GenerateContent => InputFormat::Completions,
+			format => format,
+		}
+	}
+
 	pub fn supports_prompt_guard(&self) -> bool {
 		match self {
 			InputFormat::Completions => true,
+			InputFormat::GenerateContent => true,
 			InputFormat::Messages => true,
 			InputFormat::Responses => true,
+			// Realtime messages are guarded individually by the websocket proxy.
 			InputFormat::Realtime => false,
 			InputFormat::Embeddings => false,
 			InputFormat::CountTokens => false,
 			InputFormat::Detect => false,
 			InputFormat::Rerank => false,
+			InputFormat::Batch => false,
+			InputFormat::ImageGeneration => true,
+			InputFormat::AudioTranscription => true,
+			InputFormat::AudioSpeech => true,
 		}
 	}
 
 	fn provider_format_preferences(&self) -> &'static [custom::ProviderFormat] {
 		use custom::ProviderFormat::*;
 		match self {
-			InputFormat::Completions => &[Completions, Messages],
+			InputFormat::Completions | InputFormat::GenerateContent => &[Completions, Messages],
 			InputFormat::Messages => &[Messages, Completions],
 			InputFormat::Responses => &[Responses, Completions],
 			InputFormat::Embeddings => &[Embeddings],
@@ -254,6 +360,10 @@ impl InputFormat {
 			InputFormat::CountTokens => &[AnthropicTokenCount],
 			InputFormat::Detect => &[],
 			InputFormat::Rerank => &[Rerank],
+			InputFormat::Batch => &[],
+			InputFormat::ImageGeneration => &[ImageGeneration],
+			InputFormat::AudioTranscription => &[AudioTranscription],
+			InputFormat::AudioSpeech => &[AudioSpeech],
 		}
 	}
 }
@@ -278,6 +388,12 @@ pub struct LLMRequestParams {
 	pub encoding_format: Option<Strng>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub dimensions: Option<u64>,
+	// Batches
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub batch_requests: Option<u64>,
+	/// Id of the file downloaded by a batch file content request.
+	#[serde(skip)]
+	pub batch_output_file: Option<Strng>,
 }
 impl PartialEq for LLMRequestParams {
 	fn eq(&self, _: &Self) -> bool {
@@ -305,7 +421,7 @@ impl LLMInfo {
 	}
 }
 
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LLMResponse {
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub input_tokens: Option<u64>,
@@ -345,6 +461,22 @@ pub struct LLMResponse {
 	#[serde(skip)]
 	// Time to get the first token. Only used for streaming.
 	pub first_token: Option<Instant>,
+	/// Whether the response was served from the response cache. Unset when caching is not enabled.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub response_cache: Option<policy::response_cache::ResponseCacheStatus>,
+	/// Cosine similarity of the closest request in the semantic response cache. Only set when semantic
+	/// caching is enabled and a similar request was cached.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub response_cache_similarity: Option<f32>,
+	/// Usage of each model in a batch result file. Only set for batch requests.
+	#[serde(skip)]
+	pub batch_usage: Vec<batch::BatchModelUsage>,
+	/// The number of images generated.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub output_images: Option<u64>,
+	/// The length of the input audio in seconds, rounded up, for transcriptions.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub input_audio_seconds: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -370,6 +502,17 @@ impl AIProvider {
 				.unwrap_or(custom::Provider::NAME),
 		}
 	}
+	/// The convention the provider reports its remaining rate limit with on each response.
+	pub fn rate_limit_headers(&self) -> Option<RateLimitHeaders> {
+		match self {
+			AIProvider::Anthropic(_) => Some(RateLimitHeaders::Anthropic),
+			AIProvider::OpenAI(_)
+			| AIProvider::Azure(_)
+			| AIProvider::Copilot(_)
+			| AIProvider::Custom(_) => Some(RateLimitHeaders::OpenAI),
+			AIProvider::Gemini(_) | AIProvider::Vertex(_) | AIProvider::Bedrock(_) => None,
+		}
+	}
 	fn default_base_path(&self) -> Option<&'static str> {
 		match self {
 			AIProvider::OpenAI(_) | AIProvider::Copilot(_) => Some(openai::DEFAULT_BASE_PATH),
@@ -394,10 +537,27 @@ impl AIProvider {
 	pub fn supported_formats(&self, request_model: Option<&str>) -> Vec<custom::ProviderFormat> {
 		use custom::ProviderFormat::*;
 		match self {
-			AIProvider::OpenAI(_) => vec![Completions, Responses, Embeddings, Realtime, Rerank],
+			AIProvider::OpenAI(_) => vec![
+				Completions,
+				Responses,
+				Embeddings,
+				Realtime,
+				Rerank,
+				ImageGeneration,
+				AudioTranscription,
+				AudioSpeech,
+			],
 			AIProvider::Copilot(_) => vec![Completions, Responses, Rerank],
 			AIProvider::Azure(p) => {
-				let mut formats = vec![Completions, Responses, Embeddings, Rerank];
+				let mut formats = vec![
+					Completions,
+					Responses,
+					Embeddings,
+					Rerank,
+					ImageGeneration,
+					AudioTranscription,
+					AudioSpeech,
+				];
 				if matches!(p.resource_type, azure::AzureResourceType::Foundry)
 					&& p.is_anthropic_model(request_model)
 				{
@@ -418,7 +578,7 @@ impl AIProvider {
 				let mut formats = if p.is_anthropic_model(request_model) {
 					vec![Messages, AnthropicTokenCount]
 				} else {
-					vec![Completions]
+					vec![Completions, ImageGeneration]
 				};
 				formats.extend([Embeddings, Rerank]);
 				formats
@@ -563,7 +723,10 @@ impl AIProvider {
 		path_prefix: Option<&str>,
 		has_host_override: bool,
 	) -> anyhow::Result<()> {
-		if matches!(route_type, RouteType::Passthrough | RouteType::Detect) {
+		if matches!(
+			route_type,
+			RouteType::Passthrough | RouteType::Detect | RouteType::Batches | RouteType::Files
+		) {
 			if let Some(prefix) = path_prefix {
 				http::modify_req(req, |req| {
 					http::modify_uri(req, |uri| {
@@ -843,12 +1006,13 @@ impl AIProvider {
 		backend_info: &crate::http::auth::BackendInfo,
 		policies: Option<&Policy>,
 		req: Request,
-		tokenize: bool,
+		tokenizers: Option<&tokenizer::Tokenizers>,
 		log: &mut Option<&mut RequestLog>,
 	) -> Result<RequestResult, AIError> {
-		let (parts, mut req) = self
+		let (mut parts, mut req) = self
 			.read_body_and_default_model::<types::completions::Request>(policies, req, log)
 			.await?;
+		policy::structured_output::record(policies, InputFormat::Completions, &req, &mut parts);
 
 		// If a user doesn't request usage, we will not get token information which we need
 		// We always set it.
@@ -872,7 +1036,34 @@ impl AIProvider {
 				InputFormat::Completions,
 				req,
 				parts,
-				tokenize,
+				tokenizers,
+				log,
+			)
+			.await
+	}
+
+	pub async fn process_generate_content_request(
+		&self,
+		backend_info: &crate::http::auth::BackendInfo,
+		policies: Option<&Policy>,
+		req: Request,
+		tokenizers: Option<&tokenizer::Tokenizers>,
+		log: &mut Option<&mut RequestLog>,
+	) -> Result<RequestResult, AIError> {
+		let streaming = req.uri().path().ends_with(":streamGenerateContent");
+		let (parts, mut req) = self
+			.read_body_and_default_model::<types::gemini::Request>(policies, req, log)
+			.await?;
+		req.stream = streaming;
+
+		self
+			.process_request(
+				backend_info,
+				policies,
+				InputFormat::GenerateContent,
+				req,
+				parts,
+				tokenizers,
 				log,
 			)
 			.await
@@ -883,12 +1074,13 @@ impl AIProvider {
 		backend_info: &crate::http::auth::BackendInfo,
 		policies: Option<&Policy>,
 		req: Request,
-		tokenize: bool,
+		tokenizers: Option<&tokenizer::Tokenizers>,
 		log: &mut Option<&mut RequestLog>,
 	) -> Result<RequestResult, AIError> {
-		let (parts, req) = self
+		let (mut parts, req) = self
 			.read_body_and_default_model::<types::messages::Request>(policies, req, log)
 			.await?;
+		policy::structured_output::record(policies, InputFormat::Messages, &req, &mut parts);
 
 		self
 			.process_request(
@@ -897,7 +1089,7 @@ impl AIProvider {
 				InputFormat::Messages,
 				req,
 				parts,
-				tokenize,
+				tokenizers,
 				log,
 			)
 			.await
@@ -908,7 +1100,7 @@ impl AIProvider {
 		backend_info: &crate::http::auth::BackendInfo,
 		policies: Option<&Policy>,
 		req: Request,
-		tokenize: bool,
+		tokenizers: Option<&tokenizer::Tokenizers>,
 		log: &mut Option<&mut RequestLog>,
 	) -> Result<RequestResult, AIError> {
 		let (parts, req) = self
@@ -922,7 +1114,7 @@ impl AIProvider {
 				InputFormat::Embeddings,
 				req,
 				parts,
-				tokenize,
+				tokenizers,
 				log,
 			)
 			.await
@@ -933,7 +1125,7 @@ impl AIProvider {
 		backend_info: &crate::http::auth::BackendInfo,
 		policies: Option<&Policy>,
 		req: Request,
-		tokenize: bool,
+		tokenizers: Option<&tokenizer::Tokenizers>,
 		log: &mut Option<&mut RequestLog>,
 	) -> Result<RequestResult, AIError> {
 		let (parts, req) = self
@@ -947,7 +1139,98 @@ impl AIProvider {
 				InputFormat::Rerank,
 				req,
 				parts,
-				tokenize,
+				tokenizers,
+				log,
+			)
+			.await
+	}
+
+	pub async fn process_image_generation_request(
+		&self,
+		backend_info: &crate::http::auth::BackendInfo,
+		policies: Option<&Policy>,
+		req: Request,
+		tokenizers: Option<&tokenizer::Tokenizers>,
+		log: &mut Option<&mut RequestLog>,
+	) -> Result<RequestResult, AIError> {
+		let (parts, req) = self
+			.read_body_and_default_model::<types::images::Request>(policies, req, log)
+			.await?;
+
+		self
+			.process_request(
+				backend_info,
+				policies,
+				InputFormat::ImageGeneration,
+				req,
+				parts,
+				tokenizers,
+				log,
+			)
+			.await
+	}
+
+	pub async fn process_speech_request(
+		&self,
+		backend_info: &crate::http::auth::BackendInfo,
+		policies: Option<&Policy>,
+		req: Request,
+		tokenizers: Option<&tokenizer::Tokenizers>,
+		log: &mut Option<&mut RequestLog>,
+	) -> Result<RequestResult, AIError> {
+		let (parts, req) = self
+			.read_body_and_default_model::<types::audio::SpeechRequest>(policies, req, log)
+			.await?;
+
+		self
+			.process_request(
+				backend_info,
+				policies,
+				InputFormat::AudioSpeech,
+				req,
+				parts,
+				tokenizers,
+				log,
+			)
+			.await
+	}
+
+	pub async fn process_transcription_request(
+		&self,
+		backend_info: &crate::http::auth::BackendInfo,
+		policies: Option<&Policy>,
+		hreq: Request,
+		log: &mut Option<&mut RequestLog>,
+	) -> Result<RequestResult, AIError> {
+		// Transcriptions upload the audio as multipart form data, so read_body_and_default_model
+		// (which expects JSON) does not apply.
+		let buffer = http::buffer_limit(&hreq);
+		let (parts, body) = hreq.into_parts();
+		let Ok(bytes) = http::read_body_with_limit(body, buffer).await else {
+			return Err(AIError::RequestTooLarge);
+		};
+		let Some(boundary) = types::multipart::Form::boundary(&parts.headers) else {
+			return Err(AIError::MissingField("multipart/form-data body".into()));
+		};
+		let form = types::multipart::Form::parse(boundary, bytes)
+			.await
+			.map_err(AIError::MultipartParsing)?;
+		let mut req = types::audio::TranscriptionRequest::new(form);
+
+		if let Some(provider_model) = &self.override_model() {
+			req.model = Some(provider_model.to_string());
+		} else if req.model.is_none() {
+			return Err(AIError::MissingField("model not specified".into()));
+		}
+
+		self
+			.process_request(
+				backend_info,
+				policies,
+				InputFormat::AudioTranscription,
+				req,
+				parts,
+				None,
 				log,
 			)
 			.await
@@ -958,12 +1241,13 @@ impl AIProvider {
 		backend_info: &crate::http::auth::BackendInfo,
 		policies: Option<&Policy>,
 		req: Request,
-		tokenize: bool,
+		tokenizers: Option<&tokenizer::Tokenizers>,
 		log: &mut Option<&mut RequestLog>,
 	) -> Result<RequestResult, AIError> {
 		let (mut parts, req) = self
 			.read_body_and_default_model::<types::responses::Request>(policies, req, log)
 			.await?;
+		policy::structured_output::record(policies, InputFormat::Responses, &req, &mut parts);
 
 		// Strip client-specific headers that cause AWS signature mismatches for Bedrock
 		if matches!(self, AIProvider::Bedrock(_)) {
@@ -978,7 +1262,7 @@ impl AIProvider {
 				InputFormat::Responses,
 				req,
 				parts,
-				tokenize,
+				tokenizers,
 				log,
 			)
 			.await
@@ -1030,7 +1314,7 @@ impl AIProvider {
 				InputFormat::CountTokens,
 				req,
 				parts,
-				false,
+				None,
 				log,
 			)
 			.await
@@ -1077,12 +1361,20 @@ impl AIProvider {
 				InputFormat::Detect,
 				req,
 				parts,
-				false,
+				None,
 				log,
 			)
 			.await
 	}
 
+	pub async fn process_batch_request(
+		&self,
+		policies: Option<&Policy>,
+		req: Request,
+	) -> Result<RequestResult, AIError> {
+		batch::process_request(self, policies, req).await
+	}
+
 	#[allow(clippy::too_many_arguments)]
 	async fn process_request(
 		&self,
@@ -1091,16 +1383,18 @@ impl AIProvider {
 		original_format: InputFormat,
 		mut req: impl RequestType,
 		mut parts: ::http::request::Parts,
-		tokenize: bool,
+		tokenizers: Option<&tokenizer::Tokenizers>,
 		log: &mut Option<&mut RequestLog>,
 	) -> Result<RequestResult, AIError> {
-		if let Some(p) = policies {
-			// Apply model alias resolution
-			if req.supports_model()
-				&& let Some(model) = req.model()
-				&& let Some(aliased) = p.resolve_model_alias(model.as_str())
-			{
-				*model = aliased.to_string();
+		// Apply model alias resolution and the model allowlist
+		if let Some(p) = policies
+			&& req.supports_model()
+			&& let Some(model) = req.model()
+		{
+			match p.resolve_allowed_model(model.as_str()) {
+				Ok(Some(aliased)) => *model = aliased.to_string(),
+				Ok(None) => {},
+				Err(rejection) => return Ok(RequestResult::Rejected(rejection)),
 			}
 		}
 
@@ -1124,13 +1418,39 @@ impl AIProvider {
 		};
 
 		if let Some(p) = policies {
+			if let Some(templates) = &p.prompt_templates
+				&& original_format.supports_prompt_guard()
+			{
+				// The template's variables are evaluated against the request, claims included.
+				let http_req = ::http::Request::from_parts(parts, Body::empty());
+				let rendered = templates.apply(&mut req, &http_req);
+				parts = http_req.into_parts().0;
+				match rendered {
+					Ok(Some(rendered)) => {
+						if let Some(log) = log.as_mut() {
+							log
+								.metrics
+								.prompt_template_renders
+								.get_or_create(&PromptTemplateLabels {
+									template: rendered.id.clone().into(),
+									version: rendered.version.clone().into(),
+								})
+								.inc();
+							log.llm_prompt_template = Some(rendered);
+						}
+					},
+					Ok(None) => {},
+					Err(rejection) => return Ok(RequestResult::Rejected(rejection)),
+				}
+			}
 			p.apply_prompt_enrichment(&mut req);
 
 			if original_format.supports_prompt_guard() {
 				let http_headers = &parts.headers;
 				let claims = parts.extensions.get::<Claims>().cloned();
+				let mut tokens = policy::tokenize::TokenVault::default();
 				if let Some(dr) = p
-					.apply_prompt_guard(backend_info, &mut req, http_headers, claims)
+					.apply_prompt_guard(backend_info, &mut req, http_headers, claims, &mut tokens)
 					.await
 					.map_err(|e| {
 						warn!("failed to call prompt guard webhook: {e}");
@@ -1138,18 +1458,55 @@ impl AIProvider {
 					})? {
 					return Ok(RequestResult::Rejected(dr));
 				}
+				// Picked up by the response policies to restore the original values.
+				if !tokens.is_empty() {
+					parts.extensions.insert(Arc::new(tokens));
+				}
+				// Picked up by the response policies to validate tool-call arguments.
+				if p
+					.prompt_guard
+					.as_ref()
+					.is_some_and(policy::PromptGuard::validates_tool_arguments)
+				{
+					let tools = policy::tools::DeclaredTools::new(req.get_tools());
+					if !tools.is_empty() {
+						parts.extensions.insert(Arc::new(tools));
+					}
+				}
+			}
+
+			// Checked last, so the count includes enriched prompts and masked content.
+			if let Some(context_window) = &p.context_window
+				&& original_format.supports_prompt_guard()
+				&& let Err(rejection) = context_window.apply(
+					&mut req,
+					self.provider(),
+					tokenizers,
+					log.as_ref().map(|l| l.model_catalog.as_ref()),
+				) {
+				return Ok(RequestResult::Rejected(rejection));
+			}
+
+			// Shadowed once, even if the request is retried.
+			if let Some(shadow) = &p.shadow
+				&& let Some(log) = log.as_mut()
+				&& log.llm_shadow.is_none()
+			{
+				log.llm_shadow = shadow.start(backend_info, original_format, &req);
 			}
 		}
 
-		let mut llm_info = req.to_llm_request(self.provider(), tokenize)?;
+		let mut llm_info = req.to_llm_request(self.provider(), tokenizers)?;
 		if original_format == InputFormat::Detect {
 			types::detect::amend_request_info(&mut llm_info, parts.uri.path());
 		}
 		llm_info.native_format = native_format;
 		llm_info.cache_convention = cache_convention_for(self, native_format, &llm_info.request_model);
-		if let Some(log) = log
-			&& log.cel.cel_context.needs_llm_prompt()
-			&& original_format.supports_prompt_guard()
+		if original_format.supports_prompt_guard()
+			&& (log
+				.as_ref()
+				.is_some_and(|log| log.cel.cel_context.needs_llm_prompt())
+				|| policies.is_some_and(Policy::needs_llm_prompt))
 		{
 			llm_info.prompt = Some(req.get_messages().into());
 		}
@@ -1181,19 +1538,22 @@ impl AIProvider {
 			}
 		} else {
 			match self {
-				AIProvider::Custom(_) => match (original_format, native_format) {
+				AIProvider::Custom(_) => match (original_format.base(), native_format) {
 					(_, None) => req.to_openai()?,
 					(InputFormat::Completions, Some(custom::ProviderFormat::Completions))
 					| (InputFormat::Embeddings, Some(custom::ProviderFormat::Embeddings))
 					| (InputFormat::Rerank, Some(custom::ProviderFormat::Rerank))
 					| (InputFormat::Messages, Some(custom::ProviderFormat::Completions))
-					| (InputFormat::Responses, Some(custom::ProviderFormat::Responses)) => req.to_openai()?,
+					| (InputFormat::Responses, Some(custom::ProviderFormat::Responses))
+					| (InputFormat::ImageGeneration, Some(custom::ProviderFormat::ImageGeneration))
+					| (InputFormat::AudioTranscription, Some(custom::ProviderFormat::AudioTranscription))
+					| (InputFormat::AudioSpeech, Some(custom::ProviderFormat::AudioSpeech)) => req.to_openai()?,
 					(InputFormat::Completions, Some(custom::ProviderFormat::Messages))
 					| (InputFormat::Messages, Some(custom::ProviderFormat::Messages)) => req.to_anthropic()?,
 					(InputFormat::Responses, Some(custom::ProviderFormat::Completions)) => {
 						req.to_openai_chat_completions()?
 					},
-					(InputFormat::CountTokens | InputFormat::Realtime, _) => {
+					(InputFormat::CountTokens | InputFormat::Realtime | InputFormat::GenerateContent, _) => {
 						return Err(AIError::UnsupportedConversion(strng::literal!(
 							"this request format does not use this codepath"
 						)));
@@ -1260,11 +1620,38 @@ impl AIProvider {
 		model_catalog: Option<&Arc<cost::ModelCatalog>>,
 		resp: Response,
 	) -> Result<Response, AIError> {
+		// Batch and file responses are passed through, with usage read from batch output files.
+		if req.input_format == InputFormat::Batch {
+			return batch::process_response(
+				req,
+				rate_limit,
+				req_snapshot,
+				log,
+				model_catalog.cloned(),
+				resp,
+			);
+		}
+		// Speech responses are audio; there is no usage to read, so stream them through as-is.
+		if req.input_format == InputFormat::AudioSpeech {
+			let (parts, body) = resp.into_parts();
+			return Ok(Self::finalize_response(
+				parts,
+				body,
+				req,
+				LLMResponse::default(),
+				model_catalog.map(Arc::as_ref),
+				rate_limit.budgets,
+				&log,
+			));
+		}
 		// Non-success responses are plain JSON, not event-stream data.
 		// Only enter the streaming path for successful responses; errors
 		// fall through to the buffered path where process_error translates them.
 		if req.streaming && resp.status().is_success() {
-			return self
+			let structured_output = rate_limit.structured_output.clone();
+			let model = req.request_model.clone();
+			let buffer_limit = http::response_buffer_limit(&resp);
+			let resp = self
 				.process_streaming(
 					client,
 					req,
@@ -1275,7 +1662,12 @@ impl AIProvider {
 					model_catalog.cloned(),
 					resp,
 				)
-				.await;
+				.await?;
+			// Structured output is only checked once the whole stream has been received.
+			return match structured_output {
+				Some(expected) => expected.check_stream(model, buffer_limit, resp).await,
+				None => Ok(resp),
+			};
 		}
 		let model_catalog = model_catalog.map(Arc::as_ref);
 
@@ -1335,6 +1727,7 @@ impl AIProvider {
 				req,
 				llm_resp,
 				model_catalog,
+				rate_limit.budgets,
 				&log,
 			));
 		}
@@ -1350,6 +1743,7 @@ impl AIProvider {
 					req,
 					LLMResponse::default(),
 					model_catalog,
+					rate_limit.budgets,
 					&log,
 				));
 			}
@@ -1360,6 +1754,7 @@ impl AIProvider {
 				req,
 				llm_resp,
 				model_catalog,
+				rate_limit.budgets,
 				&log,
 			));
 		}
@@ -1375,6 +1770,7 @@ impl AIProvider {
 					req,
 					LLMResponse::default(),
 					model_catalog,
+					rate_limit.budgets,
 					&log,
 				));
 			}
@@ -1385,6 +1781,38 @@ impl AIProvider {
 				req,
 				llm_resp,
 				model_catalog,
+				rate_limit.budgets,
+				&log,
+			));
+		}
+
+		// image generation and transcription have simplified response handling (like embeddings)
+		if matches!(
+			req.input_format,
+			InputFormat::ImageGeneration | InputFormat::AudioTranscription
+		) {
+			parts.headers.remove(header::CONTENT_LENGTH);
+			if !parts.status.is_success() {
+				let body = self.process_error(&req, parts.status, &bytes)?;
+				return Ok(Self::finalize_response(
+					parts,
+					body.into(),
+					req,
+					LLMResponse::default(),
+					model_catalog,
+					rate_limit.budgets,
+					&log,
+				));
+			}
+			let (llm_resp, bytes) =
+				self.process_media_response(&req, bytes, include_completion_in_log)?;
+			return Ok(Self::finalize_response(
+				parts,
+				bytes.into(),
+				req,
+				llm_resp,
+				model_catalog,
+				rate_limit.budgets,
 				&log,
 			));
 		}
@@ -1403,6 +1831,7 @@ impl AIProvider {
 				resp.as_mut(),
 				&prompt_guard_headers,
 				&rate_limit.prompt_guard,
+				rate_limit.declared_tools.as_deref(),
 			)
 			.await
 			.map_err(|e| {
@@ -1412,8 +1841,17 @@ impl AIProvider {
 				return Ok(dr);
 			}
 
+			if let Some(expected) = &rate_limit.structured_output {
+				let check = expected.check_response(req.request_model.clone(), resp.as_ref());
+				parts.extensions.insert(check);
+			}
+
 			let llm_resp = resp.to_llm_response(include_completion_in_log);
-			let body = resp.serialize().map_err(AIError::ResponseParsing)?;
+			let mut body = resp.serialize().map_err(AIError::ResponseParsing)?;
+			// Logged completions keep the tokens; only the client sees the original values.
+			if let Some(tokens) = &rate_limit.pii_tokens {
+				body = tokens.restore_body(body);
+			}
 			(llm_resp, Bytes::copy_from_slice(&body))
 		};
 
@@ -1439,11 +1877,19 @@ impl AIProvider {
 			));
 		let resp = Response::from_parts(parts, body);
 
-		if !rate_limit.local_rate_limit.is_empty() || rate_limit.remote_rate_limit.is_some() {
+		if !rate_limit.local_rate_limit.is_empty()
+			|| rate_limit.remote_rate_limit.is_some()
+			|| !rate_limit.budgets.is_empty()
+		{
+			let cost = resp
+				.extensions()
+				.get::<LLMContext>()
+				.and_then(|ctx| ctx.cost.as_ref())
+				.map(cost::Breakdown::total_usd);
 			let exec = cel::Executor::new_response(req_snapshot.as_deref(), &resp);
 			// In the initial request, we subtracted the approximate request tokens.
 			// Now we should have the real request tokens and the response tokens
-			amend_tokens(rate_limit, &llm_info, exec);
+			amend_tokens(rate_limit, &llm_info, exec, cost);
 		}
 		log.store(Some(llm_info));
 		Ok(resp)
@@ -1455,15 +1901,16 @@ impl AIProvider {
 		req: LLMRequest,
 		llm_resp: LLMResponse,
 		model_catalog: Option<&cost::ModelCatalog>,
+		budgets: Vec<policy::budget::Charge>,
 		log: &AsyncLog<llm::LLMInfo>,
 	) -> Response {
 		let llm_info = LLMInfo::new(req, llm_resp);
-		parts
-			.extensions
-			.insert(crate::cel::LLMContext::from_llm_info(
-				llm_info.clone(),
-				model_catalog,
-			));
+		let ctx = crate::cel::LLMContext::from_llm_info(llm_info.clone(), model_catalog);
+		let cost = ctx.cost.as_ref().map(cost::Breakdown::total_usd);
+		for charge in budgets {
+			charge.settle(cost)
+		}
+		parts.extensions.insert(ctx);
 		log.store(Some(llm_info));
 		Response::from_parts(parts, body)
 	}
@@ -1522,6 +1969,34 @@ impl AIProvider {
 		}
 	}
 
+	fn process_media_response(
+		&self,
+		req: &LLMRequest,
+		bytes: Bytes,
+		include_completion_in_log: bool,
+	) -> Result<(LLMResponse, Bytes), AIError> {
+		match (self, req.input_format) {
+			(AIProvider::Vertex(_), InputFormat::ImageGeneration) => {
+				let translated = conversion::vertex::from_images::translate_response(&bytes)?;
+				let llm_resp = translated.to_llm_response(false);
+				let body = translated.serialize().map_err(AIError::ResponseParsing)?;
+				Ok((llm_resp, Bytes::from(body)))
+			},
+			(_, InputFormat::ImageGeneration) => {
+				let resp = serde_json::from_slice::<types::images::Response>(&bytes)
+					.map_err(logged_response_parsing(&bytes))?;
+				Ok((resp.to_llm_response(false), bytes))
+			},
+			_ => {
+				// `text`, `srt` and `vtt` transcriptions are not JSON and carry no usage.
+				let llm_resp = serde_json::from_slice::<types::audio::TranscriptionResponse>(&bytes)
+					.map(|resp| resp.to_llm_response(include_completion_in_log))
+					.unwrap_or_default();
+				Ok((llm_resp, bytes))
+			},
+		}
+	}
+
 	fn parse_completions_response(bytes: &Bytes) -> Result<Box<dyn ResponseType>, AIError> {
 		Ok(Box::new(
 			serde_json::from_slice::<types::completions::Response>(bytes).map_err(|e| {
@@ -1559,7 +2034,7 @@ impl AIProvider {
 		req: &LLMRequest,
 		bytes: &Bytes,
 	) -> Result<Box<dyn ResponseType>, AIError> {
-		match (req.input_format, req.native_format) {
+		match (req.input_format.base(), req.native_format) {
 			(InputFormat::Completions, Some(custom::ProviderFormat::Completions)) => {
 				Self::parse_completions_response(bytes)
 			},
@@ -1593,7 +2068,7 @@ impl AIProvider {
 		req: &LLMRequest,
 		bytes: &Bytes,
 	) -> Result<Box<dyn ResponseType>, AIError> {
-		match (self, req.input_format) {
+		let resp = match (self, req.input_format.base()) {
 			(_, InputFormat::Detect) => Ok(Box::new(
 				serde_json::from_slice::<types::detect::Response>(bytes)
 					.unwrap_or_else(|_| types::detect::Response::new_raw(bytes.clone())),
@@ -1682,7 +2157,23 @@ impl AIProvider {
 			(_, InputFormat::Rerank) => {
 				unreachable!("Rerank should be handled by process_rerank_response")
 			},
+			(_, InputFormat::GenerateContent) => {
+				unreachable!("GenerateContent is translated through Completions")
+			},
+			(_, InputFormat::Batch) => {
+				unreachable!("Batch should be handled by batch::process_response")
+			},
+			(
+				_,
+				InputFormat::ImageGeneration | InputFormat::AudioTranscription | InputFormat::AudioSpeech,
+			) => {
+				unreachable!("media formats should be handled by process_media_response")
+			},
+		}?;
+		if req.input_format == InputFormat::GenerateContent {
+			return conversion::completions::from_generate_content::translate_response(resp.as_ref());
 		}
+		Ok(resp)
 	}
 
 	#[allow(clippy::too_many_arguments)]
@@ -1759,12 +2250,18 @@ impl AIProvider {
 				streaming: policy::PromptGuardStreamingMode::Enabled,
 				request: vec![],
 				response: response_policies.prompt_guard.clone(),
+				realtime_block: Default::default(),
 			};
-			temp_guard.begin_streaming_response_guard(&client, &prompt_guard_headers)
+			temp_guard.begin_streaming_response_guard(
+				&client,
+				&prompt_guard_headers,
+				response_policies.declared_tools.clone(),
+			)
 		} else {
 			vec![]
 		};
 
+		let pii_tokens = response_policies.pii_tokens.clone();
 		let logger = AmendOnDrop::new(log, response_policies, req_snapshot, model_catalog);
 		let stream_format = match self {
 			AIProvider::Bedrock(_) => "awsEventStream",
@@ -1778,7 +2275,7 @@ impl AIProvider {
 				stream_format.to_string(),
 			)
 		});
-		let translated = match (self, input_format, native_format) {
+		let translated = match (self, input_format.base(), native_format) {
 			(
 				AIProvider::Custom(_),
 				InputFormat::Completions,
@@ -1913,6 +2410,19 @@ impl AIProvider {
 			(_, InputFormat::Rerank, _) => {
 				unreachable!("Rerank should be handled by process_rerank_response")
 			},
+			(_, InputFormat::GenerateContent, _) => {
+				unreachable!("GenerateContent is translated through Completions")
+			},
+			(_, InputFormat::Batch, _) => {
+				unreachable!("Batch should be handled by batch::process_response")
+			},
+			(
+				_,
+				InputFormat::ImageGeneration | InputFormat::AudioTranscription | InputFormat::AudioSpeech,
+				_,
+			) => {
+				unreachable!("media formats should be handled by process_media_response")
+			},
 			(AIProvider::Custom(_), input, native) => {
 				return Err(AIError::UnsupportedConversion(strng::format!(
 					"custom provider cannot translate {native:?} stream to {input:?}"
@@ -1920,11 +2430,25 @@ impl AIProvider {
 			},
 		};
 
-		if !evaluators.is_empty() {
+		// Gemini clients are served by converting the completions stream.
+		let translated = if input_format == InputFormat::GenerateContent {
+			translated
+				.map(|b| conversion::completions::from_generate_content::translate_stream(b, buffer))
+		} else {
+			translated
+		};
+
+		let guarded = if !evaluators.is_empty() {
 			// `logger` is owned by the translated body; pass None to avoid double-logging.
-			return Ok(translated.map(|b| GuardedSseBody::new(b, evaluators, buffer, None)));
+			translated.map(|b| GuardedSseBody::new(b, evaluators, buffer, None))
+		} else {
+			translated
+		};
+		// Restore after the guardrails, so they evaluate the same tokenized text the provider saw.
+		match pii_tokens {
+			Some(tokens) => Ok(guarded.map(|b| policy::tokenize::RestoringSseBody::new(b, tokens))),
+			None => Ok(guarded),
 		}
-		Ok(translated)
 	}
 
 	async fn read_body_and_default_model<T: RequestType + DeserializeOwned>(
@@ -1962,7 +2486,7 @@ impl AIProvider {
 		status: ::http::StatusCode,
 		bytes: &Bytes,
 	) -> Result<Bytes, AIError> {
-		match (self, req.input_format, req.native_format) {
+		let translated = match (self, req.input_format.base(), req.native_format) {
 			(
 				AIProvider::Custom(_),
 				InputFormat::Completions,
@@ -2038,6 +2562,14 @@ impl AIProvider {
 				// Passthrough; nothing needed
 				Ok(bytes.clone())
 			},
+			(
+				_,
+				InputFormat::ImageGeneration | InputFormat::AudioTranscription | InputFormat::AudioSpeech,
+				_,
+			) => {
+				// Passthrough; these endpoints have no translated error format
+				Ok(bytes.clone())
+			},
 			(AIProvider::Anthropic(_), InputFormat::Completions, _) => {
 				conversion::messages::from_completions::translate_error(bytes)
 			},
@@ -2070,7 +2602,11 @@ impl AIProvider {
 			(_, _, _) => Err(AIError::UnsupportedConversion(strng::literal!(
 				"this provider and format is not supported"
 			))),
+		}?;
+		if req.input_format == InputFormat::GenerateContent {
+			return conversion::completions::from_generate_content::translate_error(&translated, status);
 		}
+		Ok(translated)
 	}
 }
 
@@ -2092,29 +2628,7 @@ fn num_tokens_from_messages(
 	model: &str,
 	messages: &[SimpleChatCompletionMessage],
 ) -> Result<u64, AIError> {
-	// NOTE: This estimator only accounts for textual content in normalized messages.
-	// Non-text items in Responses inputs (e.g., tool calls, images, files) are ignored here.
-	// Use provider token counting endpoints if you need precise totals for those cases.
-	let tokenizer = get_tokenizer(model).unwrap_or(Tokenizer::Cl100kBase);
-	if tokenizer != Tokenizer::Cl100kBase && tokenizer != Tokenizer::O200kBase {
-		// Chat completion is only supported chat models
-		return Err(AIError::UnsupportedModel);
-	}
-	let bpe = get_bpe_from_tokenizer(tokenizer);
-
-	let tokens_per_message = 3;
-
-	let mut num_tokens: u64 = 0;
-	for message in messages {
-		num_tokens += tokens_per_message;
-		// Role is always 1 token
-		num_tokens += 1;
-		num_tokens += bpe
-			.encode_with_special_tokens(message.content.as_str())
-			.len() as u64;
-	}
-	num_tokens += 3; // every reply is primed with <|start|>assistant<|message|>
-	Ok(num_tokens)
+	tokenizer::Tokenizers::default().count(model, messages)
 }
 
 /// Tokenizers take about 200ms to load and are lazy loaded. This loads them on demand, outside the
@@ -2179,6 +2693,8 @@ pub enum AIError {
 	PromptWebhookError,
 	#[error("failed to parse request: {0}")]
 	RequestParsing(serde_json::Error),
+	#[error("failed to parse multipart request: {0}")]
+	MultipartParsing(multer::Error),
 	#[error("failed to marshal request: {0}")]
 	RequestMarshal(serde_json::Error),
 	#[error("failed to parse response: {0}")]
@@ -2206,7 +2722,12 @@ fn response_prompt_guard_headers(
 	headers
 }
 
-fn amend_tokens(rate_limit: store::LLMResponsePolicies, llm_resp: &LLMInfo, exec: Executor) {
+fn amend_tokens(
+	rate_limit: store::LLMResponsePolicies,
+	llm_resp: &LLMInfo,
+	exec: Executor,
+	cost: Option<f64>,
+) {
 	let input_mismatch = match (
 		llm_resp.request.input_tokens,
 		llm_resp.response.input_tokens,
@@ -2227,6 +2748,9 @@ fn amend_tokens(rate_limit: store::LLMResponsePolicies, llm_resp: &LLMInfo, exec
 	if let Some(rrl) = rate_limit.remote_rate_limit {
 		rrl.amend_tokens(tokens_to_remove, &exec)
 	}
+	for charge in rate_limit.budgets {
+		charge.settle(cost)
+	}
 }
 
 pub struct AmendOnDrop {
@@ -2255,12 +2779,15 @@ impl AmendOnDrop {
 	}
 	pub fn report_rate_limit(&mut self) {
 		if let Some(pol) = self.pol.take()
-			&& (!pol.local_rate_limit.is_empty() || pol.remote_rate_limit.is_some())
+			&& (!pol.local_rate_limit.is_empty()
+				|| pol.remote_rate_limit.is_some()
+				|| !pol.budgets.is_empty())
 		{
 			self.log.non_atomic_mutate(|r| {
 				let ctx = LLMContext::from_llm_info(r.clone(), self.catalog.as_deref());
+				let cost = ctx.cost.as_ref().map(cost::Breakdown::total_usd);
 				let exec = cel::Executor::new_llm_rate_limit_streaming(self.req.as_deref(), &ctx);
-				amend_tokens(pol, r, exec)
+				amend_tokens(pol, r, exec, cost)
 			});
 		}
 	}
@@ -1,21 +1,37 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use agent_core::prelude::Strng;
 use agent_core::strng;
 use bytes::Bytes;
 use futures_util::stream;
 use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
+use rand::RngExt;
 use rand::seq::IndexedRandom;
+use rust_decimal::Decimal;
 use serde_json::Value;
 
 use crate::http::transformation_cel::TransformationMetadata;
 use crate::http::{self, Request, Response};
+use crate::llm::cost::ModelCatalog;
 use crate::types::agent::{
 	BackendReference, BackendTrafficPolicy, HeaderMatch, HeaderValueMatch, RouteBackendReference,
 	TrafficPolicy,
 };
+use crate::types::loadbalancer::Ewma;
 use crate::{apply, cel, schema_enum};
 
+/// Rough number of bytes per token, used to estimate the size of a request without tokenizing it.
+const BYTES_PER_TOKEN: u64 = 4;
+/// Output tokens assumed for cost estimates when the request does not limit them.
+const DEFAULT_ESTIMATED_OUTPUT_TOKENS: u64 = 1024;
+/// Share of lowest-latency requests sent to a random target, so latency estimates of targets that
+/// are not currently the fastest keep getting refreshed.
+const LATENCY_EXPLORATION_RATIO: f64 = 0.05;
+/// Latency recorded for a failed request that completed faster than this, so a target that fails
+/// fast is never preferred over targets that answer.
+const FAILED_REQUEST_LATENCY: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModelRoute {
@@ -57,6 +73,8 @@ pub enum VirtualModelRouting {
 	Weighted(Vec<WeightedTarget>),
 	Failover { backend_key: Strng },
 	Conditional(Vec<ConditionalTarget>),
+	LowestCost(RankedRouting),
+	LowestLatency(RankedRouting),
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -73,6 +91,114 @@ pub struct ConditionalTarget {
 	pub when: Option<Arc<cel::Expression>>,
 }
 
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankedRouting {
+	pub targets: Vec<RankedTarget>,
+	/// Targets with a lower quality tier are never selected.
+	pub min_tier: u32,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankedTarget {
+	pub model: String,
+	pub tier: u32,
+	/// Provider and model used to price this target in the model catalog.
+	pub provider: Strng,
+	pub provider_model: Strng,
+	/// Moving average of the observed time to first token, in seconds. Failed requests count as slow.
+	pub latency: Arc<Ewma>,
+}
+
+impl RankedTarget {
+	pub fn new(model: String, tier: u32, provider: Strng, provider_model: Strng) -> Self {
+		Self {
+			model,
+			tier,
+			provider,
+			provider_model,
+			latency: Default::default(),
+		}
+	}
+}
+
+impl RankedRouting {
+	/// Targets meeting the tier floor whose context window fits the request. Targets without a
+	/// context window in the catalog are assumed to fit, so a request that is too large for the
+	/// preferred target moves to a larger one.
+	fn eligible<'a>(
+		&'a self,
+		catalog: &ModelCatalog,
+		tokens: u64,
+	) -> impl Iterator<Item = &'a RankedTarget> {
+		self.targets.iter().filter(move |target| {
+			target.tier >= self.min_tier
+				&& catalog
+					.context_window(&target.provider, &target.provider_model)
+					.is_none_or(|window| tokens <= window)
+		})
+	}
+
+	fn lowest_cost(
+		&self,
+		catalog: &ModelCatalog,
+		input_tokens: u64,
+		output_tokens: u64,
+	) -> Option<&RankedTarget> {
+		// Targets the catalog cannot price are only used when no target can be priced.
+		self
+			.eligible(catalog, input_tokens + output_tokens)
+			.min_by_key(|target| {
+				match catalog.estimate(
+					&target.provider,
+					&target.provider_model,
+					input_tokens,
+					output_tokens,
+				) {
+					Some(cost) => (false, cost),
+					None => (true, Decimal::ZERO),
+				}
+			})
+	}
+
+	fn lowest_latency(&self, catalog: &ModelCatalog, tokens: u64) -> Option<&RankedTarget> {
+		if rand::rng().random_bool(LATENCY_EXPLORATION_RATIO) {
+			let eligible = self.eligible(catalog, tokens).collect::<Vec<_>>();
+			return eligible.choose(&mut rand::rng()).copied();
+		}
+		// Targets without observations have a latency of 0, so each target is measured first.
+		self
+			.eligible(catalog, tokens)
+			.min_by(|a, b| a.latency.load().total_cmp(&b.latency.load()))
+	}
+}
+
+/// Records the latency of a request routed by a lowest-latency virtual model.
+#[derive(Debug, Clone)]
+pub struct LatencyObserver(Arc<Ewma>);
+
+impl LatencyObserver {
+	/// Records the time to first token of a request, or its full duration for non-streaming
+	/// responses. Any response other than a success, including rate limits, counts as a failure and
+	/// is raised to `FAILED_REQUEST_LATENCY`, so failing targets lose their place to targets that
+	/// answer.
+	pub fn observe(
+		&self,
+		status: Option<::http::StatusCode>,
+		time_to_first_token: Option<Duration>,
+		duration: Duration,
+	) {
+		let latency = time_to_first_token.unwrap_or(duration);
+		let latency = if status.is_some_and(|s| s.is_success()) {
+			latency
+		} else {
+			latency.max(FAILED_REQUEST_LATENCY)
+		};
+		self.0.record(latency.as_secs_f64());
+	}
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModelRouter {
@@ -85,6 +211,8 @@ pub struct ModelRouter {
 pub struct ResolvedBackend {
 	pub backend: RouteBackendReference,
 	pub route_policies: Vec<TrafficPolicy>,
+	/// Set when the backend was selected by latency, to feed back the observed latency.
+	pub latency_observer: Option<LatencyObserver>,
 }
 
 pub enum ResolveResult {
@@ -118,7 +246,7 @@ impl ModelRouter {
 		}
 	}
 
-	pub async fn resolve(&self, req: &mut Request) -> ResolveResult {
+	pub async fn resolve(&self, req: &mut Request, catalog: &ModelCatalog) -> ResolveResult {
 		if is_model_list_request(req) {
 			return ResolveResult::DirectResponse(self.model_list_response(req));
 		}
@@ -140,7 +268,7 @@ impl ModelRouter {
 			.find(|model| model.name == requested_model.model)
 		{
 			return self
-				.resolve_virtual_model(virtual_model, req, requested_model.location)
+				.resolve_virtual_model(virtual_model, req, requested_model.location, catalog)
 				.await;
 		}
 		tracing::trace!(
@@ -186,7 +314,9 @@ impl ModelRouter {
 		virtual_model: &VirtualModelRoute,
 		req: &mut Request,
 		location: RequestedModelLocation,
+		catalog: &ModelCatalog,
 	) -> ResolveResult {
+		let mut latency_observer = None;
 		let target = match &virtual_model.routing {
 			VirtualModelRouting::Weighted(targets) => {
 				match targets.choose_weighted(&mut rand::rng(), |target| target.weight) {
@@ -209,6 +339,7 @@ impl ModelRouter {
 						inline_policies: vec![],
 					},
 					route_policies: virtual_model.route_policies.clone(),
+					latency_observer: None,
 				});
 			},
 			VirtualModelRouting::Conditional(targets) => {
@@ -233,12 +364,32 @@ impl ModelRouter {
 					},
 				}
 			},
+			VirtualModelRouting::LowestCost(routing) => {
+				let (input_tokens, output_tokens) = estimate_tokens(req, &location);
+				match routing.lowest_cost(catalog, input_tokens, output_tokens) {
+					Some(target) => target.model.clone(),
+					None => return ResolveResult::DirectResponse(no_eligible_target_response(virtual_model)),
+				}
+			},
+			VirtualModelRouting::LowestLatency(routing) => {
+				let (input_tokens, output_tokens) = estimate_tokens(req, &location);
+				match routing.lowest_latency(catalog, input_tokens + output_tokens) {
+					Some(target) => {
+						latency_observer = Some(LatencyObserver(target.latency.clone()));
+						target.model.clone()
+					},
+					None => return ResolveResult::DirectResponse(no_eligible_target_response(virtual_model)),
+				}
+			},
 		};
 		if let Err(resp) = rewrite_request_model(req, location, &target) {
 			return ResolveResult::DirectResponse(*resp);
 		}
 		match self.resolve_concrete_model(&target, true, req) {
-			Some(route) => ResolveResult::Backend(route),
+			Some(route) => ResolveResult::Backend(ResolvedBackend {
+				latency_observer,
+				..route
+			}),
 			None => {
 				tracing::debug!(
 					virtual_model = %virtual_model.name,
@@ -276,10 +427,43 @@ impl ModelRouter {
 				inline_policies: model.backend_policies.clone(),
 			},
 			route_policies: model.route_policies.clone(),
+			latency_observer: None,
 		})
 	}
 }
 
+/// Estimate the input and output tokens of a request from its size and output limit.
+fn estimate_tokens(req: &Request, location: &RequestedModelLocation) -> (u64, u64) {
+	let input_tokens = req
+		.extensions()
+		.get::<cel::BufferedBody>()
+		.map(|body| body.0.len() as u64 / BYTES_PER_TOKEN)
+		.unwrap_or_default();
+	let output_tokens = match location {
+		RequestedModelLocation::Body(body) => {
+			["max_completion_tokens", "max_tokens", "max_output_tokens"]
+				.into_iter()
+				.find_map(|field| body.get(field).and_then(Value::as_u64))
+		},
+		_ => None,
+	};
+	(
+		input_tokens,
+		output_tokens.unwrap_or(DEFAULT_ESTIMATED_OUTPUT_TOKENS),
+	)
+}
+
+fn no_eligible_target_response(virtual_model: &VirtualModelRoute) -> Response {
+	llm_error_response(
+		::http::StatusCode::NOT_FOUND,
+		&format!(
+			"Virtual model {} has no target meeting its minimum tier with a large enough context window",
+			virtual_model.name
+		),
+		"virtual_model_not_resolved",
+	)
+}
+
 fn model_not_found_response() -> Response {
 	llm_error_response(
 		::http::StatusCode::NOT_FOUND,
@@ -296,7 +480,11 @@ fn request_body_too_large_response() -> Response {
 	)
 }
 
-fn llm_error_response(status: ::http::StatusCode, message: &str, code: &str) -> Response {
+pub(crate) fn llm_error_response(
+	status: ::http::StatusCode,
+	message: &str,
+	code: &str,
+) -> Response {
 	::http::Response::builder()
 		.status(status)
 		.header(::http::header::CONTENT_TYPE, "application/json")
@@ -665,6 +853,156 @@ mod tests {
 		);
 	}
 
+	fn ranked(min_tier: u32) -> RankedRouting {
+		RankedRouting {
+			targets: vec![
+				RankedTarget::new("large".into(), 3, "openai".into(), "gpt-large".into()),
+				RankedTarget::new("small".into(), 1, "openai".into(), "gpt-small".into()),
+				RankedTarget::new("unpriced".into(), 3, "openai".into(), "gpt-unknown".into()),
+			],
+			min_tier,
+		}
+	}
+
+	#[test]
+	fn lowest_cost_respects_min_tier() {
+		let catalog = ModelCatalog::default();
+		catalog.replace(
+			crate::llm::cost::CatalogSnapshot::parse(
+				r#"{"providers":{"openai":{"models":{
+					"gpt-large":{"rates":{"input":"10","output":"30"}},
+					"gpt-small":{"rates":{"input":"1","output":"2"}}
+				}}}}"#,
+			)
+			.unwrap(),
+		);
+		let pick = |min_tier| {
+			ranked(min_tier)
+				.lowest_cost(&catalog, 1000, 100)
+				.map(|t| t.model.clone())
+		};
+		assert_eq!(pick(0).as_deref(), Some("small"));
+		assert_eq!(pick(2).as_deref(), Some("large"));
+		assert_eq!(pick(4), None);
+
+		// Without a catalog, the first eligible target is used.
+		let target = ranked(0)
+			.lowest_cost(&ModelCatalog::default(), 1000, 100)
+			.cloned();
+		assert_eq!(target.unwrap().model, "large");
+	}
+
+	#[test]
+	fn lowest_latency_prefers_fastest_observed_target() {
+		let catalog = ModelCatalog::default();
+		let routing = ranked(0);
+		for (target, secs) in routing.targets.iter().zip([2.0, 0.5, 1.0]) {
+			target.latency.record(secs);
+		}
+		let picks = (0..100)
+			.filter_map(|_| routing.lowest_latency(&catalog, 1000))
+			.filter(|t| t.model == "small")
+			.count();
+		// Only the exploration share goes elsewhere.
+		assert!(picks > 70, "{picks}");
+
+		// Unobserved targets are measured first, and targets below the tier floor are never used.
+		let routing = ranked(2);
+		routing.targets[0].latency.record(2.0);
+		let picks = (0..100)
+			.filter_map(|_| routing.lowest_latency(&catalog, 1000))
+			.map(|t| t.model.as_str())
+			.collect::<Vec<_>>();
+		assert!(!picks.contains(&"small"));
+		assert!(picks.iter().filter(|m| **m == "unpriced").count() > 70);
+	}
+
+	#[test]
+	fn lowest_latency_avoids_failing_target() {
+		let catalog = ModelCatalog::default();
+		let routing = ranked(0);
+		let observer = |model: &str| {
+			let target = routing.targets.iter().find(|t| t.model == model).unwrap();
+			LatencyObserver(target.latency.clone())
+		};
+		let ok = Some(::http::StatusCode::OK);
+		observer("large").observe(ok, None, Duration::from_secs(5));
+		observer("unpriced").observe(ok, None, Duration::from_secs(3));
+
+		// The failing target is only picked by exploration once it has been measured.
+		let mut failures = 0;
+		for _ in 0..100 {
+			let target = routing.lowest_latency(&catalog, 1000).unwrap();
+			if target.model == "small" {
+				failures += 1;
+				observer("small").observe(
+					Some(::http::StatusCode::BAD_GATEWAY),
+					None,
+					Duration::from_millis(10),
+				);
+			} else {
+				observer(&target.model).observe(ok, None, Duration::from_secs(3));
+			}
+		}
+		assert!(failures < 20, "{failures}");
+		assert!(routing.targets[1].latency.load() >= 60.0);
+	}
+
+	#[test]
+	fn lowest_latency_prefers_slower_target_over_rate_limited_one() {
+		let catalog = ModelCatalog::default();
+		let routing = RankedRouting {
+			targets: vec![
+				RankedTarget::new("limited".into(), 1, "openai".into(), "gpt-a".into()),
+				RankedTarget::new("slow".into(), 1, "openai".into(), "gpt-b".into()),
+			],
+			min_tier: 0,
+		};
+		let observer = |i: usize| LatencyObserver(routing.targets[i].latency.clone());
+		observer(0).observe(
+			Some(::http::StatusCode::TOO_MANY_REQUESTS),
+			None,
+			Duration::from_millis(5),
+		);
+		observer(1).observe(
+			Some(::http::StatusCode::OK),
+			Some(Duration::from_secs(2)),
+			Duration::from_secs(30),
+		);
+		// The time to first token is recorded, not the duration of the whole stream.
+		assert_eq!(routing.targets[1].latency.load(), 2.0);
+
+		let picks = (0..100)
+			.filter_map(|_| routing.lowest_latency(&catalog, 1000))
+			.filter(|t| t.model == "slow")
+			.count();
+		assert!(picks > 90, "{picks}");
+	}
+
+	#[test]
+	fn ranked_targets_must_fit_the_context_window() {
+		let catalog = ModelCatalog::default();
+		catalog.replace(
+			crate::llm::cost::CatalogSnapshot::parse(
+				r#"{"providers":{"openai":{"models":{
+					"gpt-large":{"rates":{"input":"10","output":"30"},"contextWindow":200000},
+					"gpt-small":{"rates":{"input":"1","output":"2"},"contextWindow":8000}
+				}}}}"#,
+			)
+			.unwrap(),
+		);
+		let pick = |tokens| {
+			ranked(0)
+				.lowest_cost(&catalog, tokens, 100)
+				.map(|t| t.model.clone())
+		};
+		assert_eq!(pick(1000).as_deref(), Some("small"));
+		// Too large for the cheapest target, so the larger one is used.
+		assert_eq!(pick(10_000).as_deref(), Some("large"));
+		// Targets without a known context window are still eligible.
+		assert_eq!(pick(500_000).as_deref(), Some("unpriced"));
+	}
+
 	#[tokio::test]
 	async fn body_bytes_rejects_json_body_over_buffer_limit() {
 		let request_body = br#"{"model":"real-model","messages":[{"role":"user","content":"this part is over the limit"}]}"#;
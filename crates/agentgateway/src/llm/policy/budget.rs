@@ -0,0 +1,331 @@
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use chrono::{DateTime, Datelike, Days, Months, NaiveTime, Utc};
+use quick_cache::sync::Cache;
+
+use crate::cel::Executor;
+use crate::llm::cost::ModelCatalog;
+use crate::llm::tokenizer::Tokenizers;
+use crate::llm::{LLMInfo, LLMRequest, LLMResponse};
+use crate::proxy::ProxyError;
+use crate::telemetry::log_store;
+use crate::*;
+
+#[cfg(test)]
+#[path = "budget_tests.rs"]
+mod tests;
+
+// Spend is tracked in nano-dollars so counters can be updated atomically.
+const NANOS_PER_USD: f64 = 1_000_000_000.0;
+
+fn to_nanos(usd: f64) -> i64 {
+	(usd * NANOS_PER_USD).round() as i64
+}
+
+fn to_usd(nanos: i64) -> f64 {
+	nanos as f64 / NANOS_PER_USD
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(with = "BudgetSpec"))]
+#[derive(serde::Serialize)]
+pub struct Budget {
+	#[serde(skip_serializing)]
+	counters: Arc<Counters>,
+	#[serde(flatten)]
+	pub spec: BudgetSpec,
+}
+
+impl<'de> serde::Deserialize<'de> for Budget {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		BudgetSpec::deserialize(deserializer).map(Budget::from)
+	}
+}
+
+#[apply(schema!)]
+pub struct BudgetSpec {
+	/// Name of the budget. Persisted spend is stored under this name, so renaming a budget
+	/// starts it again from zero.
+	pub name: Strng,
+	/// Maximum spend, in USD, allowed in each window. Each request reserves its projected cost before
+	/// it is sent, so concurrent requests are rejected once their reservations would exceed the limit.
+	/// The input is priced from the tokens counted by the provider, or estimated with the built-in
+	/// tokenizer when the provider does not tokenize requests. The output is priced from `max_tokens`,
+	/// when set; otherwise it is only charged once the response completes. The reservation is then
+	/// replaced with the actual cost.
+	pub limit: f64,
+	/// How often spend resets. Windows are aligned to the start of the UTC day or month.
+	pub window: BudgetWindow,
+	/// CEL expression used to split the budget, one per distinct value.
+	/// For example, `jwt.sub` gives each user their own budget.
+	/// If unset, all requests share a single budget.
+	/// If the expression fails to evaluate, the budget is skipped for the request.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub key: Option<Arc<cel::Expression>>,
+	/// Maximum number of keys to keep in memory. When exceeded, rarely used keys are evicted first.
+	/// Evicted keys are reloaded from the request log database, if one is configured; otherwise
+	/// their spend starts again from zero.
+	#[serde(default = "default_max_keys")]
+	pub max_keys: usize,
+}
+
+fn default_max_keys() -> usize {
+	http::localratelimit::DEFAULT_MAX_KEYS
+}
+
+#[apply(schema!)]
+#[derive(Copy, Eq, PartialEq)]
+pub enum BudgetWindow {
+	/// Spend resets at 00:00 UTC every day.
+	#[serde(rename = "daily")]
+	Daily,
+	/// Spend resets at 00:00 UTC on the first day of every month.
+	#[serde(rename = "monthly")]
+	Monthly,
+}
+
+impl BudgetWindow {
+	/// Returns the start and end of the window containing `now`.
+	pub fn bounds(self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+		let today = now.date_naive();
+		let (start, end) = match self {
+			BudgetWindow::Daily => (today, today + Days::new(1)),
+			BudgetWindow::Monthly => {
+				let start = today.with_day(1).unwrap_or(today);
+				(start, start + Months::new(1))
+			},
+		};
+		(
+			start.and_time(NaiveTime::MIN).and_utc(),
+			end.and_time(NaiveTime::MIN).and_utc(),
+		)
+	}
+}
+
+#[derive(Debug)]
+struct Counters {
+	// Keyed by the budget key and the start of the window (unix seconds), so a new window naturally
+	// starts a new counter and old ones age out of the cache.
+	counters: Cache<(String, i64), Arc<Counter>>,
+}
+
+#[derive(Debug, Default)]
+struct Counter {
+	spent: AtomicI64,
+	loaded: tokio::sync::OnceCell<()>,
+}
+
+impl From<BudgetSpec> for Budget {
+	fn from(spec: BudgetSpec) -> Self {
+		Budget {
+			counters: Arc::new(Counters {
+				counters: Cache::new(spec.max_keys.max(1)),
+			}),
+			spec,
+		}
+	}
+}
+
+impl Budget {
+	fn key(&self, req: &http::Request) -> Option<String> {
+		let Some(expr) = self.spec.key.as_deref() else {
+			return Some(String::new());
+		};
+		let exec = Executor::new_request(req);
+		match exec
+			.eval(expr)
+			.and_then(|v| v.as_string().map_err(Into::into))
+		{
+			Ok(key) => Some(key),
+			Err(e) => {
+				trace!(
+					"budget key evaluation failed, skipping: budget={}, expr={:?}, error={}",
+					self.spec.name, expr, e
+				);
+				None
+			},
+		}
+	}
+
+	async fn counter(&self, key: &str, window_start: DateTime<Utc>) -> Arc<Counter> {
+		let Ok(counter) = self
+			.counters
+			.counters
+			.get_or_insert_with(&(key.to_string(), window_start.timestamp()), || {
+				Ok::<_, Infallible>(Arc::default())
+			});
+		counter
+			.loaded
+			.get_or_init(|| async {
+				if !log_store::enabled() {
+					return;
+				}
+				let request = log_store::LoadSpendRequest {
+					budget: self.spec.name.to_string(),
+					key: key.to_string(),
+					window_start,
+				};
+				match log_store::load_spend(request).await {
+					Ok(spent) => {
+						counter.spent.fetch_add(to_nanos(spent), Ordering::AcqRel);
+					},
+					Err(e) => {
+						warn!(budget=%self.spec.name, "failed to load persisted budget spend: {e}");
+					},
+				}
+			})
+			.await;
+		counter
+	}
+
+	/// Reserve `projected` USD against the budget for this request.
+	/// Returns `None` if the budget does not apply to the request.
+	pub async fn charge(
+		&self,
+		req: &http::Request,
+		projected: f64,
+		now: DateTime<Utc>,
+	) -> Result<Option<Charge>, ProxyError> {
+		let Some(key) = self.key(req) else {
+			return Ok(None);
+		};
+		let (window_start, window_end) = self.spec.window.bounds(now);
+		let counter = self.counter(&key, window_start).await;
+		let reserved = to_nanos(projected).max(0);
+		let limit = to_nanos(self.spec.limit);
+		let prev = counter.spent.fetch_add(reserved, Ordering::AcqRel);
+		if prev >= limit || prev + reserved > limit {
+			counter.spent.fetch_sub(reserved, Ordering::AcqRel);
+			debug!(
+				budget=%self.spec.name,
+				spent=to_usd(prev),
+				projected,
+				limit=self.spec.limit,
+				"budget exhausted"
+			);
+			return Err(ProxyError::BudgetExceeded {
+				budget: self.spec.name.clone(),
+				reset_seconds: (window_end - now).num_seconds().max(0) as u64,
+			});
+		}
+		Ok(Some(Charge {
+			budget: self.spec.name.clone(),
+			key,
+			window_start,
+			counter,
+			reserved,
+			settled: false,
+		}))
+	}
+
+	/// Returns the spend recorded so far for `key` in the window containing `now`.
+	pub async fn spent(&self, key: &str, now: DateTime<Utc>) -> f64 {
+		let (window_start, _) = self.spec.window.bounds(now);
+		to_usd(
+			self
+				.counter(key, window_start)
+				.await
+				.spent
+				.load(Ordering::Acquire),
+		)
+	}
+}
+
+/// Project the cost of an LLM request before it is sent. Input is counted by the provider when it
+/// tokenizes requests, and estimated with the built-in tokenizer otherwise. Output is estimated
+/// from `max_tokens`, when set; otherwise only the input is counted. Unpriced requests project to
+/// zero.
+pub fn project_cost(catalog: &ModelCatalog, llm_req: &LLMRequest) -> f64 {
+	let input_tokens = llm_req.input_tokens.or_else(|| {
+		let prompt = llm_req.prompt.as_deref()?;
+		Tokenizers::default()
+			.count(&llm_req.request_model, prompt)
+			.ok()
+	});
+	let info = LLMInfo::new(
+		llm_req.clone(),
+		LLMResponse {
+			input_tokens,
+			output_tokens: llm_req.params.max_tokens,
+			..Default::default()
+		},
+	);
+	catalog
+		.project(&info)
+		.cost
+		.map(|c| c.total_usd())
+		.unwrap_or_default()
+}
+
+/// Check all budgets for an LLM request, reserving the projected cost against each one.
+/// If any budget is exhausted, reservations already made are released and the request is rejected.
+pub async fn charge_all(
+	budgets: &[Budget],
+	req: &http::Request,
+	llm_req: &LLMRequest,
+	catalog: &ModelCatalog,
+) -> Result<Vec<Charge>, ProxyError> {
+	if budgets.is_empty() {
+		return Ok(Vec::new());
+	}
+	let projected = project_cost(catalog, llm_req);
+	let now = Utc::now();
+	let mut charges = Vec::with_capacity(budgets.len());
+	for budget in budgets {
+		// On error, dropping `charges` releases the earlier reservations.
+		if let Some(charge) = budget.charge(req, projected, now).await? {
+			charges.push(charge);
+		}
+	}
+	Ok(charges)
+}
+
+/// Spend reserved against a budget for an in-flight request.
+/// The reservation is replaced with the actual cost by `settle`; if the request never completes,
+/// dropping the charge releases it.
+#[derive(Debug)]
+pub struct Charge {
+	budget: Strng,
+	key: String,
+	window_start: DateTime<Utc>,
+	counter: Arc<Counter>,
+	reserved: i64,
+	settled: bool,
+}
+
+impl Charge {
+	/// Replace the reservation with the actual cost of the request.
+	/// If the response could not be priced, the reservation is kept as the best estimate.
+	pub fn settle(mut self, cost: Option<f64>) {
+		self.settled = true;
+		let actual = cost.map(to_nanos).unwrap_or(self.reserved).max(0);
+		let delta = actual - self.reserved;
+		if delta != 0 {
+			self.counter.spent.fetch_add(delta, Ordering::AcqRel);
+		}
+		if actual > 0 {
+			log_store::record_spend(log_store::SpendRecord {
+				budget: self.budget.to_string(),
+				key: std::mem::take(&mut self.key),
+				window_start: self.window_start,
+				cost: to_usd(actual),
+			});
+		}
+	}
+}
+
+impl Drop for Charge {
+	fn drop(&mut self) {
+		if !self.settled {
+			self
+				.counter
+				.spent
+				.fetch_sub(self.reserved, Ordering::AcqRel);
+		}
+	}
+}
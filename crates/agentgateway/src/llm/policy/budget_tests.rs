@@ -0,0 +1,201 @@
+use chrono::TimeZone;
+use serde_json::json;
+
+use super::*;
+use crate::llm::cost::CatalogSnapshot;
+
+fn request_for(user: Option<&str>) -> http::Request {
+	let mut rb = ::http::Request::builder().uri("http://example.com/");
+	if let Some(user) = user {
+		rb = rb.header("x-user", user);
+	}
+	rb.body(http::Body::empty()).unwrap()
+}
+
+fn budget(spec: serde_json::Value) -> Budget {
+	serde_json::from_value(spec).unwrap()
+}
+
+fn now() -> DateTime<Utc> {
+	Utc.with_ymd_and_hms(2026, 12, 31, 18, 30, 0).unwrap()
+}
+
+fn llm_request(input_tokens: u64, max_tokens: Option<u64>) -> LLMRequest {
+	LLMRequest {
+		input_tokens: Some(input_tokens),
+		input_format: crate::llm::InputFormat::Completions,
+		native_format: None,
+		cache_convention: crate::llm::CacheTokenConvention::InputIncludesCache,
+		request_model: "my-model".into(),
+		provider: "openai".into(),
+		streaming: false,
+		params: crate::llm::LLMRequestParams {
+			max_tokens,
+			..Default::default()
+		},
+		prompt: None,
+	}
+}
+
+#[test]
+fn window_bounds() {
+	let (start, end) = BudgetWindow::Daily.bounds(now());
+	assert_eq!(start, Utc.with_ymd_and_hms(2026, 12, 31, 0, 0, 0).unwrap());
+	assert_eq!(end, Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap());
+
+	let (start, end) = BudgetWindow::Monthly.bounds(now());
+	assert_eq!(start, Utc.with_ymd_and_hms(2026, 12, 1, 0, 0, 0).unwrap());
+	assert_eq!(end, Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap());
+}
+
+#[tokio::test]
+async fn charge_settles_to_actual_cost() {
+	let b = budget(json!({"name": "team", "limit": 1.0, "window": "daily"}));
+	let req = request_for(None);
+
+	let charge = b.charge(&req, 0.25, now()).await.unwrap().unwrap();
+	assert_eq!(b.spent("", now()).await, 0.25);
+	charge.settle(Some(0.5));
+	assert_eq!(b.spent("", now()).await, 0.5);
+
+	// Unpriced responses keep the reservation.
+	let charge = b.charge(&req, 0.25, now()).await.unwrap().unwrap();
+	charge.settle(None);
+	assert_eq!(b.spent("", now()).await, 0.75);
+}
+
+#[tokio::test]
+async fn dropped_charge_is_released() {
+	let b = budget(json!({"name": "team", "limit": 1.0, "window": "daily"}));
+	let req = request_for(None);
+
+	let charge = b.charge(&req, 0.75, now()).await.unwrap().unwrap();
+	assert!(b.charge(&req, 0.5, now()).await.is_err());
+	drop(charge);
+	assert_eq!(b.spent("", now()).await, 0.0);
+	assert!(b.charge(&req, 0.5, now()).await.is_ok());
+}
+
+#[tokio::test]
+async fn exhausted_budget_rejects_until_next_window() {
+	let b = budget(json!({"name": "team", "limit": 1.0, "window": "daily"}));
+	let req = request_for(None);
+
+	b.charge(&req, 0.0, now())
+		.await
+		.unwrap()
+		.unwrap()
+		.settle(Some(1.0));
+	let err = b.charge(&req, 0.0, now()).await.unwrap_err();
+	let ProxyError::BudgetExceeded {
+		budget,
+		reset_seconds,
+	} = &err
+	else {
+		panic!("expected budget exceeded, got {err:?}");
+	};
+	assert_eq!(budget.as_str(), "team");
+	assert_eq!(*reset_seconds, 19_800);
+
+	let resp = err.into_response_with_grpc(false);
+	assert_eq!(resp.status(), ::http::StatusCode::TOO_MANY_REQUESTS);
+	assert_eq!(resp.headers().get("retry-after").unwrap(), "19800");
+
+	let tomorrow = now() + chrono::Duration::days(1);
+	assert!(b.charge(&req, 0.0, tomorrow).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn keyed_budgets_are_independent() {
+	let b = budget(json!({
+		"name": "per-user",
+		"limit": 1.0,
+		"window": "monthly",
+		"key": "request.headers['x-user']",
+	}));
+	let alice = request_for(Some("alice"));
+	let bob = request_for(Some("bob"));
+
+	b.charge(&alice, 0.0, now())
+		.await
+		.unwrap()
+		.unwrap()
+		.settle(Some(1.0));
+	assert!(b.charge(&alice, 0.0, now()).await.is_err());
+	assert!(b.charge(&bob, 0.0, now()).await.unwrap().is_some());
+
+	// Requests without a key are not subject to the budget.
+	assert!(
+		b.charge(&request_for(None), 0.0, now())
+			.await
+			.unwrap()
+			.is_none()
+	);
+}
+
+#[tokio::test]
+async fn charge_all_projects_from_catalog() {
+	let catalog = ModelCatalog::default();
+	catalog.replace(
+		CatalogSnapshot::parse(
+			r#"{"providers":{"openai":{"models":{"my-model":{"rates":{"input":"1","output":"2"}}}}}}"#,
+		)
+		.unwrap(),
+	);
+	// 1M input tokens at $1/M plus 500k max output tokens at $2/M
+	let llm_req = llm_request(1_000_000, Some(500_000));
+	assert_eq!(project_cost(&catalog, &llm_req), 2.0);
+
+	let budgets = vec![
+		budget(json!({"name": "large", "limit": 10.0, "window": "daily"})),
+		budget(json!({"name": "small", "limit": 1.0, "window": "daily"})),
+	];
+	let req = request_for(None);
+	assert!(
+		charge_all(&budgets, &req, &llm_req, &catalog)
+			.await
+			.is_err()
+	);
+	// The reservation against the larger budget is released when the smaller one rejects.
+	assert_eq!(budgets[0].spent("", Utc::now()).await, 0.0);
+
+	let llm_req = llm_request(500_000, None);
+	let charges = charge_all(&budgets, &req, &llm_req, &catalog)
+		.await
+		.unwrap();
+	assert_eq!(charges.len(), 2);
+	assert_eq!(budgets[1].spent("", Utc::now()).await, 0.5);
+}
+
+#[tokio::test]
+async fn concurrent_requests_cannot_overshoot_the_limit() {
+	let catalog = ModelCatalog::default();
+	catalog.replace(
+		CatalogSnapshot::parse(
+			r#"{"providers":{"openai":{"models":{"my-model":{"rates":{"input":"1000","output":"2000"}}}}}}"#,
+		)
+		.unwrap(),
+	);
+	// Without provider tokenization, the input is estimated from the prompt.
+	let mut llm_req = llm_request(0, None);
+	llm_req.input_tokens = None;
+	llm_req.prompt = Some(Arc::new(vec![crate::llm::SimpleChatCompletionMessage {
+		role: "user".into(),
+		content: "Summarize the quarterly report in three bullet points.".into(),
+	}]));
+	let projected = project_cost(&catalog, &llm_req);
+	assert!(projected > 0.0, "{projected}");
+
+	// One request fits in the budget, two do not.
+	let budgets = vec![budget(json!({
+		"name": "team",
+		"limit": projected * 1.5,
+		"window": "daily",
+	}))];
+	let req = request_for(None);
+	let (first, second) = tokio::join!(
+		charge_all(&budgets, &req, &llm_req, &catalog),
+		charge_all(&budgets, &req, &llm_req, &catalog),
+	);
+	assert_ne!(first.is_ok(), second.is_ok());
+}
@@ -0,0 +1,165 @@
+//! Context-window enforcement.
+//!
+//! Requests are counted before they are forwarded, so those that would not fit the model's
+//! context window are rejected or truncated here rather than failing at the provider.
+
+use ::http::StatusCode;
+
+use crate::http::Response;
+use crate::llm::RequestType;
+use crate::llm::cost::ModelCatalog;
+use crate::llm::model_router::llm_error_response;
+use crate::llm::tokenizer::Tokenizers;
+use crate::*;
+
+#[cfg(test)]
+#[path = "context_window_tests.rs"]
+mod tests;
+
+#[apply(schema!)]
+pub struct ContextWindow {
+	/// Maximum tokens a request may use, including the output tokens it asks for. Defaults to the
+	/// model's `contextWindow` in the model catalog; requests for models without one are not checked.
+	/// Requests are counted with the provider's `tokenizers` when it sets `tokenize`, and with the
+	/// built-in tokenizer of the model family otherwise.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_tokens: Option<u64>,
+	/// How to handle a request that does not fit.
+	#[serde(default)]
+	pub overflow: Overflow,
+}
+
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq)]
+pub enum Overflow {
+	/// Reject the request.
+	#[default]
+	Reject,
+	/// Drop the oldest messages of the conversation until the request fits.
+	DropOldest,
+	/// Drop messages from the middle of the conversation, keeping its start and end.
+	MiddleOut,
+}
+
+/// Roles of messages that answer a tool call, and are dropped along with the call.
+const TOOL_RESULT_ROLES: &[&str] = &["tool", "function_call_output"];
+
+impl ContextWindow {
+	/// Make `req` fit the context window of its model, truncating the conversation when configured
+	/// to. Returns the response to send instead when it cannot be made to fit.
+	pub fn apply(
+		&self,
+		req: &mut dyn RequestType,
+		provider: Strng,
+		tokenizers: Option<&Tokenizers>,
+		catalog: Option<&ModelCatalog>,
+	) -> Result<(), Response> {
+		let model = req.model().clone().unwrap_or_default();
+		let Some(limit) = self
+			.max_tokens
+			.or_else(|| catalog?.context_window(&provider, &model))
+		else {
+			return Ok(());
+		};
+		let output_tokens = req
+			.to_llm_request(provider, None)
+			.ok()
+			.and_then(|r| r.params.max_tokens)
+			.unwrap_or_default();
+		let budget = limit.saturating_sub(output_tokens);
+		let builtin = Tokenizers::default();
+		let tokenizers = tokenizers.unwrap_or(&builtin);
+		let count = |req: &dyn RequestType| {
+			tokenizers
+				.count(&model, &req.get_messages())
+				.inspect_err(|e| debug!("unable to count tokens for {model}: {e}"))
+				.ok()
+		};
+		let Some(tokens) = count(&*req) else {
+			return Ok(());
+		};
+		if tokens <= budget {
+			return Ok(());
+		}
+		if self.overflow != Overflow::Reject {
+			let excess = tokens - budget;
+			req.retain_messages(&|conversation| {
+				let counts = tokenizers
+					.message_counts(&model, conversation)
+					.unwrap_or_default();
+				let messages: Vec<_> = conversation
+					.iter()
+					.zip(counts)
+					.map(|(m, tokens)| (m.role.as_str(), tokens))
+					.collect();
+				self.overflow.select(&messages, excess)
+			});
+			if count(&*req).is_some_and(|tokens| tokens <= budget) {
+				debug!(%model, "truncated request to fit the context window");
+				return Ok(());
+			}
+		}
+		Err(llm_error_response(
+			StatusCode::BAD_REQUEST,
+			&format!(
+				"request uses {tokens} tokens, more than the {budget} available in the context window of model {model}"
+			),
+			"context_length_exceeded",
+		))
+	}
+}
+
+impl Overflow {
+	/// Choose the messages to keep, given each message's role and tokens, so that at least `excess`
+	/// tokens are dropped. The latest message is always kept, so the result may still be too large.
+	fn select(&self, messages: &[(&str, u64)], excess: u64) -> Vec<bool> {
+		let mut keep = vec![true; messages.len()];
+		let is_result = |i: usize| TOOL_RESULT_ROLES.contains(&messages[i].0);
+		let Some(mut latest) = messages.len().checked_sub(1) else {
+			return keep;
+		};
+		// When the latest message is a tool result, the call it answers is kept with it.
+		while latest > 0 && is_result(latest) {
+			latest -= 1;
+		}
+		let order: Vec<usize> = match self {
+			Overflow::Reject => return keep,
+			Overflow::DropOldest => (0..latest).collect(),
+			Overflow::MiddleOut => {
+				// The first message usually holds the task, so it is kept along with the latest one.
+				let mut order: Vec<usize> = (1..latest).collect();
+				order.sort_by_key(|i| (2 * i).abs_diff(latest));
+				order
+			},
+		};
+		let mut dropped = 0;
+		for i in order {
+			if dropped >= excess {
+				break;
+			}
+			keep[i] = false;
+			dropped += messages[i].1;
+		}
+		// A tool call and its results are dropped together.
+		for i in (1..latest).rev() {
+			if !keep[i] && is_result(i) {
+				keep[i - 1] = false;
+			}
+		}
+		for i in 1..latest {
+			if !keep[i - 1] && is_result(i) {
+				keep[i] = false;
+			}
+		}
+		// Some providers require the conversation to start with a user message.
+		if *self == Overflow::DropOldest {
+			for i in 0..latest {
+				if keep[i] && messages[i].0 == "user" {
+					break;
+				}
+				keep[i] = false;
+			}
+		}
+		keep
+	}
+}
@@ -0,0 +1,257 @@
+use serde_json::{Value, json};
+
+use super::*;
+use crate::llm::cost::CatalogSnapshot;
+use crate::llm::types::{completions, messages, responses};
+
+fn policy(value: Value) -> ContextWindow {
+	serde_json::from_value(value).unwrap()
+}
+
+fn long_text() -> String {
+	"The quick brown fox jumps over the lazy dog. ".repeat(20)
+}
+
+fn completions_request() -> completions::Request {
+	serde_json::from_value(json!({
+		"model": "gpt-4o",
+		"messages": [
+			{"role": "system", "content": "Be brief."},
+			{"role": "user", "content": long_text()},
+			{"role": "assistant", "content": "Noted."},
+			{"role": "user", "content": "What now?"}
+		]
+	}))
+	.unwrap()
+}
+
+fn roles(req: &completions::Request) -> Vec<&str> {
+	req.messages.iter().map(|m| m.role.as_str()).collect()
+}
+
+async fn error_code(resp: Response) -> Value {
+	let body = http::read_body_with_limit(resp.into_body(), 1024)
+		.await
+		.unwrap();
+	serde_json::from_slice::<Value>(&body).unwrap()["error"]["code"].clone()
+}
+
+#[test]
+fn drop_oldest_keeps_a_user_message_first() {
+	let messages = [
+		("user", 10),
+		("assistant", 10),
+		("user", 10),
+		("assistant", 10),
+		("user", 10),
+	];
+	assert_eq!(
+		Overflow::DropOldest.select(&messages, 15),
+		vec![false, false, true, true, true]
+	);
+	// Dropping only the first message would start the conversation with the assistant.
+	assert_eq!(
+		Overflow::DropOldest.select(&messages, 5),
+		vec![false, false, true, true, true]
+	);
+	assert_eq!(
+		Overflow::Reject.select(&messages, 15),
+		vec![true; messages.len()]
+	);
+}
+
+#[test]
+fn middle_out_keeps_the_start_and_end() {
+	let messages = [
+		("user", 10),
+		("assistant", 10),
+		("user", 10),
+		("assistant", 10),
+		("user", 10),
+	];
+	assert_eq!(
+		Overflow::MiddleOut.select(&messages, 15),
+		vec![true, false, false, true, true]
+	);
+	// Nothing but the first and latest messages is left to drop.
+	assert_eq!(
+		Overflow::MiddleOut.select(&messages, 1000),
+		vec![true, false, false, false, true]
+	);
+}
+
+#[test]
+fn tool_calls_and_results_are_dropped_together() {
+	let messages = [
+		("user", 10),
+		("assistant", 10),
+		("tool", 10),
+		("tool", 10),
+		("user", 10),
+		("assistant", 10),
+		("user", 10),
+	];
+	// Dropping a result drops its call and the other results of that call.
+	assert_eq!(
+		Overflow::MiddleOut.select(&messages, 5),
+		vec![true, false, false, false, true, true, true]
+	);
+
+	// A trailing tool result keeps the call it answers.
+	let messages = [
+		("user", 10),
+		("assistant", 10),
+		("user", 10),
+		("assistant", 10),
+		("tool", 10),
+	];
+	assert_eq!(
+		Overflow::DropOldest.select(&messages, 1000),
+		vec![false, false, false, true, true]
+	);
+}
+
+#[tokio::test]
+async fn oversized_request_is_rejected() {
+	let mut req = completions_request();
+	let resp = policy(json!({"maxTokens": 100}))
+		.apply(&mut req, strng::literal!("openai"), None, None)
+		.unwrap_err();
+	assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+	assert_eq!(error_code(resp).await, "context_length_exceeded");
+	assert_eq!(req.messages.len(), 4);
+
+	// A request that fits is left alone.
+	let mut req = completions_request();
+	assert!(
+		policy(json!({"maxTokens": 1000}))
+			.apply(&mut req, strng::literal!("openai"), None, None)
+			.is_ok()
+	);
+	assert_eq!(req.messages.len(), 4);
+}
+
+#[test]
+fn requested_output_tokens_count_against_the_window() {
+	let mut req = completions_request();
+	req.max_tokens = Some(900);
+	assert!(
+		policy(json!({"maxTokens": 1000}))
+			.apply(&mut req, strng::literal!("openai"), None, None)
+			.is_err()
+	);
+}
+
+#[test]
+fn oversized_request_is_truncated() {
+	let mut req = completions_request();
+	policy(json!({"maxTokens": 100, "overflow": "dropOldest"}))
+		.apply(&mut req, strng::literal!("openai"), None, None)
+		.unwrap();
+	assert_eq!(roles(&req), vec!["system", "user"]);
+	assert_eq!(req.get_messages()[1].content.as_str(), "What now?");
+
+	// Middle-out keeps the long first message, so the request still does not fit.
+	let mut req = completions_request();
+	let result = policy(json!({"maxTokens": 100, "overflow": "middleOut"})).apply(
+		&mut req,
+		strng::literal!("openai"),
+		None,
+		None,
+	);
+	assert!(result.is_err());
+}
+
+#[test]
+fn limit_defaults_to_the_catalog_context_window() {
+	let catalog = ModelCatalog::default();
+	catalog.replace(
+		CatalogSnapshot::parse(
+			r#"{"providers":{"openai":{"models":{"gpt-4o":{"rates":{"input":"1"},"contextWindow":100}}}}}"#,
+		)
+		.unwrap(),
+	);
+	let reject = policy(json!({}));
+	let mut req = completions_request();
+	assert!(
+		reject
+			.apply(&mut req, strng::literal!("openai"), None, Some(&catalog))
+			.is_err()
+	);
+	// Models the catalog has no window for are not checked.
+	assert!(
+		reject
+			.apply(&mut req, strng::literal!("anthropic"), None, Some(&catalog))
+			.is_ok()
+	);
+	assert!(
+		reject
+			.apply(&mut req, strng::literal!("openai"), None, None)
+			.is_ok()
+	);
+}
+
+#[test]
+fn responses_items_are_truncated() {
+	let mut req: responses::Request = serde_json::from_value(json!({
+		"model": "gpt-4o",
+		"input": [
+			{"role": "developer", "content": "Be brief."},
+			{"role": "user", "content": long_text()},
+			{"type": "function_call", "call_id": "call_1", "name": "lookup", "arguments": "{}"},
+			{"type": "function_call_output", "call_id": "call_1", "output": "done"},
+			{"role": "user", "content": "What now?"}
+		]
+	}))
+	.unwrap();
+	policy(json!({"maxTokens": 100, "overflow": "dropOldest"}))
+		.apply(&mut req, strng::literal!("openai"), None, None)
+		.unwrap();
+	let input = serde_json::to_value(&req).unwrap()["input"].clone();
+	assert_eq!(
+		input,
+		json!([
+			{"role": "developer", "content": "Be brief."},
+			{"role": "user", "content": "What now?"}
+		])
+	);
+}
+
+#[test]
+fn messages_tool_results_are_dropped_with_their_call() {
+	let mut req: messages::Request = serde_json::from_value(json!({
+		"model": "claude-sonnet-4",
+		"max_tokens": 10,
+		"system": "Be brief.",
+		"messages": [
+			{"role": "user", "content": long_text()},
+			{"role": "assistant", "content": [
+				{"type": "tool_use", "id": "toolu_1", "name": "lookup", "input": {}}
+			]},
+			{"role": "user", "content": [
+				{"type": "tool_result", "tool_use_id": "toolu_1", "content": "done"}
+			]},
+			{"role": "assistant", "content": "Looked it up."},
+			{"role": "user", "content": "What now?"}
+		]
+	}))
+	.unwrap();
+	policy(json!({"maxTokens": 100, "overflow": "dropOldest"}))
+		.apply(&mut req, strng::literal!("anthropic"), None, None)
+		.unwrap();
+	// The tool result is not kept as the first message once its call is dropped.
+	let messages = serde_json::to_value(&req).unwrap()["messages"].clone();
+	assert_eq!(messages, json!([{"role": "user", "content": "What now?"}]));
+}
+
+#[test]
+fn configured_tokenizers_count_the_request() {
+	let tokenizers: Tokenizers =
+		serde_json::from_value(json!([{"estimate": {"charsPerToken": 1000.0}}])).unwrap();
+	let mut req = completions_request();
+	assert!(
+		policy(json!({"maxTokens": 100}))
+			.apply(&mut req, strng::literal!("openai"), Some(&tokenizers), None)
+			.is_ok()
+	);
+}
@@ -0,0 +1,195 @@
+use ::http::{Method, header};
+use anyhow::Context as _;
+use serde::Deserialize;
+
+use crate::http::auth::BackendInfo;
+use crate::llm::policy::Judge;
+use crate::llm::{LLMInfo, RequestResult, RouteType};
+use crate::proxy::httpproxy::PolicyClient;
+use crate::telemetry::metrics::{GenAILabels, OutboundCallKind, OutboundCallSubtype};
+use crate::types::agent::{Backend, BackendTarget, ResourceName};
+use crate::*;
+
+const MAX_JUDGE_RESPONSE_BYTES: usize = 1024 * 1024;
+
+/// The verdict returned by the judge.
+#[derive(Debug, Deserialize)]
+pub struct Verdict {
+	pub flagged: bool,
+	/// Exact substrings of the content to mask.
+	#[serde(default)]
+	pub matches: Vec<String>,
+	#[serde(default)]
+	pub reason: Option<String>,
+}
+
+impl Verdict {
+	/// Parse the verdict from the judge's completion. Models without structured output support may
+	/// wrap the JSON in prose or a code fence, so only the outermost object is read.
+	pub fn parse(completion: &str) -> anyhow::Result<Verdict> {
+		let start = completion
+			.find('{')
+			.context("judge returned no JSON verdict")?;
+		let end = completion
+			.rfind('}')
+			.context("judge returned no JSON verdict")?;
+		anyhow::ensure!(start < end, "judge returned no JSON verdict");
+		Ok(serde_json::from_str(&completion[start..=end])?)
+	}
+
+	/// Replace every match in `content` with `<masked>`. Returns None if nothing matched.
+	pub fn mask(&self, content: &str) -> Option<String> {
+		let mut masked = content.to_string();
+		for m in self.matches.iter().filter(|m| !m.is_empty()) {
+			masked = masked.replace(m.as_str(), "<masked>");
+		}
+		(masked != content).then_some(masked)
+	}
+}
+
+fn default_verdict_schema() -> serde_json::Value {
+	serde_json::json!({
+		"type": "object",
+		"properties": {
+			"flagged": {"type": "boolean"},
+			"matches": {"type": "array", "items": {"type": "string"}},
+			"reason": {"type": "string"},
+		},
+		"required": ["flagged", "matches", "reason"],
+		"additionalProperties": false,
+	})
+}
+
+/// Ask the judge for a verdict on `content`. The judge's own cost is recorded in `gen_ai_cost`
+/// under the `guardrail` operation, separately from the request being judged.
+pub async fn send_request(
+	client: &PolicyClient,
+	judge: &Judge,
+	content: &str,
+) -> anyhow::Result<Verdict> {
+	let provider = &judge.provider;
+	let (target, scheme, defaults) = match &judge.host_override {
+		// With an explicit host, TLS and auth come only from the configured policies.
+		Some(target) => (target.clone(), "http", Default::default()),
+		None => (
+			provider
+				.default_connector_target(RouteType::Completions)
+				.context("custom judge providers require a host override")?,
+			"https",
+			provider.default_connector_policies().unwrap_or_default(),
+		),
+	};
+	let schema = judge
+		.verdict_schema
+		.clone()
+		.unwrap_or_else(default_verdict_schema);
+	let body = serde_json::json!({
+		"messages": [
+			{"role": "system", "content": judge.prompt},
+			{"role": "user", "content": content},
+		],
+		"temperature": 0,
+		"response_format": {
+			"type": "json_schema",
+			"json_schema": {"name": "verdict", "schema": schema},
+		},
+	});
+	let req = ::http::Request::builder()
+		.uri(format!("{scheme}://{}/", target.hostport()))
+		.method(Method::POST)
+		.header(header::CONTENT_TYPE, "application/json")
+		.body(http::Body::from(serde_json::to_vec(&body)?))?;
+	let backend_info = BackendInfo {
+		target: BackendTarget::Backend {
+			name: strng::literal!("_guardrail-judge"),
+			namespace: Default::default(),
+			section: None,
+		},
+		call_target: target,
+		inputs: client.inputs.clone(),
+	};
+	let (mut req, llm_req) = match provider
+		.process_completions_request(&backend_info, None, req, false, &mut None)
+		.await?
+	{
+		RequestResult::Success(req, llm_req) => (req, llm_req),
+		RequestResult::Rejected(_) => anyhow::bail!("judge request was rejected"),
+	};
+	provider.setup_request(
+		&mut req,
+		RouteType::Completions,
+		Some(&llm_req),
+		None,
+		None,
+		judge.host_override.is_some(),
+	)?;
+	let resp = client
+		.with_outbound(OutboundCallKind::Policy, OutboundCallSubtype::Guardrail)
+		.call_with_default_policies(
+			req,
+			Backend::Dynamic(
+				ResourceName::new(strng::literal!("_guardrail-judge"), strng::literal!("")),
+				(),
+			),
+			defaults,
+			&judge.policies,
+		)
+		.await?;
+	let (parts, body) = resp.into_parts();
+	let bytes = http::read_body_with_limit(body, MAX_JUDGE_RESPONSE_BYTES).await?;
+	if !parts.status.is_success() {
+		anyhow::bail!("judge provider returned {}", parts.status);
+	}
+	let llm_resp = provider
+		.process_success(&llm_req, &bytes)?
+		.to_llm_response(true);
+	let completion = llm_resp.completion.clone().unwrap_or_default().concat();
+	record_cost(client, LLMInfo::new(llm_req, llm_resp));
+	Verdict::parse(&completion)
+}
+
+fn record_cost(client: &PolicyClient, info: LLMInfo) {
+	let Some(cost) = client.inputs.model_catalog.project(&info).cost else {
+		return;
+	};
+	let labels = GenAILabels {
+		gen_ai_operation_name: strng::literal!("guardrail").into(),
+		gen_ai_system: info.request.provider.clone().into(),
+		gen_ai_request_model: info.request.request_model.clone().into(),
+		gen_ai_response_model: info.response.provider_model.clone().into(),
+		..Default::default()
+	};
+	client
+		.inputs
+		.metrics
+		.gen_ai_cost
+		.get_or_create(&labels)
+		.inc_by(cost.total_usd());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn verdict_is_read_from_the_completion() {
+		let v = Verdict::parse(
+			"Sure:\n```json\n{\"flagged\": true, \"matches\": [\"Acme\"], \"reason\": \"names a customer\"}\n```",
+		)
+		.unwrap();
+		assert!(v.flagged);
+		assert_eq!(v.reason.as_deref(), Some("names a customer"));
+		assert_eq!(
+			v.mask("Acme and Acme Corp").as_deref(),
+			Some("<masked> and <masked> Corp")
+		);
+		assert_eq!(v.mask("nothing here"), None);
+
+		let v = Verdict::parse("{\"flagged\": false}").unwrap();
+		assert!(!v.flagged);
+		assert!(v.matches.is_empty());
+
+		assert!(Verdict::parse("I cannot judge this").is_err());
+		assert!(Verdict::parse("{\"matches\": []}").is_err());
+	}
+}
@@ -8,7 +8,10 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use crate::http::filters::HeaderModifier;
 use crate::http::jwt::Claims;
 use crate::http::{Response, StatusCode, auth};
+use crate::llm::policy::tokenize::TokenVault;
+use crate::llm::policy::tools::DeclaredTools;
 use crate::llm::policy::webhook::{MaskActionBody, RequestAction, ResponseAction};
+use crate::llm::types::ToolCall;
 use crate::llm::{AIError, RequestType, ResponseType};
 use crate::proxy::httpproxy::PolicyClient;
 use crate::telemetry::log::RequestLog;
@@ -21,13 +24,23 @@ pub mod webhook;
 
 mod azure_content_safety;
 mod bedrock_guardrails;
+pub mod budget;
+pub mod context_window;
 mod google_model_armor;
+mod judge;
 mod moderation;
 mod pii;
+pub mod prompt_template;
+pub mod response_cache;
+pub mod semantic_cache;
+pub mod shadow;
 pub mod streaming_guardrails;
+pub mod structured_output;
 #[cfg(test)]
 #[path = "tests.rs"]
 mod tests;
+pub mod tokenize;
+pub mod tools;
 
 /// Routes stored in a deterministic order: **longest key to shortest key**, with `"*"` always last.
 ///
@@ -125,6 +138,9 @@ pub struct Policy {
 	/// Messages to add before or after the client prompt.
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	pub prompts: Option<PromptEnrichment>,
+	/// Named prompt templates clients can reference, rendered into the request by the gateway.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub prompt_templates: Option<prompt_template::PromptTemplates>,
 	/// Model name aliases that rewrite requested model names.
 	#[serde(
 		rename = "modelAliases",
@@ -137,9 +153,17 @@ pub struct Policy {
 	/// Wrapped in Arc to avoid cloning compiled regex during policy merging.
 	#[serde(skip)]
 	pub wildcard_patterns: Arc<Vec<(ModelAliasPattern, Strng)>>,
+	/// Models clients may request, checked after model aliases are applied. A `*` at the start or
+	/// end of an entry matches any prefix or suffix. All models are allowed when unset.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub allowed_models: Vec<Strng>,
 	/// Prompt caching settings for providers that support cache markers.
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	pub prompt_caching: Option<PromptCachingConfig>,
+	/// Cache complete LLM responses in the gateway, serving identical requests without calling
+	/// the provider.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub response_cache: Option<response_cache::ResponseCache>,
 	/// Route type overrides selected by request path suffix.
 	#[serde(default, skip_serializing_if = "SortedRoutes::is_empty")]
 	#[cfg_attr(
@@ -147,6 +171,22 @@ pub struct Policy {
 		schemars(with = "std::collections::HashMap<String, crate::llm::RouteType>")
 	)]
 	pub routes: SortedRoutes,
+	/// Spend budgets, in USD, enforced using costs from the model catalog.
+	/// Requests are rejected once any applicable budget is exhausted.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub budgets: Vec<budget::Budget>,
+	/// Validate output against the JSON schema the client requested, re-issuing the request when
+	/// the model returns output that does not match.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub structured_output: Option<structured_output::StructuredOutput>,
+	/// Check that requests fit the model's context window before forwarding them, rejecting or
+	/// truncating those that do not.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub context_window: Option<context_window::ContextWindow>,
+	/// Replay a sample of requests to a candidate model in the background, recording its results
+	/// next to the original response in the request log store.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub shadow: Option<shadow::Shadow>,
 }
 
 /// Wildcard pattern converted to regex for model name matching.
@@ -244,6 +284,9 @@ pub struct PromptGuard {
 	/// Guards applied to LLM responses before they reach the client.
 	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	pub response: Vec<ResponseGuard>,
+	/// What to do with a realtime websocket session when a guard blocks a message.
+	#[serde(default, skip_serializing_if = "RealtimeBlockAction::is_default")]
+	pub realtime_block: RealtimeBlockAction,
 }
 
 #[apply(schema!)]
@@ -268,6 +311,32 @@ impl PromptGuardStreamingMode {
 	}
 }
 
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq)]
+pub enum RealtimeBlockAction {
+	/// Drop the blocked message, notify the client with an error event and keep the session open.
+	#[default]
+	Drop,
+	/// Close the websocket session with both the client and the provider.
+	Close,
+}
+
+impl RealtimeBlockAction {
+	pub(crate) fn is_default(&self) -> bool {
+		*self == Self::Drop
+	}
+}
+
+/// The result of applying request guards to text carried by a realtime websocket event.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RealtimeGuardOutcome {
+	Allow,
+	/// A guard masked part of the text; the event should be forwarded with the redacted text.
+	Masked(String),
+	/// A guard rejected the text; holds the rejection body.
+	Rejected(Bytes),
+}
+
 enum GuardrailOutcome {
 	None,
 	Masked,
@@ -293,6 +362,20 @@ pub trait StreamingEvaluator: Send {
 	/// Evaluate a text window. Returns `Some(Blocked)` if the content should be blocked.
 	async fn evaluate(&mut self, window: &str) -> anyhow::Result<Option<StreamingGuardrailOutcome>>;
 
+	/// Whether the evaluator checks tool calls, which the driver then assembles from the stream.
+	fn checks_tool_calls(&self) -> bool {
+		false
+	}
+
+	/// Evaluate tool calls completed since the last evaluation. Returns `Some(Blocked)` if the
+	/// content should be blocked.
+	async fn evaluate_tool_calls(
+		&mut self,
+		_calls: &[ToolCall],
+	) -> anyhow::Result<Option<StreamingGuardrailOutcome>> {
+		Ok(None)
+	}
+
 	/// Returns the failure mode to apply when `evaluate` returns an error.
 	/// Guard types without an explicit `failure_mode` field default to `FailOpen`.
 	fn failure_mode(&self) -> FailureMode {
@@ -308,6 +391,7 @@ pub enum StreamingGuardrailOutcome {
 
 struct TextResponse {
 	content: String,
+	tool_calls: Vec<ToolCall>,
 }
 
 impl crate::llm::ResponseType for TextResponse {
@@ -337,6 +421,10 @@ impl crate::llm::ResponseType for TextResponse {
 	fn serialize(&self) -> serde_json::Result<Vec<u8>> {
 		serde_json::to_vec(&self.to_webhook_choices())
 	}
+
+	fn tool_calls(&self) -> Vec<ToolCall> {
+		self.tool_calls.clone()
+	}
 }
 
 /// Adapter that wraps plain text extracted from a realtime WebSocket event as a `RequestType`.
@@ -365,7 +453,7 @@ impl crate::llm::RequestType for TextRequest {
 	fn to_llm_request(
 		&self,
 		_: agent_core::prelude::Strng,
-		_: bool,
+		_: Option<&crate::llm::tokenizer::Tokenizers>,
 	) -> Result<crate::llm::LLMRequest, crate::llm::AIError> {
 		unimplemented!("TextRequest does not support to_llm_request")
 	}
@@ -387,19 +475,24 @@ impl crate::llm::RequestType for TextRequest {
 impl PromptGuard {
 	/// Apply request guards to a plain-text string extracted from a realtime WebSocket frame.
 	///
-	/// Returns the rejection body if the content should be blocked. Masking outcomes are
-	/// treated as pass because the realtime path cannot rewrite WebSocket frames in place.
+	/// Returns the rejection body if the content should be blocked, or the redacted text if a
+	/// guard masked it so the caller can rewrite the event before forwarding it.
 	pub async fn apply_realtime_request_guards(
 		&self,
 		text: &str,
 		client: &crate::proxy::httpproxy::PolicyClient,
-	) -> Option<Bytes> {
+	) -> RealtimeGuardOutcome {
 		let headers = ::http::HeaderMap::new();
+		let mut masked = false;
 		let mut req = TextRequest {
 			content: text.to_string(),
 		};
 		for g in &self.request {
-			match Policy::apply_single_request_guard(g, &mut req, &headers, client, None).await {
+			// Tokenized content cannot be restored in the realtime path either, so the vault is discarded.
+			let mut tokens = TokenVault::default();
+			match Policy::apply_single_request_guard(g, &mut req, &headers, client, None, &mut tokens)
+				.await
+			{
 				Ok(GuardrailOutcome::Rejected(rejected)) => {
 					Policy::record_guardrail_trip(
 						client,
@@ -412,10 +505,10 @@ impl PromptGuard {
 						.await
 						.map(|b| b.to_bytes())
 						.unwrap_or_else(|_| g.rejection.body.clone());
-					return Some(body);
+					return RealtimeGuardOutcome::Rejected(body);
 				},
-				// Masking is not supported in the realtime path; treat as pass but still record.
 				Ok(GuardrailOutcome::Masked) => {
+					masked = true;
 					Policy::record_guardrail_trip(
 						client,
 						crate::telemetry::metrics::GuardrailPhase::Request,
@@ -444,7 +537,7 @@ impl PromptGuard {
 							crate::telemetry::metrics::GuardrailPhase::Request,
 							crate::telemetry::metrics::GuardrailAction::Reject,
 						);
-						return Some(g.rejection.body.clone());
+						return RealtimeGuardOutcome::Rejected(g.rejection.body.clone());
 					},
 					FailureMode::FailOpen => {
 						tracing::warn!("request guard error in realtime path, failing open: {e}");
@@ -457,7 +550,11 @@ impl PromptGuard {
 				},
 			}
 		}
-		None
+		if masked {
+			RealtimeGuardOutcome::Masked(req.content)
+		} else {
+			RealtimeGuardOutcome::Allow
+		}
 	}
 
 	/// Returns `true` if there is at least one response guard configured.
@@ -465,6 +562,15 @@ impl PromptGuard {
 		!self.response.is_empty()
 	}
 
+	/// Returns `true` if a response guard validates tool-call arguments, and so needs the tools the
+	/// request declared.
+	pub fn validates_tool_arguments(&self) -> bool {
+		self
+			.response
+			.iter()
+			.any(|g| matches!(&g.kind, ResponseGuardKind::Tools(t) if t.validate_arguments))
+	}
+
 	/// Build one `StreamingEvaluator` per configured response guard.
 	///
 	/// Each evaluator is a stateless wrapper around the existing non-streaming
@@ -473,11 +579,19 @@ impl PromptGuard {
 		&self,
 		client: &crate::proxy::httpproxy::PolicyClient,
 		http_headers: &HeaderMap,
+		declared_tools: Option<Arc<DeclaredTools>>,
 	) -> Vec<Box<dyn StreamingEvaluator>> {
 		self
 			.response
 			.iter()
-			.map(|g| streaming_guardrails::make_evaluator(g, client.clone(), http_headers.clone()))
+			.map(|g| {
+				streaming_guardrails::make_evaluator(
+					g,
+					client.clone(),
+					http_headers.clone(),
+					declared_tools.clone(),
+				)
+			})
 			.collect()
 	}
 
@@ -492,8 +606,9 @@ impl PromptGuard {
 		}
 		let mut resp = TextResponse {
 			content: window.to_string(),
+			tool_calls: Vec::new(),
 		};
-		match Policy::apply_single_response_guard(guard, &mut resp, http_headers, client).await? {
+		match Policy::apply_single_response_guard(guard, &mut resp, http_headers, client, None).await? {
 			GuardrailOutcome::Rejected(rejected) => {
 				let body = rejected.into_body().collect().await?.to_bytes();
 				Ok(Some(StreamingGuardrailOutcome::Blocked(body)))
@@ -509,6 +624,30 @@ impl PromptGuard {
 			GuardrailOutcome::FailOpen => Ok(None),
 		}
 	}
+
+	/// Check tool calls assembled from a streaming response against a tools guard. Calls whose
+	/// arguments would be masked are blocked, as streamed calls cannot be rewritten.
+	pub async fn evaluate_streaming_tool_calls(
+		guard: &ResponseGuard,
+		calls: &[ToolCall],
+		declared_tools: Option<&DeclaredTools>,
+	) -> anyhow::Result<Option<StreamingGuardrailOutcome>> {
+		let ResponseGuardKind::Tools(t) = &guard.kind else {
+			return Ok(None);
+		};
+		let mut resp = TextResponse {
+			content: String::new(),
+			tool_calls: calls.to_vec(),
+		};
+		let rejected =
+			match Policy::apply_tools_response(&mut resp, &guard.rejection, t, declared_tools)? {
+				GuardrailOutcome::Rejected(rejected) => rejected,
+				GuardrailOutcome::Masked => guard.rejection.as_response(),
+				GuardrailOutcome::None | GuardrailOutcome::FailOpen => return Ok(None),
+			};
+		let body = rejected.into_body().collect().await?.to_bytes();
+		Ok(Some(StreamingGuardrailOutcome::Blocked(body)))
+	}
 }
 
 impl Policy {
@@ -578,6 +717,35 @@ impl Policy {
 		None
 	}
 
+	pub fn model_allowed(&self, model: &str) -> bool {
+		self.allowed_models.is_empty()
+			|| self.allowed_models.iter().any(|pattern| {
+				if pattern.as_str() == "*" {
+					true
+				} else if let Some(prefix) = pattern.strip_suffix('*') {
+					model.starts_with(prefix)
+				} else if let Some(suffix) = pattern.strip_prefix('*') {
+					model.ends_with(suffix)
+				} else {
+					pattern.as_str() == model
+				}
+			})
+	}
+
+	/// Resolve the model sent to the provider, applying aliases and the model allowlist.
+	pub fn resolve_allowed_model(&self, model: &str) -> Result<Option<Strng>, Response> {
+		let aliased = self.resolve_model_alias(model);
+		let effective = aliased.map(Strng::as_str).unwrap_or(model);
+		if !self.model_allowed(effective) {
+			return Err(crate::llm::model_router::llm_error_response(
+				StatusCode::FORBIDDEN,
+				&format!("model '{effective}' is not allowed"),
+				"model_not_allowed",
+			));
+		}
+		Ok(aliased.cloned())
+	}
+
 	pub fn apply_prompt_enrichment(&self, chat: &mut dyn RequestType) {
 		if let Some(prompts) = &self.prompts {
 			if !prompts.prepend.is_empty() {
@@ -598,7 +766,7 @@ impl Policy {
 				wildcard = Some(*rt);
 				continue;
 			}
-			if path.ends_with(path_suffix.as_str()) {
+			if Self::route_matches(path, path_suffix.as_str()) {
 				return *rt;
 			}
 		}
@@ -606,10 +774,36 @@ impl Policy {
 		wildcard.unwrap_or(crate::llm::RouteType::Completions)
 	}
 
+	/// Route keys match the end of the path. A `*` within a key matches a single path segment, so
+	/// `/v1/batches/*` matches `/v1/batches/batch_abc` but not `/v1/batches/batch_abc/cancel`.
+	fn route_matches(path: &str, key: &str) -> bool {
+		let Some((prefix, suffix)) = key.split_once('*') else {
+			return path.ends_with(key);
+		};
+		let Some(rest) = path.strip_suffix(suffix) else {
+			return false;
+		};
+		let Some(slash) = rest.rfind('/') else {
+			return false;
+		};
+		let (before, segment) = rest.split_at(slash + 1);
+		!segment.is_empty() && before.ends_with(prefix)
+	}
+
 	pub fn has_request_body_mutations(&self) -> bool {
 		self.defaults.is_some() || self.overrides.is_some() || self.transformations.is_some()
 	}
 
+	/// Whether the prompt must be recorded on the LLM request for these policies to apply.
+	/// Budgets estimate the input tokens from it when the provider does not tokenize requests.
+	pub fn needs_llm_prompt(&self) -> bool {
+		!self.budgets.is_empty()
+			|| self
+				.response_cache
+				.as_ref()
+				.is_some_and(|c| c.spec.semantic.is_some())
+	}
+
 	pub fn unmarshal_request<T: DeserializeOwned>(
 		&self,
 		bytes: &Bytes,
@@ -659,12 +853,15 @@ impl Policy {
 		exec.eval(expression).ok()?.json().ok()
 	}
 
+	/// Apply the request guards. Values replaced by `tokenize` rules are recorded in `tokens` so
+	/// they can be restored in the response.
 	pub async fn apply_prompt_guard(
 		&self,
 		backend_info: &auth::BackendInfo,
 		req: &mut dyn RequestType,
 		http_headers: &HeaderMap,
 		claims: Option<Claims>,
+		tokens: &mut TokenVault,
 	) -> anyhow::Result<Option<Response>> {
 		let client = PolicyClient::new(backend_info.inputs.clone());
 		for g in self
@@ -673,7 +870,9 @@ impl Policy {
 			.iter()
 			.flat_map(|g| g.request.iter())
 		{
-			match Self::apply_single_request_guard(g, req, http_headers, &client, claims.clone()).await? {
+			match Self::apply_single_request_guard(g, req, http_headers, &client, claims.clone(), tokens)
+				.await?
+			{
 				GuardrailOutcome::Rejected(res) => {
 					Self::record_guardrail_trip(
 						&client,
@@ -720,9 +919,10 @@ impl Policy {
 		http_headers: &HeaderMap,
 		client: &PolicyClient,
 		claims: Option<Claims>,
+		tokens: &mut TokenVault,
 	) -> anyhow::Result<GuardrailOutcome> {
 		match &guard.kind {
-			RequestGuardKind::Regex(rg) => Self::apply_regex(req, rg, &guard.rejection),
+			RequestGuardKind::Regex(rg) => Self::apply_regex(req, rg, &guard.rejection, tokens),
 			RequestGuardKind::Webhook(wh) => Self::apply_webhook(req, http_headers, client, wh).await,
 			RequestGuardKind::OpenAIModeration(m) => {
 				match Self::apply_moderation(req, claims.clone(), client, &guard.rejection, m).await? {
@@ -748,6 +948,8 @@ impl Policy {
 					None => Ok(GuardrailOutcome::None),
 				}
 			},
+			RequestGuardKind::Judge(j) => Self::apply_judge(req, client, &guard.rejection, j).await,
+			RequestGuardKind::Tools(t) => Ok(Self::apply_tools(req, &guard.rejection, t)),
 			RequestGuardKind::AzureContentSafety(acs) => {
 				match Self::apply_azure_content_safety_request(
 					req,
@@ -780,6 +982,154 @@ impl Policy {
 		}
 	}
 
+	async fn apply_judge(
+		req: &mut dyn RequestType,
+		client: &PolicyClient,
+		rej: &RequestRejection,
+		j: &Judge,
+	) -> anyhow::Result<GuardrailOutcome> {
+		let mut msgs = req.get_messages();
+		let content = msgs.iter().map(|m| m.content.as_str()).join("\n\n");
+		if content.is_empty() {
+			return Ok(GuardrailOutcome::None);
+		}
+		let verdict = match judge::send_request(client, j, &content).await {
+			Ok(verdict) => verdict,
+			Err(e) => return Self::judge_failure(j, e),
+		};
+		if !verdict.flagged {
+			return Ok(GuardrailOutcome::None);
+		}
+		if !matches!(j.action, Action::Reject) {
+			let mut any_changed = false;
+			for msg in &mut msgs {
+				if let Some(masked) = verdict.mask(&msg.content) {
+					any_changed = true;
+					msg.content = masked.into();
+				}
+			}
+			if any_changed {
+				req.set_messages(msgs);
+				return Ok(GuardrailOutcome::Masked);
+			}
+		}
+		debug!(
+			"judge rejected request: {}",
+			verdict.reason.as_deref().unwrap_or("no reason specified")
+		);
+		Ok(GuardrailOutcome::Rejected(rej.as_response()))
+	}
+
+	async fn apply_judge_response(
+		resp: &mut dyn ResponseType,
+		client: &PolicyClient,
+		rej: &RequestRejection,
+		j: &Judge,
+	) -> anyhow::Result<GuardrailOutcome> {
+		let mut choices = resp.to_webhook_choices();
+		let content = choices
+			.iter()
+			.map(|c| c.message.content.as_str())
+			.join("\n\n");
+		if content.is_empty() {
+			return Ok(GuardrailOutcome::None);
+		}
+		let verdict = match judge::send_request(client, j, &content).await {
+			Ok(verdict) => verdict,
+			Err(e) => return Self::judge_failure(j, e),
+		};
+		if !verdict.flagged {
+			return Ok(GuardrailOutcome::None);
+		}
+		if !matches!(j.action, Action::Reject) {
+			let mut any_changed = false;
+			for choice in &mut choices {
+				if let Some(masked) = verdict.mask(&choice.message.content) {
+					any_changed = true;
+					choice.message.content = masked.into();
+				}
+			}
+			if any_changed {
+				resp.set_webhook_choices(choices)?;
+				return Ok(GuardrailOutcome::Masked);
+			}
+		}
+		debug!(
+			"judge rejected response: {}",
+			verdict.reason.as_deref().unwrap_or("no reason specified")
+		);
+		Ok(GuardrailOutcome::Rejected(rej.as_response()))
+	}
+
+	fn judge_failure(j: &Judge, e: anyhow::Error) -> anyhow::Result<GuardrailOutcome> {
+		match j.failure_mode {
+			FailureMode::FailOpen => {
+				warn!("judge guardrail unavailable, failing open: {e}");
+				Ok(GuardrailOutcome::FailOpen)
+			},
+			FailureMode::FailClosed => Err(e),
+		}
+	}
+
+	fn apply_tools(
+		req: &mut dyn RequestType,
+		rej: &RequestRejection,
+		t: &ToolGuard,
+	) -> GuardrailOutcome {
+		let disallowed: Vec<String> = req
+			.get_tools()
+			.into_iter()
+			.map(|tool| tool.name)
+			.filter(|name| !t.allows(name))
+			.collect();
+		if disallowed.is_empty() {
+			return GuardrailOutcome::None;
+		}
+		if !t.strip {
+			debug!(tools = ?disallowed, "rejecting request with disallowed tools");
+			return GuardrailOutcome::Rejected(rej.as_response());
+		}
+		debug!(tools = ?disallowed, "removing disallowed tools from request");
+		req.retain_tools(&|name| t.allows(name));
+		GuardrailOutcome::Masked
+	}
+
+	fn apply_tools_response(
+		resp: &mut dyn ResponseType,
+		rej: &RequestRejection,
+		t: &ToolGuard,
+		declared_tools: Option<&DeclaredTools>,
+	) -> anyhow::Result<GuardrailOutcome> {
+		let calls = resp.tool_calls();
+		for call in &calls {
+			if let Err(e) = tools::check_call(t, call, declared_tools) {
+				debug!("rejecting tool call: {e:#}");
+				return Ok(GuardrailOutcome::Rejected(rej.as_response()));
+			}
+		}
+		let Some(rules) = &t.arguments else {
+			return Ok(GuardrailOutcome::None);
+		};
+		let mut any_changed = false;
+		let mut arguments = Vec::with_capacity(calls.len());
+		for call in calls {
+			let mut args = call.arguments;
+			match tools::apply_regex(&mut args, rules) {
+				tools::ArgumentsResult::Reject => {
+					return Ok(GuardrailOutcome::Rejected(rej.as_response()));
+				},
+				tools::ArgumentsResult::Masked => any_changed = true,
+				tools::ArgumentsResult::Unchanged => {},
+			}
+			arguments.push(args);
+		}
+		if any_changed {
+			resp.set_tool_call_arguments(arguments)?;
+			return Ok(GuardrailOutcome::Masked);
+		}
+		Ok(GuardrailOutcome::None)
+	}
+
 	async fn apply_bedrock_guardrails_request(
 		req: &mut dyn RequestType,
 		claims: Option<Claims>,
@@ -956,11 +1306,12 @@ impl Policy {
 		req: &mut dyn RequestType,
 		rgx: &RegexRules,
 		rej: &RequestRejection,
+		tokens: &mut TokenVault,
 	) -> anyhow::Result<GuardrailOutcome> {
 		let mut msgs = req.get_messages();
 		let mut any_changed = false;
 		for msg in &mut msgs {
-			match Self::apply_prompt_guard_regex(&msg.content, rgx) {
+			match Self::apply_prompt_guard_regex(&msg.content, rgx, Some(&mut *tokens)) {
 				Some(RegexResult::Reject) => {
 					return Ok(GuardrailOutcome::Rejected(rej.as_response()));
 				},
@@ -986,7 +1337,8 @@ impl Policy {
 		let mut msgs = resp.to_webhook_choices();
 		let mut any_changed = false;
 		for msg in &mut msgs {
-			match Self::apply_prompt_guard_regex(&msg.message.content, rgx) {
+			// Responses are never restored, so tokenize rules mask here.
+			match Self::apply_prompt_guard_regex(&msg.message.content, rgx, None) {
 				Some(RegexResult::Reject) => {
 					return Ok(GuardrailOutcome::Rejected(rej.as_response()));
 				},
@@ -1195,7 +1547,13 @@ impl Policy {
 	// 	}
 	// }
 
-	fn apply_prompt_guard_regex(original_content: &str, rgx: &RegexRules) -> Option<RegexResult> {
+	/// Apply regex rules to `original_content`. `tokens` is only given for request content; without
+	/// it, `tokenize` rules fall back to masking.
+	fn apply_prompt_guard_regex(
+		original_content: &str,
+		rgx: &RegexRules,
+		mut tokens: Option<&mut TokenVault>,
+	) -> Option<RegexResult> {
 		let mut current_content = original_content.to_string();
 		let mut content_modified = false;
 
@@ -1209,33 +1567,43 @@ impl Policy {
 						Builtin::PhoneNumber => &*pii::PHONE,
 						Builtin::Email => &*pii::EMAIL,
 						Builtin::CaSin => &*pii::CA_SIN,
+						Builtin::Iban => &*pii::IBAN,
+						Builtin::SwiftCode => &*pii::SWIFT,
+						Builtin::IpAddress => &*pii::IP,
+						Builtin::UkNino => &*pii::UK_NINO,
+						Builtin::Passport => &*pii::PASSPORT,
+						Builtin::ApiKey => &*pii::API_KEY,
+						Builtin::PrivateKey => &*pii::PRIVATE_KEY,
+						Builtin::Jwt => &*pii::JWT,
 					};
 					let results = pii::recognizer(rec, &current_content);
 
 					if !results.is_empty() {
-						match &rgx.action {
-							Action::Reject => {
-								return Some(RegexResult::Reject);
-							},
-							Action::Mask => {
-								// Replace matches in reverse order while also combining any overlapping ranges
-								let replacement = format!("<{}>", results[0].entity_type);
-								for range in results
-									.into_iter()
-									.map(|r| r.start..r.end)
-									.sorted_unstable_by(|a, b| b.start.cmp(&a.start).then_with(|| a.end.cmp(&b.end)))
-									.coalesce(|a, b| {
-										if b.end > a.start {
-											Ok(b.start..std::cmp::max(a.end, b.end))
-										} else {
-											Err((a, b))
-										}
-									}) {
-									current_content.replace_range(range, &replacement);
-								}
-								content_modified = true;
-							},
+						if matches!(rgx.action, Action::Reject) {
+							return Some(RegexResult::Reject);
 						}
+						let entity_type = results[0].entity_type.clone();
+						// Replace matches in reverse order while also combining any overlapping ranges
+						let ranges: Vec<_> = results
+							.into_iter()
+							.map(|r| r.start..r.end)
+							.sorted_unstable_by(|a, b| b.start.cmp(&a.start).then_with(|| a.end.cmp(&b.end)))
+							.coalesce(|a, b| {
+								if b.end > a.start {
+									Ok(b.start..std::cmp::max(a.end, b.end))
+								} else {
+									Err((a, b))
+								}
+							})
+							.collect();
+						Self::replace_ranges(
+							&mut current_content,
+							ranges,
+							&entity_type,
+							&rgx.action,
+							tokens.as_deref_mut(),
+						);
+						content_modified = true;
 					}
 				},
 				RegexRule::Regex { pattern } => {
@@ -1245,18 +1613,18 @@ impl Policy {
 						.collect();
 
 					if !ranges.is_empty() {
-						match &rgx.action {
-							Action::Reject => {
-								return Some(RegexResult::Reject);
-							},
-							Action::Mask => {
-								// Process matches in reverse order to avoid index shifting
-								for range in ranges.into_iter().rev() {
-									current_content.replace_range(range, "<masked>");
-								}
-								content_modified = true;
-							},
+						if matches!(rgx.action, Action::Reject) {
+							return Some(RegexResult::Reject);
 						}
+						// Process matches in reverse order to avoid index shifting
+						Self::replace_ranges(
+							&mut current_content,
+							ranges.into_iter().rev().collect(),
+							"masked",
+							&rgx.action,
+							tokens.as_deref_mut(),
+						);
+						content_modified = true;
 					}
 				},
 			}
@@ -1268,14 +1636,49 @@ impl Policy {
 		None
 	}
 
+	/// Replace `ranges`, which must be in reverse order, with `<entity_type>` or, when tokenizing,
+	/// with a surrogate token per matched value.
+	fn replace_ranges(
+		content: &mut String,
+		ranges: Vec<std::ops::Range<usize>>,
+		entity_type: &str,
+		action: &Action,
+		tokens: Option<&mut TokenVault>,
+	) {
+		match (action, tokens) {
+			(Action::Tokenize, Some(tokens)) => {
+				// Allocate tokens in reading order so the first match is `_1`.
+				let entity_type = entity_type.to_uppercase();
+				let replacements: Vec<String> = ranges
+					.iter()
+					.rev()
+					.map(|r| tokens.tokenize(&entity_type, &content[r.clone()]))
+					.collect();
+				for (range, token) in ranges.into_iter().zip(replacements.into_iter().rev()) {
+					content.replace_range(range, &token);
+				}
+			},
+			_ => {
+				let replacement = format!("<{entity_type}>");
+				for range in ranges {
+					content.replace_range(range, &replacement);
+				}
+			},
+		}
+	}
+
+	/// Apply the response guards. `declared_tools` are the tools the request declared, used to
+	/// validate tool-call arguments.
 	pub async fn apply_response_prompt_guard(
 		client: &PolicyClient,
 		resp: &mut dyn ResponseType,
 		http_headers: &HeaderMap,
 		guards: &Vec<ResponseGuard>,
+		declared_tools: Option<&DeclaredTools>,
 	) -> anyhow::Result<Option<Response>> {
 		for g in guards {
-			match Self::apply_single_response_guard(g, resp, http_headers, client).await? {
+			match Self::apply_single_response_guard(g, resp, http_headers, client, declared_tools).await?
+			{
 				GuardrailOutcome::Rejected(res) => {
 					Self::record_guardrail_trip(
 						client,
@@ -1315,6 +1718,7 @@ impl Policy {
 		resp: &mut dyn ResponseType,
 		http_headers: &HeaderMap,
 		client: &PolicyClient,
+		declared_tools: Option<&DeclaredTools>,
 	) -> anyhow::Result<GuardrailOutcome> {
 		match &guard.kind {
 			ResponseGuardKind::Regex(rg) => Self::apply_regex_response(resp, rg, &guard.rejection),
@@ -1332,6 +1736,12 @@ impl Policy {
 					None => Ok(GuardrailOutcome::None),
 				}
 			},
+			ResponseGuardKind::Judge(j) => {
+				Self::apply_judge_response(resp, client, &guard.rejection, j).await
+			},
+			ResponseGuardKind::Tools(t) => {
+				Self::apply_tools_response(resp, &guard.rejection, t, declared_tools)
+			},
 			ResponseGuardKind::AzureContentSafety(acs) => {
 				match Self::apply_azure_content_safety_response(resp, None, client, &guard.rejection, acs)
 					.await?
@@ -1365,6 +1775,7 @@ impl RequestGuard {
 	fn failure_mode(&self) -> FailureMode {
 		match &self.kind {
 			RequestGuardKind::Webhook(wh) => wh.failure_mode,
+			RequestGuardKind::Judge(j) => j.failure_mode,
 			_ => FailureMode::FailOpen,
 		}
 	}
@@ -1384,6 +1795,10 @@ pub enum RequestGuardKind {
 	GoogleModelArmor(GoogleModelArmor),
 	/// Use Azure Content Safety to evaluate the prompt.
 	AzureContentSafety(AzureContentSafety),
+	/// Ask another AI provider to judge the prompt.
+	Judge(Judge),
+	/// Restrict the tools the request makes available to the model.
+	Tools(ToolGuard),
 }
 
 #[apply(schema!)]
@@ -1443,6 +1858,22 @@ pub enum Builtin {
 	Email,
 	/// Canadian Social Insurance Number pattern.
 	CaSin,
+	/// International Bank Account Number, validated by its checksum.
+	Iban,
+	/// SWIFT/BIC bank code. Only matched next to words such as "swift" or "bic".
+	SwiftCode,
+	/// IPv4 or IPv6 address.
+	IpAddress,
+	/// U.K. National Insurance number pattern.
+	UkNino,
+	/// Passport number pattern. Only matched next to words such as "passport".
+	Passport,
+	/// AWS, GCP, GitHub and OpenAI-style API keys and access tokens.
+	ApiKey,
+	/// PEM encoded private key block.
+	PrivateKey,
+	/// JSON Web Token.
+	Jwt,
 }
 
 #[apply(schema!)]
@@ -1509,6 +1940,77 @@ pub struct Moderation {
 	pub policies: Vec<BackendTrafficPolicy>,
 }
 
+/// An LLM-as-judge guardrail: the content is sent to another AI provider, which returns a JSON
+/// verdict.
+#[apply(schema!)]
+pub struct Judge {
+	/// Provider that judges the content. Set `model` to the judging model; a small or local model is
+	/// usually enough.
+	pub provider: crate::llm::AIProvider,
+	/// Host to send judge requests to, instead of the provider's default.
+	/// Required for custom providers.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub host_override: Option<crate::types::agent::Target>,
+	/// Backend policies used when calling the judge.
+	#[serde(
+		default,
+		deserialize_with = "crate::types::local::de_from_local_backend_policy",
+		skip_serializing_if = "Vec::is_empty"
+	)]
+	#[cfg_attr(
+		feature = "schema",
+		schemars(with = "Option<crate::types::local::SimpleLocalBackendPolicies>")
+	)]
+	pub policies: Vec<BackendTrafficPolicy>,
+	/// Instructions for the judge, sent as the system prompt. The content being judged is sent as
+	/// the user message.
+	pub prompt: String,
+	/// JSON schema the verdict must follow, requested as structured output. It must have a boolean
+	/// `flagged` property and, for the `mask` action, a `matches` array of the exact substrings to
+	/// mask. Defaults to an object with `flagged`, `matches` and `reason`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub verdict_schema: Option<serde_json::Value>,
+	/// Action to take when the judge flags the content. Flagged content without any `matches` to
+	/// mask is rejected. `tokenize` behaves like `mask`.
+	#[serde(default)]
+	pub action: Action,
+	/// Behavior when the judge is unreachable or returns an invalid verdict.
+	/// Defaults to `failClosed`.
+	#[serde(default, skip_serializing_if = "crate::serdes::is_default")]
+	pub failure_mode: FailureMode,
+}
+
+/// A guardrail over tool use. As a request guard it checks the tools the request declares; as a
+/// response guard it checks the tool calls the model makes. Completions, Messages and Responses
+/// requests are supported.
+///
+/// When `streaming` is enabled, streamed tool calls are held back until they are complete and then
+/// checked. A streamed call whose arguments would be masked is rejected instead, as it cannot be
+/// rewritten. Otherwise, like other response guards, it does not check streamed responses.
+#[apply(schema!)]
+pub struct ToolGuard {
+	/// Tool names that may be used. When set, any other tool is disallowed. A trailing `*` matches
+	/// any suffix. Built-in tools without a name, such as `web_search`, are matched by type.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub allow: Option<Vec<String>>,
+	/// Tool names that may not be used. Takes precedence over `allow`.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub deny: Vec<String>,
+	/// Remove disallowed tools from the request instead of rejecting it.
+	/// Only applicable to request guards; disallowed tool calls in a response are always rejected.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub strip: bool,
+	/// Reject tool calls whose arguments do not match the JSON schema the request declared for the
+	/// tool, or that call a tool the request did not declare.
+	/// Only applicable to response guards.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub validate_arguments: bool,
+	/// Regex rules applied to every string value in tool-call arguments.
+	/// Only applicable to response guards.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub arguments: Option<RegexRules>,
+}
+
 /// Configuration for AWS Bedrock Guardrails integration.
 #[apply(schema!)]
 pub struct BedrockGuardrails {
@@ -1621,6 +2123,9 @@ pub enum Action {
 	Mask,
 	/// Reject the request or response when content matches.
 	Reject,
+	/// Replace matching request content with per-request surrogate tokens, and restore the original
+	/// values in the response. Behaves like `mask` for response guards.
+	Tokenize,
 }
 
 #[apply(schema!)]
@@ -1669,6 +2174,10 @@ pub enum ResponseGuardKind {
 	GoogleModelArmor(GoogleModelArmor),
 	/// Use Azure Content Safety to evaluate the response.
 	AzureContentSafety(AzureContentSafety),
+	/// Ask another AI provider to judge the response.
+	Judge(Judge),
+	/// Check the tool calls the model makes.
+	Tools(ToolGuard),
 }
 
 #[apply(schema!)]
@@ -1923,9 +2432,46 @@ fn test_apply_prompt_guard_regex_mask(
 			action: Action::Mask,
 			rules,
 		},
+		None,
 	);
 	match result {
 		Some(RegexResult::Mask(masked)) => assert_eq!(masked, expected),
 		_ => panic!("expected masked result"),
 	}
 }
+
+#[test]
+fn test_apply_prompt_guard_regex_tokenize() {
+	let rgx = RegexRules {
+		action: Action::Tokenize,
+		rules: vec![
+			RegexRule::Builtin {
+				builtin: Builtin::Email,
+			},
+			RegexRule::Regex {
+				pattern: regex::Regex::new(r"id-\d+").unwrap(),
+			},
+		],
+	};
+	let input = "mail john@example.com, jane@other.com and john@example.com about id-42";
+	let mut tokens = TokenVault::default();
+	let Some(RegexResult::Mask(tokenized)) =
+		Policy::apply_prompt_guard_regex(input, &rgx, Some(&mut tokens))
+	else {
+		panic!("expected tokenized result");
+	};
+	assert_eq!(
+		tokenized,
+		"mail <EMAIL_ADDRESS_1>, <EMAIL_ADDRESS_2> and <EMAIL_ADDRESS_1> about <MASKED_1>"
+	);
+	assert_eq!(tokens.restore(&tokenized), input);
+
+	// Without a vault, as for responses, tokenize rules mask.
+	let Some(RegexResult::Mask(masked)) = Policy::apply_prompt_guard_regex(input, &rgx, None) else {
+		panic!("expected masked result");
+	};
+	assert_eq!(
+		masked,
+		"mail <EMAIL_ADDRESS>, <EMAIL_ADDRESS> and <EMAIL_ADDRESS> about <masked>"
+	);
+}
@@ -0,0 +1,61 @@
+use crate::llm::policy::pii::pattern_recognizer::PatternRecognizer;
+use crate::llm::policy::pii::recognizer::Recognizer;
+
+pub struct ApiKeyRecognizer {
+	recognizer: PatternRecognizer,
+}
+
+impl ApiKeyRecognizer {
+	pub fn new() -> Self {
+		let mut recognizer = PatternRecognizer::new(
+			"API_KEY",
+			vec![
+				"key".to_string(),
+				"token".to_string(),
+				"secret".to_string(),
+				"credential".to_string(),
+				"password".to_string(),
+			],
+		)
+		.with_min_score(0.4);
+		recognizer.add_pattern(
+			"AWS access key ID",
+			r"\b(?:AKIA|ASIA|ABIA|ACCA)[0-9A-Z]{16}\b",
+			0.9,
+		);
+		// Secret access keys have no prefix; only report them next to a context word.
+		recognizer.add_pattern(
+			"AWS secret access key (weak)",
+			r"\b[A-Za-z0-9/+]{40}\b",
+			0.1,
+		);
+		recognizer.add_pattern("GCP API key", r"\bAIza[0-9A-Za-z_\-]{35}\b", 0.9);
+		recognizer.add_pattern(
+			"GitHub token",
+			r"\b(?:ghp|gho|ghu|ghs|ghr)_[A-Za-z0-9]{36}\b",
+			0.95,
+		);
+		recognizer.add_pattern(
+			"GitHub fine-grained token",
+			r"\bgithub_pat_[A-Za-z0-9_]{82}\b",
+			0.95,
+		);
+		// Also matches other providers using the same `sk-` convention.
+		recognizer.add_pattern(
+			"OpenAI-style secret key",
+			r"\bsk-(?:[a-z]+-)?[A-Za-z0-9_\-]{20,}",
+			0.8,
+		);
+
+		Self { recognizer }
+	}
+}
+
+impl Recognizer for ApiKeyRecognizer {
+	fn recognize(&self, text: &str) -> Vec<super::recognizer_result::RecognizerResult> {
+		self.recognizer.recognize(text)
+	}
+	fn name(&self) -> &str {
+		self.recognizer.name()
+	}
+}
@@ -0,0 +1,56 @@
+use crate::llm::policy::pii::pattern_recognizer::PatternRecognizer;
+use crate::llm::policy::pii::recognizer::Recognizer;
+
+pub struct IbanRecognizer {
+	recognizer: PatternRecognizer,
+}
+
+impl IbanRecognizer {
+	pub fn new() -> Self {
+		let mut recognizer = PatternRecognizer::new(
+			"IBAN_CODE",
+			vec![
+				"iban".to_string(),
+				"bank".to_string(),
+				"account".to_string(),
+				"transaction".to_string(),
+			],
+		)
+		.with_validator(valid_iban);
+		// Country code, check digits, then up to 30 alphanumerics, optionally grouped by four.
+		recognizer.add_pattern(
+			"IBAN (medium)",
+			r"\b[A-Z]{2}[0-9]{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b",
+			0.5,
+		);
+
+		Self { recognizer }
+	}
+}
+
+/// Validate the ISO 13616 mod-97 checksum.
+fn valid_iban(candidate: &str) -> bool {
+	let iban: String = candidate.chars().filter(|c| *c != ' ').collect();
+	if !(15..=34).contains(&iban.len()) {
+		return false;
+	}
+	let (head, tail) = iban.split_at(4);
+	let mut remainder = 0u32;
+	for c in tail.chars().chain(head.chars()) {
+		let Some(value) = c.to_digit(36) else {
+			return false;
+		};
+		let digits = if value < 10 { 10 } else { 100 };
+		remainder = (remainder * digits + value) % 97;
+	}
+	remainder == 1
+}
+
+impl Recognizer for IbanRecognizer {
+	fn recognize(&self, text: &str) -> Vec<super::recognizer_result::RecognizerResult> {
+		self.recognizer.recognize(text)
+	}
+	fn name(&self) -> &str {
+		self.recognizer.name()
+	}
+}
@@ -0,0 +1,46 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::llm::policy::pii::pattern_recognizer::PatternRecognizer;
+use crate::llm::policy::pii::recognizer::Recognizer;
+
+pub struct IpRecognizer {
+	recognizer: PatternRecognizer,
+}
+
+impl IpRecognizer {
+	pub fn new() -> Self {
+		let mut recognizer = PatternRecognizer::new(
+			"IP_ADDRESS",
+			vec![
+				"ip".to_string(),
+				"ipv4".to_string(),
+				"ipv6".to_string(),
+				"address".to_string(),
+				"host".to_string(),
+			],
+		)
+		.with_validator(valid_ip);
+		recognizer.add_pattern("IPv4", r"\b[0-9]{1,3}(?:\.[0-9]{1,3}){3}\b", 0.6);
+		// Candidates with at least two colons; the validator rejects times and other lookalikes.
+		recognizer.add_pattern(
+			"IPv6",
+			r"(?:[0-9A-Fa-f]{1,4}:|:)(?:[0-9A-Fa-f]{0,4}:){1,6}[0-9A-Fa-f]{0,4}",
+			0.6,
+		);
+
+		Self { recognizer }
+	}
+}
+
+fn valid_ip(candidate: &str) -> bool {
+	candidate.parse::<Ipv4Addr>().is_ok() || candidate.parse::<Ipv6Addr>().is_ok()
+}
+
+impl Recognizer for IpRecognizer {
+	fn recognize(&self, text: &str) -> Vec<super::recognizer_result::RecognizerResult> {
+		self.recognizer.recognize(text)
+	}
+	fn name(&self) -> &str {
+		self.recognizer.name()
+	}
+}
@@ -0,0 +1,48 @@
+use base64::Engine as _;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+use crate::llm::policy::pii::pattern_recognizer::PatternRecognizer;
+use crate::llm::policy::pii::recognizer::Recognizer;
+
+pub struct JwtRecognizer {
+	recognizer: PatternRecognizer,
+}
+
+impl JwtRecognizer {
+	pub fn new() -> Self {
+		let mut recognizer = PatternRecognizer::new(
+			"JWT",
+			vec!["jwt".to_string(), "token".to_string(), "bearer".to_string()],
+		)
+		.with_validator(valid_header);
+		// A JSON header and payload both start with `{"`, which base64url encodes as `eyJ`.
+		recognizer.add_pattern(
+			"JWT",
+			r"\beyJ[A-Za-z0-9_\-]+\.eyJ[A-Za-z0-9_\-]+\.[A-Za-z0-9_\-]*",
+			0.8,
+		);
+
+		Self { recognizer }
+	}
+}
+
+/// The header must decode to a JSON object naming the signing algorithm.
+fn valid_header(candidate: &str) -> bool {
+	let Some(header) = candidate.split('.').next() else {
+		return false;
+	};
+	URL_SAFE_NO_PAD
+		.decode(header)
+		.ok()
+		.and_then(|h| serde_json::from_slice::<serde_json::Value>(&h).ok())
+		.is_some_and(|h| h.get("alg").is_some())
+}
+
+impl Recognizer for JwtRecognizer {
+	fn recognize(&self, text: &str) -> Vec<super::recognizer_result::RecognizerResult> {
+		self.recognizer.recognize(text)
+	}
+	fn name(&self) -> &str {
+		self.recognizer.name()
+	}
+}
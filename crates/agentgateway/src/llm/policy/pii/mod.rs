@@ -6,13 +6,21 @@ use crate::llm::policy::pii::email_recognizer::EmailRecognizer;
 use crate::llm::policy::pii::phone_recognizer::PhoneRecognizer;
 use crate::llm::policy::pii::recognizer::Recognizer;
 
+mod api_key_recognizer;
 mod ca_sin_recognizer;
 mod credit_card_recognizer;
 mod email_recognizer;
+mod iban_recognizer;
+mod ip_recognizer;
+mod jwt_recognizer;
+mod passport_recognizer;
 mod pattern_recognizer;
 mod phone_recognizer;
+mod private_key_recognizer;
 mod recognizer;
 mod recognizer_result;
+mod swift_recognizer;
+mod uk_nino_recognizer;
 mod url_recognizer;
 mod us_ssn_recognizer;
 
@@ -31,6 +39,30 @@ pub static SSN: Lazy<Box<dyn Recognizer + Sync + Send + 'static>> =
 pub static CA_SIN: Lazy<Box<dyn Recognizer + Sync + Send + 'static>> =
 	Lazy::new(|| Box::new(ca_sin_recognizer::CaSinRecognizer::new()));
 
+pub static IBAN: Lazy<Box<dyn Recognizer + Sync + Send + 'static>> =
+	Lazy::new(|| Box::new(iban_recognizer::IbanRecognizer::new()));
+
+pub static SWIFT: Lazy<Box<dyn Recognizer + Sync + Send + 'static>> =
+	Lazy::new(|| Box::new(swift_recognizer::SwiftRecognizer::new()));
+
+pub static IP: Lazy<Box<dyn Recognizer + Sync + Send + 'static>> =
+	Lazy::new(|| Box::new(ip_recognizer::IpRecognizer::new()));
+
+pub static UK_NINO: Lazy<Box<dyn Recognizer + Sync + Send + 'static>> =
+	Lazy::new(|| Box::new(uk_nino_recognizer::UkNinoRecognizer::new()));
+
+pub static PASSPORT: Lazy<Box<dyn Recognizer + Sync + Send + 'static>> =
+	Lazy::new(|| Box::new(passport_recognizer::PassportRecognizer::new()));
+
+pub static API_KEY: Lazy<Box<dyn Recognizer + Sync + Send + 'static>> =
+	Lazy::new(|| Box::new(api_key_recognizer::ApiKeyRecognizer::new()));
+
+pub static PRIVATE_KEY: Lazy<Box<dyn Recognizer + Sync + Send + 'static>> =
+	Lazy::new(|| Box::new(private_key_recognizer::PrivateKeyRecognizer::new()));
+
+pub static JWT: Lazy<Box<dyn Recognizer + Sync + Send + 'static>> =
+	Lazy::new(|| Box::new(jwt_recognizer::JwtRecognizer::new()));
+
 #[allow(clippy::borrowed_box)]
 pub fn recognizer(
 	r: &Box<dyn Recognizer + Sync + Send + 'static>,
@@ -0,0 +1,34 @@
+use crate::llm::policy::pii::pattern_recognizer::PatternRecognizer;
+use crate::llm::policy::pii::recognizer::Recognizer;
+
+pub struct PassportRecognizer {
+	recognizer: PatternRecognizer,
+}
+
+impl PassportRecognizer {
+	pub fn new() -> Self {
+		let mut recognizer = PatternRecognizer::new(
+			"PASSPORT",
+			vec![
+				"passport".to_string(),
+				"passports".to_string(),
+				"travel document".to_string(),
+			],
+		)
+		// Passport numbers look like many other identifiers, so only report them with context.
+		.with_min_score(0.4);
+		recognizer.add_pattern("Passport (weak)", r"\b[A-Z]{1,2}[0-9]{6,8}\b", 0.1);
+		recognizer.add_pattern("Passport (very weak)", r"\b[0-9]{9}\b", 0.05);
+
+		Self { recognizer }
+	}
+}
+
+impl Recognizer for PassportRecognizer {
+	fn recognize(&self, text: &str) -> Vec<super::recognizer_result::RecognizerResult> {
+		self.recognizer.recognize(text)
+	}
+	fn name(&self) -> &str {
+		self.recognizer.name()
+	}
+}
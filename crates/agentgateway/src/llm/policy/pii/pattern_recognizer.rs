@@ -3,6 +3,13 @@ use regex::Regex;
 use super::recognizer::Recognizer;
 use super::recognizer_result::RecognizerResult;
 
+/// Score added to a match when one of the recognizer's context words appears shortly before it.
+const CONTEXT_SIMILARITY_FACTOR: f32 = 0.35;
+/// Lowest score a match supported by context can have.
+const MIN_SCORE_WITH_CONTEXT: f32 = 0.4;
+/// How many words before a match are searched for context words.
+const CONTEXT_WINDOW_WORDS: usize = 5;
+
 #[derive(Debug)]
 pub struct Pattern {
 	#[allow(dead_code)]
@@ -13,10 +20,10 @@ pub struct Pattern {
 
 pub struct PatternRecognizer {
 	patterns: Vec<Pattern>,
-	#[allow(dead_code)]
 	context: Vec<String>,
 	entity_type: String,
-	// validator: Option<&'a dyn PatternValidator>,
+	validator: Option<fn(&str) -> bool>,
+	min_score: f32,
 }
 
 impl PatternRecognizer {
@@ -25,13 +32,23 @@ impl PatternRecognizer {
 			patterns: Vec::new(),
 			context,
 			entity_type: entity_type.to_string(),
-			// validator: None,
+			validator: None,
+			min_score: 0.0,
 		}
 	}
-	// pub fn with_validator(mut self, validator: &'a dyn PatternValidator) -> Self {
-	//     self.validator = Some(validator);
-	//     self
-	// }
+
+	/// Discard matches the validator rejects, such as numbers with an invalid checksum.
+	pub fn with_validator(mut self, validator: fn(&str) -> bool) -> Self {
+		self.validator = Some(validator);
+		self
+	}
+
+	/// Discard matches scoring below `min_score` after context is taken into account. Weak patterns
+	/// can use this to only report matches that are preceded by a context word.
+	pub fn with_min_score(mut self, min_score: f32) -> Self {
+		self.min_score = min_score;
+		self
+	}
 
 	pub fn add_pattern(&mut self, name: &str, regex: &str, score: f32) {
 		let pattern = Pattern {
@@ -41,6 +58,28 @@ impl PatternRecognizer {
 		};
 		self.patterns.push(pattern);
 	}
+
+	/// Whether any context word appears in the few words before `start`.
+	fn has_context(&self, text: &str, start: usize) -> bool {
+		if self.context.is_empty() {
+			return false;
+		}
+		let words: Vec<String> = text[..start]
+			.split(|c: char| !c.is_alphanumeric())
+			.filter(|w| !w.is_empty())
+			.rev()
+			.take(CONTEXT_WINDOW_WORDS)
+			.map(str::to_lowercase)
+			.collect();
+		let window = format!(
+			" {} ",
+			words.into_iter().rev().collect::<Vec<_>>().join(" ")
+		);
+		self
+			.context
+			.iter()
+			.any(|c| window.contains(&format!(" {} ", c.to_lowercase())))
+	}
 }
 
 impl Recognizer for PatternRecognizer {
@@ -50,27 +89,25 @@ impl Recognizer for PatternRecognizer {
 			for cap in pattern.regex.captures_iter(text) {
 				if let Some(matched) = cap.get(0) {
 					let candidate = matched.as_str();
-					let score = pattern.score;
-					let valid = true;
-					// if let Some(validator) = self.validator {
-					//     if let Some(false) = validator.validate(candidate) {
-					//         valid = false;
-					//         score = 0.0;
-					//     }
-					//     if let Some(true) = validator.invalidate(candidate) {
-					//         valid = false;
-					//         score = 0.0;
-					//     }
-					// }
-					if valid {
-						results.push(RecognizerResult {
-							entity_type: self.entity_type.clone(),
-							matched: candidate.to_string(),
-							start: matched.start(),
-							end: matched.end(),
-							score,
-						});
+					if self.validator.is_some_and(|validate| !validate(candidate)) {
+						continue;
+					}
+					let mut score = pattern.score;
+					if self.has_context(text, matched.start()) {
+						score = (score + CONTEXT_SIMILARITY_FACTOR)
+							.max(MIN_SCORE_WITH_CONTEXT)
+							.min(1.0);
+					}
+					if score < self.min_score {
+						continue;
 					}
+					results.push(RecognizerResult {
+						entity_type: self.entity_type.clone(),
+						matched: candidate.to_string(),
+						start: matched.start(),
+						end: matched.end(),
+						score,
+					});
 				}
 			}
 		}
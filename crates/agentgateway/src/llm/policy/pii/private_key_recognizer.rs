@@ -0,0 +1,29 @@
+use crate::llm::policy::pii::pattern_recognizer::PatternRecognizer;
+use crate::llm::policy::pii::recognizer::Recognizer;
+
+pub struct PrivateKeyRecognizer {
+	recognizer: PatternRecognizer,
+}
+
+impl PrivateKeyRecognizer {
+	pub fn new() -> Self {
+		let mut recognizer = PatternRecognizer::new("PRIVATE_KEY", vec![]);
+		// RSA, EC, OPENSSH, ENCRYPTED and plain PKCS#8 PEM blocks.
+		recognizer.add_pattern(
+			"PEM private key",
+			r"(?s)-----BEGIN (?:[A-Z0-9]+ )*PRIVATE KEY-----.*?-----END (?:[A-Z0-9]+ )*PRIVATE KEY-----",
+			1.0,
+		);
+
+		Self { recognizer }
+	}
+}
+
+impl Recognizer for PrivateKeyRecognizer {
+	fn recognize(&self, text: &str) -> Vec<super::recognizer_result::RecognizerResult> {
+		self.recognizer.recognize(text)
+	}
+	fn name(&self) -> &str {
+		self.recognizer.name()
+	}
+}
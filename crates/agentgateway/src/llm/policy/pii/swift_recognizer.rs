@@ -0,0 +1,38 @@
+use crate::llm::policy::pii::pattern_recognizer::PatternRecognizer;
+use crate::llm::policy::pii::recognizer::Recognizer;
+
+pub struct SwiftRecognizer {
+	recognizer: PatternRecognizer,
+}
+
+impl SwiftRecognizer {
+	pub fn new() -> Self {
+		let mut recognizer = PatternRecognizer::new(
+			"SWIFT_CODE",
+			vec![
+				"swift".to_string(),
+				"bic".to_string(),
+				"bank".to_string(),
+				"routing".to_string(),
+			],
+		)
+		// Any 8 or 11 letter upper case word has the shape of a BIC, so require context.
+		.with_min_score(0.4);
+		recognizer.add_pattern(
+			"SWIFT (weak)",
+			r"\b[A-Z]{4}[A-Z]{2}[A-Z2-9][A-NP-Z0-9](?:[A-Z0-9]{3})?\b",
+			0.1,
+		);
+
+		Self { recognizer }
+	}
+}
+
+impl Recognizer for SwiftRecognizer {
+	fn recognize(&self, text: &str) -> Vec<super::recognizer_result::RecognizerResult> {
+		self.recognizer.recognize(text)
+	}
+	fn name(&self) -> &str {
+		self.recognizer.name()
+	}
+}
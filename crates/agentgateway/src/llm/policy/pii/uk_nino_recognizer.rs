@@ -0,0 +1,43 @@
+use crate::llm::policy::pii::pattern_recognizer::PatternRecognizer;
+use crate::llm::policy::pii::recognizer::Recognizer;
+
+pub struct UkNinoRecognizer {
+	recognizer: PatternRecognizer,
+}
+
+impl UkNinoRecognizer {
+	pub fn new() -> Self {
+		let mut recognizer = PatternRecognizer::new(
+			"UK_NINO",
+			vec![
+				"national insurance".to_string(),
+				"ni number".to_string(),
+				"nino".to_string(),
+			],
+		)
+		.with_validator(valid_prefix);
+		recognizer.add_pattern(
+			"NINO (medium)",
+			r"\b[A-CEGHJ-PR-TW-Z][A-CEGHJ-NPR-TW-Z] ?[0-9]{2} ?[0-9]{2} ?[0-9]{2} ?[A-D]\b",
+			0.5,
+		);
+
+		Self { recognizer }
+	}
+}
+
+/// Prefixes that are never allocated.
+fn valid_prefix(candidate: &str) -> bool {
+	!["BG", "GB", "KN", "NK", "NT", "TN", "ZZ"]
+		.iter()
+		.any(|p| candidate.starts_with(p))
+}
+
+impl Recognizer for UkNinoRecognizer {
+	fn recognize(&self, text: &str) -> Vec<super::recognizer_result::RecognizerResult> {
+		self.recognizer.recognize(text)
+	}
+	fn name(&self) -> &str {
+		self.recognizer.name()
+	}
+}
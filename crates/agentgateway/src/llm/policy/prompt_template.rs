@@ -0,0 +1,187 @@
+//! Named prompt templates.
+//!
+//! Clients reference a template by id in the `prompt_template` field of the request body, along
+//! with the variables to render it with. The gateway renders the template into the request, so
+//! prompts are managed centrally rather than in every client.
+
+use std::collections::HashMap;
+
+use ::http::StatusCode;
+
+use crate::cel::Executor;
+use crate::http::Response;
+use crate::llm::model_router::llm_error_response;
+use crate::llm::{RequestType, SimpleChatCompletionMessage};
+use crate::*;
+
+#[cfg(test)]
+#[path = "prompt_template_tests.rs"]
+mod tests;
+
+#[apply(schema!)]
+pub struct PromptTemplates {
+	/// Templates clients may reference. Versions of a template share its id; requests that do not
+	/// ask for a version get the last one listed.
+	pub templates: Vec<PromptTemplate>,
+	/// Reject requests that do not reference a template.
+	#[serde(default)]
+	pub required: bool,
+}
+
+#[apply(schema!)]
+pub struct PromptTemplate {
+	/// Id requests reference the template by.
+	pub id: Strng,
+	/// Version of the template, which requests may ask for along with the id.
+	pub version: Strng,
+	/// Messages added before the client's messages. `{{name}}` is replaced with the value of the
+	/// variable `name`.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub prepend: Vec<SimpleChatCompletionMessage>,
+	/// Messages added after the client's messages, rendered like `prepend`.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub append: Vec<SimpleChatCompletionMessage>,
+	/// Variables computed from CEL expressions, such as `jwt.sub`. These take precedence over the
+	/// variables supplied by the client.
+	#[serde(default, skip_serializing_if = "HashMap::is_empty")]
+	pub variables: HashMap<String, Arc<cel::Expression>>,
+}
+
+/// The template a request references.
+#[derive(Debug, Deserialize)]
+struct TemplateRef {
+	id: Strng,
+	#[serde(default)]
+	version: Option<Strng>,
+	#[serde(default)]
+	variables: HashMap<String, serde_json::Value>,
+}
+
+/// A template that was rendered into a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rendered {
+	pub id: Strng,
+	pub version: Strng,
+}
+
+impl PromptTemplates {
+	/// Render the template `req` references into it. `http_req` provides the context for the
+	/// template's CEL variables. Returns the response to send instead when the template cannot be
+	/// rendered.
+	pub fn apply(
+		&self,
+		req: &mut dyn RequestType,
+		http_req: &crate::http::Request,
+	) -> Result<Option<Rendered>, Response> {
+		let Some(reference) = req.take_prompt_template() else {
+			if self.required {
+				return Err(invalid("request must reference a prompt template"));
+			}
+			return Ok(None);
+		};
+		let reference: TemplateRef = serde_json::from_value(reference)
+			.map_err(|e| invalid(&format!("invalid prompt_template: {e}")))?;
+		let template = self
+			.templates
+			.iter()
+			.rev()
+			.find(|t| {
+				t.id == reference.id
+					&& reference
+						.version
+						.as_ref()
+						.is_none_or(|version| &t.version == version)
+			})
+			.ok_or_else(|| {
+				llm_error_response(
+					StatusCode::NOT_FOUND,
+					&format!(
+						"prompt template '{}'{} not found",
+						reference.id,
+						reference
+							.version
+							.as_ref()
+							.map(|v| format!(" version '{v}'"))
+							.unwrap_or_default()
+					),
+					"prompt_template_not_found",
+				)
+			})?;
+
+		let mut variables: HashMap<&str, String> = reference
+			.variables
+			.iter()
+			.map(|(k, v)| (k.as_str(), variable_text(v)))
+			.collect();
+		if !template.variables.is_empty() {
+			let exec = Executor::new_request(http_req);
+			for (name, expr) in &template.variables {
+				let value = exec
+					.eval(expr)
+					.map_err(|e| e.to_string())
+					.and_then(|v| v.json().map_err(|e| e.to_string()))
+					.map_err(|e| {
+						debug!(template=%template.id, variable=%name, "failed to evaluate variable: {e}");
+						invalid(&format!("variable '{name}' could not be evaluated"))
+					})?;
+				variables.insert(name.as_str(), variable_text(&value));
+			}
+		}
+
+		let render_all = |messages: &[SimpleChatCompletionMessage]| {
+			messages
+				.iter()
+				.map(|m| {
+					Ok(SimpleChatCompletionMessage {
+						role: m.role.clone(),
+						content: render(&m.content, &variables)?.into(),
+					})
+				})
+				.collect::<Result<Vec<_>, Response>>()
+		};
+		let prepend = render_all(&template.prepend)?;
+		let append = render_all(&template.append)?;
+		if !prepend.is_empty() {
+			req.prepend_prompts(prepend);
+		}
+		if !append.is_empty() {
+			req.append_prompts(append);
+		}
+		Ok(Some(Rendered {
+			id: template.id.clone(),
+			version: template.version.clone(),
+		}))
+	}
+}
+
+/// Replace each `{{name}}` in `text` with the value of the variable `name`.
+fn render(text: &str, variables: &HashMap<&str, String>) -> Result<String, Response> {
+	let mut out = String::with_capacity(text.len());
+	let mut rest = text;
+	while let Some(start) = rest.find("{{") {
+		let Some(len) = rest[start + 2..].find("}}") else {
+			break;
+		};
+		let name = rest[start + 2..start + 2 + len].trim();
+		let value = variables
+			.get(name)
+			.ok_or_else(|| invalid(&format!("missing prompt template variable '{name}'")))?;
+		out.push_str(&rest[..start]);
+		out.push_str(value);
+		rest = &rest[start + 2 + len + 2..];
+	}
+	out.push_str(rest);
+	Ok(out)
+}
+
+/// Strings are inserted as-is; other values as JSON.
+fn variable_text(value: &serde_json::Value) -> String {
+	match value {
+		serde_json::Value::String(s) => s.clone(),
+		other => other.to_string(),
+	}
+}
+
+fn invalid(message: &str) -> Response {
+	llm_error_response(StatusCode::BAD_REQUEST, message, "invalid_prompt_template")
+}
@@ -0,0 +1,160 @@
+use secrecy::SecretString;
+use serde_json::{Map, Value, json};
+
+use super::*;
+use crate::http::jwt::Claims;
+use crate::llm::types::completions;
+
+fn templates(value: Value) -> PromptTemplates {
+	serde_json::from_value(value).unwrap()
+}
+
+fn library() -> PromptTemplates {
+	templates(json!({
+		"templates": [
+			{
+				"id": "support",
+				"version": "1",
+				"prepend": [{"role": "system", "content": "You help customers."}]
+			},
+			{
+				"id": "support",
+				"version": "2",
+				"prepend": [{"role": "system", "content": "You help {{ customer }} customers of {{user}}."}],
+				"append": [{"role": "user", "content": "Answer in {{language}}."}],
+				"variables": {"user": "jwt.sub"}
+			}
+		]
+	}))
+}
+
+fn request(prompt_template: Option<Value>) -> completions::Request {
+	let mut body = json!({
+		"model": "gpt-4o",
+		"messages": [{"role": "user", "content": "Where is my order?"}]
+	});
+	if let Some(prompt_template) = prompt_template {
+		body["prompt_template"] = prompt_template;
+	}
+	serde_json::from_value(body).unwrap()
+}
+
+fn http_request(sub: &str) -> crate::http::Request {
+	let mut req = crate::http::Request::new(crate::http::Body::empty());
+	let mut inner = Map::new();
+	inner.insert("sub".to_string(), json!(sub));
+	req.extensions_mut().insert(Claims {
+		inner,
+		jwt: SecretString::new("header.payload.signature".into()),
+	});
+	req
+}
+
+fn messages(req: &completions::Request) -> Vec<(String, String)> {
+	req
+		.get_messages()
+		.into_iter()
+		.map(|m| (m.role.to_string(), m.content.to_string()))
+		.collect()
+}
+
+async fn error_code(resp: Response) -> Value {
+	let body = http::read_body_with_limit(resp.into_body(), 1024)
+		.await
+		.unwrap();
+	serde_json::from_slice::<Value>(&body).unwrap()["error"]["code"].clone()
+}
+
+#[test]
+fn latest_version_is_rendered() {
+	let mut req = request(Some(json!({
+		"id": "support",
+		"variables": {"customer": "premium", "language": "French", "user": "spoofed"}
+	})));
+	let rendered = library()
+		.apply(&mut req, &http_request("acme"))
+		.unwrap()
+		.unwrap();
+	assert_eq!(
+		rendered,
+		Rendered {
+			id: strng::literal!("support"),
+			version: strng::literal!("2"),
+		}
+	);
+	// Variables computed by the gateway take precedence over the client's.
+	assert_eq!(
+		messages(&req),
+		vec![
+			(
+				"system".to_string(),
+				"You help premium customers of acme.".to_string()
+			),
+			("user".to_string(), "Where is my order?".to_string()),
+			("user".to_string(), "Answer in French.".to_string()),
+		]
+	);
+	// The reference is not forwarded to the provider.
+	assert!(req.rest.get("prompt_template").is_none());
+}
+
+#[test]
+fn version_can_be_pinned() {
+	let mut req = request(Some(json!({"id": "support", "version": "1"})));
+	let rendered = library()
+		.apply(&mut req, &http_request("acme"))
+		.unwrap()
+		.unwrap();
+	assert_eq!(rendered.version.as_str(), "1");
+	assert_eq!(messages(&req)[0].1, "You help customers.");
+}
+
+#[tokio::test]
+async fn invalid_references_are_rejected() {
+	let unknown = library()
+		.apply(
+			&mut request(Some(json!({"id": "support", "version": "3"}))),
+			&http_request("acme"),
+		)
+		.unwrap_err();
+	assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+	assert_eq!(error_code(unknown).await, "prompt_template_not_found");
+
+	let missing_variable = library()
+		.apply(
+			&mut request(Some(
+				json!({"id": "support", "variables": {"customer": "premium"}}),
+			)),
+			&http_request("acme"),
+		)
+		.unwrap_err();
+	assert_eq!(missing_variable.status(), StatusCode::BAD_REQUEST);
+	assert_eq!(
+		error_code(missing_variable).await,
+		"invalid_prompt_template"
+	);
+}
+
+#[test]
+fn template_may_be_required() {
+	let mut req = request(None);
+	assert_eq!(
+		library().apply(&mut req, &http_request("acme")).unwrap(),
+		None
+	);
+	assert_eq!(messages(&req).len(), 1);
+
+	let mut required = library();
+	required.required = true;
+	assert!(required.apply(&mut req, &http_request("acme")).is_err());
+}
+
+#[test]
+fn render_substitutes_variables() {
+	let variables = HashMap::from([("a", "1".to_string()), ("b", "2".to_string())]);
+	let rendered = |text: &str| render(text, &variables).ok();
+	assert_eq!(rendered("{{a}} and {{ b }}").as_deref(), Some("1 and 2"));
+	assert_eq!(rendered("no variables").as_deref(), Some("no variables"));
+	assert_eq!(rendered("unclosed {{a").as_deref(), Some("unclosed {{a"));
+	assert_eq!(rendered("{{c}}"), None);
+}
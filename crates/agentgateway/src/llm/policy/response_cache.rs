@@ -0,0 +1,505 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use ::http::{HeaderMap, HeaderName, StatusCode, header};
+use bytes::{Bytes, BytesMut};
+use http_body::{Body, Frame, SizeHint};
+use pin_project_lite::pin_project;
+use prometheus_client::encoding::EncodeLabelValue;
+use quick_cache::Weighter;
+use quick_cache::sync::Cache;
+use sha2::{Digest, Sha256};
+
+use crate::cel::{Executor, LLMContext};
+use crate::llm::cost::ModelCatalog;
+use crate::llm::policy::semantic_cache;
+use crate::llm::{LLMInfo, LLMRequest, LLMResponse};
+use crate::proxy::ProxyError;
+use crate::proxy::httpproxy::PolicyClient;
+use crate::telemetry::log::AsyncLog;
+use crate::*;
+
+#[cfg(test)]
+#[path = "response_cache_tests.rs"]
+mod tests;
+
+const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+const DEFAULT_MAX_ENTRY_BYTES: usize = 1024 * 1024;
+const DEFAULT_BYPASS_HEADER: HeaderName = HeaderName::from_static("x-cache-bypass");
+
+// Response headers that are replayed on a cache hit. Everything else describes the original
+// upstream exchange and is not meaningful for a cached response.
+const CACHED_HEADERS: [HeaderName; 2] = [header::CONTENT_TYPE, header::CONTENT_ENCODING];
+
+type Key = [u8; 32];
+
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, EncodeLabelValue,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum ResponseCacheStatus {
+	Hit,
+	Miss,
+}
+
+impl ResponseCacheStatus {
+	pub fn as_str(self) -> &'static str {
+		match self {
+			ResponseCacheStatus::Hit => "hit",
+			ResponseCacheStatus::Miss => "miss",
+		}
+	}
+}
+
+impl std::fmt::Display for ResponseCacheStatus {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(with = "ResponseCacheSpec"))]
+#[derive(serde::Serialize)]
+pub struct ResponseCache {
+	#[serde(skip_serializing)]
+	store: Arc<Cache<Key, Arc<Entry>, EntryWeighter>>,
+	#[serde(flatten)]
+	pub spec: ResponseCacheSpec,
+}
+
+impl<'de> serde::Deserialize<'de> for ResponseCache {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		ResponseCacheSpec::deserialize(deserializer).map(ResponseCache::from)
+	}
+}
+
+#[apply(schema!)]
+pub struct ResponseCacheSpec {
+	/// How long a cached response is served before identical requests are sent to the provider again.
+	#[serde(with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub ttl: Duration,
+	/// CEL expression used to split the cache, one per distinct value.
+	/// For example, `jwt.sub` never serves one user's cached responses to another.
+	/// If unset, cached responses are shared by all callers of the route.
+	/// If the expression fails to evaluate, the cache is skipped for the request.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub key: Option<Arc<cel::Expression>>,
+	/// Maximum total size, in bytes, of cached response bodies. When exceeded, rarely used responses
+	/// are evicted first.
+	#[serde(default = "default_max_bytes")]
+	pub max_bytes: u64,
+	/// Responses with a body larger than this, in bytes, are not cached.
+	#[serde(default = "default_max_entry_bytes")]
+	pub max_entry_bytes: usize,
+	/// Requests with this header skip the cache: they are always sent to the provider, and their
+	/// response is not stored. The header is removed before the request is forwarded.
+	#[serde(default = "default_bypass_header", with = "http_serde::header_name")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub bypass_header: HeaderName,
+	/// Also serve cached responses to requests whose last user message is similar, but not identical,
+	/// to a cached request. Similarity is computed from embeddings of the message.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub semantic: Option<semantic_cache::SemanticCache>,
+}
+
+fn default_max_bytes() -> u64 {
+	DEFAULT_MAX_BYTES
+}
+
+fn default_max_entry_bytes() -> usize {
+	DEFAULT_MAX_ENTRY_BYTES
+}
+
+fn default_bypass_header() -> HeaderName {
+	DEFAULT_BYPASS_HEADER
+}
+
+impl From<ResponseCacheSpec> for ResponseCache {
+	fn from(spec: ResponseCacheSpec) -> Self {
+		let max_bytes = spec.max_bytes.max(1);
+		// Size the cache index assuming typical completions of a few KB.
+		let estimated_items = (max_bytes / 4096).clamp(16, 65_536) as usize;
+		ResponseCache {
+			store: Arc::new(Cache::with_weighter(
+				estimated_items,
+				max_bytes,
+				EntryWeighter,
+			)),
+			spec,
+		}
+	}
+}
+
+#[derive(Debug)]
+struct Entry {
+	status: StatusCode,
+	headers: HeaderMap,
+	body: Bytes,
+	response: LLMResponse,
+	expires_at: Instant,
+}
+
+#[derive(Debug, Clone)]
+struct EntryWeighter;
+
+impl Weighter<Key, Arc<Entry>> for EntryWeighter {
+	fn weight(&self, _key: &Key, entry: &Arc<Entry>) -> u64 {
+		// Never return a zero weight, so empty bodies still count against the capacity.
+		entry.body.len() as u64 + 1
+	}
+}
+
+/// The result of looking up a request in the response cache.
+#[derive(Debug)]
+pub enum Lookup {
+	/// A cached response is available and should be returned without calling the provider.
+	Hit(Hit),
+	/// No cached response is available. The provider's response should be captured with
+	/// [`Pending::capture`] so it can be served to later requests.
+	Miss(Pending),
+}
+
+impl ResponseCache {
+	/// Look up the request in the cache. Returns `None` if the request bypasses the cache.
+	/// The request body must already be translated for the provider, since it is part of the key.
+	/// Requests without an exact match are then looked up in the semantic cache, if enabled.
+	pub async fn lookup(
+		&self,
+		req: &mut http::Request,
+		llm_req: &LLMRequest,
+		client: &PolicyClient,
+	) -> Result<Option<Lookup>, ProxyError> {
+		if req.headers_mut().remove(&self.spec.bypass_header).is_some() {
+			trace!("response cache bypassed by request header");
+			return Ok(None);
+		}
+		let Some(partition) = self.partition(req) else {
+			return Ok(None);
+		};
+		let body = http::inspect_body(req)
+			.await
+			.map_err(ProxyError::Processing)?;
+		let key = cache_key(llm_req, &partition, req.uri().path(), &body);
+		if let Some(entry) = self.store.get(&key) {
+			if entry.expires_at > Instant::now() {
+				return Ok(Some(Lookup::Hit(Hit {
+					entry,
+					similarity: None,
+				})));
+			}
+			self.store.remove(&key);
+		}
+		let semantic = match &self.spec.semantic {
+			Some(semantic) => semantic.lookup(req, llm_req, client).await,
+			None => None,
+		};
+		Ok(Some(self.semantic_result(key, semantic)))
+	}
+
+	fn partition(&self, req: &http::Request) -> Option<String> {
+		let Some(expr) = self.spec.key.as_deref() else {
+			return Some(String::new());
+		};
+		let exec = Executor::new_request(req);
+		match exec
+			.eval(expr)
+			.and_then(|v| v.as_string().map_err(Into::into))
+		{
+			Ok(key) => Some(key),
+			Err(e) => {
+				trace!(
+					"response cache key evaluation failed, skipping: expr={:?}, error={}",
+					expr, e
+				);
+				None
+			},
+		}
+	}
+
+	fn semantic_result(&self, key: Key, semantic: Option<semantic_cache::Lookup>) -> Lookup {
+		let semantic = match semantic {
+			Some(semantic_cache::Lookup::Hit {
+				similarity,
+				body,
+				response,
+			}) => {
+				let mut headers = HeaderMap::new();
+				headers.insert(
+					header::CONTENT_TYPE,
+					::http::HeaderValue::from_static("application/json"),
+				);
+				return Lookup::Hit(Hit {
+					entry: Arc::new(Entry {
+						status: StatusCode::OK,
+						headers,
+						body,
+						response,
+						expires_at: Instant::now(),
+					}),
+					similarity: Some(similarity),
+				});
+			},
+			Some(semantic_cache::Lookup::Miss(pending)) => Some(pending),
+			None => None,
+		};
+		Lookup::Miss(Pending {
+			cache: self.clone(),
+			key,
+			semantic,
+		})
+	}
+
+	fn insert(&self, key: Key, entry: Entry) {
+		if entry.body.len() > self.spec.max_entry_bytes {
+			return;
+		}
+		self.store.insert(key, Arc::new(entry));
+	}
+}
+
+/// Build the cache key for a request.
+/// JSON bodies are hashed with object keys in sorted order, so requests that differ only in field
+/// order share an entry.
+fn cache_key(llm_req: &LLMRequest, partition: &str, path: &str, body: &[u8]) -> Key {
+	let mut hasher = Sha256::new();
+	for part in [
+		partition,
+		llm_req.provider.as_str(),
+		llm_req.request_model.as_str(),
+		path,
+	] {
+		hash_str(&mut hasher, part);
+	}
+	match serde_json::from_slice::<serde_json::Value>(body) {
+		Ok(value) => hash_json(&mut hasher, &value),
+		Err(_) => hasher.update(body),
+	}
+	hasher.finalize().into()
+}
+
+fn hash_str(hasher: &mut Sha256, s: &str) {
+	// Length-prefix strings so adjacent values cannot run together.
+	hasher.update((s.len() as u64).to_le_bytes());
+	hasher.update(s.as_bytes());
+}
+
+fn hash_json(hasher: &mut Sha256, value: &serde_json::Value) {
+	use serde_json::Value;
+	match value {
+		Value::Object(map) => {
+			hasher.update(b"{");
+			let mut entries: Vec<_> = map.iter().collect();
+			entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+			for (k, v) in entries {
+				hash_str(hasher, k);
+				hash_json(hasher, v);
+			}
+			hasher.update(b"}");
+		},
+		Value::Array(items) => {
+			hasher.update(b"[");
+			for v in items {
+				hash_json(hasher, v);
+			}
+			hasher.update(b"]");
+		},
+		Value::String(s) => {
+			hasher.update(b"s");
+			hash_str(hasher, s);
+		},
+		other => {
+			hasher.update(b"v");
+			hash_str(hasher, &other.to_string());
+		},
+	}
+}
+
+/// A cached response.
+#[derive(Debug)]
+pub struct Hit {
+	entry: Arc<Entry>,
+	/// Set when the response was found by the semantic cache.
+	similarity: Option<f32>,
+}
+
+impl Hit {
+	/// Build the response to return to the client, along with the LLM info to record for it.
+	/// Token counts are those of the original response; cost is always zero.
+	pub fn into_response(
+		self,
+		request: LLMRequest,
+		model_catalog: &ModelCatalog,
+	) -> (http::Response, LLMInfo) {
+		let Hit { entry, similarity } = self;
+		let info = LLMInfo::new(
+			request,
+			LLMResponse {
+				response_cache: Some(ResponseCacheStatus::Hit),
+				response_cache_similarity: similarity,
+				..entry.response.clone()
+			},
+		);
+		let mut resp = ::http::Response::new(http::Body::from(entry.body.clone()));
+		*resp.status_mut() = entry.status;
+		*resp.headers_mut() = entry.headers.clone();
+		resp
+			.extensions_mut()
+			.insert(LLMContext::from_llm_info(info.clone(), Some(model_catalog)));
+		(resp, info)
+	}
+}
+
+/// A request that missed the cache, whose response may be stored once it completes.
+#[derive(Debug)]
+pub struct Pending {
+	cache: ResponseCache,
+	key: Key,
+	semantic: Option<semantic_cache::Pending>,
+}
+
+impl Pending {
+	/// Record the response as a cache miss, and store it in the cache once the body has been fully
+	/// sent to the client. Only successful responses are stored; streams that fail or are
+	/// abandoned part way through are not.
+	pub fn capture(self, resp: http::Response, log: AsyncLog<LLMInfo>) -> http::Response {
+		let similarity = self.semantic.as_ref().and_then(|s| s.similarity);
+		log.non_atomic_mutate(|info| {
+			info.response.response_cache = Some(ResponseCacheStatus::Miss);
+			info.response.response_cache_similarity = similarity;
+		});
+		let (mut parts, body) = resp.into_parts();
+		if let Some(ctx) = parts.extensions.get_mut::<LLMContext>() {
+			ctx.response_cache = Some(strng::new(ResponseCacheStatus::Miss.as_str()));
+			ctx.response_cache_similarity = similarity.map(f64::from);
+		}
+		// Output that failed structured output validation is not worth serving again.
+		let invalid_output = parts
+			.extensions
+			.get::<super::structured_output::Check>()
+			.is_some_and(|c| !c.valid);
+		if parts.status != StatusCode::OK || invalid_output {
+			return ::http::Response::from_parts(parts, body);
+		}
+		let headers = CACHED_HEADERS
+			.iter()
+			.filter_map(|h| parts.headers.get(h).map(|v| (h.clone(), v.clone())))
+			.collect();
+		let body = http::Body::new(CaptureBody {
+			body,
+			buffer: BytesMut::new(),
+			capture: Some(Capture {
+				cache: self.cache,
+				key: self.key,
+				semantic: self.semantic,
+				status: parts.status,
+				headers,
+				log,
+			}),
+		});
+		::http::Response::from_parts(parts, body)
+	}
+}
+
+struct Capture {
+	cache: ResponseCache,
+	key: Key,
+	semantic: Option<semantic_cache::Pending>,
+	status: StatusCode,
+	headers: HeaderMap,
+	log: AsyncLog<LLMInfo>,
+}
+
+impl Capture {
+	fn complete(self, body: Bytes) {
+		// The LLM info is complete once the body has been read, including usage parsed from the end
+		// of streams.
+		let Some(info) = self.log.load_clone() else {
+			return;
+		};
+		let response = LLMResponse {
+			first_token: None,
+			response_cache: None,
+			response_cache_similarity: None,
+			..info.response
+		};
+		// Semantic hits may be converted to another format, which requires an uncompressed body.
+		if let Some(semantic) = self.semantic
+			&& !self.headers.contains_key(header::CONTENT_ENCODING)
+		{
+			semantic.insert(body.clone(), response.clone(), self.cache.spec.ttl);
+		}
+		self.cache.insert(
+			self.key,
+			Entry {
+				status: self.status,
+				headers: self.headers,
+				body,
+				response,
+				expires_at: Instant::now() + self.cache.spec.ttl,
+			},
+		);
+	}
+}
+
+pin_project! {
+	struct CaptureBody {
+		#[pin]
+		body: http::Body,
+		buffer: BytesMut,
+		capture: Option<Capture>,
+	}
+}
+
+impl Body for CaptureBody {
+	type Data = Bytes;
+	type Error = http::Error;
+
+	fn poll_frame(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+		let mut this = self.project();
+		let frame = std::task::ready!(this.body.as_mut().poll_frame(cx));
+		match &frame {
+			Some(Ok(frame)) => {
+				if let Some(data) = frame.data_ref()
+					&& let Some(capture) = this.capture.as_ref()
+				{
+					if this.buffer.len() + data.len() > capture.cache.spec.max_entry_bytes {
+						trace!("response too large to cache");
+						*this.capture = None;
+						*this.buffer = BytesMut::new();
+					} else {
+						this.buffer.extend_from_slice(data);
+					}
+				}
+			},
+			Some(Err(_)) => {
+				*this.capture = None;
+			},
+			None => {},
+		}
+		// Callers may stop polling once the body reports the end of the stream, so complete as soon
+		// as that happens rather than waiting for the final `None`.
+		if (frame.is_none() || this.body.is_end_stream())
+			&& let Some(capture) = this.capture.take()
+		{
+			capture.complete(std::mem::take(this.buffer).freeze());
+		}
+		Poll::Ready(frame)
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.body.is_end_stream()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		self.body.size_hint()
+	}
+}
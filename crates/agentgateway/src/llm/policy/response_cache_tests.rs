@@ -0,0 +1,239 @@
+use serde_json::json;
+
+use super::*;
+use crate::test_helpers::proxymock::setup_proxy_test;
+
+fn cache(spec: serde_json::Value) -> ResponseCache {
+	serde_json::from_value(spec).unwrap()
+}
+
+fn request(body: serde_json::Value) -> http::Request {
+	::http::Request::builder()
+		.uri("http://example.com/v1/chat/completions")
+		.body(http::Body::from(serde_json::to_vec(&body).unwrap()))
+		.unwrap()
+}
+
+fn llm_request() -> LLMRequest {
+	LLMRequest {
+		input_tokens: Some(10),
+		input_format: crate::llm::InputFormat::Completions,
+		native_format: None,
+		cache_convention: crate::llm::CacheTokenConvention::InputIncludesCache,
+		request_model: "my-model".into(),
+		provider: "openai".into(),
+		streaming: false,
+		params: Default::default(),
+		prompt: None,
+	}
+}
+
+fn response_log() -> AsyncLog<LLMInfo> {
+	let log = AsyncLog::default();
+	log.store(Some(LLMInfo::new(
+		llm_request(),
+		LLMResponse {
+			input_tokens: Some(10),
+			output_tokens: Some(5),
+			..Default::default()
+		},
+	)));
+	log
+}
+
+fn response(status: StatusCode, body: http::Body) -> http::Response {
+	::http::Response::builder()
+		.status(status)
+		.header(header::CONTENT_TYPE, "application/json")
+		.header("x-request-id", "abc")
+		.body(body)
+		.unwrap()
+}
+
+async fn read(resp: http::Response) -> Bytes {
+	http::read_body_with_limit(resp.into_body(), usize::MAX)
+		.await
+		.unwrap()
+}
+
+fn client() -> PolicyClient {
+	PolicyClient::new(setup_proxy_test("{}").unwrap().pi)
+}
+
+async fn lookup(cache: &ResponseCache, body: serde_json::Value) -> Option<Lookup> {
+	cache
+		.lookup(&mut request(body), &llm_request(), &client())
+		.await
+		.unwrap()
+}
+
+async fn miss(cache: &ResponseCache, body: serde_json::Value) -> Pending {
+	match lookup(cache, body).await {
+		Some(Lookup::Miss(pending)) => pending,
+		other => panic!("expected miss, got {other:?}"),
+	}
+}
+
+#[test]
+fn key_ignores_field_order() {
+	let a = br#"{"model":"m","messages":[{"role":"user","content":"hi"}],"temperature":0.5}"#;
+	let b = br#"{"temperature":0.5,"messages":[{"content":"hi","role":"user"}],"model":"m"}"#;
+	let c = br#"{"model":"m","messages":[{"role":"user","content":"bye"}],"temperature":0.5}"#;
+	let req = llm_request();
+	assert_eq!(
+		cache_key(&req, "", "/v1/chat/completions", a),
+		cache_key(&req, "", "/v1/chat/completions", b)
+	);
+	assert_ne!(
+		cache_key(&req, "", "/v1/chat/completions", a),
+		cache_key(&req, "", "/v1/chat/completions", c)
+	);
+	assert_ne!(
+		cache_key(&req, "", "/v1/chat/completions", a),
+		cache_key(&req, "", "/v1/responses", a)
+	);
+}
+
+#[tokio::test]
+async fn miss_then_hit() {
+	let cache = cache(json!({"ttl": "1h"}));
+	let body = json!({"model": "my-model", "messages": [{"role": "user", "content": "hi"}]});
+
+	let log = response_log();
+	let resp = miss(&cache, body.clone()).await.capture(
+		response(StatusCode::OK, http::Body::from("completion")),
+		log.clone(),
+	);
+	assert_eq!(read(resp).await, "completion");
+	assert_eq!(
+		log.load_clone().unwrap().response.response_cache,
+		Some(ResponseCacheStatus::Miss)
+	);
+
+	let Some(Lookup::Hit(hit)) = lookup(&cache, body).await else {
+		panic!("expected hit");
+	};
+	let (resp, info) = hit.into_response(llm_request(), &ModelCatalog::default());
+	assert_eq!(resp.status(), StatusCode::OK);
+	assert_eq!(
+		resp.headers().get(header::CONTENT_TYPE).unwrap(),
+		"application/json"
+	);
+	assert!(resp.headers().get("x-request-id").is_none());
+	let ctx = resp.extensions().get::<LLMContext>().unwrap();
+	assert_eq!(ctx.response_cache.as_deref(), Some("hit"));
+	assert_eq!(ctx.output_tokens, Some(5));
+	assert_eq!(info.response.response_cache, Some(ResponseCacheStatus::Hit));
+	assert_eq!(read(resp).await, "completion");
+}
+
+#[tokio::test]
+async fn key_partitions_cache() {
+	let cache = cache(json!({"ttl": "1h", "key": "request.headers['x-user']"}));
+	let body = json!({"model": "my-model", "messages": [{"role": "user", "content": "hi"}]});
+	let lookup_as = |user: Option<&'static str>| {
+		let mut req = request(body.clone());
+		if let Some(user) = user {
+			req.headers_mut().insert("x-user", user.parse().unwrap());
+		}
+		let cache = &cache;
+		async move {
+			cache
+				.lookup(&mut req, &llm_request(), &client())
+				.await
+				.unwrap()
+		}
+	};
+
+	let Some(Lookup::Miss(pending)) = lookup_as(Some("alice")).await else {
+		panic!("expected miss");
+	};
+	let resp = pending.capture(
+		response(StatusCode::OK, http::Body::from("completion")),
+		response_log(),
+	);
+	read(resp).await;
+	assert!(matches!(
+		lookup_as(Some("alice")).await,
+		Some(Lookup::Hit(_))
+	));
+	assert!(matches!(
+		lookup_as(Some("bob")).await,
+		Some(Lookup::Miss(_))
+	));
+	// Requests without a key skip the cache.
+	assert!(lookup_as(None).await.is_none());
+}
+
+#[tokio::test]
+async fn streamed_responses_are_cached() {
+	let cache = cache(json!({"ttl": "1h"}));
+	let body = json!({"model": "my-model", "stream": true});
+
+	let chunks = ["data: a\n\n", "data: b\n\n", "data: [DONE]\n\n"]
+		.map(|c| Ok::<_, std::io::Error>(Bytes::from_static(c.as_bytes())));
+	let stream = http::Body::from_stream(futures_util::stream::iter(chunks));
+	let resp = miss(&cache, body.clone())
+		.await
+		.capture(response(StatusCode::OK, stream), response_log());
+	let sent = read(resp).await;
+
+	let Some(Lookup::Hit(hit)) = lookup(&cache, body).await else {
+		panic!("expected hit");
+	};
+	let (resp, _) = hit.into_response(llm_request(), &ModelCatalog::default());
+	assert_eq!(read(resp).await, sent);
+}
+
+#[tokio::test]
+async fn bypass_header_skips_cache() {
+	let cache = cache(json!({"ttl": "1h", "bypassHeader": "x-no-cache"}));
+	let mut req = request(json!({"model": "my-model"}));
+	req
+		.headers_mut()
+		.insert("x-no-cache", "true".parse().unwrap());
+	assert!(
+		cache
+			.lookup(&mut req, &llm_request(), &client())
+			.await
+			.unwrap()
+			.is_none()
+	);
+	assert!(
+		req.headers().get("x-no-cache").is_none(),
+		"bypass header is not forwarded"
+	);
+}
+
+#[tokio::test]
+async fn errors_and_large_responses_are_not_cached() {
+	let cache = cache(json!({"ttl": "1h", "maxEntryBytes": 8}));
+
+	let body = json!({"model": "my-model", "n": 1});
+	let resp = miss(&cache, body.clone()).await.capture(
+		response(StatusCode::TOO_MANY_REQUESTS, http::Body::from("busy")),
+		response_log(),
+	);
+	read(resp).await;
+	assert!(matches!(lookup(&cache, body).await, Some(Lookup::Miss(_))));
+
+	let body = json!({"model": "my-model", "n": 2});
+	let resp = miss(&cache, body.clone()).await.capture(
+		response(StatusCode::OK, http::Body::from("a very long completion")),
+		response_log(),
+	);
+	assert_eq!(read(resp).await, "a very long completion");
+	assert!(matches!(lookup(&cache, body).await, Some(Lookup::Miss(_))));
+}
+
+#[tokio::test]
+async fn expired_entries_miss() {
+	let cache = cache(json!({"ttl": "0s"}));
+	let body = json!({"model": "my-model"});
+	let resp = miss(&cache, body.clone()).await.capture(
+		response(StatusCode::OK, http::Body::from("completion")),
+		response_log(),
+	);
+	read(resp).await;
+	assert!(matches!(lookup(&cache, body).await, Some(Lookup::Miss(_))));
+}
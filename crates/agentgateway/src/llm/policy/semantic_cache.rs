@@ -0,0 +1,658 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use ::http::{Method, header};
+use anyhow::Context as _;
+use bytes::Bytes;
+use quick_cache::sync::Cache;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{Row, SqlitePool};
+
+use crate::cel::Executor;
+use crate::http::auth::BackendInfo;
+use crate::llm::policy::response_cache::ResponseCacheStatus;
+use crate::llm::{
+	AIProvider, InputFormat, LLMRequest, LLMResponse, RequestResult, RouteType, conversion,
+};
+use crate::proxy::httpproxy::PolicyClient;
+use crate::telemetry::metrics::{
+	Metrics, OutboundCallKind, OutboundCallSubtype, SemanticCacheLabels,
+};
+use crate::types::agent::{Backend, BackendTarget, BackendTrafficPolicy, ResourceName, Target};
+use crate::*;
+
+#[cfg(test)]
+#[path = "semantic_cache_tests.rs"]
+mod tests;
+
+const DEFAULT_THRESHOLD: f32 = 0.95;
+const DEFAULT_MAX_ENTRIES: usize = 1000;
+// Embeddings responses are small, but some providers return very large vectors as JSON.
+const MAX_EMBEDDINGS_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(with = "SemanticCacheSpec"))]
+#[derive(serde::Serialize)]
+pub struct SemanticCache {
+	#[serde(skip_serializing)]
+	index: Arc<Index>,
+	#[serde(flatten)]
+	pub spec: SemanticCacheSpec,
+}
+
+impl<'de> serde::Deserialize<'de> for SemanticCache {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		let spec = SemanticCacheSpec::deserialize(deserializer)?;
+		if !(0.0..=1.0).contains(&spec.threshold) {
+			return Err(serde::de::Error::custom(
+				"semantic cache threshold must be between 0 and 1",
+			));
+		}
+		Ok(SemanticCache::from(spec))
+	}
+}
+
+#[apply(schema!)]
+pub struct SemanticCacheSpec {
+	/// Provider used to compute embeddings of the last user message of each request.
+	pub embeddings: EmbeddingsBackend,
+	/// Minimum cosine similarity, between 0 and 1, between a request and a cached request for the
+	/// cached response to be served.
+	#[serde(default = "default_threshold")]
+	pub threshold: f32,
+	/// CEL expression used to split the cache into independent scopes, one per distinct value.
+	/// For example, `jwt.sub` never serves one user's cached responses to another.
+	/// If unset, all requests for the same provider and model share a scope.
+	/// If the expression fails to evaluate, the semantic cache is skipped for the request.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub key: Option<Arc<cel::Expression>>,
+	/// Where cached embeddings and responses are stored.
+	#[serde(default)]
+	pub store: SemanticStore,
+	/// Maximum number of responses kept per scope. When exceeded, the oldest responses are evicted.
+	#[serde(default = "default_max_entries")]
+	pub max_entries: usize,
+	/// Maximum number of scopes kept in memory. When exceeded, rarely used scopes are evicted first.
+	/// Only used with the `memory` store.
+	#[serde(default = "default_max_keys")]
+	pub max_keys: usize,
+}
+
+#[apply(schema!)]
+pub struct EmbeddingsBackend {
+	/// Provider used to compute embeddings. The provider must support the embeddings route, and should
+	/// set `model` to the embeddings model to use.
+	pub provider: AIProvider,
+	/// Host to send embeddings requests to, instead of the provider's default.
+	/// Required for custom providers.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub host_override: Option<Target>,
+	/// Backend policies used when calling the embeddings provider.
+	#[serde(
+		default,
+		deserialize_with = "crate::types::local::de_from_local_backend_policy",
+		skip_serializing_if = "Vec::is_empty"
+	)]
+	#[cfg_attr(
+		feature = "schema",
+		schemars(with = "Option<crate::types::local::SimpleLocalBackendPolicies>")
+	)]
+	pub policies: Vec<BackendTrafficPolicy>,
+}
+
+#[apply(schema!)]
+#[derive(Default)]
+pub enum SemanticStore {
+	/// Keep cached responses in memory. They are lost when the gateway restarts.
+	#[default]
+	Memory,
+	/// Keep cached responses in a local SQLite database, created if it does not exist.
+	Sqlite {
+		/// Path to the database file.
+		path: String,
+	},
+}
+
+fn default_threshold() -> f32 {
+	DEFAULT_THRESHOLD
+}
+
+fn default_max_entries() -> usize {
+	DEFAULT_MAX_ENTRIES
+}
+
+fn default_max_keys() -> usize {
+	http::localratelimit::DEFAULT_MAX_KEYS
+}
+
+impl From<SemanticCacheSpec> for SemanticCache {
+	fn from(spec: SemanticCacheSpec) -> Self {
+		let index = match &spec.store {
+			SemanticStore::Memory => Index::Memory(Cache::new(spec.max_keys.max(1))),
+			SemanticStore::Sqlite { path } => Index::Sqlite(SqliteIndex::new(path)),
+		};
+		SemanticCache {
+			index: Arc::new(index),
+			spec,
+		}
+	}
+}
+
+/// Request formats whose responses can be stored and replayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+	Completions,
+	Messages,
+	Responses,
+}
+
+impl Format {
+	fn from_input(format: InputFormat) -> Option<Format> {
+		match format {
+			InputFormat::Completions => Some(Format::Completions),
+			InputFormat::Messages => Some(Format::Messages),
+			InputFormat::Responses => Some(Format::Responses),
+			_ => None,
+		}
+	}
+
+	fn as_str(self) -> &'static str {
+		match self {
+			Format::Completions => "completions",
+			Format::Messages => "messages",
+			Format::Responses => "responses",
+		}
+	}
+
+	fn parse(s: &str) -> Option<Format> {
+		match s {
+			"completions" => Some(Format::Completions),
+			"messages" => Some(Format::Messages),
+			"responses" => Some(Format::Responses),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Debug)]
+struct Entry {
+	embedding: Vec<f32>,
+	format: Format,
+	body: Bytes,
+	response: LLMResponse,
+	expires_at: SystemTime,
+}
+
+/// The result of a semantic lookup.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub(super) enum Lookup {
+	/// A similar request was cached. The body is already in the caller's format.
+	Hit {
+		similarity: f32,
+		body: Bytes,
+		response: LLMResponse,
+	},
+	/// No cached request was similar enough. The response can be stored once it completes.
+	Miss(Pending),
+}
+
+impl SemanticCache {
+	/// Look up a cached response for a request similar to this one.
+	/// Returns `None` if the request is not eligible for semantic caching, or the embeddings
+	/// provider could not be reached; the request is then sent to the provider as usual.
+	pub(super) async fn lookup(
+		&self,
+		req: &http::Request,
+		llm_req: &LLMRequest,
+		client: &PolicyClient,
+	) -> Option<Lookup> {
+		// Streaming responses are replayed byte for byte, so they cannot be converted between
+		// formats; only complete responses are cached semantically.
+		if llm_req.streaming {
+			return None;
+		}
+		let format = Format::from_input(llm_req.input_format)?;
+		let text = last_user_message(llm_req)?;
+		let scope = self.scope(req, llm_req)?;
+		let embedding = match self.embed(client, text).await {
+			Ok(embedding) => embedding,
+			Err(e) => {
+				warn!("semantic cache embeddings request failed, skipping: {e}");
+				return None;
+			},
+		};
+		self
+			.find(scope, embedding, format, &client.inputs.metrics)
+			.await
+	}
+
+	/// Match an embedded request against the cached requests in its scope.
+	async fn find(
+		&self,
+		scope: String,
+		embedding: Vec<f32>,
+		format: Format,
+		metrics: &Metrics,
+	) -> Option<Lookup> {
+		let best = match self.index.search(&scope, &embedding).await {
+			Ok(best) => best,
+			Err(e) => {
+				warn!("semantic cache lookup failed, skipping: {e}");
+				return None;
+			},
+		};
+		let similarity = best.as_ref().map(|(similarity, _)| *similarity);
+		if let Some((similarity, entry)) = best
+			&& similarity >= self.spec.threshold
+		{
+			match replay(entry.format, &entry.body, format) {
+				Some(body) => {
+					observe(metrics, ResponseCacheStatus::Hit, similarity);
+					return Some(Lookup::Hit {
+						similarity,
+						body,
+						response: entry.response.clone(),
+					});
+				},
+				None => trace!(
+					"cached {} response cannot be served as {}",
+					entry.format.as_str(),
+					format.as_str()
+				),
+			}
+		}
+		if let Some(similarity) = similarity {
+			observe(metrics, ResponseCacheStatus::Miss, similarity);
+		}
+		Some(Lookup::Miss(Pending {
+			cache: self.clone(),
+			scope,
+			embedding,
+			format,
+			similarity,
+		}))
+	}
+
+	/// The scope a request is cached in. Requests are only matched against others in the same scope.
+	fn scope(&self, req: &http::Request, llm_req: &LLMRequest) -> Option<String> {
+		let key = match self.spec.key.as_deref() {
+			Some(expr) => {
+				let exec = Executor::new_request(req);
+				match exec
+					.eval(expr)
+					.and_then(|v| v.as_string().map_err(Into::into))
+				{
+					Ok(key) => key,
+					Err(e) => {
+						trace!(
+							"semantic cache key evaluation failed, skipping: expr={:?}, error={}",
+							expr, e
+						);
+						return None;
+					},
+				}
+			},
+			None => String::new(),
+		};
+		Some(format!(
+			"{}\0{}\0{}",
+			llm_req.provider, llm_req.request_model, key
+		))
+	}
+
+	/// Compute the normalized embedding of the text using the configured embeddings provider.
+	async fn embed(&self, client: &PolicyClient, text: &str) -> anyhow::Result<Vec<f32>> {
+		let backend = &self.spec.embeddings;
+		let provider = &backend.provider;
+		let (target, scheme, defaults) = match &backend.host_override {
+			// With an explicit host, TLS and auth come only from the configured policies.
+			Some(target) => (target.clone(), "http", Default::default()),
+			None => (
+				provider
+					.default_connector_target(RouteType::Embeddings)
+					.context("custom embeddings providers require a host override")?,
+				"https",
+				provider.default_connector_policies().unwrap_or_default(),
+			),
+		};
+		let req = ::http::Request::builder()
+			.uri(format!("{scheme}://{}/", target.hostport()))
+			.method(Method::POST)
+			.header(header::CONTENT_TYPE, "application/json")
+			.body(http::Body::from(serde_json::to_vec(
+				&serde_json::json!({ "input": text }),
+			)?))?;
+		let backend_info = BackendInfo {
+			target: BackendTarget::Backend {
+				name: strng::literal!("_semantic-cache-embeddings"),
+				namespace: Default::default(),
+				section: None,
+			},
+			call_target: target,
+			inputs: client.inputs.clone(),
+		};
+		let (mut req, llm_req) = match provider
+			.process_embeddings_request(&backend_info, None, req, false, &mut None)
+			.await?
+		{
+			RequestResult::Success(req, llm_req) => (req, llm_req),
+			RequestResult::Rejected(_) => anyhow::bail!("embeddings request was rejected"),
+		};
+		provider.setup_request(
+			&mut req,
+			RouteType::Embeddings,
+			Some(&llm_req),
+			None,
+			None,
+			backend.host_override.is_some(),
+		)?;
+		let resp = client
+			.with_outbound(OutboundCallKind::Policy, OutboundCallSubtype::Embeddings)
+			.call_with_default_policies(
+				req,
+				Backend::Dynamic(
+					ResourceName::new(
+						strng::literal!("_semantic-cache-embeddings"),
+						strng::literal!(""),
+					),
+					(),
+				),
+				defaults,
+				&backend.policies,
+			)
+			.await?;
+		let (parts, body) = resp.into_parts();
+		let bytes = http::read_body_with_limit(body, MAX_EMBEDDINGS_RESPONSE_BYTES).await?;
+		if !parts.status.is_success() {
+			anyhow::bail!("embeddings provider returned {}", parts.status);
+		}
+		let (_, bytes) = provider.process_embeddings_response(&llm_req, &parts.headers, bytes)?;
+		let resp: EmbeddingsResponse = serde_json::from_slice(&bytes)?;
+		let embedding = resp
+			.data
+			.into_iter()
+			.next()
+			.context("embeddings response has no data")?
+			.embedding;
+		Ok(normalize(embedding))
+	}
+}
+
+#[derive(serde::Deserialize)]
+struct EmbeddingsResponse {
+	data: Vec<EmbeddingsData>,
+}
+
+#[derive(serde::Deserialize)]
+struct EmbeddingsData {
+	embedding: Vec<f32>,
+}
+
+fn observe(metrics: &Metrics, result: ResponseCacheStatus, similarity: f32) {
+	metrics
+		.semantic_cache_similarity
+		.get_or_create(&SemanticCacheLabels { result })
+		.observe(similarity as f64);
+}
+
+fn last_user_message(llm_req: &LLMRequest) -> Option<&str> {
+	llm_req
+		.prompt
+		.as_deref()?
+		.iter()
+		.rev()
+		.find(|m| m.role == "user")
+		.map(|m| m.content.as_str())
+		.filter(|s| !s.is_empty())
+}
+
+/// Scale the vector to unit length, so cosine similarity is a dot product.
+fn normalize(mut v: Vec<f32>) -> Vec<f32> {
+	let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+	if norm > 0.0 {
+		v.iter_mut().for_each(|x| *x /= norm);
+	}
+	v
+}
+
+/// Cosine similarity of two normalized vectors. Vectors of different dimensions, such as those from
+/// a previously configured embeddings model, never match.
+fn similarity(a: &[f32], b: &[f32]) -> Option<f32> {
+	(a.len() == b.len()).then(|| a.iter().zip(b).map(|(x, y)| x * y).sum())
+}
+
+/// Convert a cached response body to the caller's format.
+fn replay(stored: Format, body: &Bytes, requested: Format) -> Option<Bytes> {
+	let translated = match (stored, requested) {
+		(a, b) if a == b => return Some(body.clone()),
+		(Format::Completions, Format::Messages) => {
+			conversion::completions::from_messages::translate_response(body)
+		},
+		(Format::Messages, Format::Completions) => {
+			conversion::messages::from_completions::translate_response(body)
+		},
+		_ => return None,
+	};
+	translated
+		.and_then(|resp| {
+			resp
+				.serialize()
+				.map_err(crate::llm::AIError::ResponseParsing)
+		})
+		.inspect_err(|e| debug!("failed to convert cached response: {e}"))
+		.ok()
+		.map(Bytes::from)
+}
+
+/// A request that missed the semantic cache, whose response may be stored once it completes.
+#[derive(Debug)]
+pub(super) struct Pending {
+	cache: SemanticCache,
+	scope: String,
+	embedding: Vec<f32>,
+	format: Format,
+	/// The similarity of the closest cached request, if any.
+	pub(super) similarity: Option<f32>,
+}
+
+impl Pending {
+	pub(super) fn insert(self, body: Bytes, response: LLMResponse, ttl: Duration) {
+		let entry = Entry {
+			embedding: self.embedding,
+			format: self.format,
+			body,
+			response,
+			expires_at: SystemTime::now() + ttl,
+		};
+		self
+			.cache
+			.index
+			.insert(self.scope, entry, self.cache.spec.max_entries.max(1));
+	}
+}
+
+// Entries in a scope, oldest first.
+type Entries = Arc<Mutex<VecDeque<Arc<Entry>>>>;
+
+#[derive(Debug)]
+enum Index {
+	Memory(Cache<String, Entries>),
+	Sqlite(SqliteIndex),
+}
+
+impl Index {
+	/// Find the most similar unexpired entry in the scope.
+	async fn search(
+		&self,
+		scope: &str,
+		embedding: &[f32],
+	) -> anyhow::Result<Option<(f32, Arc<Entry>)>> {
+		let now = SystemTime::now();
+		let candidates = match self {
+			Index::Memory(scopes) => {
+				let Some(entries) = scopes.get(scope) else {
+					return Ok(None);
+				};
+				let mut entries = entries.lock().expect("mutex poisoned");
+				entries.retain(|e| e.expires_at > now);
+				entries.iter().cloned().collect::<Vec<_>>()
+			},
+			Index::Sqlite(sqlite) => sqlite.load(scope, now).await?,
+		};
+		Ok(
+			candidates
+				.into_iter()
+				.filter_map(|e| similarity(embedding, &e.embedding).map(|s| (s, e)))
+				.max_by(|(a, _), (b, _)| a.total_cmp(b)),
+		)
+	}
+
+	fn insert(&self, scope: String, entry: Entry, max_entries: usize) {
+		match self {
+			Index::Memory(scopes) => {
+				let Ok(entries) =
+					scopes.get_or_insert_with(&scope, || Ok::<_, std::convert::Infallible>(Arc::default()));
+				let mut entries = entries.lock().expect("mutex poisoned");
+				entries.push_back(Arc::new(entry));
+				while entries.len() > max_entries {
+					entries.pop_front();
+				}
+			},
+			Index::Sqlite(sqlite) => {
+				let sqlite = sqlite.clone();
+				tokio::spawn(async move {
+					if let Err(e) = sqlite.insert(&scope, &entry, max_entries).await {
+						warn!("failed to store semantic cache entry: {e}");
+					}
+				});
+			},
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+struct SqliteIndex {
+	path: String,
+	pool: Arc<tokio::sync::OnceCell<SqlitePool>>,
+}
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS semantic_cache (
+	id INTEGER PRIMARY KEY AUTOINCREMENT,
+	scope TEXT NOT NULL,
+	embedding BLOB NOT NULL,
+	format TEXT NOT NULL,
+	body BLOB NOT NULL,
+	response TEXT NOT NULL,
+	expires_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS semantic_cache_scope ON semantic_cache (scope, id);
+"#;
+
+impl SqliteIndex {
+	fn new(path: &str) -> Self {
+		Self {
+			path: path.to_string(),
+			pool: Default::default(),
+		}
+	}
+
+	// Connect on first use, since config is loaded outside of an async context.
+	async fn pool(&self) -> anyhow::Result<&SqlitePool> {
+		self
+			.pool
+			.get_or_try_init(|| async {
+				let options = SqliteConnectOptions::new()
+					.filename(&self.path)
+					.create_if_missing(true)
+					.journal_mode(SqliteJournalMode::Wal)
+					.busy_timeout(Duration::from_secs(5));
+				let pool = SqlitePoolOptions::new()
+					.max_connections(4)
+					.connect_with(options)
+					.await
+					.context("failed to connect semantic cache sqlite database")?;
+				sqlx::raw_sql(SCHEMA).execute(&pool).await?;
+				Ok(pool)
+			})
+			.await
+	}
+
+	async fn load(&self, scope: &str, now: SystemTime) -> anyhow::Result<Vec<Arc<Entry>>> {
+		let rows = sqlx::query(
+			"SELECT embedding, format, body, response, expires_at FROM semantic_cache \
+			 WHERE scope = ? AND expires_at > ?",
+		)
+		.bind(scope)
+		.bind(unix_millis(now))
+		.fetch_all(self.pool().await?)
+		.await?;
+		let entries = rows
+			.into_iter()
+			.filter_map(|row| {
+				let embedding: Vec<u8> = row.try_get("embedding").ok()?;
+				let format: String = row.try_get("format").ok()?;
+				let body: Vec<u8> = row.try_get("body").ok()?;
+				let response: String = row.try_get("response").ok()?;
+				let expires_at: i64 = row.try_get("expires_at").ok()?;
+				Some(Arc::new(Entry {
+					embedding: embedding
+						.chunks_exact(4)
+						.map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+						.collect(),
+					format: Format::parse(&format)?,
+					body: Bytes::from(body),
+					response: serde_json::from_str(&response).ok()?,
+					expires_at: SystemTime::UNIX_EPOCH + Duration::from_millis(expires_at.max(0) as u64),
+				}))
+			})
+			.collect();
+		Ok(entries)
+	}
+
+	async fn insert(&self, scope: &str, entry: &Entry, max_entries: usize) -> anyhow::Result<()> {
+		let embedding = entry
+			.embedding
+			.iter()
+			.flat_map(|x| x.to_le_bytes())
+			.collect::<Vec<u8>>();
+		let mut tx = self.pool().await?.begin().await?;
+		sqlx::query(
+			"INSERT INTO semantic_cache (scope, embedding, format, body, response, expires_at) \
+			 VALUES (?, ?, ?, ?, ?, ?)",
+		)
+		.bind(scope)
+		.bind(embedding)
+		.bind(entry.format.as_str())
+		.bind(entry.body.to_vec())
+		.bind(serde_json::to_string(&entry.response)?)
+		.bind(unix_millis(entry.expires_at))
+		.execute(&mut *tx)
+		.await?;
+		// Drop expired entries, and the oldest entries beyond the per-scope limit.
+		sqlx::query(
+			"DELETE FROM semantic_cache WHERE scope = ? AND (expires_at <= ? OR id NOT IN \
+			 (SELECT id FROM semantic_cache WHERE scope = ? ORDER BY id DESC LIMIT ?))",
+		)
+		.bind(scope)
+		.bind(unix_millis(SystemTime::now()))
+		.bind(scope)
+		.bind(max_entries as i64)
+		.execute(&mut *tx)
+		.await?;
+		tx.commit().await?;
+		Ok(())
+	}
+}
+
+fn unix_millis(t: SystemTime) -> i64 {
+	t.duration_since(SystemTime::UNIX_EPOCH)
+		.map(|d| d.as_millis() as i64)
+		.unwrap_or_default()
+}
@@ -0,0 +1,213 @@
+use prometheus_client::registry::Registry;
+use serde_json::json;
+
+use super::*;
+
+fn cache(spec: serde_json::Value) -> SemanticCache {
+	let mut config = json!({
+		"embeddings": {"provider": {"openAI": {"model": "text-embedding-3-small"}}},
+	});
+	config
+		.as_object_mut()
+		.unwrap()
+		.extend(spec.as_object().unwrap().clone());
+	serde_json::from_value(config).unwrap()
+}
+
+fn metrics() -> Metrics {
+	Metrics::new(&mut Registry::default(), Default::default())
+}
+
+const COMPLETION: &str = r#"{"id":"chatcmpl-1","object":"chat.completion","created":1,"model":"gpt-4o","choices":[{"index":0,"message":{"role":"assistant","content":"Paris"},"finish_reason":"stop"}],"usage":{"prompt_tokens":5,"completion_tokens":1,"total_tokens":6}}"#;
+
+fn response() -> LLMResponse {
+	LLMResponse {
+		input_tokens: Some(5),
+		output_tokens: Some(1),
+		..Default::default()
+	}
+}
+
+async fn find(cache: &SemanticCache, scope: &str, embedding: Vec<f32>, format: Format) -> Lookup {
+	cache
+		.find(scope.to_string(), normalize(embedding), format, &metrics())
+		.await
+		.unwrap()
+}
+
+async fn store(cache: &SemanticCache, scope: &str, embedding: Vec<f32>, format: Format) {
+	let Lookup::Miss(pending) = find(cache, scope, embedding, format).await else {
+		panic!("expected miss");
+	};
+	pending.insert(
+		Bytes::from_static(COMPLETION.as_bytes()),
+		response(),
+		Duration::from_secs(3600),
+	);
+}
+
+#[test]
+fn similarity_of_normalized_vectors() {
+	let a = normalize(vec![3.0, 4.0]);
+	assert_eq!(a, vec![0.6, 0.8]);
+	assert!((similarity(&a, &a).unwrap() - 1.0).abs() < 1e-6);
+	assert_eq!(similarity(&a, &normalize(vec![-4.0, 3.0])), Some(0.0));
+	assert_eq!(similarity(&a, &[1.0, 0.0, 0.0]), None);
+	assert_eq!(normalize(vec![0.0, 0.0]), vec![0.0, 0.0]);
+}
+
+#[test]
+fn threshold_must_be_a_similarity() {
+	let config = json!({
+		"embeddings": {"provider": {"openAI": {}}},
+		"threshold": 1.5,
+	});
+	assert!(serde_json::from_value::<SemanticCache>(config).is_err());
+}
+
+#[tokio::test]
+async fn similar_requests_hit() {
+	let cache = cache(json!({"threshold": 0.9}));
+	store(&cache, "scope", vec![1.0, 0.1], Format::Completions).await;
+
+	let Lookup::Hit {
+		similarity,
+		body,
+		response,
+	} = find(&cache, "scope", vec![1.0, 0.2], Format::Completions).await
+	else {
+		panic!("expected hit");
+	};
+	assert!(similarity > 0.99);
+	assert_eq!(body, COMPLETION);
+	assert_eq!(response.output_tokens, Some(1));
+
+	// Dissimilar requests miss, but still report the closest match.
+	let Lookup::Miss(pending) = find(&cache, "scope", vec![0.1, 1.0], Format::Completions).await
+	else {
+		panic!("expected miss");
+	};
+	assert!(pending.similarity.unwrap() < 0.9);
+}
+
+#[tokio::test]
+async fn scopes_are_isolated() {
+	let cache = cache(json!({}));
+	store(&cache, "tenant-a", vec![1.0, 0.0], Format::Completions).await;
+	let Lookup::Miss(pending) = find(&cache, "tenant-b", vec![1.0, 0.0], Format::Completions).await
+	else {
+		panic!("expected miss");
+	};
+	assert_eq!(pending.similarity, None);
+}
+
+#[test]
+fn scope_from_key_expression() {
+	let cache = cache(json!({"key": "request.headers[\"x-tenant\"]"}));
+	let llm_req = LLMRequest {
+		input_tokens: None,
+		input_format: InputFormat::Completions,
+		native_format: None,
+		cache_convention: crate::llm::CacheTokenConvention::InputIncludesCache,
+		request_model: "gpt-4o".into(),
+		provider: "openai".into(),
+		streaming: false,
+		params: Default::default(),
+		prompt: None,
+	};
+	let req = |tenant: Option<&str>| {
+		let mut rb = ::http::Request::builder().uri("http://example.com/v1/chat/completions");
+		if let Some(tenant) = tenant {
+			rb = rb.header("x-tenant", tenant);
+		}
+		rb.body(http::Body::empty()).unwrap()
+	};
+	let a = cache.scope(&req(Some("a")), &llm_req).unwrap();
+	let b = cache.scope(&req(Some("b")), &llm_req).unwrap();
+	assert_ne!(a, b);
+	assert_eq!(a, cache.scope(&req(Some("a")), &llm_req).unwrap());
+	assert_eq!(cache.scope(&req(None), &llm_req), None);
+}
+
+#[tokio::test]
+async fn hits_are_replayed_in_the_callers_format() {
+	let cache = cache(json!({}));
+	store(&cache, "scope", vec![1.0, 0.0], Format::Completions).await;
+
+	let Lookup::Hit { body, .. } = find(&cache, "scope", vec![1.0, 0.0], Format::Messages).await
+	else {
+		panic!("expected hit");
+	};
+	let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+	assert_eq!(body["type"], "message");
+	assert_eq!(body["content"][0]["text"], "Paris");
+
+	// There is no conversion from completions to responses, so the cached entry is not used.
+	assert!(matches!(
+		find(&cache, "scope", vec![1.0, 0.0], Format::Responses).await,
+		Lookup::Miss(_)
+	));
+}
+
+#[tokio::test]
+async fn oldest_entries_are_evicted() {
+	let cache = cache(json!({"maxEntries": 1}));
+	store(&cache, "scope", vec![1.0, 0.0], Format::Completions).await;
+	store(&cache, "scope", vec![0.0, 1.0], Format::Completions).await;
+	assert!(matches!(
+		find(&cache, "scope", vec![1.0, 0.0], Format::Completions).await,
+		Lookup::Miss(_)
+	));
+	assert!(matches!(
+		find(&cache, "scope", vec![0.0, 1.0], Format::Completions).await,
+		Lookup::Hit { .. }
+	));
+}
+
+#[tokio::test]
+async fn sqlite_store() {
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("cache.db");
+	let cache = cache(json!({"store": {"sqlite": {"path": path.to_str().unwrap()}}}));
+	let Index::Sqlite(sqlite) = cache.index.as_ref() else {
+		panic!("expected sqlite index");
+	};
+	let entry = |embedding: Vec<f32>, ttl: Duration| Entry {
+		embedding: normalize(embedding),
+		format: Format::Completions,
+		body: Bytes::from_static(COMPLETION.as_bytes()),
+		response: response(),
+		expires_at: SystemTime::now() + ttl,
+	};
+	sqlite
+		.insert(
+			"scope",
+			&entry(vec![1.0, 0.0], Duration::from_secs(3600)),
+			10,
+		)
+		.await
+		.unwrap();
+	sqlite
+		.insert("scope", &entry(vec![0.0, 1.0], Duration::ZERO), 10)
+		.await
+		.unwrap();
+
+	let Lookup::Hit {
+		similarity,
+		body,
+		response,
+	} = find(&cache, "scope", vec![1.0, 0.0], Format::Completions).await
+	else {
+		panic!("expected hit");
+	};
+	assert!((similarity - 1.0).abs() < 1e-6);
+	assert_eq!(body, COMPLETION);
+	assert_eq!(response.input_tokens, Some(5));
+
+	// Expired entries are ignored.
+	let Lookup::Miss(pending) = find(&cache, "scope", vec![0.0, 1.0], Format::Completions).await
+	else {
+		panic!("expected miss");
+	};
+	assert!(pending.similarity.unwrap() < 0.01);
+}
@@ -0,0 +1,421 @@
+//! Shadow evaluation of alternate models.
+//!
+//! A sample of requests is replayed in the background to a candidate model, converted to the
+//! candidate provider's format. Once both have completed, the candidate's result is recorded in the
+//! request log store alongside the original response, without affecting what the client receives.
+
+use std::time::{Duration, Instant};
+
+use ::http::{Method, StatusCode, header};
+use anyhow::Context as _;
+use chrono::{DateTime, Utc};
+use rand::RngExt;
+use rust_decimal::prelude::ToPrimitive;
+use serde_json::{Map, Value, json};
+use tokio::sync::oneshot;
+
+use crate::cel::LLMContext;
+use crate::http::auth::BackendInfo;
+use crate::llm::{
+	AIProvider, InputFormat, LLMInfo, RequestResult, RequestType, RouteType,
+	SimpleChatCompletionMessage,
+};
+use crate::proxy::httpproxy::PolicyClient;
+use crate::telemetry::log_store;
+use crate::telemetry::metrics::{OutboundCallKind, OutboundCallSubtype};
+use crate::types::agent::{Backend, BackendTarget, BackendTrafficPolicy, ResourceName, Target};
+use crate::*;
+
+const MAX_SHADOW_RESPONSE_BYTES: usize = 4 * 1024 * 1024;
+
+#[apply(schema!)]
+pub struct Shadow {
+	/// Provider of the candidate model. Set `model` to the candidate model.
+	pub provider: AIProvider,
+	/// Host to send shadow requests to, instead of the provider's default.
+	/// Required for custom providers.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub host_override: Option<Target>,
+	/// Backend policies used when calling the candidate.
+	#[serde(
+		default,
+		deserialize_with = "crate::types::local::de_from_local_backend_policy",
+		skip_serializing_if = "Vec::is_empty"
+	)]
+	#[cfg_attr(
+		feature = "schema",
+		schemars(with = "Option<crate::types::local::SimpleLocalBackendPolicies>")
+	)]
+	pub policies: Vec<BackendTrafficPolicy>,
+	/// Fraction of requests to replay, from 0.0 to 1.0.
+	pub percentage: f64,
+}
+
+/// The outcome of the request being shadowed, reported once its response completes.
+#[derive(Debug)]
+pub struct Primary {
+	pub status: Option<StatusCode>,
+	pub duration: Duration,
+	pub trace_id: Option<String>,
+	pub span_id: Option<String>,
+	pub llm: Option<LLMContext>,
+}
+
+/// Reports the outcome of the request being shadowed, so it is recorded along with the candidate's.
+#[derive(Debug)]
+pub struct PrimaryObserver(oneshot::Sender<Primary>);
+
+impl PrimaryObserver {
+	pub fn observe(self, primary: Primary) {
+		let _ = self.0.send(primary);
+	}
+}
+
+/// The candidate's result.
+struct Candidate {
+	started_at: DateTime<Utc>,
+	duration: Duration,
+	status: Option<StatusCode>,
+	llm: Option<LLMContext>,
+	error: Option<String>,
+}
+
+impl Shadow {
+	/// Start replaying `req` to the candidate if it is sampled. Returns the observer the outcome of
+	/// the original request must be reported to.
+	pub fn start(
+		&self,
+		backend_info: &BackendInfo,
+		format: InputFormat,
+		req: &dyn RequestType,
+	) -> Option<PrimaryObserver> {
+		// Without a log store there is nowhere to record the comparison.
+		if !log_store::enabled() || !rand::rng().random_bool(self.percentage.clamp(0.0, 1.0)) {
+			return None;
+		}
+		let body = match format {
+			InputFormat::Completions | InputFormat::Responses => req.to_openai(),
+			InputFormat::Messages => req.to_anthropic(),
+			_ => return None,
+		};
+		let body = match body
+			.map_err(anyhow::Error::from)
+			.and_then(|b| replay_body(&b))
+		{
+			Ok(body) => body,
+			Err(e) => {
+				debug!("unable to shadow request: {e}");
+				return None;
+			},
+		};
+		let prompt = req.get_messages();
+		let client = PolicyClient::new(backend_info.inputs.clone());
+		let shadow = self.clone();
+		let (tx, rx) = oneshot::channel();
+		tokio::task::spawn(async move {
+			let started_at = Utc::now();
+			let start = Instant::now();
+			let result = shadow.replay(&client, format, body).await;
+			let duration = start.elapsed();
+			let candidate = match result {
+				Ok((status, llm)) => Candidate {
+					started_at,
+					duration,
+					status: Some(status),
+					llm,
+					error: None,
+				},
+				Err(e) => {
+					debug!("shadow request failed: {e}");
+					Candidate {
+						started_at,
+						duration,
+						status: None,
+						llm: None,
+						error: Some(e.to_string()),
+					}
+				},
+			};
+			// The original request may have been abandoned before completing.
+			let primary = rx.await.ok();
+			log_store::emit(record(&prompt, candidate, primary));
+		});
+		Some(PrimaryObserver(tx))
+	}
+
+	/// Send the request to the candidate. Responses other than success have no LLM result.
+	async fn replay(
+		&self,
+		client: &PolicyClient,
+		format: InputFormat,
+		body: Vec<u8>,
+	) -> anyhow::Result<(StatusCode, Option<LLMContext>)> {
+		let provider = &self.provider;
+		let route_type = match format {
+			InputFormat::Completions => RouteType::Completions,
+			InputFormat::Messages => RouteType::Messages,
+			InputFormat::Responses => RouteType::Responses,
+			_ => anyhow::bail!("{format:?} requests cannot be shadowed"),
+		};
+		let (target, scheme, defaults) = match &self.host_override {
+			// With an explicit host, TLS and auth come only from the configured policies.
+			Some(target) => (target.clone(), "http", Default::default()),
+			None => (
+				provider
+					.default_connector_target(route_type)
+					.context("custom shadow providers require a host override")?,
+				"https",
+				provider.default_connector_policies().unwrap_or_default(),
+			),
+		};
+		let req = ::http::Request::builder()
+			.uri(format!("{scheme}://{}/", target.hostport()))
+			.method(Method::POST)
+			.header(header::CONTENT_TYPE, "application/json")
+			.body(http::Body::from(body))?;
+		let backend_info = BackendInfo {
+			target: BackendTarget::Backend {
+				name: strng::literal!("_llm-shadow"),
+				namespace: Default::default(),
+				section: None,
+			},
+			call_target: target,
+			inputs: client.inputs.clone(),
+		};
+		let result = match format {
+			InputFormat::Messages => {
+				provider
+					.process_messages_request(&backend_info, None, req, None, &mut None)
+					.await?
+			},
+			InputFormat::Responses => {
+				provider
+					.process_responses_request(&backend_info, None, req, None, &mut None)
+					.await?
+			},
+			_ => {
+				provider
+					.process_completions_request(&backend_info, None, req, None, &mut None)
+					.await?
+			},
+		};
+		let (mut req, llm_req) = match result {
+			RequestResult::Success(req, llm_req) => (req, llm_req),
+			RequestResult::Rejected(_) => anyhow::bail!("shadow request was rejected"),
+		};
+		provider.setup_request(
+			&mut req,
+			route_type,
+			Some(&llm_req),
+			None,
+			None,
+			self.host_override.is_some(),
+		)?;
+		let resp = client
+			.with_outbound(OutboundCallKind::Mirror, OutboundCallSubtype::Llm)
+			.call_with_default_policies(
+				req,
+				Backend::Dynamic(
+					ResourceName::new(strng::literal!("_llm-shadow"), strng::literal!("")),
+					(),
+				),
+				defaults,
+				&self.policies,
+			)
+			.await?;
+		let (parts, body) = resp.into_parts();
+		let bytes = http::read_body_with_limit(body, MAX_SHADOW_RESPONSE_BYTES).await?;
+		if !parts.status.is_success() {
+			return Ok((parts.status, None));
+		}
+		let llm_resp = provider
+			.process_success(&llm_req, &bytes)?
+			.to_llm_response(true);
+		let info = LLMInfo::new(llm_req, llm_resp);
+		let llm = LLMContext::from_llm_info(info, Some(client.inputs.model_catalog.as_ref()));
+		Ok((parts.status, Some(llm)))
+	}
+}
+
+/// Prepare a request body for replay. Responses are always read whole, so streaming is turned off.
+fn replay_body(body: &[u8]) -> anyhow::Result<Vec<u8>> {
+	let mut body: Value = serde_json::from_slice(body)?;
+	let obj = body
+		.as_object_mut()
+		.context("request body is not an object")?;
+	obj.remove("stream");
+	obj.remove("stream_options");
+	Ok(serde_json::to_vec(&body)?)
+}
+
+fn cost(llm: &LLMContext) -> Option<f64> {
+	llm.cost.as_ref().and_then(|c| c.total().to_f64())
+}
+
+fn total_tokens(llm: &LLMContext) -> Option<u64> {
+	llm
+		.total_tokens
+		.or_else(|| Some(llm.input_tokens? + llm.output_tokens?))
+}
+
+fn to_i64(value: Option<u64>) -> Option<i64> {
+	value.map(|value| value.min(i64::MAX as u64) as i64)
+}
+
+/// The log record for a shadowed request. The candidate's result fills the record, and the
+/// original's is recorded under `agw.ai.shadow.primary.*` attributes so the two can be compared.
+fn record(
+	prompt: &[SimpleChatCompletionMessage],
+	candidate: Candidate,
+	primary: Option<Primary>,
+) -> log_store::StoredRequestLog {
+	let mut attributes = Map::new();
+	attributes.insert("agw.ai.shadow".to_string(), Value::Bool(true));
+	if let Some(primary) = &primary {
+		let mut put = |key: &str, value: Value| {
+			if !value.is_null() {
+				attributes.insert(format!("agw.ai.shadow.primary.{key}"), value);
+			}
+		};
+		put("status", json!(primary.status.map(|s| s.as_u16())));
+		put("durationMs", json!(primary.duration.as_millis() as u64));
+		if let Some(llm) = &primary.llm {
+			put("provider", json!(llm.provider));
+			put("requestModel", json!(llm.request_model));
+			put("responseModel", json!(llm.response_model));
+			put("inputTokens", json!(llm.input_tokens));
+			put("outputTokens", json!(llm.output_tokens));
+			put("totalTokens", json!(total_tokens(llm)));
+			put("cost", json!(cost(llm)));
+			put("completion", json!(llm.completion));
+		}
+	}
+	let llm = candidate.llm.as_ref();
+	let request_prompt_json = serde_json::to_value(prompt).ok();
+	let response_completion_json = llm
+		.and_then(|llm| llm.completion.as_ref())
+		.and_then(|completion| serde_json::to_value(completion).ok());
+	let payload = (request_prompt_json.is_some() || response_completion_json.is_some()).then_some(
+		log_store::StoredRequestLogPayload {
+			request_prompt_json,
+			response_completion_json,
+		},
+	);
+	let completed_at =
+		candidate.started_at + chrono::Duration::from_std(candidate.duration).unwrap_or_default();
+	log_store::StoredRequestLog {
+		id: uuid::Uuid::new_v4().to_string(),
+		started_at: candidate.started_at,
+		completed_at,
+		duration_ms: candidate.duration.as_millis().min(i64::MAX as u128) as i64,
+		// The trace of the original request, to find the two together.
+		trace_id: primary.as_ref().and_then(|p| p.trace_id.clone()),
+		span_id: primary.as_ref().and_then(|p| p.span_id.clone()),
+		http_status: candidate.status.map(|s| i64::from(s.as_u16())),
+		error: candidate.error,
+		gen_ai_operation_name: Some("shadow".to_string()),
+		gen_ai_provider_name: llm.map(|llm| llm.provider.to_string()),
+		gen_ai_request_model: llm.map(|llm| llm.request_model.to_string()),
+		gen_ai_response_model: llm.and_then(|llm| llm.response_model.as_ref().map(ToString::to_string)),
+		input_tokens: to_i64(llm.and_then(|llm| llm.input_tokens)),
+		output_tokens: to_i64(llm.and_then(|llm| llm.output_tokens)),
+		total_tokens: to_i64(llm.and_then(total_tokens)),
+		cost: llm.and_then(cost),
+		agentgateway_user: None,
+		agentgateway_group: None,
+		user_agent_name: None,
+		has_payload: payload.is_some(),
+		attributes_json: Value::Object(attributes),
+		payload,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn replay_is_not_streamed() {
+		let body = replay_body(
+			br#"{"model":"gpt-4o","stream":true,"stream_options":{"include_usage":true},"messages":[]}"#,
+		)
+		.unwrap();
+		assert_eq!(
+			serde_json::from_slice::<Value>(&body).unwrap(),
+			json!({"model": "gpt-4o", "messages": []})
+		);
+		assert!(replay_body(b"[]").is_err());
+	}
+
+	#[test]
+	fn record_compares_candidate_with_primary() {
+		let llm = |provider: &str, model: &str, completion: &str| {
+			let mut llm: LLMContext = serde_json::from_value(json!({
+				"streaming": false,
+				"requestModel": model,
+				"provider": provider,
+				"inputTokens": 10,
+				"outputTokens": 5,
+				"params": {},
+			}))
+			.unwrap();
+			llm.completion = Some(vec![completion.to_string()]);
+			llm
+		};
+		let candidate = Candidate {
+			started_at: Utc::now(),
+			duration: Duration::from_millis(250),
+			status: Some(StatusCode::OK),
+			llm: Some(llm("anthropic", "claude-haiku-4-5", "Hi!")),
+			error: None,
+		};
+		let primary = Primary {
+			status: Some(StatusCode::OK),
+			duration: Duration::from_millis(400),
+			trace_id: Some("trace".to_string()),
+			span_id: None,
+			llm: Some(llm("openai", "gpt-4o", "Hello!")),
+		};
+		let prompt = [SimpleChatCompletionMessage {
+			role: "user".into(),
+			content: "Hi".into(),
+		}];
+		let record = record(&prompt, candidate, Some(primary));
+		assert_eq!(record.gen_ai_operation_name.as_deref(), Some("shadow"));
+		assert_eq!(
+			record.gen_ai_request_model.as_deref(),
+			Some("claude-haiku-4-5")
+		);
+		assert_eq!(record.duration_ms, 250);
+		assert_eq!(record.total_tokens, Some(15));
+		assert_eq!(record.trace_id.as_deref(), Some("trace"));
+		assert_eq!(
+			record.attributes_json,
+			json!({
+				"agw.ai.shadow": true,
+				"agw.ai.shadow.primary.status": 200,
+				"agw.ai.shadow.primary.durationMs": 400,
+				"agw.ai.shadow.primary.provider": "openai",
+				"agw.ai.shadow.primary.requestModel": "gpt-4o",
+				"agw.ai.shadow.primary.inputTokens": 10,
+				"agw.ai.shadow.primary.outputTokens": 5,
+				"agw.ai.shadow.primary.totalTokens": 15,
+				"agw.ai.shadow.primary.completion": ["Hello!"],
+			})
+		);
+		let payload = record.payload.unwrap();
+		assert_eq!(payload.response_completion_json, Some(json!(["Hi!"])));
+
+		// A candidate that failed is still recorded, without LLM details.
+		let failed = Candidate {
+			started_at: Utc::now(),
+			duration: Duration::from_millis(10),
+			status: None,
+			llm: None,
+			error: Some("connection refused".to_string()),
+		};
+		let record = record(&prompt, failed, None);
+		assert_eq!(record.error.as_deref(), Some("connection refused"));
+		assert_eq!(record.attributes_json, json!({"agw.ai.shadow": true}));
+	}
+}
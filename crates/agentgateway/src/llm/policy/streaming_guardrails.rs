@@ -16,16 +16,22 @@
 //!
 //! This is not 100% accurate: a guard that needs full-response context, or a
 //! pattern spanning more than the overlap window, can be missed.
+//!
+//! Tool calls are not windowed. When an evaluator checks them, each call is assembled
+//! from its deltas and frames are held until the call is complete, so a call is never
+//! partially forwarded before it is checked. A call that outgrows the buffer limit is blocked.
 
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use ::http::HeaderMap;
 use bytes::Bytes;
 use http_body::Frame;
 use pin_project_lite::pin_project;
+use serde_json::Value;
 use tokio_sse_codec::{Event, Frame as SseFrame, SseDecoder};
 use tokio_util::codec::Decoder;
 use tracing::warn;
@@ -34,6 +40,8 @@ use super::{
 	FailureMode, ResponseGuard, ResponseGuardKind, StreamingEvaluator, StreamingGuardrailOutcome,
 };
 use crate::llm::policy::PromptGuard;
+use crate::llm::policy::tools::DeclaredTools;
+use crate::llm::types::ToolCall;
 use crate::proxy::httpproxy::PolicyClient;
 
 /// Text bytes accumulated before triggering a guardrail evaluation.
@@ -45,6 +53,9 @@ pub const DEFAULT_EVAL_THRESHOLD: usize = 1024;
 /// patterns spanning a batch boundary are still seen contiguously.
 pub const OVERLAP_BYTES: usize = 256;
 
+/// Rejection sent when a tool call does not fit in the buffer, so it cannot be checked.
+const TOOL_CALL_TOO_LARGE: &[u8] = b"Tool call exceeds the guardrail buffer limit";
+
 /// Return the last `max_bytes` of `s`, respecting UTF-8 char boundaries.
 pub fn tail_chars(s: &str, max_bytes: usize) -> &str {
 	if s.len() <= max_bytes {
@@ -63,26 +74,51 @@ pub async fn evaluate_window(
 	window: &str,
 ) -> Option<Bytes> {
 	for ev in evaluators.iter_mut() {
-		match ev.evaluate(window).await {
-			Ok(Some(StreamingGuardrailOutcome::Blocked(body))) => {
-				tracing::debug!("streaming guardrail blocked response window");
-				return Some(body);
-			},
-			Ok(None) => {},
-			Err(e) => match ev.failure_mode() {
-				FailureMode::FailClosed => {
-					warn!("streaming guardrail error, failing closed: {e}");
-					return Some(Bytes::from_static(b"Content blocked by guardrail policy"));
-				},
-				FailureMode::FailOpen => {
-					warn!("streaming guardrail error, failing open: {e}");
-				},
-			},
+		let result = ev.evaluate(window).await;
+		if let Some(body) = blocked_body(ev.as_ref(), result) {
+			tracing::debug!("streaming guardrail blocked response window");
+			return Some(body);
 		}
 	}
 	None
 }
 
+/// Run all evaluators against tool calls assembled from the stream. Returns the rejection body if
+/// any evaluator blocked.
+pub async fn evaluate_tool_calls(
+	evaluators: &mut [Box<dyn StreamingEvaluator>],
+	calls: &[ToolCall],
+) -> Option<Bytes> {
+	for ev in evaluators.iter_mut() {
+		let result = ev.evaluate_tool_calls(calls).await;
+		if let Some(body) = blocked_body(ev.as_ref(), result) {
+			tracing::debug!("streaming guardrail blocked tool call");
+			return Some(body);
+		}
+	}
+	None
+}
+
+fn blocked_body(
+	ev: &dyn StreamingEvaluator,
+	result: anyhow::Result<Option<StreamingGuardrailOutcome>>,
+) -> Option<Bytes> {
+	match result {
+		Ok(Some(StreamingGuardrailOutcome::Blocked(body))) => Some(body),
+		Ok(None) => None,
+		Err(e) => match ev.failure_mode() {
+			FailureMode::FailClosed => {
+				warn!("streaming guardrail error, failing closed: {e}");
+				Some(Bytes::from_static(b"Content blocked by guardrail policy"))
+			},
+			FailureMode::FailOpen => {
+				warn!("streaming guardrail error, failing open: {e}");
+				None
+			},
+		},
+	}
+}
+
 // ---------------------------------------------------------------------------
 // Factory
 // ---------------------------------------------------------------------------
@@ -92,11 +128,13 @@ pub fn make_evaluator(
 	guard: &ResponseGuard,
 	client: PolicyClient,
 	http_headers: HeaderMap,
+	declared_tools: Option<Arc<DeclaredTools>>,
 ) -> Box<dyn StreamingEvaluator> {
 	Box::new(ResponseGuardEvaluator {
 		guard: guard.clone(),
 		client,
 		http_headers,
+		declared_tools,
 	})
 }
 
@@ -104,6 +142,7 @@ struct ResponseGuardEvaluator {
 	guard: ResponseGuard,
 	client: PolicyClient,
 	http_headers: HeaderMap,
+	declared_tools: Option<Arc<DeclaredTools>>,
 }
 
 #[async_trait::async_trait]
@@ -111,6 +150,7 @@ impl StreamingEvaluator for ResponseGuardEvaluator {
 	fn failure_mode(&self) -> FailureMode {
 		match &self.guard.kind {
 			ResponseGuardKind::Webhook(wh) => wh.failure_mode,
+			ResponseGuardKind::Judge(j) => j.failure_mode,
 			_ => FailureMode::FailOpen,
 		}
 	}
@@ -124,6 +164,159 @@ impl StreamingEvaluator for ResponseGuardEvaluator {
 		)
 		.await
 	}
+
+	fn checks_tool_calls(&self) -> bool {
+		matches!(self.guard.kind, ResponseGuardKind::Tools(_))
+	}
+
+	async fn evaluate_tool_calls(
+		&mut self,
+		calls: &[ToolCall],
+	) -> anyhow::Result<Option<StreamingGuardrailOutcome>> {
+		PromptGuard::evaluate_streaming_tool_calls(&self.guard, calls, self.declared_tools.as_deref())
+			.await
+	}
+}
+
+// ---------------------------------------------------------------------------
+// Tool calls
+// ---------------------------------------------------------------------------
+
+/// Assembles the tool calls of a streamed response from their deltas, so they can be checked once
+/// complete. Calls are keyed by choice and position, as formats index them.
+#[derive(Default)]
+struct ToolCallAssembler {
+	partial: BTreeMap<(u64, u64), PartialToolCall>,
+	completed: Vec<ToolCall>,
+}
+
+#[derive(Default)]
+struct PartialToolCall {
+	name: String,
+	arguments: String,
+}
+
+impl PartialToolCall {
+	fn named(name: &Value) -> Self {
+		PartialToolCall {
+			name: name.as_str().unwrap_or_default().to_string(),
+			arguments: String::new(),
+		}
+	}
+
+	fn into_call(self) -> ToolCall {
+		let arguments = if self.arguments.is_empty() {
+			"{}"
+		} else {
+			&self.arguments
+		};
+		ToolCall::from_encoded(&self.name, arguments)
+	}
+}
+
+impl ToolCallAssembler {
+	fn observe(&mut self, event: &Value) {
+		let index = |v: &Value| v.as_u64().unwrap_or_default();
+		match event.get("type").and_then(Value::as_str) {
+			// Anthropic messages: a tool_use block, with its input streamed as partial JSON.
+			Some("content_block_start") if event["content_block"]["type"] == "tool_use" => {
+				let call = PartialToolCall::named(&event["content_block"]["name"]);
+				self.partial.insert((0, index(&event["index"])), call);
+			},
+			Some("content_block_delta") => {
+				if let Some(call) = self.partial.get_mut(&(0, index(&event["index"])))
+					&& let Some(json) = event["delta"]["partial_json"].as_str()
+				{
+					call.arguments.push_str(json);
+				}
+			},
+			Some("content_block_stop") => {
+				if let Some(call) = self.partial.remove(&(0, index(&event["index"]))) {
+					self.completed.push(call.into_call());
+				}
+			},
+			// OpenAI responses: the done event carries the full call.
+			Some("response.output_item.added") if event["item"]["type"] == "function_call" => {
+				let call = PartialToolCall::named(&event["item"]["name"]);
+				self
+					.partial
+					.insert((0, index(&event["output_index"])), call);
+			},
+			Some("response.output_item.done") if event["item"]["type"] == "function_call" => {
+				self.partial.remove(&(0, index(&event["output_index"])));
+				let item = &event["item"];
+				self.completed.push(ToolCall::from_encoded(
+					item["name"].as_str().unwrap_or_default(),
+					item["arguments"].as_str().unwrap_or_default(),
+				));
+			},
+			_ => {},
+		}
+		// OpenAI completions: fragments of each call, complete once the choice finishes.
+		for choice in event["choices"].as_array().into_iter().flatten() {
+			let choice_index = index(&choice["index"]);
+			for delta in choice["delta"]["tool_calls"]
+				.as_array()
+				.into_iter()
+				.flatten()
+			{
+				let call = self
+					.partial
+					.entry((choice_index, index(&delta["index"])))
+					.or_default();
+				if let Some(name) = delta["function"]["name"].as_str() {
+					call.name.push_str(name);
+				}
+				if let Some(arguments) = delta["function"]["arguments"].as_str() {
+					call.arguments.push_str(arguments);
+				}
+			}
+			if choice["finish_reason"].is_string() {
+				let finished: Vec<_> = self
+					.partial
+					.range((choice_index, 0)..=(choice_index, u64::MAX))
+					.map(|(k, _)| *k)
+					.collect();
+				for key in finished {
+					if let Some(call) = self.partial.remove(&key) {
+						self.completed.push(call.into_call());
+					}
+				}
+			}
+		}
+		// Gemini generateContent: calls are sent whole.
+		for part in event["candidates"][0]["content"]["parts"]
+			.as_array()
+			.into_iter()
+			.flatten()
+		{
+			if let Some(call) = part.get("functionCall") {
+				self.completed.push(ToolCall {
+					name: call["name"].as_str().unwrap_or_default().to_string(),
+					arguments: call
+						.get("args")
+						.cloned()
+						.unwrap_or_else(|| Value::Object(Default::default())),
+				});
+			}
+		}
+	}
+
+	/// Complete the calls left open when the stream ends.
+	fn finish(&mut self) {
+		let partial = std::mem::take(&mut self.partial);
+		self
+			.completed
+			.extend(partial.into_values().map(PartialToolCall::into_call));
+	}
+
+	fn in_progress(&self) -> bool {
+		!self.partial.is_empty()
+	}
+
+	fn take_completed(&mut self) -> Vec<ToolCall> {
+		std::mem::take(&mut self.completed)
+	}
 }
 
 // ---------------------------------------------------------------------------
@@ -175,6 +368,8 @@ pin_project! {
 		held_bytes: usize,
 		pending_text: String,
 		overlap_tail: String,
+		// Set when an evaluator checks tool calls.
+		tool_calls: Option<ToolCallAssembler>,
 		sse_decoder: SseDecoder<Bytes>,
 		decode_buffer: bytes::BytesMut,
 		state: GuardedBodyState,
@@ -216,6 +411,10 @@ impl GuardedSseBody {
 		logger: Option<crate::llm::AmendOnDrop>,
 		eval_threshold: usize,
 	) -> crate::http::Body {
+		let tool_calls = evaluators
+			.iter()
+			.any(|ev| ev.checks_tool_calls())
+			.then(ToolCallAssembler::default);
 		crate::http::Body::new(Self {
 			inner,
 			evaluators,
@@ -225,6 +424,7 @@ impl GuardedSseBody {
 			held_bytes: 0,
 			pending_text: String::new(),
 			overlap_tail: String::new(),
+			tool_calls,
 			sse_decoder: SseDecoder::with_max_size(buffer_limit),
 			decode_buffer: bytes::BytesMut::new(),
 			state: GuardedBodyState::Buffering,
@@ -232,38 +432,58 @@ impl GuardedSseBody {
 		})
 	}
 
-	/// Extract text delta from a parsed SSE frame if present.
-	fn extract_text_delta(frame: SseFrame<Bytes>) -> Option<String> {
+	/// Parse the JSON payload of an SSE frame, if any.
+	fn parse_event(frame: SseFrame<Bytes>) -> Option<Value> {
 		let SseFrame::Event(Event { data, .. }) = frame else {
 			return None;
 		};
 		if data.as_ref() == b"[DONE]" {
 			return None;
 		}
-		if let Ok(v) = serde_json::from_slice::<serde_json::Value>(&data) {
-			// OpenAI responses: response.output_text.delta
-			if v.get("type").and_then(|t| t.as_str()) == Some("response.output_text.delta")
-				&& let Some(text) = v.get("delta").and_then(|s| s.as_str())
-			{
-				return Some(text.to_string());
-			}
-			// OpenAI completions: choices[0].delta.content
-			if let Some(text) = v
-				.get("choices")
-				.and_then(|c| c.get(0))
-				.and_then(|c| c.get("delta"))
-				.and_then(|d| d.get("content"))
-				.and_then(|s| s.as_str())
-			{
-				return Some(text.to_string());
-			}
-			// Anthropic messages: delta.text
-			if let Some(text) = v
-				.get("delta")
-				.and_then(|d| d.get("text"))
-				.and_then(|s| s.as_str())
-			{
-				return Some(text.to_string());
+		serde_json::from_slice(&data).ok()
+	}
+
+	/// Extract text delta from a parsed SSE event if present.
+	fn extract_text_delta(v: &Value) -> Option<String> {
+		// OpenAI responses: response.output_text.delta
+		if v.get("type").and_then(|t| t.as_str()) == Some("response.output_text.delta")
+			&& let Some(text) = v.get("delta").and_then(|s| s.as_str())
+		{
+			return Some(text.to_string());
+		}
+		// OpenAI completions: choices[0].delta.content
+		if let Some(text) = v
+			.get("choices")
+			.and_then(|c| c.get(0))
+			.and_then(|c| c.get("delta"))
+			.and_then(|d| d.get("content"))
+			.and_then(|s| s.as_str())
+		{
+			return Some(text.to_string());
+		}
+		// Anthropic messages: delta.text
+		if let Some(text) = v
+			.get("delta")
+			.and_then(|d| d.get("text"))
+			.and_then(|s| s.as_str())
+		{
+			return Some(text.to_string());
+		}
+		// Gemini generateContent: candidates[0].content.parts[].text
+		if let Some(parts) = v
+			.get("candidates")
+			.and_then(|c| c.get(0))
+			.and_then(|c| c.get("content"))
+			.and_then(|c| c.get("parts"))
+			.and_then(|p| p.as_array())
+		{
+			let text: String = parts
+				.iter()
+				.filter(|p| p.get("thought").and_then(|t| t.as_bool()) != Some(true))
+				.filter_map(|p| p.get("text").and_then(|s| s.as_str()))
+				.collect();
+			if !text.is_empty() {
+				return Some(text);
 			}
 		}
 		None
@@ -320,6 +540,9 @@ impl http_body::Body for GuardedSseBody {
 							this.held_frames.clear();
 							*this.held_bytes = 0;
 							*this.state = GuardedBodyState::Blocked(body);
+						} else if !*eof && this.tool_calls.as_ref().is_some_and(|c| c.in_progress()) {
+							// Keep holding the frames of a tool call until it can be checked.
+							*this.state = GuardedBodyState::Buffering;
 						} else {
 							let queue: VecDeque<Bytes> = this.held_frames.drain(..).collect();
 							*this.held_bytes = 0;
@@ -347,8 +570,13 @@ impl http_body::Body for GuardedSseBody {
 							loop {
 								match this.sse_decoder.decode(this.decode_buffer) {
 									Ok(Some(sse_frame)) => {
-										if let Some(delta) = GuardedSseBody::extract_text_delta(sse_frame) {
-											this.pending_text.push_str(&delta);
+										if let Some(event) = GuardedSseBody::parse_event(sse_frame) {
+											if let Some(delta) = GuardedSseBody::extract_text_delta(&event) {
+												this.pending_text.push_str(&delta);
+											}
+											if let Some(tool_calls) = this.tool_calls.as_mut() {
+												tool_calls.observe(&event);
+											}
 										}
 									},
 									Ok(None) => break,
@@ -364,33 +592,54 @@ impl http_body::Body for GuardedSseBody {
 							}
 
 							let over_limit = *this.held_bytes >= *this.buffer_limit;
-							if this.pending_text.len() >= *this.eval_threshold || over_limit {
+							let (calls, calls_in_progress) = match this.tool_calls.as_mut() {
+								Some(tool_calls) => (tool_calls.take_completed(), tool_calls.in_progress()),
+								None => (Vec::new(), false),
+							};
+							if over_limit && calls_in_progress {
+								// The held frames cannot be flushed without forwarding an unchecked call.
+								this.held_frames.clear();
+								*this.held_bytes = 0;
+								*this.state = GuardedBodyState::Blocked(Bytes::from_static(TOOL_CALL_TOO_LARGE));
+								continue;
+							}
+							if this.pending_text.len() >= *this.eval_threshold || over_limit || !calls.is_empty()
+							{
 								// Having a full buffer but empty text implies that the
 								// buffer is full of non-text frames (e.g. control frames or unsupported SSE formats that fail to decode).
 								// In that case, flush the buffer as-is without evaluation, to avoid stalling on unprocessable content.
-								if this.pending_text.is_empty() {
+								if this.pending_text.is_empty() && calls.is_empty() {
 									let queue: VecDeque<Bytes> = this.held_frames.drain(..).collect();
 									*this.held_bytes = 0;
 									*this.state = GuardedBodyState::Flushing { queue, eof: false };
 									continue;
 								}
-								let batch = std::mem::take(this.pending_text);
-								let window = format!("{}{}", this.overlap_tail, batch);
-								*this.overlap_tail = tail_chars(&window, OVERLAP_BYTES).to_string();
-								let mut evaluators = std::mem::take(this.evaluators);
-								let fut: EvalFuture = Box::pin(async move {
-									let blocked_body = evaluate_window(&mut evaluators, &window).await;
-									(evaluators, blocked_body)
-								});
-								*this.state = GuardedBodyState::Evaluating { fut, eof: false };
+								let window = if this.pending_text.is_empty() {
+									String::new()
+								} else {
+									let batch = std::mem::take(this.pending_text);
+									let window = format!("{}{}", this.overlap_tail, batch);
+									*this.overlap_tail = tail_chars(&window, OVERLAP_BYTES).to_string();
+									window
+								};
+								let evaluators = std::mem::take(this.evaluators);
+								*this.state = GuardedBodyState::Evaluating {
+									fut: evaluation(evaluators, window, calls),
+									eof: false,
+								};
 							}
 						},
 						Poll::Ready(None) => {
 							loop {
 								match this.sse_decoder.decode_eof(this.decode_buffer) {
 									Ok(Some(sse_frame)) => {
-										if let Some(delta) = GuardedSseBody::extract_text_delta(sse_frame) {
-											this.pending_text.push_str(&delta);
+										if let Some(event) = GuardedSseBody::parse_event(sse_frame) {
+											if let Some(delta) = GuardedSseBody::extract_text_delta(&event) {
+												this.pending_text.push_str(&delta);
+											}
+											if let Some(tool_calls) = this.tool_calls.as_mut() {
+												tool_calls.observe(&event);
+											}
 										}
 									},
 									Ok(None) => break,
@@ -401,23 +650,33 @@ impl http_body::Body for GuardedSseBody {
 									},
 								}
 							}
+							let calls = match this.tool_calls.as_mut() {
+								Some(tool_calls) => {
+									tool_calls.finish();
+									tool_calls.take_completed()
+								},
+								None => Vec::new(),
+							};
 
-							if this.pending_text.is_empty() {
+							if this.pending_text.is_empty() && calls.is_empty() {
 								let queue: VecDeque<Bytes> = this.held_frames.drain(..).collect();
 								*this.held_bytes = 0;
 								*this.state = GuardedBodyState::Flushing { queue, eof: true };
 								continue;
 							}
 
-							let batch = std::mem::take(this.pending_text);
-							let window = format!("{}{}", this.overlap_tail, batch);
+							let window = if this.pending_text.is_empty() {
+								String::new()
+							} else {
+								let batch = std::mem::take(this.pending_text);
+								format!("{}{}", this.overlap_tail, batch)
+							};
 							this.overlap_tail.clear();
-							let mut evaluators = std::mem::take(this.evaluators);
-							let fut: EvalFuture = Box::pin(async move {
-								let blocked_body = evaluate_window(&mut evaluators, &window).await;
-								(evaluators, blocked_body)
-							});
-							*this.state = GuardedBodyState::Evaluating { fut, eof: true };
+							let evaluators = std::mem::take(this.evaluators);
+							*this.state = GuardedBodyState::Evaluating {
+								fut: evaluation(evaluators, window, calls),
+								eof: true,
+							};
 						},
 					}
 				},
@@ -426,6 +685,24 @@ impl http_body::Body for GuardedSseBody {
 	}
 }
 
+/// Evaluate a text window, if not empty, then the completed tool calls, if any.
+fn evaluation(
+	mut evaluators: Vec<Box<dyn StreamingEvaluator>>,
+	window: String,
+	calls: Vec<ToolCall>,
+) -> EvalFuture {
+	Box::pin(async move {
+		let mut blocked_body = None;
+		if !window.is_empty() {
+			blocked_body = evaluate_window(&mut evaluators, &window).await;
+		}
+		if blocked_body.is_none() && !calls.is_empty() {
+			blocked_body = evaluate_tool_calls(&mut evaluators, &calls).await;
+		}
+		(evaluators, blocked_body)
+	})
+}
+
 #[cfg(test)]
 mod tests {
 	use http_body_util::BodyExt as _;
@@ -495,6 +772,37 @@ mod tests {
 		}
 	}
 
+	// Blocks calls to the named tool.
+	struct ToolEvaluator {
+		deny: &'static str,
+	}
+
+	#[async_trait::async_trait]
+	impl StreamingEvaluator for ToolEvaluator {
+		async fn evaluate(
+			&mut self,
+			_window: &str,
+		) -> anyhow::Result<Option<StreamingGuardrailOutcome>> {
+			Ok(None)
+		}
+
+		fn checks_tool_calls(&self) -> bool {
+			true
+		}
+
+		async fn evaluate_tool_calls(
+			&mut self,
+			calls: &[ToolCall],
+		) -> anyhow::Result<Option<StreamingGuardrailOutcome>> {
+			if calls.iter().any(|c| c.name == self.deny) {
+				return Ok(Some(StreamingGuardrailOutcome::Blocked(
+					Bytes::from_static(b"blocked"),
+				)));
+			}
+			Ok(None)
+		}
+	}
+
 	fn sse_bytes(content: &str) -> Bytes {
 		Bytes::from(format!("data: {}\n\n", content))
 	}
@@ -645,6 +953,105 @@ mod tests {
 		assert!(!contains(&bytes, b"card number"));
 	}
 
+	fn tool_call_chunks(name: &str) -> Vec<Bytes> {
+		vec![
+			delta_bytes("let me check"),
+			sse_bytes(&format!(
+				r#"{{"choices":[{{"index":0,"delta":{{"tool_calls":[{{"index":0,"id":"call_1","function":{{"name":"{name}","arguments":""}}}}]}}}}]}}"#
+			)),
+			sse_bytes(
+				r#"{"choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"cmd\":"}}]}}]}"#,
+			),
+			sse_bytes(
+				r#"{"choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"ls\"}"}}]}}]}"#,
+			),
+			sse_bytes(r#"{"choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#),
+			sse_bytes("[DONE]"),
+		]
+	}
+
+	#[tokio::test]
+	async fn test_streamed_tool_call_blocked() {
+		let body = make_body(tool_call_chunks("exec"));
+		let guarded = GuardedSseBody::with_threshold(
+			body,
+			vec![Box::new(ToolEvaluator { deny: "exec" })],
+			1024 * 1024,
+			None,
+			4,
+		);
+
+		let bytes = guarded.collect().await.unwrap().to_bytes();
+		// Text before the call is flushed, but no part of the call is forwarded.
+		assert!(contains(&bytes, b"let me check"));
+		assert!(contains(&bytes, b"guardrail_blocked"));
+		assert!(!contains(&bytes, b"exec"));
+		assert!(!contains(&bytes, b"cmd"));
+	}
+
+	#[tokio::test]
+	async fn test_streamed_tool_call_allowed() {
+		let chunks = tool_call_chunks("search");
+		let body = make_body(chunks.clone());
+		let guarded = GuardedSseBody::with_threshold(
+			body,
+			vec![Box::new(ToolEvaluator { deny: "exec" })],
+			1024 * 1024,
+			None,
+			4,
+		);
+
+		let bytes = guarded.collect().await.unwrap().to_bytes();
+		assert_eq!(bytes, chunks.concat());
+	}
+
+	#[tokio::test]
+	async fn test_streamed_tool_call_over_buffer_limit_blocked() {
+		let body = make_body(tool_call_chunks("search"));
+		let guarded = GuardedSseBody::with_threshold(
+			body,
+			vec![Box::new(ToolEvaluator { deny: "exec" })],
+			200,
+			None,
+			4,
+		);
+
+		let bytes = guarded.collect().await.unwrap().to_bytes();
+		assert!(contains(&bytes, TOOL_CALL_TOO_LARGE));
+		assert!(!contains(&bytes, b"cmd"));
+	}
+
+	#[test]
+	fn test_tool_call_assembler_formats() {
+		let mut calls = ToolCallAssembler::default();
+		for event in [
+			serde_json::json!({"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "name": "exec", "input": {}}}),
+			serde_json::json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "{\"cmd\":"}}),
+			serde_json::json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "\"ls\"}"}}),
+		] {
+			calls.observe(&event);
+		}
+		assert!(calls.in_progress());
+		calls.observe(&serde_json::json!({"type": "content_block_stop", "index": 1}));
+		assert!(!calls.in_progress());
+		let completed = calls.take_completed();
+		assert_eq!(completed.len(), 1);
+		assert_eq!(completed[0].name, "exec");
+		assert_eq!(completed[0].arguments, serde_json::json!({"cmd": "ls"}));
+
+		calls.observe(&serde_json::json!({"type": "response.output_item.added", "output_index": 0, "item": {"type": "function_call", "name": "exec", "arguments": ""}}));
+		assert!(calls.in_progress());
+		calls.observe(&serde_json::json!({"type": "response.output_item.done", "output_index": 0, "item": {"type": "function_call", "name": "exec", "arguments": "{\"cmd\":\"ls\"}"}}));
+		assert!(!calls.in_progress());
+		assert_eq!(
+			calls.take_completed()[0].arguments,
+			serde_json::json!({"cmd": "ls"})
+		);
+
+		calls.observe(&serde_json::json!({"candidates": [{"content": {"parts": [{"functionCall": {"name": "exec", "args": {"cmd": "ls"}}}]}}]}));
+		assert_eq!(calls.take_completed()[0].name, "exec");
+	}
+
 	#[test]
 	fn test_tail_chars_respects_utf8_boundaries() {
 		let s = "héllo wörld";
@@ -0,0 +1,219 @@
+//! Structured-output enforcement.
+//!
+//! Requests that ask for output matching a JSON schema, via `response_format: json_schema` or a
+//! tool the model is forced to call, have the final output validated against that schema. When it
+//! does not match, the proxy re-issues the request with the invalid output and the validation error
+//! appended, up to `maxRepairs` times, before returning the last response to the client.
+
+use ::http::request::Parts;
+use bytes::Bytes;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::http::Response;
+use crate::llm::policy::Policy;
+use crate::llm::types::{OutputSchema, completions, encode_tool_arguments, messages, responses};
+use crate::llm::{AIError, InputFormat, RequestType, ResponseType, SimpleChatCompletionMessage};
+use crate::*;
+
+#[cfg(test)]
+#[path = "structured_output_tests.rs"]
+mod tests;
+
+#[apply(schema!)]
+pub struct StructuredOutput {
+	/// How many times a request is re-issued after its output fails validation. Defaults to 2.
+	#[serde(default = "default_max_repairs")]
+	pub max_repairs: u8,
+}
+
+fn default_max_repairs() -> u8 {
+	2
+}
+
+/// The output a request expects, recorded so the response can be checked and the request repaired.
+#[derive(Debug)]
+pub struct Expected {
+	format: InputFormat,
+	output: OutputSchema,
+	/// The client's request, before enrichment and guardrails, which apply again to a repair.
+	request: Bytes,
+	max_repairs: u8,
+}
+
+/// Record the output `req` expects when the policy enforces structured output. This runs before
+/// prompt enrichment and guardrails, so a repaired request is built from the client's own body.
+pub fn record<T: RequestType + Serialize>(
+	policies: Option<&Policy>,
+	format: InputFormat,
+	req: &T,
+	parts: &mut Parts,
+) {
+	let Some(policy) = policies.and_then(|p| p.structured_output.as_ref()) else {
+		return;
+	};
+	let Some(output) = req.output_schema() else {
+		return;
+	};
+	let request = match serde_json::to_vec(req) {
+		Ok(request) => Bytes::from(request),
+		Err(e) => {
+			warn!("failed to record request for structured output: {e}");
+			return;
+		},
+	};
+	parts.extensions.insert(Arc::new(Expected {
+		format,
+		output,
+		request,
+		max_repairs: policy.max_repairs,
+	}));
+}
+
+/// The result of checking a response, attached to it so the proxy can re-issue the request.
+#[derive(Debug, Clone)]
+pub struct Check {
+	pub model: Strng,
+	pub max_repairs: u8,
+	pub valid: bool,
+	/// The request to send instead, when the output did not match the schema.
+	pub repair: Option<Bytes>,
+}
+
+impl Expected {
+	/// Check a buffered response.
+	pub fn check_response(&self, model: Strng, resp: &dyn ResponseType) -> Check {
+		let output = match &self.output.tool {
+			Some(tool) => resp
+				.tool_calls()
+				.into_iter()
+				.find(|c| &c.name == tool)
+				.map(|c| encode_tool_arguments(&c.arguments)),
+			None => resp
+				.to_llm_response(true)
+				.completion
+				.map(|parts| parts.concat()),
+		};
+		self.check(model, output)
+	}
+
+	/// Buffer a streamed response and check its output. The events are passed on unchanged, but
+	/// only once the stream is complete.
+	pub async fn check_stream(
+		&self,
+		model: Strng,
+		buffer_limit: usize,
+		resp: Response,
+	) -> Result<Response, AIError> {
+		let (mut parts, body) = resp.into_parts();
+		let bytes = http::read_body_with_limit(body, buffer_limit)
+			.await
+			.map_err(|_| AIError::ResponseTooLarge)?;
+		let output = self.stream_output(&bytes);
+		parts.extensions.insert(self.check(model, output));
+		parts.headers.remove(::http::header::CONTENT_LENGTH);
+		Ok(Response::from_parts(parts, http::Body::from(bytes)))
+	}
+
+	fn check(&self, model: Strng, output: Option<String>) -> Check {
+		let error = match &output {
+			None => Some("no output was returned".to_string()),
+			Some(output) => self.validate(output).err(),
+		};
+		let Some(error) = error else {
+			return Check {
+				model,
+				max_repairs: self.max_repairs,
+				valid: true,
+				repair: None,
+			};
+		};
+		debug!("structured output did not match the schema: {error}");
+		let repair = self
+			.repair(output.as_deref().unwrap_or_default(), &error)
+			.inspect_err(|e| warn!("failed to build structured output repair: {e}"))
+			.ok();
+		Check {
+			model,
+			max_repairs: self.max_repairs,
+			valid: false,
+			repair,
+		}
+	}
+
+	fn validate(&self, output: &str) -> Result<(), String> {
+		let value: Value =
+			serde_json::from_str(output).map_err(|e| format!("output is not valid JSON: {e}"))?;
+		json::validate_schema(&self.output.schema, &value).map_err(|e| format!("{e:#}"))
+	}
+
+	/// The client's request, with the invalid output and the reason it was rejected appended.
+	fn repair(&self, output: &str, error: &str) -> anyhow::Result<Bytes> {
+		let prompts = vec![
+			SimpleChatCompletionMessage {
+				role: strng::literal!("assistant"),
+				content: strng::new(output),
+			},
+			SimpleChatCompletionMessage {
+				role: strng::literal!("user"),
+				content: strng::format!(
+					"The previous response did not match the required JSON schema: {error}. Respond again with only JSON that matches the schema."
+				),
+			},
+		];
+		let body = match self.format {
+			InputFormat::Completions => append::<completions::Request>(&self.request, prompts),
+			InputFormat::Messages => append::<messages::Request>(&self.request, prompts),
+			InputFormat::Responses => append::<responses::Request>(&self.request, prompts),
+			format => anyhow::bail!("structured output is not supported for {format:?}"),
+		}?;
+		Ok(Bytes::from(body))
+	}
+
+	/// The output carried by the client-format SSE events of a streamed response.
+	fn stream_output(&self, body: &[u8]) -> Option<String> {
+		let text = std::str::from_utf8(body).ok()?;
+		let mut output = String::new();
+		let mut tool_block = None;
+		let mut found = false;
+		for data in text.lines().filter_map(|l| l.strip_prefix("data:")) {
+			let Ok(event) = serde_json::from_str::<Value>(data.trim_start()) else {
+				continue;
+			};
+			let delta = match event.get("type").and_then(Value::as_str) {
+				Some("content_block_start") => {
+					if let Some(tool) = &self.output.tool
+						&& event.pointer("/content_block/name").and_then(Value::as_str) == Some(tool)
+					{
+						tool_block = event.get("index").cloned();
+					}
+					None
+				},
+				Some("content_block_delta") => match &self.output.tool {
+					Some(_) if tool_block.is_some() && event.get("index") == tool_block.as_ref() => {
+						event.pointer("/delta/partial_json")
+					},
+					Some(_) => None,
+					None => event.pointer("/delta/text"),
+				},
+				Some("response.output_text.delta") => event.get("delta"),
+				_ => event.pointer("/choices/0/delta/content"),
+			};
+			if let Some(delta) = delta.and_then(Value::as_str) {
+				output.push_str(delta);
+				found = true;
+			}
+		}
+		found.then_some(output)
+	}
+}
+
+fn append<T: RequestType + Serialize + DeserializeOwned>(
+	request: &[u8],
+	prompts: Vec<SimpleChatCompletionMessage>,
+) -> anyhow::Result<Vec<u8>> {
+	let mut req: T = serde_json::from_slice(request)?;
+	req.append_prompts(prompts);
+	Ok(serde_json::to_vec(&req)?)
+}
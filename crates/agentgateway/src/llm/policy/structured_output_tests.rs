@@ -0,0 +1,215 @@
+use serde_json::json;
+
+use super::*;
+
+fn person_schema() -> Value {
+	json!({
+		"type": "object",
+		"properties": {
+			"name": {"type": "string"},
+			"age": {"type": "integer", "minimum": 0}
+		},
+		"required": ["name", "age"],
+		"additionalProperties": false
+	})
+}
+
+fn completions_request() -> completions::Request {
+	serde_json::from_value(json!({
+		"model": "gpt-4o",
+		"messages": [{"role": "user", "content": "Who wrote Dune?"}],
+		"response_format": {
+			"type": "json_schema",
+			"json_schema": {"name": "person", "schema": person_schema()}
+		}
+	}))
+	.unwrap()
+}
+
+fn messages_request() -> messages::Request {
+	serde_json::from_value(json!({
+		"model": "claude-sonnet-4",
+		"max_tokens": 100,
+		"messages": [{"role": "user", "content": "Who wrote Dune?"}],
+		"tools": [{"name": "record_person", "input_schema": person_schema()}],
+		"tool_choice": {"type": "tool", "name": "record_person"}
+	}))
+	.unwrap()
+}
+
+fn expected<T: RequestType + Serialize>(format: InputFormat, req: &T) -> Expected {
+	Expected {
+		format,
+		output: req
+			.output_schema()
+			.expect("request should ask for structured output"),
+		request: Bytes::from(serde_json::to_vec(req).unwrap()),
+		max_repairs: 2,
+	}
+}
+
+fn completions_response(content: &str) -> completions::Response {
+	serde_json::from_value(json!({
+		"model": "gpt-4o",
+		"choices": [{
+			"index": 0,
+			"finish_reason": "stop",
+			"message": {"role": "assistant", "content": content}
+		}]
+	}))
+	.unwrap()
+}
+
+#[test]
+fn output_schema_is_read_from_each_format() {
+	assert_eq!(
+		completions_request().output_schema(),
+		Some(OutputSchema {
+			schema: person_schema(),
+			tool: None,
+		})
+	);
+
+	let req: responses::Request = serde_json::from_value(json!({
+		"model": "gpt-4o",
+		"input": "Who wrote Dune?",
+		"text": {"format": {"type": "json_schema", "name": "person", "schema": person_schema()}}
+	}))
+	.unwrap();
+	assert_eq!(req.output_schema().map(|o| o.schema), Some(person_schema()));
+
+	assert_eq!(
+		messages_request().output_schema(),
+		Some(OutputSchema {
+			schema: person_schema(),
+			tool: Some("record_person".to_string()),
+		})
+	);
+
+	// Plain JSON mode has no schema to enforce.
+	let req: completions::Request = serde_json::from_value(json!({
+		"model": "gpt-4o",
+		"messages": [{"role": "user", "content": "hi"}],
+		"response_format": {"type": "json_object"}
+	}))
+	.unwrap();
+	assert_eq!(req.output_schema(), None);
+}
+
+#[test]
+fn valid_output_passes() {
+	let expected = expected(InputFormat::Completions, &completions_request());
+	let resp = completions_response(r#"{"name": "Frank Herbert", "age": 65}"#);
+	let check = expected.check_response(strng::literal!("gpt-4o"), &resp);
+	assert!(check.valid);
+	assert!(check.repair.is_none());
+}
+
+#[test]
+fn invalid_output_builds_a_repair_request() {
+	let expected = expected(InputFormat::Completions, &completions_request());
+	for output in [r#"{"name": "Frank Herbert"}"#, "Frank Herbert wrote Dune."] {
+		let check = expected.check_response(strng::literal!("gpt-4o"), &completions_response(output));
+		assert!(!check.valid);
+		let repair: Value = serde_json::from_slice(&check.repair.unwrap()).unwrap();
+		let messages = repair["messages"].as_array().unwrap();
+		assert_eq!(messages.len(), 3);
+		assert_eq!(messages[1], json!({"role": "assistant", "content": output}));
+		assert_eq!(messages[2]["role"], "user");
+		assert!(
+			messages[2]["content"]
+				.as_str()
+				.unwrap()
+				.contains("did not match the required JSON schema"),
+		);
+		// The rest of the request is unchanged.
+		assert_eq!(
+			repair["response_format"],
+			completions_request().rest["response_format"]
+		);
+	}
+}
+
+#[test]
+fn forced_tool_input_is_checked() {
+	let expected = expected(InputFormat::Messages, &messages_request());
+	let resp = |input: Value| -> messages::Response {
+		serde_json::from_value(json!({
+			"id": "msg_1",
+			"type": "message",
+			"role": "assistant",
+			"model": "claude-sonnet-4",
+			"stop_reason": "tool_use",
+			"stop_sequence": null,
+			"usage": {"input_tokens": 1, "output_tokens": 1},
+			"content": [
+				{"type": "tool_use", "id": "tu_1", "name": "record_person", "input": input}
+			]
+		}))
+		.unwrap()
+	};
+	let model = strng::literal!("claude-sonnet-4");
+	assert!(
+		expected
+			.check_response(
+				model.clone(),
+				&resp(json!({"name": "Frank Herbert", "age": 65}))
+			)
+			.valid
+	);
+	let check = expected.check_response(model, &resp(json!({"name": "Frank Herbert", "age": -1})));
+	assert!(!check.valid);
+	let repair: Value = serde_json::from_slice(&check.repair.unwrap()).unwrap();
+	assert_eq!(repair["messages"].as_array().unwrap().len(), 3);
+	assert_eq!(repair["tool_choice"]["name"], "record_person");
+}
+
+#[test]
+fn streamed_output_is_read_from_events() {
+	let expected_completions = expected(InputFormat::Completions, &completions_request());
+	let body = [r#"{"name": "#, r#""Frank Herbert", "age": 65}"#]
+		.iter()
+		.map(|d| {
+			format!(
+				"data: {}\n\n",
+				json!({"choices": [{"index": 0, "delta": {"content": d}}]})
+			)
+		})
+		.collect::<String>()
+		+ "data: [DONE]\n\n";
+	assert_eq!(
+		expected_completions
+			.stream_output(body.as_bytes())
+			.as_deref(),
+		Some(r#"{"name": "Frank Herbert", "age": 65}"#)
+	);
+	assert_eq!(
+		expected_completions.stream_output(b"data: [DONE]\n\n"),
+		None
+	);
+
+	let expected_messages = expected(InputFormat::Messages, &messages_request());
+	let events = [
+		json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+		json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Sure"}}),
+		json!({"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "id": "tu_1", "name": "record_person", "input": {}}}),
+		json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "{\"name\": \"Frank"}}),
+		json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": " Herbert\"}"}}),
+	];
+	let body: String = events
+		.iter()
+		.map(|e| format!("event: {}\ndata: {e}\n\n", e["type"].as_str().unwrap()))
+		.collect();
+	assert_eq!(
+		expected_messages.stream_output(body.as_bytes()).as_deref(),
+		Some(r#"{"name": "Frank Herbert"}"#)
+	);
+}
+
+#[test]
+fn max_repairs_defaults_to_two() {
+	let policy: StructuredOutput = serde_json::from_value(json!({})).unwrap();
+	assert_eq!(policy.max_repairs, 2);
+	let policy: StructuredOutput = serde_json::from_value(json!({"maxRepairs": 0})).unwrap();
+	assert_eq!(policy.max_repairs, 0);
+}
@@ -20,13 +20,18 @@ async fn webhook_fail_open_emits_single_metric() {
 			}),
 		}],
 		response: vec![],
+		realtime_block: Default::default(),
 	};
 
 	let client = crate::test_helpers::policy_client();
 	let blocked = guard
 		.apply_realtime_request_guards("hello world", &client)
 		.await;
-	assert!(blocked.is_none(), "FailOpen must not block the request");
+	assert_eq!(
+		blocked,
+		RealtimeGuardOutcome::Allow,
+		"FailOpen must not block the request"
+	);
 
 	let fail_open = client
 		.inputs
@@ -54,6 +59,80 @@ async fn webhook_fail_open_emits_single_metric() {
 	);
 }
 
+/// A judge that cannot be reached fails open like a webhook does.
+#[tokio::test]
+async fn judge_fail_open_emits_single_metric() {
+	use crate::telemetry::metrics::{GuardrailAction, GuardrailLabels, GuardrailPhase};
+
+	let guard: PromptGuard = serde_json::from_value(serde_json::json!({
+		"request": [{
+			"judge": {
+				// Custom providers have no default host, so the judge call fails before sending.
+				"provider": {"custom": {"formats": [{"type": "completions"}]}},
+				"prompt": "Flag prompts that mention competitors.",
+				"failureMode": "failOpen",
+			}
+		}]
+	}))
+	.unwrap();
+
+	let client = crate::test_helpers::policy_client();
+	let blocked = guard
+		.apply_realtime_request_guards("hello world", &client)
+		.await;
+	assert_eq!(
+		blocked,
+		RealtimeGuardOutcome::Allow,
+		"FailOpen must not block the request"
+	);
+
+	let fail_open = client
+		.inputs
+		.metrics
+		.guardrail_checks
+		.get_or_create(&GuardrailLabels {
+			phase: GuardrailPhase::Request,
+			action: GuardrailAction::FailOpen,
+		})
+		.get();
+	assert_eq!(fail_open, 1, "FailOpen should be recorded exactly once");
+}
+
+/// Realtime events are redacted when a guard masks their text, and blocked when one rejects it.
+#[tokio::test]
+async fn realtime_request_guards_redact_and_reject() {
+	let guard: PromptGuard = serde_json::from_value(serde_json::json!({
+		"request": [
+			{"regex": {"action": "mask", "rules": [{"pattern": "secret"}]}},
+			{"regex": {"action": "reject", "rules": [{"pattern": "forbidden"}]}}
+		],
+		"realtimeBlock": "close",
+	}))
+	.unwrap();
+	assert_eq!(guard.realtime_block, RealtimeBlockAction::Close);
+
+	let client = crate::test_helpers::policy_client();
+	assert_eq!(
+		guard
+			.apply_realtime_request_guards("hello world", &client)
+			.await,
+		RealtimeGuardOutcome::Allow
+	);
+	let RealtimeGuardOutcome::Masked(masked) = guard
+		.apply_realtime_request_guards("my secret plan", &client)
+		.await
+	else {
+		panic!("expected the text to be masked");
+	};
+	assert!(!masked.contains("secret"), "{masked}");
+	assert!(matches!(
+		guard
+			.apply_realtime_request_guards("a forbidden plan", &client)
+			.await,
+		RealtimeGuardOutcome::Rejected(_)
+	));
+}
+
 #[test]
 fn test_get_webhook_forward_headers() {
 	let mut headers = HeaderMap::new();
@@ -338,6 +417,68 @@ fn test_resolve_route() {
 	);
 }
 
+#[test]
+fn test_resolve_route_segment_wildcard() {
+	let policy = Policy {
+		routes: SortedRoutes::from_iter([
+			(strng::new("/v1/batches"), crate::llm::RouteType::Batches),
+			(strng::new("/v1/batches/*"), crate::llm::RouteType::Batches),
+			(
+				strng::new("/v1/files/*/content"),
+				crate::llm::RouteType::Files,
+			),
+			(strng::new("*"), crate::llm::RouteType::Passthrough),
+		]),
+		..Default::default()
+	};
+
+	assert_eq!(
+		policy.resolve_route("/v1/batches/batch_abc"),
+		crate::llm::RouteType::Batches
+	);
+	assert_eq!(
+		policy.resolve_route("/openai/v1/files/file-abc/content"),
+		crate::llm::RouteType::Files
+	);
+	// `*` matches exactly one non-empty segment
+	assert_eq!(
+		policy.resolve_route("/v1/batches/"),
+		crate::llm::RouteType::Passthrough
+	);
+	assert_eq!(
+		policy.resolve_route("/v1/batches/batch_abc/cancel"),
+		crate::llm::RouteType::Passthrough
+	);
+	assert_eq!(
+		policy.resolve_route("/v1/files/a/b/content"),
+		crate::llm::RouteType::Passthrough
+	);
+}
+
+#[test]
+fn test_model_allowlist() {
+	let mut policy = Policy {
+		model_aliases: HashMap::from([(strng::new("fast"), strng::new("gpt-4o-mini"))]),
+		allowed_models: vec![strng::new("gpt-4o*"), strng::new("*-embedding")],
+		..Default::default()
+	};
+	policy.compile_model_alias_patterns();
+
+	assert!(policy.model_allowed("gpt-4o"));
+	assert!(policy.model_allowed("text-embedding"));
+	assert!(!policy.model_allowed("o3"));
+	assert!(Policy::default().model_allowed("o3"));
+
+	// The allowlist applies to the aliased model
+	assert_eq!(
+		policy.resolve_allowed_model("fast").unwrap(),
+		Some(strng::new("gpt-4o-mini"))
+	);
+	assert_eq!(policy.resolve_allowed_model("gpt-4o").unwrap(), None);
+	let rejected = policy.resolve_allowed_model("o3").unwrap_err();
+	assert_eq!(rejected.status(), ::http::StatusCode::FORBIDDEN);
+}
+
 #[test]
 fn test_model_alias_wildcard_resolution() {
 	let mut policy = Policy {
@@ -950,6 +1091,41 @@ mod prompt_guard_config_tests {
 
 	use super::*;
 
+	#[test]
+	fn test_judge_config_deserialization() {
+		let json = json!({
+			"promptGuard": {
+				"response": [{
+					"judge": {
+						"provider": {"openAI": {"model": "gpt-4o-mini"}},
+						"prompt": "Flag answers that give medical advice.",
+						"verdictSchema": {
+							"type": "object",
+							"properties": {"flagged": {"type": "boolean"}},
+							"required": ["flagged"]
+						},
+						"action": "reject"
+					}
+				}]
+			}
+		});
+
+		let policy: Policy = serde_json::from_value(json).unwrap();
+		let prompt_guard = policy.prompt_guard.unwrap();
+		assert_eq!(prompt_guard.response.len(), 1);
+
+		match &prompt_guard.response[0].kind {
+			ResponseGuardKind::Judge(j) => {
+				assert!(matches!(j.provider, crate::llm::AIProvider::OpenAI(_)));
+				assert!(matches!(j.action, Action::Reject));
+				assert_eq!(j.failure_mode, FailureMode::FailClosed);
+				assert!(j.verdict_schema.is_some());
+				assert!(j.host_override.is_none());
+			},
+			_ => panic!("Expected Judge guard kind"),
+		}
+	}
+
 	#[test]
 	fn test_bedrock_guardrails_config_deserialization() {
 		let json = json!({
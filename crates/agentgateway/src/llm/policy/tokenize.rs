@@ -0,0 +1,312 @@
+//! Reversible PII tokenization.
+//!
+//! Request guards with the `tokenize` action replace detected entities with surrogate tokens such as
+//! `<EMAIL_ADDRESS_1>` before the prompt is sent upstream. The [`TokenVault`] holding the mapping
+//! lives only for the duration of the request and is used to put the original values back into the
+//! response, so the provider never sees them but the client still gets a coherent answer.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+
+use bytes::{Bytes, BytesMut};
+use http_body::Frame;
+use pin_project_lite::pin_project;
+use serde_json::Value;
+
+use crate::*;
+
+#[cfg(test)]
+#[path = "tokenize_tests.rs"]
+mod tests;
+
+/// Per-request mapping between original values and their surrogate tokens.
+#[derive(Debug, Default)]
+pub struct TokenVault {
+	tokens: HashMap<String, String>,
+	originals: HashMap<String, String>,
+	counters: HashMap<String, usize>,
+}
+
+impl TokenVault {
+	pub fn is_empty(&self) -> bool {
+		self.originals.is_empty()
+	}
+
+	/// Return the token for `original`, allocating `<ENTITY_n>` the first time a value is seen so
+	/// repeated values map to the same token.
+	pub fn tokenize(&mut self, entity: &str, original: &str) -> String {
+		if let Some(token) = self.tokens.get(original) {
+			return token.clone();
+		}
+		let n = self.counters.entry(entity.to_string()).or_default();
+		*n += 1;
+		let token = format!("<{entity}_{n}>");
+		self.tokens.insert(original.to_string(), token.clone());
+		self.originals.insert(token.clone(), original.to_string());
+		token
+	}
+
+	/// Replace every known token in `text` with its original value.
+	pub fn restore<'a>(&self, text: &'a str) -> Cow<'a, str> {
+		if self.is_empty() || !text.contains('<') {
+			return Cow::Borrowed(text);
+		}
+		let mut out = String::with_capacity(text.len());
+		let mut rest = text;
+		while let Some(start) = rest.find('<') {
+			out.push_str(&rest[..start]);
+			let candidate = &rest[start..];
+			match candidate[1..].find(['<', '>']) {
+				Some(i) if candidate.as_bytes()[i + 1] == b'>' => {
+					let token = &candidate[..i + 2];
+					out.push_str(self.originals.get(token).map_or(token, String::as_str));
+					rest = &candidate[i + 2..];
+				},
+				_ => {
+					out.push('<');
+					rest = &candidate[1..];
+				},
+			}
+		}
+		out.push_str(rest);
+		Cow::Owned(out)
+	}
+
+	/// Restore tokens in every string of a JSON document. Working on the parsed value, rather than
+	/// the raw bytes, keeps original values that need escaping valid JSON.
+	pub fn restore_json(&self, value: &mut Value) {
+		match value {
+			Value::String(s) => {
+				if let Cow::Owned(restored) = self.restore(s) {
+					*s = restored;
+				}
+			},
+			Value::Array(items) => items.iter_mut().for_each(|v| self.restore_json(v)),
+			Value::Object(fields) => fields.values_mut().for_each(|v| self.restore_json(v)),
+			_ => {},
+		}
+	}
+
+	/// Restore tokens in a JSON response body. Bodies that are not JSON are returned unchanged.
+	pub fn restore_body(&self, body: Vec<u8>) -> Vec<u8> {
+		let Ok(mut value) = serde_json::from_slice::<Value>(&body) else {
+			return body;
+		};
+		self.restore_json(&mut value);
+		serde_json::to_vec(&value).unwrap_or(body)
+	}
+
+	/// Length of a trailing, incomplete token in `text`, such as `<EMAIL_AD`. Streamed deltas can
+	/// split a token across events, so this suffix has to wait for the next delta.
+	pub fn partial_token_len(&self, text: &str) -> usize {
+		let Some(start) = text.rfind('<') else {
+			return 0;
+		};
+		let suffix = &text[start..];
+		if self
+			.originals
+			.keys()
+			.any(|token| token.len() > suffix.len() && token.starts_with(suffix))
+		{
+			suffix.len()
+		} else {
+			0
+		}
+	}
+}
+
+/// A decoded SSE event: its non-data lines, kept verbatim, and its JSON payload.
+struct SseEvent {
+	fields: Vec<String>,
+	data: Value,
+}
+
+impl SseEvent {
+	fn parse(block: &[u8]) -> Option<SseEvent> {
+		let text = std::str::from_utf8(block).ok()?;
+		let mut fields = Vec::new();
+		let mut data: Option<String> = None;
+		for line in text.lines().filter(|l| !l.is_empty()) {
+			match line.strip_prefix("data:") {
+				Some(d) => {
+					let d = d.strip_prefix(' ').unwrap_or(d);
+					match &mut data {
+						Some(acc) => {
+							acc.push('\n');
+							acc.push_str(d);
+						},
+						None => data = Some(d.to_string()),
+					}
+				},
+				None => fields.push(line.to_string()),
+			}
+		}
+		let data = serde_json::from_str(&data?).ok()?;
+		Some(SseEvent { fields, data })
+	}
+
+	fn encode(mut self, vault: &TokenVault, out: &mut BytesMut) {
+		vault.restore_json(&mut self.data);
+		for field in &self.fields {
+			out.extend_from_slice(field.as_bytes());
+			out.extend_from_slice(b"\n");
+		}
+		out.extend_from_slice(b"data: ");
+		out.extend_from_slice(self.data.to_string().as_bytes());
+		out.extend_from_slice(b"\n\n");
+	}
+
+	/// The streamed text delta, for the same formats the streaming guardrails understand.
+	fn text_delta(&mut self) -> Option<&mut String> {
+		let v = &self.data;
+		let pointer = if v.get("type").and_then(Value::as_str) == Some("response.output_text.delta") {
+			"/delta".to_string()
+		} else if let Some(parts) = v
+			.pointer("/candidates/0/content/parts")
+			.and_then(Value::as_array)
+		{
+			let i = parts
+				.iter()
+				.rposition(|p| p.get("text").is_some_and(Value::is_string))?;
+			format!("/candidates/0/content/parts/{i}/text")
+		} else {
+			["/choices/0/delta/content", "/delta/text"]
+				.into_iter()
+				.find(|p| v.pointer(p).is_some_and(Value::is_string))?
+				.to_string()
+		};
+		match self.data.pointer_mut(&pointer) {
+			Some(Value::String(s)) => Some(s),
+			_ => None,
+		}
+	}
+}
+
+/// Restores tokens in SSE events. A delta ending in an incomplete token is held back until the
+/// next delta completes it; any other event releases it unchanged.
+struct SseRestorer {
+	vault: Arc<TokenVault>,
+	held: Option<(SseEvent, String)>,
+}
+
+impl SseRestorer {
+	fn push(&mut self, block: &[u8], out: &mut BytesMut) {
+		let Some(mut event) = SseEvent::parse(block) else {
+			// Not JSON (comments, `[DONE]`, ...); nothing to restore.
+			self.flush(out);
+			out.extend_from_slice(block);
+			return;
+		};
+		let Some(delta) = event.text_delta() else {
+			self.flush(out);
+			event.encode(&self.vault, out);
+			return;
+		};
+		if let Some((held, partial)) = self.held.take() {
+			delta.insert_str(0, &partial);
+			held.encode(&self.vault, out);
+		}
+		let partial_len = self.vault.partial_token_len(delta);
+		if partial_len == 0 {
+			event.encode(&self.vault, out);
+		} else {
+			let partial = delta.split_off(delta.len() - partial_len);
+			self.held = Some((event, partial));
+		}
+	}
+
+	fn flush(&mut self, out: &mut BytesMut) {
+		if let Some((mut held, partial)) = self.held.take() {
+			if let Some(delta) = held.text_delta() {
+				delta.push_str(&partial);
+			}
+			held.encode(&self.vault, out);
+		}
+	}
+}
+
+/// Split the next complete SSE event, including its terminating blank line, off `buf`.
+fn next_event(buf: &mut BytesMut) -> Option<Bytes> {
+	let mut line_start = 0;
+	while let Some(pos) = buf[line_start..].iter().position(|b| *b == b'\n') {
+		let end = line_start + pos;
+		if matches!(&buf[line_start..end], b"" | b"\r") {
+			return Some(buf.split_to(end + 1).freeze());
+		}
+		line_start = end + 1;
+	}
+	None
+}
+
+pin_project! {
+	// An `http_body::Body` wrapper that restores surrogate tokens in an SSE response.
+	pub struct RestoringSseBody {
+		#[pin]
+		inner: http::Body,
+		decode_buffer: BytesMut,
+		restorer: SseRestorer,
+		trailers: Option<Frame<Bytes>>,
+		done: bool,
+	}
+}
+
+impl RestoringSseBody {
+	// We do actually return Self; just wrapped in an http_body::Body.
+	#[allow(clippy::new_ret_no_self)]
+	pub fn new(inner: http::Body, vault: Arc<TokenVault>) -> http::Body {
+		http::Body::new(Self {
+			inner,
+			decode_buffer: BytesMut::new(),
+			restorer: SseRestorer { vault, held: None },
+			trailers: None,
+			done: false,
+		})
+	}
+}
+
+impl http_body::Body for RestoringSseBody {
+	type Data = Bytes;
+	type Error = http::Error;
+
+	fn poll_frame(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+		let mut this = self.project();
+		loop {
+			if *this.done {
+				return Poll::Ready(this.trailers.take().map(Ok));
+			}
+			let mut out = BytesMut::new();
+			match ready!(this.inner.as_mut().poll_frame(cx)) {
+				Some(Ok(frame)) => match frame.into_data() {
+					Ok(data) => {
+						this.decode_buffer.extend_from_slice(&data);
+						while let Some(event) = next_event(this.decode_buffer) {
+							this.restorer.push(&event, &mut out);
+						}
+					},
+					Err(trailers) => {
+						*this.trailers = Some(trailers);
+						*this.done = true;
+					},
+				},
+				Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+				None => *this.done = true,
+			}
+			if *this.done {
+				// A trailing event without its blank line is still forwarded.
+				if !this.decode_buffer.is_empty() {
+					let rest = this.decode_buffer.split();
+					this.restorer.push(&rest, &mut out);
+				}
+				this.restorer.flush(&mut out);
+			}
+			if !out.is_empty() {
+				return Poll::Ready(Some(Ok(Frame::data(out.freeze()))));
+			}
+		}
+	}
+}
@@ -0,0 +1,127 @@
+use http_body_util::BodyExt as _;
+use serde_json::json;
+
+use super::*;
+
+fn vault() -> TokenVault {
+	let mut vault = TokenVault::default();
+	assert_eq!(
+		vault.tokenize("EMAIL_ADDRESS", "jane@example.com"),
+		"<EMAIL_ADDRESS_1>"
+	);
+	assert_eq!(
+		vault.tokenize("PHONE_NUMBER", "555-0100"),
+		"<PHONE_NUMBER_1>"
+	);
+	assert_eq!(
+		vault.tokenize("EMAIL_ADDRESS", "john@example.com"),
+		"<EMAIL_ADDRESS_2>"
+	);
+	vault
+}
+
+fn sse(chunks: &[&str]) -> http::Body {
+	use std::convert::Infallible;
+
+	use futures_util::stream;
+	let chunks: Vec<Bytes> = chunks.iter().map(|c| Bytes::from(c.to_string())).collect();
+	http::Body::from_stream(stream::iter(
+		chunks.into_iter().map(Ok::<Bytes, Infallible>),
+	))
+}
+
+fn delta(text: &str) -> String {
+	format!(
+		"data: {}\n\n",
+		json!({"choices": [{"index": 0, "delta": {"content": text}}]})
+	)
+}
+
+async fn restored_text(body: http::Body) -> String {
+	let bytes = body.collect().await.unwrap().to_bytes();
+	String::from_utf8(bytes.to_vec())
+		.unwrap()
+		.split("\n\n")
+		.filter_map(|e| e.strip_prefix("data: "))
+		.filter_map(|d| serde_json::from_str::<Value>(d).ok())
+		.filter_map(|v| {
+			v.pointer("/choices/0/delta/content")?
+				.as_str()
+				.map(str::to_string)
+		})
+		.collect()
+}
+
+#[test]
+fn tokens_are_stable_per_value() {
+	let mut vault = vault();
+	assert_eq!(
+		vault.tokenize("EMAIL_ADDRESS", "jane@example.com"),
+		"<EMAIL_ADDRESS_1>"
+	);
+	assert_eq!(
+		vault.restore("mail <EMAIL_ADDRESS_1> or <EMAIL_ADDRESS_2>, not <EMAIL_ADDRESS_3> or <b>"),
+		"mail jane@example.com or john@example.com, not <EMAIL_ADDRESS_3> or <b>"
+	);
+	assert_eq!(vault.restore("<<PHONE_NUMBER_1>>"), "<555-0100>");
+}
+
+#[test]
+fn restore_json_keeps_escaping_valid() {
+	let mut vault = TokenVault::default();
+	let token = vault.tokenize("MASKED", "say \"hi\"");
+	let body =
+		serde_json::to_vec(&json!({"choices": [{"message": {"content": format!("ok: {token}")}}]}))
+			.unwrap();
+	let restored: Value = serde_json::from_slice(&vault.restore_body(body)).unwrap();
+	assert_eq!(
+		restored.pointer("/choices/0/message/content"),
+		Some(&json!("ok: say \"hi\""))
+	);
+	assert_eq!(vault.restore_body(b"not json".to_vec()), b"not json");
+}
+
+#[test]
+fn partial_tokens_are_detected() {
+	let vault = vault();
+	assert_eq!(vault.partial_token_len("write to <EMAIL_AD"), 9);
+	assert_eq!(vault.partial_token_len("write to <"), 1);
+	assert_eq!(vault.partial_token_len("write to <EMAIL_ADDRESS_1>"), 0);
+	assert_eq!(vault.partial_token_len("a < b"), 0);
+}
+
+#[tokio::test]
+async fn stream_restores_tokens_split_across_events() {
+	let vault = Arc::new(vault());
+	let first = delta("Contact <EMAIL_");
+	let second = delta("ADDRESS_1> or <PHONE");
+	let third = delta("_NUMBER_1> now");
+	// Events are also split across body frames.
+	let (a, b) = second.split_at(10);
+	let body = sse(&[&first, a, b, &third, "data: [DONE]\n\n"]);
+	let out = RestoringSseBody::new(body, vault);
+	assert_eq!(
+		restored_text(out).await,
+		"Contact jane@example.com or 555-0100 now"
+	);
+}
+
+#[tokio::test]
+async fn stream_releases_unfinished_tokens() {
+	let vault = Arc::new(vault());
+	let finish = format!(
+		"event: chunk\ndata: {}\n\n",
+		json!({"choices": [{"index": 0, "delta": {}, "finish_reason": "stop"}]})
+	);
+	let body = sse(&[&delta("a <EMAIL"), &finish, &delta("b <PHONE_NUMBER_1")]);
+	let bytes = RestoringSseBody::new(body, vault)
+		.collect()
+		.await
+		.unwrap()
+		.to_bytes();
+	let out = String::from_utf8(bytes.to_vec()).unwrap();
+	assert!(out.contains("a <EMAIL"), "{out}");
+	assert!(out.contains("event: chunk\ndata: "), "{out}");
+	assert!(out.contains("b <PHONE_NUMBER_1"), "{out}");
+	assert!(out.find("a <EMAIL") < out.find("event: chunk"), "{out}");
+}
@@ -0,0 +1,114 @@
+//! Guardrails over the tools a request declares and the tool calls a model makes.
+
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+use serde_json::Value;
+
+use crate::json;
+use crate::llm::policy::{Policy, RegexResult, RegexRules, ToolGuard};
+use crate::llm::types::{ToolCall, ToolDefinition};
+
+#[cfg(test)]
+#[path = "tools_tests.rs"]
+mod tests;
+
+/// The tools a request declared, with their argument schemas. Recorded from the request so
+/// response guards can validate the arguments of the calls the model makes.
+#[derive(Debug, Default)]
+pub struct DeclaredTools(HashMap<String, Option<Value>>);
+
+impl DeclaredTools {
+	pub fn new(tools: Vec<ToolDefinition>) -> Self {
+		Self(tools.into_iter().map(|t| (t.name, t.parameters)).collect())
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+}
+
+impl ToolGuard {
+	/// Whether the guard permits a tool with this name. `deny` takes precedence over `allow`.
+	pub fn allows(&self, name: &str) -> bool {
+		if self.deny.iter().any(|p| name_matches(p, name)) {
+			return false;
+		}
+		self
+			.allow
+			.as_ref()
+			.is_none_or(|allow| allow.iter().any(|p| name_matches(p, name)))
+	}
+}
+
+/// Match a tool name against a pattern. A trailing `*` matches any suffix.
+fn name_matches(pattern: &str, name: &str) -> bool {
+	match pattern.strip_suffix('*') {
+		Some(prefix) => name.starts_with(prefix),
+		None => pattern == name,
+	}
+}
+
+/// Check that a tool call is allowed and, when enabled, that its arguments match the schema the
+/// request declared for the tool.
+pub(super) fn check_call(
+	guard: &ToolGuard,
+	call: &ToolCall,
+	declared: Option<&DeclaredTools>,
+) -> anyhow::Result<()> {
+	anyhow::ensure!(
+		guard.allows(&call.name),
+		"tool {} is not allowed",
+		call.name
+	);
+	if !guard.validate_arguments {
+		return Ok(());
+	}
+	let Some(declared) = declared else {
+		return Ok(());
+	};
+	let schema = declared
+		.0
+		.get(&call.name)
+		.with_context(|| format!("tool {} was not declared in the request", call.name))?;
+	if let Some(schema) = schema {
+		json::validate_schema(schema, &call.arguments)
+			.with_context(|| format!("invalid arguments for tool {}", call.name))?;
+	}
+	Ok(())
+}
+
+pub(super) enum ArgumentsResult {
+	Unchanged,
+	Masked,
+	Reject,
+}
+
+/// Apply regex rules to every string value in tool-call arguments, masking matches in place.
+pub(super) fn apply_regex(arguments: &mut Value, rules: &RegexRules) -> ArgumentsResult {
+	match arguments {
+		Value::String(s) => match Policy::apply_prompt_guard_regex(s, rules, None) {
+			Some(RegexResult::Reject) => ArgumentsResult::Reject,
+			Some(RegexResult::Mask(masked)) => {
+				*s = masked;
+				ArgumentsResult::Masked
+			},
+			None => ArgumentsResult::Unchanged,
+		},
+		Value::Array(items) => fold(items.iter_mut(), rules),
+		Value::Object(fields) => fold(fields.values_mut(), rules),
+		_ => ArgumentsResult::Unchanged,
+	}
+}
+
+fn fold<'a>(values: impl Iterator<Item = &'a mut Value>, rules: &RegexRules) -> ArgumentsResult {
+	let mut result = ArgumentsResult::Unchanged;
+	for v in values {
+		match apply_regex(v, rules) {
+			ArgumentsResult::Reject => return ArgumentsResult::Reject,
+			ArgumentsResult::Masked => result = ArgumentsResult::Masked,
+			ArgumentsResult::Unchanged => {},
+		}
+	}
+	result
+}
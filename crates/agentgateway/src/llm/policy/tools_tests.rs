@@ -0,0 +1,238 @@
+use serde_json::json;
+
+use super::*;
+use crate::llm::policy::{Action, Builtin, GuardrailOutcome, RegexRule, RequestRejection};
+use crate::llm::types::{completions, messages, responses};
+use crate::llm::{RequestType, ResponseType};
+
+fn guard(value: Value) -> ToolGuard {
+	serde_json::from_value(value).unwrap()
+}
+
+fn weather_schema() -> Value {
+	json!({
+		"type": "object",
+		"properties": {
+			"city": {"type": "string", "minLength": 1},
+			"unit": {"enum": ["c", "f"]}
+		},
+		"required": ["city"],
+		"additionalProperties": false
+	})
+}
+
+fn completions_request() -> completions::Request {
+	serde_json::from_value(json!({
+		"model": "gpt-4o",
+		"messages": [{"role": "user", "content": "hi"}],
+		"tools": [
+			{"type": "function", "function": {"name": "get_weather", "parameters": weather_schema()}},
+			{"type": "function", "function": {"name": "shell_exec", "parameters": {"type": "object"}}}
+		],
+		"tool_choice": {"type": "function", "function": {"name": "shell_exec"}},
+		"parallel_tool_calls": true
+	}))
+	.unwrap()
+}
+
+fn completions_response(arguments: &str) -> completions::Response {
+	serde_json::from_value(json!({
+		"model": "gpt-4o",
+		"choices": [{
+			"index": 0,
+			"finish_reason": "tool_calls",
+			"message": {
+				"role": "assistant",
+				"tool_calls": [{
+					"id": "call_1",
+					"type": "function",
+					"function": {"name": "get_weather", "arguments": arguments}
+				}]
+			}
+		}]
+	}))
+	.unwrap()
+}
+
+fn declared() -> DeclaredTools {
+	DeclaredTools::new(completions_request().get_tools())
+}
+
+#[test]
+fn names_match_allow_and_deny_lists() {
+	let g = guard(json!({"allow": ["get_*", "search"], "deny": ["get_secret"]}));
+	assert!(g.allows("get_weather"));
+	assert!(g.allows("search"));
+	assert!(!g.allows("get_secret"));
+	assert!(!g.allows("searcher"));
+	assert!(!g.allows("shell_exec"));
+	assert!(guard(json!({})).allows("anything"));
+}
+
+#[test]
+fn request_with_disallowed_tools_is_rejected() {
+	let g = guard(json!({"deny": ["shell_*"]}));
+	let mut req = completions_request();
+	let outcome = Policy::apply_tools(&mut req, &RequestRejection::default(), &g);
+	assert!(matches!(outcome, GuardrailOutcome::Rejected(_)));
+	assert_eq!(req.get_tools().len(), 2);
+}
+
+#[test]
+fn disallowed_tools_are_stripped_from_completions() {
+	let g = guard(json!({"deny": ["shell_*"], "strip": true}));
+	let mut req = completions_request();
+	let outcome = Policy::apply_tools(&mut req, &RequestRejection::default(), &g);
+	assert!(matches!(outcome, GuardrailOutcome::Masked));
+	let names: Vec<_> = req.get_tools().into_iter().map(|t| t.name).collect();
+	assert_eq!(names, vec!["get_weather"]);
+	// The forced choice named a removed tool.
+	assert!(req.tool_choice.is_none());
+	assert!(req.rest.get("parallel_tool_calls").is_some());
+
+	// Removing every tool removes the tool settings entirely.
+	let g = guard(json!({"allow": [], "strip": true}));
+	Policy::apply_tools(&mut req, &RequestRejection::default(), &g);
+	assert!(req.tools.is_none());
+	assert!(req.rest.get("parallel_tool_calls").is_none());
+}
+
+#[test]
+fn disallowed_tools_are_stripped_from_messages_and_responses() {
+	let g = guard(json!({"allow": ["get_weather"], "strip": true}));
+
+	let mut req: messages::Request = serde_json::from_value(json!({
+		"model": "claude-sonnet-4",
+		"max_tokens": 100,
+		"messages": [{"role": "user", "content": "hi"}],
+		"tools": [
+			{"name": "get_weather", "input_schema": weather_schema()},
+			{"type": "web_search_20250305", "name": "web_search"}
+		],
+		"tool_choice": {"type": "tool", "name": "web_search"}
+	}))
+	.unwrap();
+	let tools = req.get_tools();
+	assert_eq!(tools[0].parameters, Some(weather_schema()));
+	Policy::apply_tools(&mut req, &RequestRejection::default(), &g);
+	assert_eq!(req.rest["tools"].as_array().unwrap().len(), 1);
+	assert!(req.rest.get("tool_choice").is_none());
+
+	let mut req: responses::Request = serde_json::from_value(json!({
+		"model": "gpt-4o",
+		"input": "hi",
+		"tools": [
+			{"type": "function", "name": "get_weather", "parameters": weather_schema()},
+			{"type": "web_search"}
+		],
+		"tool_choice": "required"
+	}))
+	.unwrap();
+	let names: Vec<_> = req.get_tools().into_iter().map(|t| t.name).collect();
+	assert_eq!(names, vec!["get_weather", "web_search"]);
+	Policy::apply_tools(&mut req, &RequestRejection::default(), &g);
+	assert_eq!(req.rest["tools"].as_array().unwrap().len(), 1);
+	assert_eq!(req.rest["tool_choice"], json!("required"));
+}
+
+#[test]
+fn tool_call_arguments_are_validated_against_the_declared_schema() {
+	let g = guard(json!({"validateArguments": true}));
+	let declared = declared();
+	let check = |arguments: &str| {
+		let resp = completions_response(arguments);
+		check_call(&g, &resp.tool_calls()[0], Some(&declared))
+	};
+	assert!(check(r#"{"city": "Paris", "unit": "c"}"#).is_ok());
+	assert!(check(r#"{"unit": "c"}"#).is_err());
+	assert!(check(r#"{"city": "Paris", "unit": "k"}"#).is_err());
+	assert!(check(r#"{"city": "Paris", "extra": 1}"#).is_err());
+	assert!(check(r#"{"city": "#).is_err());
+
+	let call = ToolCall {
+		name: "undeclared".to_string(),
+		arguments: json!({}),
+	};
+	assert!(check_call(&g, &call, Some(&declared)).is_err());
+	// Without validation, only the name lists apply.
+	assert!(check_call(&guard(json!({})), &call, Some(&declared)).is_ok());
+}
+
+#[test]
+fn response_with_disallowed_tool_call_is_rejected() {
+	let g = guard(json!({"deny": ["get_weather"]}));
+	let mut resp = completions_response(r#"{"city": "Paris"}"#);
+	let outcome = Policy::apply_tools_response(&mut resp, &RequestRejection::default(), &g, None);
+	assert!(matches!(outcome, Ok(GuardrailOutcome::Rejected(_))));
+}
+
+#[test]
+fn tool_call_arguments_are_masked() {
+	let g = ToolGuard {
+		allow: None,
+		deny: vec![],
+		strip: false,
+		validate_arguments: false,
+		arguments: Some(RegexRules {
+			action: Action::Mask,
+			rules: vec![RegexRule::Builtin {
+				builtin: Builtin::Email,
+			}],
+		}),
+	};
+
+	let mut resp = completions_response(r#"{"to": ["jane@example.com"], "subject": "hi"}"#);
+	let outcome = Policy::apply_tools_response(&mut resp, &RequestRejection::default(), &g, None);
+	assert!(matches!(outcome, Ok(GuardrailOutcome::Masked)));
+	assert_eq!(
+		resp.tool_calls()[0].arguments,
+		json!({"to": ["<EMAIL_ADDRESS>"], "subject": "hi"})
+	);
+
+	let mut resp: messages::Response = serde_json::from_value(json!({
+		"id": "msg_1",
+		"type": "message",
+		"role": "assistant",
+		"model": "claude-sonnet-4",
+		"stop_reason": "tool_use",
+		"stop_sequence": null,
+		"usage": {"input_tokens": 1, "output_tokens": 1},
+		"content": [
+			{"type": "text", "text": "Sending"},
+			{"type": "tool_use", "id": "tu_1", "name": "send_email", "input": {"to": "jane@example.com"}}
+		]
+	}))
+	.unwrap();
+	let outcome = Policy::apply_tools_response(&mut resp, &RequestRejection::default(), &g, None);
+	assert!(matches!(outcome, Ok(GuardrailOutcome::Masked)));
+	assert_eq!(
+		resp.tool_calls()[0].arguments,
+		json!({"to": "<EMAIL_ADDRESS>"})
+	);
+
+	let mut resp: responses::Response = serde_json::from_value(json!({
+		"id": "resp_1",
+		"status": "completed",
+		"model": "gpt-4o",
+		"output": [{
+			"type": "function_call",
+			"id": "fc_1",
+			"call_id": "call_1",
+			"name": "send_email",
+			"arguments": "{\"to\":\"jane@example.com\"}",
+			"status": "completed"
+		}]
+	}))
+	.unwrap();
+	let g = ToolGuard {
+		arguments: Some(RegexRules {
+			action: Action::Reject,
+			rules: vec![RegexRule::Builtin {
+				builtin: Builtin::Email,
+			}],
+		}),
+		..g
+	};
+	let outcome = Policy::apply_tools_response(&mut resp, &RequestRejection::default(), &g, None);
+	assert!(matches!(outcome, Ok(GuardrailOutcome::Rejected(_))));
+}
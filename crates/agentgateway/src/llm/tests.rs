@@ -32,8 +32,19 @@ fn streaming_amend_on_drop_updates_local_rate_limit() {
 			tokens_per_fill: 10,
 			fill_interval: std::time::Duration::from_secs(60),
 			limit_type: crate::http::localratelimit::RateLimitType::Tokens,
+			key: None,
+			max_keys: crate::http::localratelimit::DEFAULT_MAX_KEYS,
 		})
 		.unwrap();
+	let metrics = crate::telemetry::metrics::Metrics::new(
+		&mut prometheus_client::registry::Registry::default(),
+		Default::default(),
+	);
+	let req = ::http::Request::new(crate::http::Body::empty());
+	let bucket = rate_limit
+		.check_llm_request(&req, &llm_request_with_tokens(None), &metrics)
+		.unwrap()
+		.unwrap();
 	let log = AsyncLog::default();
 	log.store(Some(LLMInfo {
 		request: llm_request_with_tokens(Some(2)),
@@ -47,7 +58,7 @@ fn streaming_amend_on_drop_updates_local_rate_limit() {
 	let mut amend = AmendOnDrop::new(
 		log,
 		LLMResponsePolicies {
-			local_rate_limit: vec![rate_limit.clone()],
+			local_rate_limit: vec![bucket],
 			..Default::default()
 		},
 		None,
@@ -57,12 +68,12 @@ fn streaming_amend_on_drop_updates_local_rate_limit() {
 
 	assert!(
 		rate_limit
-			.check_llm_request(&llm_request_with_tokens(Some(7)))
+			.check_llm_request(&req, &llm_request_with_tokens(Some(7)), &metrics)
 			.is_err()
 	);
 	assert!(
 		rate_limit
-			.check_llm_request(&llm_request_with_tokens(Some(6)))
+			.check_llm_request(&req, &llm_request_with_tokens(Some(6)), &metrics)
 			.is_ok()
 	);
 }
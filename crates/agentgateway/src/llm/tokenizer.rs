@@ -0,0 +1,219 @@
+//! Tokenizers used to count the tokens of a request before it is sent.
+//!
+//! OpenAI models are counted with their tiktoken encoding, and other model families with an
+//! estimate calibrated against their own tokenizer. Rules configured on a provider can select a
+//! HuggingFace `tokenizer.json` instead, for models whose tokenizer is published.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tiktoken_rs::tokenizer::get_tokenizer;
+
+use crate::llm::{AIError, SimpleChatCompletionMessage, get_bpe_from_tokenizer};
+use crate::*;
+
+#[cfg(test)]
+#[path = "tokenizer_tests.rs"]
+mod tests;
+
+/// Tokens added to each message for its role and the separators around it.
+const TOKENS_PER_MESSAGE: u64 = 4;
+/// Tokens that prime the reply.
+const REPLY_TOKENS: u64 = 3;
+
+/// Characters per token for model families without a published tokenizer, measured on English
+/// prose. Models that match none of these fall back to `cl100kBase`.
+const ESTIMATES: &[(&str, f64)] = &[("claude", 3.5), ("gemini", 4.0), ("gemma", 4.0)];
+
+/// Rules selecting how request tokens are counted, checked in order. Models that match no rule
+/// use the built-in tokenizer for the model family.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(transparent)]
+pub struct Tokenizers(Vec<TokenizerRule>);
+
+#[apply(schema!)]
+pub struct TokenizerRule {
+	/// Models the rule applies to. A `*` at the end of an entry matches any suffix. The rule applies
+	/// to every model when unset.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub models: Vec<Strng>,
+	#[serde(flatten)]
+	pub tokenizer: Tokenizer,
+}
+
+#[apply(schema!)]
+pub enum Tokenizer {
+	/// Count with an OpenAI tiktoken encoding.
+	Tiktoken(Encoding),
+	/// Count with a HuggingFace `tokenizer.json` file, loaded when the configuration is.
+	File(#[cfg_attr(feature = "schema", schemars(with = "PathBuf"))] HuggingFaceTokenizer),
+	/// Estimate the count from the length of the text.
+	Estimate(Estimate),
+}
+
+#[apply(schema!)]
+#[derive(Copy)]
+pub struct Estimate {
+	/// Average number of characters per token.
+	pub chars_per_token: f64,
+}
+
+#[apply(schema_enum!)]
+pub enum Encoding {
+	O200kBase,
+	Cl100kBase,
+}
+
+impl Tokenizers {
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// Count the tokens of a request for `model`, made of `messages`.
+	pub fn count(
+		&self,
+		model: &str,
+		messages: &[SimpleChatCompletionMessage],
+	) -> Result<u64, AIError> {
+		let tokens: u64 = self.message_counts(model, messages)?.into_iter().sum();
+		Ok(tokens + REPLY_TOKENS)
+	}
+
+	/// Count the tokens of each of `messages`, not counting the tokens that prime the reply.
+	pub fn message_counts(
+		&self,
+		model: &str,
+		messages: &[SimpleChatCompletionMessage],
+	) -> Result<Vec<u64>, AIError> {
+		let configured = self
+			.0
+			.iter()
+			.find(|rule| rule.matches(model))
+			.map(|rule| &rule.tokenizer);
+		let builtin;
+		let tokenizer = match configured {
+			Some(tokenizer) => tokenizer,
+			None => {
+				builtin = Tokenizer::builtin(model)?;
+				&builtin
+			},
+		};
+		Ok(
+			messages
+				.iter()
+				.map(|m| tokenizer.count_message(m))
+				.collect(),
+		)
+	}
+}
+
+impl TokenizerRule {
+	fn matches(&self, model: &str) -> bool {
+		self.models.is_empty()
+			|| self
+				.models
+				.iter()
+				.any(|pattern| match pattern.strip_suffix('*') {
+					Some(prefix) => model.starts_with(prefix),
+					None => pattern.as_str() == model,
+				})
+	}
+}
+
+impl Tokenizer {
+	/// The tokenizer used for `model` when no rule selects one.
+	pub fn builtin(model: &str) -> Result<Tokenizer, AIError> {
+		use tiktoken_rs::tokenizer::Tokenizer as Tiktoken;
+		match get_tokenizer(model) {
+			Some(Tiktoken::O200kBase) => return Ok(Tokenizer::Tiktoken(Encoding::O200kBase)),
+			Some(Tiktoken::Cl100kBase) => return Ok(Tokenizer::Tiktoken(Encoding::Cl100kBase)),
+			// Chat completion is only supported chat models
+			Some(_) => return Err(AIError::UnsupportedModel),
+			None => {},
+		}
+		let lower = model.to_ascii_lowercase();
+		let estimate = ESTIMATES
+			.iter()
+			.find(|(family, _)| lower.contains(family))
+			.map(|(_, chars_per_token)| {
+				Tokenizer::Estimate(Estimate {
+					chars_per_token: *chars_per_token,
+				})
+			});
+		Ok(estimate.unwrap_or(Tokenizer::Tiktoken(Encoding::Cl100kBase)))
+	}
+
+	/// Tokens of a message, including the tokens framing it.
+	pub fn count_message(&self, message: &SimpleChatCompletionMessage) -> u64 {
+		TOKENS_PER_MESSAGE + self.count_text(message.content.as_str())
+	}
+
+	pub fn count_text(&self, text: &str) -> u64 {
+		match self {
+			Tokenizer::Tiktoken(encoding) => {
+				let tokenizer = match encoding {
+					Encoding::O200kBase => tiktoken_rs::tokenizer::Tokenizer::O200kBase,
+					Encoding::Cl100kBase => tiktoken_rs::tokenizer::Tokenizer::Cl100kBase,
+				};
+				get_bpe_from_tokenizer(tokenizer)
+					.encode_with_special_tokens(text)
+					.len() as u64
+			},
+			Tokenizer::File(tokenizer) => tokenizer.count(text),
+			Tokenizer::Estimate(Estimate { chars_per_token }) => {
+				(text.chars().count() as f64 / chars_per_token.max(f64::EPSILON)).ceil() as u64
+			},
+		}
+	}
+}
+
+/// A HuggingFace tokenizer, configured by the path of its `tokenizer.json` file.
+#[derive(Clone)]
+pub struct HuggingFaceTokenizer {
+	path: PathBuf,
+	tokenizer: Arc<tokenizers::Tokenizer>,
+}
+
+impl HuggingFaceTokenizer {
+	pub fn load(path: PathBuf) -> anyhow::Result<Self> {
+		let tokenizer = tokenizers::Tokenizer::from_file(&path)
+			.map_err(|e| anyhow::anyhow!("failed to load tokenizer {}: {e}", path.display()))?;
+		Ok(Self {
+			path,
+			tokenizer: Arc::new(tokenizer),
+		})
+	}
+
+	fn count(&self, text: &str) -> u64 {
+		match self.tokenizer.encode_fast(text, false) {
+			Ok(encoding) => encoding.len() as u64,
+			Err(e) => {
+				debug!("failed to tokenize with {}: {e}", self.path.display());
+				Tokenizer::Tiktoken(Encoding::Cl100kBase).count_text(text)
+			},
+		}
+	}
+}
+
+impl fmt::Debug for HuggingFaceTokenizer {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("HuggingFaceTokenizer")
+			.field(&self.path)
+			.finish()
+	}
+}
+
+impl Serialize for HuggingFaceTokenizer {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		self.path.serialize(serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for HuggingFaceTokenizer {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let path = PathBuf::deserialize(deserializer)?;
+		Self::load(path).map_err(serde::de::Error::custom)
+	}
+}
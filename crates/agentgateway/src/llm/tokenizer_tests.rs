@@ -0,0 +1,107 @@
+use serde_json::{Value, json};
+
+use super::*;
+
+fn message(role: &str, content: &str) -> SimpleChatCompletionMessage {
+	SimpleChatCompletionMessage {
+		role: role.into(),
+		content: content.into(),
+	}
+}
+
+fn tokenizers(value: Value) -> Tokenizers {
+	serde_json::from_value(value).unwrap()
+}
+
+const WORD_LEVEL: &str = r#"{
+	"version": "1.0",
+	"truncation": null,
+	"padding": null,
+	"added_tokens": [],
+	"normalizer": null,
+	"pre_tokenizer": {"type": "Whitespace"},
+	"post_processor": null,
+	"decoder": null,
+	"model": {"type": "WordLevel", "vocab": {"[UNK]": 0, "hello": 1, "world": 2}, "unk_token": "[UNK]"}
+}"#;
+
+#[test]
+fn builtin_tokenizer_follows_the_model_family() {
+	let cases = [
+		("gpt-4o", Tokenizer::Tiktoken(Encoding::O200kBase)),
+		("gpt-4", Tokenizer::Tiktoken(Encoding::Cl100kBase)),
+		(
+			"claude-sonnet-4-5",
+			Tokenizer::Estimate(Estimate {
+				chars_per_token: 3.5,
+			}),
+		),
+		(
+			"gemini-2.5-pro",
+			Tokenizer::Estimate(Estimate {
+				chars_per_token: 4.0,
+			}),
+		),
+		("llama-3.1-70b", Tokenizer::Tiktoken(Encoding::Cl100kBase)),
+	];
+	for (model, want) in cases {
+		let got = Tokenizer::builtin(model).unwrap();
+		assert_eq!(format!("{got:?}"), format!("{want:?}"), "{model}");
+	}
+	// Completion-only encodings cannot count chat messages.
+	assert!(Tokenizer::builtin("text-davinci-003").is_err());
+}
+
+#[test]
+fn estimate_counts_characters() {
+	let estimate = Tokenizer::Estimate(Estimate {
+		chars_per_token: 4.0,
+	});
+	assert_eq!(estimate.count_text(""), 0);
+	assert_eq!(estimate.count_text("abcd"), 1);
+	assert_eq!(estimate.count_text("abcde"), 2);
+	assert_eq!(estimate.count_message(&message("user", "abcd")), 5);
+}
+
+#[test]
+fn rules_are_matched_in_order() {
+	let t = tokenizers(json!([
+		{"models": ["claude-*"], "estimate": {"charsPerToken": 1.0}},
+		{"models": ["my-model"], "estimate": {"charsPerToken": 2.0}},
+	]));
+	let messages = [message("user", "abcdefgh")];
+	assert_eq!(t.count("claude-opus-4", &messages).unwrap(), 4 + 8 + 3);
+	assert_eq!(t.count("my-model", &messages).unwrap(), 4 + 4 + 3);
+	// Models matching no rule use the built-in tokenizer.
+	assert_eq!(
+		t.count("my-model-2", &messages).unwrap(),
+		Tokenizers::default()
+			.count("my-model-2", &messages)
+			.unwrap()
+	);
+
+	let all = tokenizers(json!([{"tiktoken": "o200kBase"}]));
+	assert_eq!(
+		all.count("my-model", &messages).unwrap(),
+		Tokenizers::default().count("gpt-4o", &messages).unwrap()
+	);
+}
+
+#[test]
+fn huggingface_tokenizer_is_loaded_from_file() {
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("tokenizer.json");
+	std::fs::write(&path, WORD_LEVEL).unwrap();
+
+	let t = tokenizers(json!([{"models": ["my-model"], "file": path}]));
+	let messages = [message("user", "hello world, hello")];
+	assert_eq!(t.count("my-model", &messages).unwrap(), 4 + 4 + 3);
+	// The file is serialized by its path.
+	assert_eq!(
+		serde_json::to_value(&t).unwrap(),
+		json!([{"models": ["my-model"], "file": path}])
+	);
+
+	let missing = dir.path().join("missing.json");
+	assert!(serde_json::from_value::<Tokenizers>(json!([{"file": missing}])).is_err());
+}
@@ -0,0 +1,319 @@
+use agent_core::prelude::Strng;
+use agent_core::strng;
+use serde::{Deserialize, Serialize};
+
+use crate::llm::tokenizer::Tokenizers;
+use crate::llm::types::RequestType;
+use crate::llm::types::multipart::Form;
+use crate::llm::{AIError, InputFormat, LLMRequest, LLMRequestParams, SimpleChatCompletionMessage};
+
+/// OpenAI /v1/audio/speech request.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct SpeechRequest {
+	pub model: Option<String>,
+	pub input: String,
+
+	// Everything else - passthrough
+	#[serde(flatten, default)]
+	pub rest: serde_json::Value,
+}
+
+impl RequestType for SpeechRequest {
+	fn model(&mut self) -> &mut Option<String> {
+		&mut self.model
+	}
+
+	fn prepend_prompts(&mut self, _prompts: Vec<SimpleChatCompletionMessage>) {
+		// Ignored
+	}
+
+	fn append_prompts(&mut self, _prompts: Vec<SimpleChatCompletionMessage>) {
+		// Ignored
+	}
+
+	fn to_llm_request(
+		&self,
+		provider: Strng,
+		_tokenizers: Option<&Tokenizers>,
+	) -> Result<LLMRequest, AIError> {
+		Ok(LLMRequest {
+			input_tokens: None,
+			input_format: InputFormat::AudioSpeech,
+			native_format: Some(crate::llm::custom::ProviderFormat::AudioSpeech),
+			cache_convention: crate::llm::CacheTokenConvention::pending(),
+			request_model: strng::new(self.model.as_deref().unwrap_or_default()),
+			provider,
+			streaming: false,
+			params: LLMRequestParams::default(),
+			prompt: Default::default(),
+		})
+	}
+
+	fn get_messages(&self) -> Vec<SimpleChatCompletionMessage> {
+		vec![SimpleChatCompletionMessage {
+			role: strng::literal!("user"),
+			content: strng::new(&self.input),
+		}]
+	}
+
+	fn set_messages(&mut self, messages: Vec<SimpleChatCompletionMessage>) {
+		if let Some(message) = messages.into_iter().next() {
+			self.input = message.content.to_string();
+		}
+	}
+
+	fn to_openai(&self) -> Result<Vec<u8>, AIError> {
+		serde_json::to_vec(&self).map_err(AIError::RequestMarshal)
+	}
+}
+
+/// OpenAI /v1/audio/transcriptions request. The audio is uploaded as multipart form data; only the
+/// model and the optional prompt are inspected, everything else is forwarded as-is.
+#[derive(Debug, Clone)]
+pub struct TranscriptionRequest {
+	pub form: Form,
+	pub model: Option<String>,
+	pub prompt: Option<String>,
+}
+
+impl TranscriptionRequest {
+	pub fn new(form: Form) -> Self {
+		TranscriptionRequest {
+			model: form.text("model").map(|m| m.trim().to_string()),
+			prompt: form.text("prompt").map(str::to_string),
+			form,
+		}
+	}
+}
+
+impl RequestType for TranscriptionRequest {
+	fn model(&mut self) -> &mut Option<String> {
+		&mut self.model
+	}
+
+	fn prepend_prompts(&mut self, _prompts: Vec<SimpleChatCompletionMessage>) {
+		// Ignored
+	}
+
+	fn append_prompts(&mut self, _prompts: Vec<SimpleChatCompletionMessage>) {
+		// Ignored
+	}
+
+	fn to_llm_request(
+		&self,
+		provider: Strng,
+		_tokenizers: Option<&Tokenizers>,
+	) -> Result<LLMRequest, AIError> {
+		Ok(LLMRequest {
+			input_tokens: None,
+			input_format: InputFormat::AudioTranscription,
+			native_format: Some(crate::llm::custom::ProviderFormat::AudioTranscription),
+			cache_convention: crate::llm::CacheTokenConvention::pending(),
+			request_model: strng::new(self.model.as_deref().unwrap_or_default()),
+			provider,
+			streaming: false,
+			params: LLMRequestParams {
+				temperature: self
+					.form
+					.text("temperature")
+					.and_then(|t| t.trim().parse().ok()),
+				..Default::default()
+			},
+			prompt: Default::default(),
+		})
+	}
+
+	fn get_messages(&self) -> Vec<SimpleChatCompletionMessage> {
+		self
+			.prompt
+			.iter()
+			.map(|prompt| SimpleChatCompletionMessage {
+				role: strng::literal!("user"),
+				content: strng::new(prompt),
+			})
+			.collect()
+	}
+
+	fn set_messages(&mut self, messages: Vec<SimpleChatCompletionMessage>) {
+		if self.prompt.is_some()
+			&& let Some(message) = messages.into_iter().next()
+		{
+			self.prompt = Some(message.content.to_string());
+		}
+	}
+
+	fn to_openai(&self) -> Result<Vec<u8>, AIError> {
+		let mut form = self.form.clone();
+		if let Some(model) = &self.model {
+			form.set_text("model", model);
+		}
+		if let Some(prompt) = &self.prompt {
+			form.set_text("prompt", prompt);
+		}
+		Ok(form.to_bytes().to_vec())
+	}
+}
+
+/// OpenAI /v1/audio/transcriptions response, in the `json` or `verbose_json` format. Other formats
+/// are plain text and carry no usage.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct TranscriptionResponse {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub text: Option<String>,
+	/// Length of the audio in seconds, only in the `verbose_json` format.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub duration: Option<f64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub usage: Option<TranscriptionUsage>,
+	#[serde(flatten, default)]
+	pub rest: serde_json::Value,
+}
+
+/// Token-priced models report token usage; duration-priced models such as `whisper-1` report the
+/// length of the audio.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranscriptionUsage {
+	Tokens {
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		input_tokens: Option<u64>,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		output_tokens: Option<u64>,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		total_tokens: Option<u64>,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		input_token_details: Option<TranscriptionInputDetails>,
+	},
+	Duration {
+		seconds: f64,
+	},
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct TranscriptionInputDetails {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub text_tokens: Option<u64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub audio_tokens: Option<u64>,
+}
+
+impl crate::llm::types::ResponseType for TranscriptionResponse {
+	fn to_llm_response(&self, include_completion_in_log: bool) -> crate::llm::LLMResponse {
+		let completion = if include_completion_in_log {
+			self.text.clone().map(|text| vec![text])
+		} else {
+			None
+		};
+		// Audio is billed per started second.
+		let seconds = |s: f64| s.max(0.0).ceil() as u64;
+		match &self.usage {
+			Some(TranscriptionUsage::Tokens {
+				input_tokens,
+				output_tokens,
+				total_tokens,
+				input_token_details,
+			}) => crate::llm::LLMResponse {
+				input_tokens: *input_tokens,
+				input_text_tokens: input_token_details.as_ref().and_then(|d| d.text_tokens),
+				input_audio_tokens: input_token_details.as_ref().and_then(|d| d.audio_tokens),
+				output_tokens: *output_tokens,
+				total_tokens: *total_tokens,
+				input_audio_seconds: self.duration.map(seconds),
+				completion,
+				..Default::default()
+			},
+			Some(TranscriptionUsage::Duration { seconds: s }) => crate::llm::LLMResponse {
+				input_audio_seconds: Some(seconds(*s)),
+				completion,
+				..Default::default()
+			},
+			None => crate::llm::LLMResponse {
+				input_audio_seconds: self.duration.map(seconds),
+				completion,
+				..Default::default()
+			},
+		}
+	}
+
+	fn to_webhook_choices(&self) -> Vec<crate::llm::policy::webhook::ResponseChoice> {
+		vec![]
+	}
+
+	fn set_webhook_choices(
+		&mut self,
+		_resp: Vec<crate::llm::policy::webhook::ResponseChoice>,
+	) -> anyhow::Result<()> {
+		Ok(())
+	}
+
+	fn serialize(&self) -> serde_json::Result<Vec<u8>> {
+		serde_json::to_vec(self)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use bytes::Bytes;
+
+	use super::*;
+	use crate::llm::types::ResponseType;
+
+	const BOUNDARY: &str = "audio-boundary";
+
+	fn upload() -> Bytes {
+		Bytes::from(format!(
+			"--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.wav\"\r\n\
+			 Content-Type: audio/wav\r\n\r\nRIFF\x00\x01\r\n\
+			 --{BOUNDARY}\r\nContent-Disposition: form-data; name=\"model\"\r\n\r\n whisper-1 \r\n\
+			 --{BOUNDARY}\r\nContent-Disposition: form-data; name=\"prompt\"\r\n\r\nmeeting notes\r\n\
+			 --{BOUNDARY}--\r\n"
+		))
+	}
+
+	#[tokio::test]
+	async fn transcription_form_is_rewritten_in_place() {
+		let form = Form::parse(BOUNDARY.to_string(), upload()).await.unwrap();
+		let mut req = TranscriptionRequest::new(form);
+		assert_eq!(req.model.as_deref(), Some("whisper-1"));
+		assert_eq!(
+			req.get_messages().first().map(|m| m.content.as_str()),
+			Some("meeting notes")
+		);
+
+		*req.model() = Some("gpt-4o-transcribe".to_string());
+		req.set_messages(vec![SimpleChatCompletionMessage {
+			role: strng::literal!("user"),
+			content: strng::literal!("<redacted>"),
+		}]);
+		let out = Form::parse(BOUNDARY.to_string(), Bytes::from(req.to_openai().unwrap()))
+			.await
+			.unwrap();
+		assert_eq!(out.text("model"), Some("gpt-4o-transcribe"));
+		assert_eq!(out.text("prompt"), Some("<redacted>"));
+		assert_eq!(
+			out.get("file").map(|f| f.data.as_ref()),
+			Some(&b"RIFF\x00\x01"[..])
+		);
+	}
+
+	#[test]
+	fn transcription_usage_is_tokens_or_seconds() {
+		let tokens: TranscriptionResponse = serde_json::from_str(
+			r#"{"text":"hi","usage":{"type":"tokens","input_tokens":14,"output_tokens":45,"total_tokens":59,"input_token_details":{"text_tokens":0,"audio_tokens":14}}}"#,
+		)
+		.unwrap();
+		let llm = tokens.to_llm_response(true);
+		assert_eq!(llm.input_tokens, Some(14));
+		assert_eq!(llm.input_audio_tokens, Some(14));
+		assert_eq!(llm.output_tokens, Some(45));
+		assert_eq!(llm.input_audio_seconds, None);
+		assert_eq!(llm.completion, Some(vec!["hi".to_string()]));
+
+		let duration: TranscriptionResponse =
+			serde_json::from_str(r#"{"text":"hi","usage":{"type":"duration","seconds":8.2}}"#).unwrap();
+		let llm = duration.to_llm_response(false);
+		assert_eq!(llm.input_audio_seconds, Some(9));
+		assert_eq!(llm.input_tokens, None);
+		assert_eq!(llm.completion, None);
+	}
+}
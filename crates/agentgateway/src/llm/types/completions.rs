@@ -5,7 +5,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::llm::bedrock::Provider;
 use crate::llm::policy::webhook::{Message, ResponseChoice};
-use crate::llm::types::{ResponseType, SimpleChatCompletionMessage};
+use crate::llm::tokenizer::Tokenizers;
+use crate::llm::types::{ResponseType, SimpleChatCompletionMessage, ToolCall};
 use crate::llm::{AIError, InputFormat, LLMRequest, LLMRequestParams, LLMResponse, conversion};
 use crate::{json, llm};
 
@@ -197,6 +198,11 @@ impl ResponseType for Response {
 				None
 			},
 			first_token: Default::default(),
+			response_cache: None,
+			response_cache_similarity: None,
+			batch_usage: Vec::new(),
+			output_images: None,
+			input_audio_seconds: None,
 		}
 	}
 
@@ -227,6 +233,63 @@ impl ResponseType for Response {
 	fn serialize(&self) -> serde_json::Result<Vec<u8>> {
 		serde_json::to_vec(&self)
 	}
+
+	fn tool_calls(&self) -> Vec<ToolCall> {
+		self
+			.choices
+			.iter()
+			.flat_map(|c| c.message.tool_calls())
+			.filter_map(|tc| {
+				let f = tc.get("function")?;
+				Some(ToolCall::from_encoded(
+					f.get("name")?.as_str()?,
+					f.get("arguments")?.as_str()?,
+				))
+			})
+			.collect()
+	}
+
+	fn set_tool_call_arguments(&mut self, arguments: Vec<serde_json::Value>) -> anyhow::Result<()> {
+		let mut slots: Vec<&mut serde_json::Value> = self
+			.choices
+			.iter_mut()
+			.flat_map(|c| c.message.tool_calls_mut())
+			.filter_map(|tc| {
+				let f = tc.get_mut("function")?.as_object_mut()?;
+				if !f.get("name").is_some_and(serde_json::Value::is_string) {
+					return None;
+				}
+				f.get_mut("arguments").filter(|a| a.is_string())
+			})
+			.collect();
+		if slots.len() != arguments.len() {
+			anyhow::bail!("tool call count mismatch");
+		}
+		for (slot, args) in slots.iter_mut().zip(arguments) {
+			**slot = serde_json::Value::String(super::encode_tool_arguments(&args));
+		}
+		Ok(())
+	}
+}
+
+impl ResponseMessage {
+	fn tool_calls(&self) -> &[serde_json::Value] {
+		self
+			.rest
+			.get("tool_calls")
+			.and_then(serde_json::Value::as_array)
+			.map(Vec::as_slice)
+			.unwrap_or_default()
+	}
+
+	fn tool_calls_mut(&mut self) -> &mut [serde_json::Value] {
+		self
+			.rest
+			.get_mut("tool_calls")
+			.and_then(serde_json::Value::as_array_mut)
+			.map(Vec::as_mut_slice)
+			.unwrap_or_default()
+	}
 }
 
 impl super::RequestType for Request {
@@ -271,15 +334,15 @@ impl super::RequestType for Request {
 		}
 	}
 
-	fn to_llm_request(&self, provider: Strng, tokenize: bool) -> Result<LLMRequest, AIError> {
+	fn to_llm_request(
+		&self,
+		provider: Strng,
+		tokenizers: Option<&Tokenizers>,
+	) -> Result<LLMRequest, AIError> {
 		let model = strng::new(self.model.as_deref().unwrap_or_default());
-		let input_tokens = if tokenize {
-			let messages = self.get_messages();
-			let tokens = crate::llm::num_tokens_from_messages(&model, &messages)?;
-			Some(tokens)
-		} else {
-			None
-		};
+		let input_tokens = tokenizers
+			.map(|t| t.count(&model, &self.get_messages()))
+			.transpose()?;
 		// Pass the original body through
 		let llm = LLMRequest {
 			input_tokens,
@@ -301,6 +364,8 @@ impl super::RequestType for Request {
 					.map(Into::into),
 				encoding_format: None,
 				dimensions: None,
+				batch_requests: None,
+				batch_output_file: None,
 			},
 			prompt: Default::default(),
 		};
@@ -308,42 +373,70 @@ impl super::RequestType for Request {
 	}
 
 	fn get_messages(&self) -> Vec<SimpleChatCompletionMessage> {
-		self
-			.messages
-			.iter()
-			.map(|m| {
-				let content = m
-					.content
-					.as_ref()
-					.and_then(|c| match c {
-						Content::Text(t) => Some(strng::new(t)),
-						Content::Array(parts) if !parts.is_empty() => {
-							let text = parts.iter().filter_map(|part| part.text.as_deref()).fold(
-								String::new(),
-								|mut acc, s| {
-									if !acc.is_empty() {
-										acc.push(' ');
-									}
-									acc.push_str(s);
-									acc
-								},
-							);
-							Some(strng::new(&text))
-						},
-						_ => None,
-					})
-					.unwrap_or_default();
-				SimpleChatCompletionMessage {
-					role: strng::new(&m.role),
-					content,
-				}
-			})
-			.collect()
+		self.messages.iter().map(simple_message).collect()
 	}
 
 	fn set_messages(&mut self, messages: Vec<SimpleChatCompletionMessage>) {
 		self.messages = messages.into_iter().map(convert_message).collect();
 	}
+
+	fn get_tools(&self) -> Vec<super::ToolDefinition> {
+		super::tool_definitions(self.tools.as_deref().unwrap_or_default())
+	}
+
+	fn retain_tools(&mut self, keep: &dyn Fn(&str) -> bool) {
+		super::retain_tools(&mut self.tools, &mut self.tool_choice, &mut self.rest, keep);
+	}
+
+	fn retain_messages(&mut self, select: &dyn Fn(&[SimpleChatCompletionMessage]) -> Vec<bool>) {
+		let is_system = |m: &RequestMessage| matches!(m.role.as_str(), "system" | "developer");
+		let conversation: Vec<_> = self
+			.messages
+			.iter()
+			.filter(|m| !is_system(m))
+			.map(simple_message)
+			.collect();
+		let mut keep = select(&conversation).into_iter();
+		self
+			.messages
+			.retain(|m| is_system(m) || keep.next().unwrap_or(true));
+	}
+
+	fn output_schema(&self) -> Option<super::OutputSchema> {
+		super::OutputSchema::from_format(self.rest.get("response_format"), "/json_schema/schema")
+	}
+
+	fn take_prompt_template(&mut self) -> Option<serde_json::Value> {
+		super::take_prompt_template_in(&mut self.rest)
+	}
+}
+
+fn simple_message(m: &RequestMessage) -> SimpleChatCompletionMessage {
+	let content =
+		m.content
+			.as_ref()
+			.and_then(|c| match c {
+				Content::Text(t) => Some(strng::new(t)),
+				Content::Array(parts) if !parts.is_empty() => {
+					let text = parts.iter().filter_map(|part| part.text.as_deref()).fold(
+						String::new(),
+						|mut acc, s| {
+							if !acc.is_empty() {
+								acc.push(' ');
+							}
+							acc.push_str(s);
+							acc
+						},
+					);
+					Some(strng::new(&text))
+				},
+				_ => None,
+			})
+			.unwrap_or_default();
+	SimpleChatCompletionMessage {
+		role: strng::new(&m.role),
+		content,
+	}
 }
 
 fn convert_message(r: SimpleChatCompletionMessage) -> RequestMessage {
@@ -3,6 +3,7 @@ use agent_core::strng;
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 
+use crate::llm::tokenizer::Tokenizers;
 use crate::llm::types::{RequestType, messages};
 use crate::llm::{
 	AIError, InputFormat, LLMRequest, SimpleChatCompletionMessage, conversion,
@@ -33,7 +34,11 @@ impl RequestType for Request {
 		messages::append_prompts_helper(&mut self.messages, &mut self.system, prompts);
 	}
 
-	fn to_llm_request(&self, provider: Strng, _tokenize: bool) -> Result<LLMRequest, AIError> {
+	fn to_llm_request(
+		&self,
+		provider: Strng,
+		_tokenizers: Option<&Tokenizers>,
+	) -> Result<LLMRequest, AIError> {
 		let model = strng::new(self.model.as_deref().unwrap_or_default());
 		Ok(LLMRequest {
 			// We never tokenize these, so always empty
@@ -11,6 +11,7 @@ use x509_parser::nom::AsBytes;
 use crate::llm::bedrock::Provider;
 use crate::llm::policy::PromptCachingConfig;
 use crate::llm::policy::webhook::ResponseChoice;
+use crate::llm::tokenizer::Tokenizers;
 use crate::llm::{
 	AIError, AmendOnDrop, InputFormat, LLMRequest, LLMRequestParams, LLMResponse, RequestType,
 	ResponseType, SimpleChatCompletionMessage,
@@ -76,7 +77,11 @@ impl RequestType for Request {
 		// Not supported
 	}
 
-	fn to_llm_request(&self, provider: Strng, _tokenize: bool) -> Result<LLMRequest, AIError> {
+	fn to_llm_request(
+		&self,
+		provider: Strng,
+		_tokenizers: Option<&Tokenizers>,
+	) -> Result<LLMRequest, AIError> {
 		Ok(LLMRequest {
 			// We never tokenize these, so always empty
 			input_tokens: None,
@@ -102,6 +107,8 @@ impl RequestType for Request {
 					.lookup(lookups::ENCODING_FORMAT, |v| v.as_str())
 					.map(Into::into),
 				dimensions: self.lookup(lookups::DIMENSIONS, |v| v.as_u64()),
+				batch_requests: None,
+				batch_output_file: None,
 			},
 			prompt: Default::default(),
 		})
@@ -142,6 +149,7 @@ impl RequestType for Request {
 
 pub fn amend_request_info(llm_info: &mut LLMRequest, path: &str) {
 	if path.ends_with(":streamRawPredict")
+		|| path.ends_with(":streamGenerateContent")
 		|| path.ends_with("/invoke-with-response-stream")
 		|| path.ends_with("/converse-stream")
 	{
@@ -157,6 +165,11 @@ pub(crate) fn extract_model_from_path(path: &str) -> Option<Strng> {
 		path
 			.split_once("/publishers/anthropic/models/")
 			.and_then(|(_, rest)| rest.split_once(':').map(|(model, _)| model))
+	} else if path.ends_with(":streamGenerateContent") || path.ends_with(":generateContent") {
+		// Gemini (/v1beta/models/{model}) and Vertex (.../publishers/google/models/{model})
+		path
+			.rsplit_once("/models/")
+			.and_then(|(_, rest)| rest.split_once(':').map(|(model, _)| model))
 	} else if path.ends_with("/invoke-with-response-stream")
 		|| path.ends_with("/invoke")
 		|| path.ends_with("/converse-stream")
@@ -247,6 +260,19 @@ mod tests {
 		assert!(!llm_info.streaming);
 	}
 
+	#[test]
+	fn amend_request_info_extracts_gemini_generate_content_model() {
+		let mut llm_info = llm_request();
+
+		amend_request_info(
+			&mut llm_info,
+			"/v1beta/models/gemini-2.5-flash:streamGenerateContent",
+		);
+
+		assert_eq!(llm_info.request_model, "gemini-2.5-flash");
+		assert!(llm_info.streaming);
+	}
+
 	#[test]
 	fn amend_request_info_extracts_bedrock_invoke_model() {
 		let mut llm_info = llm_request();
@@ -415,6 +441,11 @@ impl ResponseType for Response {
 			completion: None,
 			// TODO: we could probably derive this
 			first_token: None,
+			response_cache: None,
+			response_cache_similarity: None,
+			batch_usage: Vec::new(),
+			output_images: None,
+			input_audio_seconds: None,
 		}
 	}
 
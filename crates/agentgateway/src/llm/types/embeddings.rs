@@ -3,6 +3,7 @@ use agent_core::strng;
 use serde::{Deserialize, Serialize};
 
 use crate::json;
+use crate::llm::tokenizer::Tokenizers;
 use crate::llm::types::RequestType;
 use crate::llm::{AIError, InputFormat, LLMRequest, LLMRequestParams, SimpleChatCompletionMessage};
 
@@ -60,7 +61,11 @@ impl RequestType for Request {
 		// Ignored
 	}
 
-	fn to_llm_request(&self, provider: Strng, _tokenize: bool) -> Result<LLMRequest, AIError> {
+	fn to_llm_request(
+		&self,
+		provider: Strng,
+		_tokenizers: Option<&Tokenizers>,
+	) -> Result<LLMRequest, AIError> {
 		let model = strng::new(self.model.as_deref().unwrap_or_default());
 		Ok(LLMRequest {
 			// We never tokenize these, so always empty
@@ -83,6 +88,8 @@ impl RequestType for Request {
 					typed::EncodingFormat::Float => strng::literal!("float"),
 				}),
 				dimensions: self.dimensions.map(|d| d as u64),
+				batch_requests: None,
+				batch_output_file: None,
 			},
 			prompt: Default::default(),
 		})
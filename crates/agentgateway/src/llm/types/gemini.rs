@@ -0,0 +1,436 @@
+use agent_core::strng;
+use agent_core::strng::Strng;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::llm::policy::webhook::{Message, ResponseChoice};
+use crate::llm::tokenizer::Tokenizers;
+use crate::llm::types::{RequestType, ResponseType, SimpleChatCompletionMessage};
+use crate::llm::{AIError, InputFormat, LLMRequest, LLMRequestParams, LLMResponse, conversion};
+
+/// Google GenAI `generateContent` / `streamGenerateContent` request.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Request {
+	#[serde(default)]
+	pub contents: Vec<Content>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub system_instruction: Option<Content>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub generation_config: Option<GenerationConfig>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub tools: Option<Vec<Tool>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub tool_config: Option<ToolConfig>,
+
+	/// The model is part of the request path rather than the body.
+	#[serde(skip)]
+	pub model: Option<String>,
+	/// Streaming is selected by calling `streamGenerateContent` rather than by a body field.
+	#[serde(skip)]
+	pub stream: bool,
+
+	#[serde(flatten, default)]
+	pub rest: serde_json::Value,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Serialize)]
+pub struct Content {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub role: Option<String>,
+	#[serde(default)]
+	pub parts: Vec<Part>,
+}
+
+impl Content {
+	fn text(&self) -> String {
+		self
+			.parts
+			.iter()
+			.filter(|p| !p.thought.unwrap_or_default())
+			.filter_map(|p| p.text.as_deref())
+			.join("\n")
+	}
+
+	fn from_text(role: Option<&str>, text: impl Into<String>) -> Self {
+		Content {
+			role: role.map(str::to_string),
+			parts: vec![Part::from_text(text)],
+		}
+	}
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Part {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub text: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub inline_data: Option<Blob>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub file_data: Option<FileData>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub function_call: Option<FunctionCall>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub function_response: Option<FunctionResponse>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub thought: Option<bool>,
+	#[serde(flatten, default)]
+	pub rest: serde_json::Value,
+}
+
+impl Part {
+	pub fn from_text(text: impl Into<String>) -> Self {
+		Part {
+			text: Some(text.into()),
+			..Default::default()
+		}
+	}
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Blob {
+	pub mime_type: String,
+	pub data: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileData {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub mime_type: Option<String>,
+	pub file_uri: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct FunctionCall {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub id: Option<String>,
+	pub name: String,
+	#[serde(default)]
+	pub args: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct FunctionResponse {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub id: Option<String>,
+	pub name: String,
+	#[serde(default)]
+	pub response: serde_json::Value,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationConfig {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub stop_sequences: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub response_mime_type: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub response_schema: Option<serde_json::Value>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub response_json_schema: Option<serde_json::Value>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub candidate_count: Option<u8>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub max_output_tokens: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub temperature: Option<f32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub top_p: Option<f32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub seed: Option<i64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub presence_penalty: Option<f32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub frequency_penalty: Option<f32>,
+	#[serde(flatten, default)]
+	pub rest: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tool {
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub function_declarations: Vec<FunctionDeclaration>,
+	#[serde(flatten, default)]
+	pub rest: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionDeclaration {
+	pub name: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub parameters: Option<serde_json::Value>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub parameters_json_schema: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolConfig {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub function_calling_config: Option<FunctionCallingConfig>,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionCallingConfig {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub mode: Option<FunctionCallingMode>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub allowed_function_names: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FunctionCallingMode {
+	Auto,
+	Any,
+	None,
+	Validated,
+	#[serde(other)]
+	ModeUnspecified,
+}
+
+/// Google GenAI `generateContent` response, or a single `streamGenerateContent` chunk.
+#[derive(Debug, Default, Deserialize, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Response {
+	#[serde(default)]
+	pub candidates: Vec<Candidate>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub usage_metadata: Option<UsageMetadata>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub model_version: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub response_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Candidate {
+	pub content: Content,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub finish_reason: Option<FinishReason>,
+	#[serde(default)]
+	pub index: u32,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FinishReason {
+	Stop,
+	MaxTokens,
+	Safety,
+	#[serde(other)]
+	Other,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageMetadata {
+	#[serde(default)]
+	pub prompt_token_count: u32,
+	#[serde(default)]
+	pub candidates_token_count: u32,
+	#[serde(default)]
+	pub total_token_count: u32,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub cached_content_token_count: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub thoughts_token_count: Option<u32>,
+}
+
+fn role_from_simple(role: &str) -> &'static str {
+	match role {
+		"assistant" | "model" => "model",
+		_ => "user",
+	}
+}
+
+fn text_parts(prompts: Vec<SimpleChatCompletionMessage>) -> Vec<Part> {
+	prompts
+		.into_iter()
+		.map(|p| Part::from_text(p.content.to_string()))
+		.collect()
+}
+
+fn contents_from(prompts: Vec<SimpleChatCompletionMessage>) -> impl Iterator<Item = Content> {
+	prompts
+		.into_iter()
+		.map(|p| Content::from_text(Some(role_from_simple(&p.role)), p.content.to_string()))
+}
+
+impl RequestType for Request {
+	fn model(&mut self) -> &mut Option<String> {
+		&mut self.model
+	}
+
+	fn prepend_prompts(&mut self, prompts: Vec<SimpleChatCompletionMessage>) {
+		let (system_prompts, message_prompts): (Vec<_>, Vec<_>) = prompts
+			.into_iter()
+			.partition(|p| p.role.as_str() == "system");
+		if !system_prompts.is_empty() {
+			let parts = text_parts(system_prompts);
+			let system = self.system_instruction.get_or_insert_default();
+			system.parts.splice(0..0, parts);
+		}
+		self.contents.splice(0..0, contents_from(message_prompts));
+	}
+
+	fn append_prompts(&mut self, prompts: Vec<SimpleChatCompletionMessage>) {
+		let (system_prompts, message_prompts): (Vec<_>, Vec<_>) = prompts
+			.into_iter()
+			.partition(|p| p.role.as_str() == "system");
+		if !system_prompts.is_empty() {
+			let parts = text_parts(system_prompts);
+			let system = self.system_instruction.get_or_insert_default();
+			system.parts.extend(parts);
+		}
+		self.contents.extend(contents_from(message_prompts));
+	}
+
+	fn to_llm_request(
+		&self,
+		provider: Strng,
+		tokenizers: Option<&Tokenizers>,
+	) -> Result<LLMRequest, AIError> {
+		let model = strng::new(self.model.as_deref().unwrap_or_default());
+		let input_tokens = tokenizers
+			.map(|t| t.count(&model, &self.get_messages()))
+			.transpose()?;
+		let config = self.generation_config.as_ref();
+		Ok(LLMRequest {
+			input_tokens,
+			input_format: InputFormat::GenerateContent,
+			native_format: Some(crate::llm::custom::ProviderFormat::Completions),
+			cache_convention: crate::llm::CacheTokenConvention::pending(),
+			request_model: model,
+			provider,
+			streaming: self.stream,
+			params: LLMRequestParams {
+				temperature: config.and_then(|c| c.temperature).map(Into::into),
+				top_p: config.and_then(|c| c.top_p).map(Into::into),
+				frequency_penalty: config.and_then(|c| c.frequency_penalty).map(Into::into),
+				presence_penalty: config.and_then(|c| c.presence_penalty).map(Into::into),
+				seed: config.and_then(|c| c.seed),
+				max_tokens: config.and_then(|c| c.max_output_tokens).map(Into::into),
+				encoding_format: None,
+				dimensions: None,
+				batch_requests: None,
+			},
+			prompt: Default::default(),
+		})
+	}
+
+	fn get_messages(&self) -> Vec<SimpleChatCompletionMessage> {
+		let system = self
+			.system_instruction
+			.as_ref()
+			.map(|s| SimpleChatCompletionMessage {
+				role: strng::literal!("system"),
+				content: strng::new(s.text()),
+			});
+		let messages = self.contents.iter().map(|c| SimpleChatCompletionMessage {
+			role: match c.role.as_deref() {
+				Some("model") => strng::literal!("assistant"),
+				_ => strng::literal!("user"),
+			},
+			content: strng::new(c.text()),
+		});
+		system.into_iter().chain(messages).collect()
+	}
+
+	fn set_messages(&mut self, messages: Vec<SimpleChatCompletionMessage>) {
+		let (system_prompts, message_prompts): (Vec<_>, Vec<_>) = messages
+			.into_iter()
+			.partition(|m| m.role.as_str() == "system");
+		self.system_instruction = if system_prompts.is_empty() {
+			None
+		} else {
+			Some(Content {
+				role: None,
+				parts: text_parts(system_prompts),
+			})
+		};
+		self.contents = contents_from(message_prompts).collect();
+	}
+
+	fn to_openai(&self) -> Result<Vec<u8>, AIError> {
+		conversion::completions::from_generate_content::translate(self)?.to_openai()
+	}
+
+	fn to_anthropic(&self) -> Result<Vec<u8>, AIError> {
+		conversion::completions::from_generate_content::translate(self)?.to_anthropic()
+	}
+
+	fn to_bedrock(
+		&self,
+		provider: &crate::llm::bedrock::Provider,
+		headers: Option<&::http::HeaderMap>,
+		prompt_caching: Option<&crate::llm::policy::PromptCachingConfig>,
+	) -> Result<Vec<u8>, AIError> {
+		conversion::completions::from_generate_content::translate(self)?.to_bedrock(
+			provider,
+			headers,
+			prompt_caching,
+		)
+	}
+
+	fn to_vertex(&self, provider: &crate::llm::vertex::Provider) -> Result<Vec<u8>, AIError> {
+		conversion::completions::from_generate_content::translate(self)?.to_vertex(provider)
+	}
+}
+
+impl ResponseType for Response {
+	fn to_llm_response(&self, include_completion_in_log: bool) -> LLMResponse {
+		let usage = self.usage_metadata.as_ref();
+		LLMResponse {
+			input_tokens: usage.map(|u| u.prompt_token_count as u64),
+			output_tokens: usage.map(|u| u.candidates_token_count as u64),
+			total_tokens: usage.map(|u| u.total_token_count as u64),
+			reasoning_tokens: usage.and_then(|u| u.thoughts_token_count.map(Into::into)),
+			cached_input_tokens: usage.and_then(|u| u.cached_content_token_count.map(Into::into)),
+			provider_model: self.model_version.as_deref().map(strng::new),
+			completion: include_completion_in_log
+				.then(|| self.candidates.iter().map(|c| c.content.text()).collect()),
+			..Default::default()
+		}
+	}
+
+	fn to_webhook_choices(&self) -> Vec<ResponseChoice> {
+		self
+			.candidates
+			.iter()
+			.map(|c| ResponseChoice {
+				message: Message {
+					role: "assistant".into(),
+					content: c.content.text().into(),
+				},
+			})
+			.collect()
+	}
+
+	fn set_webhook_choices(&mut self, choices: Vec<ResponseChoice>) -> anyhow::Result<()> {
+		if self.candidates.len() != choices.len() {
+			anyhow::bail!("webhook response message count mismatch");
+		}
+		for (c, wh) in self.candidates.iter_mut().zip(choices) {
+			c.content.parts.retain(|p| p.text.is_none());
+			c.content
+				.parts
+				.insert(0, Part::from_text(wh.message.content.to_string()));
+		}
+		Ok(())
+	}
+
+	fn serialize(&self) -> serde_json::Result<Vec<u8>> {
+		serde_json::to_vec(&self)
+	}
+}
@@ -0,0 +1,143 @@
+use agent_core::prelude::Strng;
+use agent_core::strng;
+use serde::{Deserialize, Serialize};
+
+use crate::llm::tokenizer::Tokenizers;
+use crate::llm::types::RequestType;
+use crate::llm::{AIError, InputFormat, LLMRequest, LLMRequestParams, SimpleChatCompletionMessage};
+
+/// OpenAI /v1/images/generations request.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct Request {
+	pub model: Option<String>,
+	pub prompt: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub n: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub size: Option<String>,
+
+	// Everything else - passthrough
+	#[serde(flatten, default)]
+	pub rest: serde_json::Value,
+}
+
+impl RequestType for Request {
+	fn model(&mut self) -> &mut Option<String> {
+		&mut self.model
+	}
+
+	fn prepend_prompts(&mut self, _prompts: Vec<SimpleChatCompletionMessage>) {
+		// Ignored
+	}
+
+	fn append_prompts(&mut self, _prompts: Vec<SimpleChatCompletionMessage>) {
+		// Ignored
+	}
+
+	fn to_llm_request(
+		&self,
+		provider: Strng,
+		_tokenizers: Option<&Tokenizers>,
+	) -> Result<LLMRequest, AIError> {
+		let model = strng::new(self.model.as_deref().unwrap_or_default());
+		Ok(LLMRequest {
+			// We never tokenize these, so always empty
+			input_tokens: None,
+			input_format: InputFormat::ImageGeneration,
+			native_format: Some(crate::llm::custom::ProviderFormat::ImageGeneration),
+			cache_convention: crate::llm::CacheTokenConvention::pending(),
+			request_model: model,
+			provider,
+			streaming: false,
+			params: LLMRequestParams::default(),
+			prompt: Default::default(),
+		})
+	}
+
+	fn get_messages(&self) -> Vec<SimpleChatCompletionMessage> {
+		vec![SimpleChatCompletionMessage {
+			role: strng::literal!("user"),
+			content: strng::new(&self.prompt),
+		}]
+	}
+
+	fn set_messages(&mut self, messages: Vec<SimpleChatCompletionMessage>) {
+		if let Some(message) = messages.into_iter().next() {
+			self.prompt = message.content.to_string();
+		}
+	}
+
+	fn to_openai(&self) -> Result<Vec<u8>, AIError> {
+		serde_json::to_vec(&self).map_err(AIError::RequestMarshal)
+	}
+
+	fn to_vertex(&self, _provider: &crate::llm::vertex::Provider) -> Result<Vec<u8>, AIError> {
+		crate::llm::conversion::vertex::from_images::translate(self)
+	}
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct Response {
+	#[serde(default)]
+	pub data: Vec<serde_json::Value>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub usage: Option<Usage>,
+	#[serde(flatten, default)]
+	pub rest: serde_json::Value,
+}
+
+/// Token usage, reported by token-priced image models such as `gpt-image-1`.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct Usage {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub input_tokens: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub output_tokens: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub total_tokens: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub input_tokens_details: Option<InputTokensDetails>,
+	#[serde(flatten, default)]
+	pub rest: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct InputTokensDetails {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub text_tokens: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub image_tokens: Option<u64>,
+}
+
+impl crate::llm::types::ResponseType for Response {
+	fn to_llm_response(&self, _include_completion_in_log: bool) -> crate::llm::LLMResponse {
+		let usage = self.usage.as_ref();
+		let details = usage.and_then(|u| u.input_tokens_details.as_ref());
+		crate::llm::LLMResponse {
+			input_tokens: usage.and_then(|u| u.input_tokens),
+			input_text_tokens: details.and_then(|d| d.text_tokens),
+			input_image_tokens: details.and_then(|d| d.image_tokens),
+			output_tokens: usage.and_then(|u| u.output_tokens),
+			// Image models only output images
+			output_image_tokens: usage.and_then(|u| u.output_tokens),
+			total_tokens: usage.and_then(|u| u.total_tokens),
+			output_images: Some(self.data.len() as u64),
+			..Default::default()
+		}
+	}
+
+	fn to_webhook_choices(&self) -> Vec<crate::llm::policy::webhook::ResponseChoice> {
+		vec![]
+	}
+
+	fn set_webhook_choices(
+		&mut self,
+		_resp: Vec<crate::llm::policy::webhook::ResponseChoice>,
+	) -> anyhow::Result<()> {
+		Ok(())
+	}
+
+	fn serialize(&self) -> serde_json::Result<Vec<u8>> {
+		serde_json::to_vec(self)
+	}
+}
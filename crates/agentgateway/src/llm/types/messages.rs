@@ -4,7 +4,10 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::llm::policy::webhook::{Message, ResponseChoice};
-use crate::llm::types::{RequestType, ResponseType, SimpleChatCompletionMessage};
+use crate::llm::tokenizer::Tokenizers;
+use crate::llm::types::{
+	RequestType, ResponseType, SimpleChatCompletionMessage, ToolCall, ToolDefinition,
+};
 use crate::llm::{AIError, InputFormat, LLMRequest, LLMRequestParams, LLMResponse, conversion};
 
 #[derive(Debug, Deserialize, Clone, Serialize, Default)]
@@ -146,39 +149,51 @@ pub fn get_messages_helper(
 		}
 	}
 
-	out.extend(messages.iter().map(|m| {
-		let content = m
-			.content
-			.as_ref()
-			.and_then(|c| match c {
-				ContentBlock::Text(t) => Some(strng::new(t)),
-				ContentBlock::Array(parts) if !parts.is_empty() => {
-					let text = parts
-						.iter()
-						.filter_map(|part| match part {
-							ContentPart::Text { text, .. } => Some(text.as_str()),
-							_ => None,
-						})
-						.fold(String::new(), |mut acc, s| {
-							if !acc.is_empty() {
-								acc.push(' ');
-							}
-							acc.push_str(s);
-							acc
-						});
-					Some(strng::new(&text))
-				},
-				_ => None,
-			})
-			.unwrap_or_default();
-		SimpleChatCompletionMessage {
-			role: strng::new(&m.role),
-			content,
-		}
-	}));
+	out.extend(messages.iter().map(simple_message));
 	out
 }
 
+/// Whether a user message returns the results of the tool calls of the previous message.
+fn returns_tool_results(m: &RequestMessage) -> bool {
+	let Some(ContentBlock::Array(parts)) = &m.content else {
+		return false;
+	};
+	parts
+		.iter()
+		.any(|part| matches!(part, ContentPart::Unknown(v) if v["type"] == "tool_result"))
+}
+
+fn simple_message(m: &RequestMessage) -> SimpleChatCompletionMessage {
+	let content = m
+		.content
+		.as_ref()
+		.and_then(|c| match c {
+			ContentBlock::Text(t) => Some(strng::new(t)),
+			ContentBlock::Array(parts) if !parts.is_empty() => {
+				let text = parts
+					.iter()
+					.filter_map(|part| match part {
+						ContentPart::Text { text, .. } => Some(text.as_str()),
+						_ => None,
+					})
+					.fold(String::new(), |mut acc, s| {
+						if !acc.is_empty() {
+							acc.push(' ');
+						}
+						acc.push_str(s);
+						acc
+					});
+				Some(strng::new(&text))
+			},
+			_ => None,
+		})
+		.unwrap_or_default();
+	SimpleChatCompletionMessage {
+		role: strng::new(&m.role),
+		content,
+	}
+}
+
 impl RequestType for Request {
 	fn model(&mut self) -> &mut Option<String> {
 		&mut self.model
@@ -192,15 +207,15 @@ impl RequestType for Request {
 		append_prompts_helper(&mut self.messages, &mut self.system, prompts);
 	}
 
-	fn to_llm_request(&self, provider: Strng, tokenize: bool) -> Result<LLMRequest, AIError> {
+	fn to_llm_request(
+		&self,
+		provider: Strng,
+		tokenizers: Option<&Tokenizers>,
+	) -> Result<LLMRequest, AIError> {
 		let model = strng::new(self.model.as_deref().unwrap_or_default());
-		let input_tokens = if tokenize {
-			let messages = self.get_messages();
-			let tokens = crate::llm::num_tokens_from_messages(&model, &messages)?;
-			Some(tokens)
-		} else {
-			None
-		};
+		let input_tokens = tokenizers
+			.map(|t| t.count(&model, &self.get_messages()))
+			.transpose()?;
 		// Pass the original body through
 		let llm = LLMRequest {
 			input_tokens,
@@ -219,6 +234,7 @@ impl RequestType for Request {
 				max_tokens: self.max_tokens,
 				encoding_format: None,
 				dimensions: None,
+				batch_requests: None,
 			},
 			prompt: Default::default(),
 		};
@@ -251,6 +267,58 @@ impl RequestType for Request {
 		self.messages = message_prompts.into_iter().map(Into::into).collect();
 	}
 
+	fn get_tools(&self) -> Vec<ToolDefinition> {
+		match self.rest.get("tools") {
+			Some(serde_json::Value::Array(tools)) => crate::llm::types::tool_definitions(tools),
+			_ => Vec::new(),
+		}
+	}
+
+	fn retain_tools(&mut self, keep: &dyn Fn(&str) -> bool) {
+		crate::llm::types::retain_tools_in(&mut self.rest, keep);
+	}
+
+	fn retain_messages(&mut self, select: &dyn Fn(&[SimpleChatCompletionMessage]) -> Vec<bool>) {
+		// System prompts are kept separately, so every message is part of the conversation.
+		let conversation: Vec<_> = self
+			.messages
+			.iter()
+			.map(|m| {
+				let mut message = simple_message(m);
+				if returns_tool_results(m) {
+					message.role = strng::literal!("tool");
+				}
+				message
+			})
+			.collect();
+		let mut keep = select(&conversation).into_iter();
+		self.messages.retain(|_| keep.next().unwrap_or(true));
+	}
+
+	fn output_schema(&self) -> Option<crate::llm::types::OutputSchema> {
+		use crate::llm::types::OutputSchema;
+		if let Some(schema) =
+			OutputSchema::from_format(self.rest.pointer("/output_config/format"), "/schema")
+		{
+			return Some(schema);
+		}
+		// A tool the model is forced to call produces its output as the tool's input.
+		let choice = self.rest.get("tool_choice")?;
+		if choice.get("type").and_then(serde_json::Value::as_str) != Some("tool") {
+			return None;
+		}
+		let name = choice.get("name").and_then(serde_json::Value::as_str)?;
+		let tool = self.get_tools().into_iter().find(|t| t.name == name)?;
+		Some(OutputSchema {
+			schema: tool.parameters?,
+			tool: Some(tool.name),
+		})
+	}
+
+	fn take_prompt_template(&mut self) -> Option<serde_json::Value> {
+		crate::llm::types::take_prompt_template_in(&mut self.rest)
+	}
+
 	fn to_openai(&self) -> Result<Vec<u8>, AIError> {
 		conversion::completions::from_messages::translate(self)
 	}
@@ -389,6 +457,11 @@ impl ResponseType for Response {
 				None
 			},
 			first_token: Default::default(),
+			response_cache: None,
+			response_cache_similarity: None,
+			batch_usage: Vec::new(),
+			output_images: None,
+			input_audio_seconds: None,
 		}
 	}
 
@@ -421,6 +494,43 @@ impl ResponseType for Response {
 	fn serialize(&self) -> serde_json::Result<Vec<u8>> {
 		serde_json::to_vec(&self)
 	}
+
+	fn tool_calls(&self) -> Vec<ToolCall> {
+		self
+			.content
+			.iter()
+			.filter_map(Content::tool_use)
+			.map(|(name, input)| ToolCall {
+				name: name.to_string(),
+				arguments: input.clone(),
+			})
+			.collect()
+	}
+
+	fn set_tool_call_arguments(&mut self, arguments: Vec<serde_json::Value>) -> anyhow::Result<()> {
+		let blocks: Vec<_> = self
+			.content
+			.iter_mut()
+			.filter(|c| c.tool_use().is_some())
+			.collect();
+		if blocks.len() != arguments.len() {
+			anyhow::bail!("tool call count mismatch");
+		}
+		for (block, input) in blocks.into_iter().zip(arguments) {
+			block.rest["input"] = input;
+		}
+		Ok(())
+	}
+}
+
+impl Content {
+	/// The name and input of a `tool_use` block.
+	fn tool_use(&self) -> Option<(&str, &serde_json::Value)> {
+		if self.rest.get("type").and_then(serde_json::Value::as_str) != Some("tool_use") {
+			return None;
+		}
+		Some((self.rest.get("name")?.as_str()?, self.rest.get("input")?))
+	}
 }
 
 // 'typed' provides a typed accessor
@@ -1033,6 +1143,11 @@ pub mod typed {
 					None
 				},
 				first_token: Default::default(),
+				response_cache: None,
+				response_cache_similarity: None,
+				batch_usage: Vec::new(),
+				output_images: None,
+				input_audio_seconds: None,
 			}
 		}
 
@@ -1073,5 +1188,37 @@ pub mod typed {
 		fn serialize(&self) -> serde_json::Result<Vec<u8>> {
 			serde_json::to_vec(&self)
 		}
+
+		fn tool_calls(&self) -> Vec<crate::llm::types::ToolCall> {
+			self
+				.content
+				.iter()
+				.filter_map(|c| match c {
+					ContentBlock::ToolUse { name, input, .. } => Some(crate::llm::types::ToolCall {
+						name: name.clone(),
+						arguments: input.clone(),
+					}),
+					_ => None,
+				})
+				.collect()
+		}
+
+		fn set_tool_call_arguments(&mut self, arguments: Vec<serde_json::Value>) -> anyhow::Result<()> {
+			let inputs: Vec<_> = self
+				.content
+				.iter_mut()
+				.filter_map(|c| match c {
+					ContentBlock::ToolUse { input, .. } => Some(input),
+					_ => None,
+				})
+				.collect();
+			if inputs.len() != arguments.len() {
+				anyhow::bail!("tool call count mismatch");
+			}
+			for (input, args) in inputs.into_iter().zip(arguments) {
+				*input = args;
+			}
+			Ok(())
+		}
 	}
 }
@@ -1,9 +1,13 @@
+pub mod audio;
 pub mod bedrock;
 pub mod completions;
 pub mod count_tokens;
 pub mod detect;
 pub mod embeddings;
+pub mod gemini;
+pub mod images;
 pub mod messages;
+pub mod multipart;
 pub mod rerank;
 pub mod responses;
 pub mod vertex;
@@ -13,6 +17,7 @@ use agent_core::strng;
 use serde::Serialize;
 
 use crate::apply;
+use crate::llm::tokenizer::Tokenizers;
 use crate::llm::{AIError, LLMRequest, LLMResponse};
 use crate::serdes::schema;
 
@@ -26,6 +31,16 @@ pub trait ResponseType: Send + Sync {
 		resp: Vec<crate::llm::policy::webhook::ResponseChoice>,
 	) -> anyhow::Result<()>;
 	fn serialize(&self) -> serde_json::Result<Vec<u8>>;
+
+	/// Tool calls made by the model, in response order.
+	fn tool_calls(&self) -> Vec<ToolCall> {
+		Vec::new()
+	}
+
+	/// Replace the arguments of the calls returned by `tool_calls`, in the same order.
+	fn set_tool_call_arguments(&mut self, _arguments: Vec<serde_json::Value>) -> anyhow::Result<()> {
+		Ok(())
+	}
 }
 
 /// RequestType is an abstraction over provider/endpoint specific request formats that enables
@@ -37,10 +52,37 @@ pub trait RequestType: Send + Sync {
 	fn model(&mut self) -> &mut Option<String>;
 	fn prepend_prompts(&mut self, prompts: Vec<SimpleChatCompletionMessage>);
 	fn append_prompts(&mut self, prompts: Vec<SimpleChatCompletionMessage>);
-	fn to_llm_request(&self, provider: Strng, tokenize: bool) -> Result<LLMRequest, AIError>;
+	fn to_llm_request(
+		&self,
+		provider: Strng,
+		tokenizers: Option<&Tokenizers>,
+	) -> Result<LLMRequest, AIError>;
 	fn get_messages(&self) -> Vec<SimpleChatCompletionMessage>;
 	fn set_messages(&mut self, messages: Vec<SimpleChatCompletionMessage>);
 
+	/// Tools the request makes available to the model.
+	fn get_tools(&self) -> Vec<ToolDefinition> {
+		Vec::new()
+	}
+
+	/// Remove the tools for which `keep` returns false.
+	fn retain_tools(&mut self, _keep: &dyn Fn(&str) -> bool) {}
+
+	/// Remove messages from the conversation, keeping system prompts. `select` is given the
+	/// conversation in order and returns whether to keep each message. Messages returning tool
+	/// results have the `tool` role, or `function_call_output` for Responses items.
+	fn retain_messages(&mut self, _select: &dyn Fn(&[SimpleChatCompletionMessage]) -> Vec<bool>) {}
+
+	/// The JSON schema the request requires the model's output to match, if any.
+	fn output_schema(&self) -> Option<OutputSchema> {
+		None
+	}
+
+	/// Remove the prompt template the client referenced from the request, returning it.
+	fn take_prompt_template(&mut self) -> Option<serde_json::Value> {
+		None
+	}
+
 	fn to_openai(&self) -> Result<Vec<u8>, AIError> {
 		Err(AIError::UnsupportedConversion(strng::literal!("openai")))
 	}
@@ -83,6 +125,150 @@ pub struct SimpleChatCompletionMessage {
 	pub content: Strng,
 }
 
+/// A tool declared in a request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolDefinition {
+	pub name: String,
+	/// JSON schema of the tool's arguments, if it declares one.
+	pub parameters: Option<serde_json::Value>,
+}
+
+/// A tool call made by the model. Arguments that are not valid JSON are kept as a string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+	pub name: String,
+	pub arguments: serde_json::Value,
+}
+
+impl ToolCall {
+	/// Build a call from arguments encoded as a JSON string, as OpenAI formats send them.
+	pub fn from_encoded(name: &str, arguments: &str) -> ToolCall {
+		ToolCall {
+			name: name.to_string(),
+			arguments: serde_json::from_str(arguments)
+				.unwrap_or_else(|_| serde_json::Value::String(arguments.to_string())),
+		}
+	}
+}
+
+/// The JSON schema a request requires the model's output to match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputSchema {
+	pub schema: serde_json::Value,
+	/// Set when the output is produced by forcing a call to this tool, rather than as text.
+	pub tool: Option<String>,
+}
+
+impl OutputSchema {
+	/// Read a `{"type": "json_schema", ...}` format object, with the schema at `pointer`.
+	pub fn from_format(format: Option<&serde_json::Value>, pointer: &str) -> Option<OutputSchema> {
+		let format = format?;
+		if format.get("type").and_then(serde_json::Value::as_str) != Some("json_schema") {
+			return None;
+		}
+		Some(OutputSchema {
+			schema: format.pointer(pointer)?.clone(),
+			tool: None,
+		})
+	}
+}
+
+/// Encode tool-call arguments back into the JSON string form OpenAI formats use.
+pub fn encode_tool_arguments(arguments: &serde_json::Value) -> String {
+	match arguments {
+		serde_json::Value::String(raw) => raw.clone(),
+		v => v.to_string(),
+	}
+}
+
+/// The name of a tool definition in any supported format. Built-in tools without a name, such as
+/// `{"type": "web_search"}`, are named by their type.
+fn tool_name(tool: &serde_json::Value) -> Option<&str> {
+	["/function/name", "/custom/name", "/name", "/type"]
+		.into_iter()
+		.find_map(|p| tool.pointer(p).and_then(serde_json::Value::as_str))
+}
+
+/// Tool definitions from a request's `tools` array, for any of the supported formats.
+pub fn tool_definitions(tools: &[serde_json::Value]) -> Vec<ToolDefinition> {
+	tools
+		.iter()
+		.filter_map(|tool| {
+			let name = tool_name(tool)?.to_string();
+			let parameters = ["/function/parameters", "/parameters", "/input_schema"]
+				.into_iter()
+				.find_map(|p| tool.pointer(p))
+				.cloned();
+			Some(ToolDefinition { name, parameters })
+		})
+		.collect()
+}
+
+/// Remove the tools `keep` rejects, along with a `tool_choice` that forces a removed tool. Providers
+/// reject an empty tool list, so when no tools remain the tool settings are removed entirely.
+pub fn retain_tools(
+	tools: &mut Option<Vec<serde_json::Value>>,
+	tool_choice: &mut Option<serde_json::Value>,
+	rest: &mut serde_json::Value,
+	keep: &dyn Fn(&str) -> bool,
+) {
+	let Some(list) = tools else {
+		return;
+	};
+	list.retain(|tool| tool_name(tool).is_none_or(keep));
+	if list.is_empty() {
+		*tools = None;
+		*tool_choice = None;
+		if let Some(rest) = rest.as_object_mut() {
+			rest.remove("parallel_tool_calls");
+		}
+		return;
+	}
+	let forced = tool_choice
+		.as_ref()
+		.and_then(|c| {
+			["/function/name", "/name"]
+				.into_iter()
+				.find_map(|p| c.pointer(p))
+		})
+		.and_then(serde_json::Value::as_str);
+	if forced.is_some_and(|name| !keep(name)) {
+		*tool_choice = None;
+	}
+}
+
+/// Remove the `prompt_template` field from the passthrough fields of a request.
+pub fn take_prompt_template_in(rest: &mut serde_json::Value) -> Option<serde_json::Value> {
+	rest.as_object_mut()?.remove("prompt_template")
+}
+
+/// Like [`retain_tools`], for formats that keep `tools` and `tool_choice` in their passthrough
+/// fields.
+pub fn retain_tools_in(rest: &mut serde_json::Value, keep: &dyn Fn(&str) -> bool) {
+	let Some(fields) = rest.as_object_mut() else {
+		return;
+	};
+	let mut tools = match fields.remove("tools") {
+		Some(serde_json::Value::Array(tools)) => Some(tools),
+		Some(other) => {
+			fields.insert("tools".to_string(), other);
+			return;
+		},
+		None => return,
+	};
+	let mut tool_choice = fields.remove("tool_choice");
+	retain_tools(&mut tools, &mut tool_choice, rest, keep);
+	let Some(fields) = rest.as_object_mut() else {
+		return;
+	};
+	if let Some(tools) = tools {
+		fields.insert("tools".to_string(), serde_json::Value::Array(tools));
+	}
+	if let Some(tool_choice) = tool_choice {
+		fields.insert("tool_choice".to_string(), tool_choice);
+	}
+}
+
 pub fn serialize_str<T: Serialize>(value: &T) -> Option<Strng> {
 	serde_json::to_value(value).ok()?.as_str().map(Into::into)
 }
@@ -0,0 +1,107 @@
+//! Buffered multipart/form-data bodies, for endpoints that upload files alongside form fields.
+
+use ::http::HeaderMap;
+use ::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use bytes::{BufMut, Bytes, BytesMut};
+use futures_util::stream;
+
+#[derive(Debug, Clone)]
+pub struct Form {
+	boundary: String,
+	pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Field {
+	pub name: Option<String>,
+	pub headers: HeaderMap,
+	pub data: Bytes,
+}
+
+impl Form {
+	/// The boundary of a multipart/form-data request, if it is one.
+	pub fn boundary(headers: &HeaderMap) -> Option<String> {
+		headers
+			.get(CONTENT_TYPE)
+			.and_then(|content_type| content_type.to_str().ok())
+			.and_then(|content_type| multer::parse_boundary(content_type).ok())
+	}
+
+	pub async fn parse(boundary: String, body: Bytes) -> Result<Form, multer::Error> {
+		let stream = stream::once(std::future::ready(Ok::<Bytes, multer::Error>(body)));
+		let mut multipart = multer::Multipart::new(stream, boundary.as_str());
+		let mut fields = Vec::new();
+		while let Some(field) = multipart.next_field().await? {
+			let name = field.name().map(str::to_string);
+			let headers = field.headers().clone();
+			let data = field.bytes().await?;
+			fields.push(Field {
+				name,
+				headers,
+				data,
+			});
+		}
+		Ok(Form { boundary, fields })
+	}
+
+	pub fn get(&self, name: &str) -> Option<&Field> {
+		self.fields.iter().find(|f| f.name.as_deref() == Some(name))
+	}
+
+	pub fn get_mut(&mut self, name: &str) -> Option<&mut Field> {
+		self
+			.fields
+			.iter_mut()
+			.find(|f| f.name.as_deref() == Some(name))
+	}
+
+	/// The value of a text field. Fields that are not valid UTF-8 are ignored.
+	pub fn text(&self, name: &str) -> Option<&str> {
+		self
+			.get(name)
+			.and_then(|f| std::str::from_utf8(&f.data).ok())
+	}
+
+	/// Set a text field, replacing its value or adding it when missing.
+	pub fn set_text(&mut self, name: &str, value: &str) {
+		let data = Bytes::copy_from_slice(value.as_bytes());
+		match self.get_mut(name) {
+			Some(field) => field.data = data,
+			None => {
+				let mut headers = HeaderMap::new();
+				let disposition = format!("form-data; name=\"{name}\"");
+				if let Ok(disposition) = disposition.parse() {
+					headers.insert(CONTENT_DISPOSITION, disposition);
+				}
+				self.fields.push(Field {
+					name: Some(name.to_string()),
+					headers,
+					data,
+				});
+			},
+		}
+	}
+
+	/// Encode the form with its original boundary, so the request content type stays valid.
+	pub fn to_bytes(&self) -> Bytes {
+		let mut out = BytesMut::new();
+		for field in &self.fields {
+			out.put_slice(b"--");
+			out.put_slice(self.boundary.as_bytes());
+			out.put_slice(b"\r\n");
+			for (k, v) in &field.headers {
+				out.put_slice(k.as_str().as_bytes());
+				out.put_slice(b": ");
+				out.put_slice(v.as_bytes());
+				out.put_slice(b"\r\n");
+			}
+			out.put_slice(b"\r\n");
+			out.put_slice(&field.data);
+			out.put_slice(b"\r\n");
+		}
+		out.put_slice(b"--");
+		out.put_slice(self.boundary.as_bytes());
+		out.put_slice(b"--\r\n");
+		out.freeze()
+	}
+}
@@ -2,6 +2,7 @@ use agent_core::prelude::Strng;
 use agent_core::strng;
 use serde::{Deserialize, Serialize};
 
+use crate::llm::tokenizer::Tokenizers;
 use crate::llm::types::RequestType;
 use crate::llm::{AIError, InputFormat, LLMRequest, LLMRequestParams, SimpleChatCompletionMessage};
 
@@ -103,7 +104,11 @@ impl RequestType for Request {
 
 	fn append_prompts(&mut self, _prompts: Vec<SimpleChatCompletionMessage>) {}
 
-	fn to_llm_request(&self, provider: Strng, _tokenize: bool) -> Result<LLMRequest, AIError> {
+	fn to_llm_request(
+		&self,
+		provider: Strng,
+		_tokenizers: Option<&Tokenizers>,
+	) -> Result<LLMRequest, AIError> {
 		let model = strng::new(self.model.as_deref().unwrap_or_default());
 		Ok(LLMRequest {
 			input_tokens: None,
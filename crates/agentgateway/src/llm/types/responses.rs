@@ -7,6 +7,7 @@ use self::typed::{
 	Role,
 };
 use super::*;
+use crate::llm::tokenizer::Tokenizers;
 use crate::llm::{
 	AIError, InputFormat, LLMRequest, LLMRequestParams, LLMResponse, RequestType, ResponseType,
 	conversion,
@@ -324,15 +325,15 @@ impl RequestType for Request {
 		self.input = RequestInput::Items(items);
 	}
 
-	fn to_llm_request(&self, provider: Strng, tokenize: bool) -> Result<LLMRequest, AIError> {
+	fn to_llm_request(
+		&self,
+		provider: Strng,
+		tokenizers: Option<&Tokenizers>,
+	) -> Result<LLMRequest, AIError> {
 		let model = strng::new(self.model.as_deref().unwrap_or_default());
-		let input_tokens = if tokenize {
-			let messages = self.get_messages();
-			let tokens = crate::llm::num_tokens_from_messages(&model, &messages)?;
-			Some(tokens)
-		} else {
-			None
-		};
+		let input_tokens = tokenizers
+			.map(|t| t.count(&model, &self.get_messages()))
+			.transpose()?;
 		Ok(LLMRequest {
 			input_tokens,
 			input_format: InputFormat::Responses,
@@ -350,6 +351,7 @@ impl RequestType for Request {
 				max_tokens: self.max_output_tokens.map(Into::into),
 				encoding_format: None,
 				dimensions: None,
+				batch_requests: None,
 			},
 			prompt: Default::default(),
 		})
@@ -379,6 +381,58 @@ impl RequestType for Request {
 		);
 	}
 
+	fn get_tools(&self) -> Vec<ToolDefinition> {
+		match self.rest.get("tools") {
+			Some(Value::Array(tools)) => tool_definitions(tools),
+			_ => Vec::new(),
+		}
+	}
+
+	fn retain_tools(&mut self, keep: &dyn Fn(&str) -> bool) {
+		retain_tools_in(&mut self.rest, keep);
+	}
+
+	fn retain_messages(&mut self, select: &dyn Fn(&[SimpleChatCompletionMessage]) -> Vec<bool>) {
+		let RequestInput::Items(items) = &mut self.input else {
+			return;
+		};
+		let is_system = |item: &RawInputItem| {
+			matches!(
+				item.0.get("role").and_then(Value::as_str),
+				Some("system" | "developer")
+			)
+		};
+		// Items other than messages, such as tool calls and their outputs, are named by their type.
+		let conversation: Vec<_> = items
+			.iter()
+			.filter(|item| !is_system(item))
+			.map(|item| {
+				item
+					.as_simple_message()
+					.unwrap_or_else(|| SimpleChatCompletionMessage {
+						role: strng::new(
+							item
+								.0
+								.get("type")
+								.and_then(Value::as_str)
+								.unwrap_or_default(),
+						),
+						content: strng::new(item.0.to_string()),
+					})
+			})
+			.collect();
+		let mut keep = select(&conversation).into_iter();
+		items.retain(|item| is_system(item) || keep.next().unwrap_or(true));
+	}
+
+	fn output_schema(&self) -> Option<OutputSchema> {
+		OutputSchema::from_format(self.rest.pointer("/text/format"), "/schema")
+	}
+
+	fn take_prompt_template(&mut self) -> Option<serde_json::Value> {
+		take_prompt_template_in(&mut self.rest)
+	}
+
 	fn to_openai(&self) -> Result<Vec<u8>, AIError> {
 		// Passthrough - just serialize
 		serde_json::to_vec(&self).map_err(AIError::RequestMarshal)
@@ -455,6 +509,11 @@ impl ResponseType for Response {
 				None
 			},
 			first_token: Default::default(),
+			response_cache: None,
+			response_cache_similarity: None,
+			batch_usage: Vec::new(),
+			output_images: None,
+			input_audio_seconds: None,
 		}
 	}
 
@@ -519,6 +578,35 @@ impl ResponseType for Response {
 	fn serialize(&self) -> serde_json::Result<Vec<u8>> {
 		serde_json::to_vec(&self)
 	}
+
+	fn tool_calls(&self) -> Vec<ToolCall> {
+		self
+			.output
+			.iter()
+			.filter_map(|o| match o {
+				OutputItem::FunctionCall(f) => Some(ToolCall::from_encoded(&f.name, &f.arguments)),
+				_ => None,
+			})
+			.collect()
+	}
+
+	fn set_tool_call_arguments(&mut self, arguments: Vec<Value>) -> anyhow::Result<()> {
+		let calls: Vec<_> = self
+			.output
+			.iter_mut()
+			.filter_map(|o| match o {
+				OutputItem::FunctionCall(f) => Some(f),
+				_ => None,
+			})
+			.collect();
+		if calls.len() != arguments.len() {
+			anyhow::bail!("tool call count mismatch");
+		}
+		for (call, args) in calls.into_iter().zip(arguments) {
+			call.arguments = encode_tool_arguments(&args);
+		}
+		Ok(())
+	}
 }
 
 pub mod typed {
@@ -77,3 +77,37 @@ pub struct RankResponseRecord {
 	#[serde(default)]
 	pub score: Option<f64>,
 }
+
+// ---- Vertex Imagen ----
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImagePredictRequest {
+	pub instances: Vec<ImageInstance>,
+	pub parameters: ImageParameters,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageInstance {
+	pub prompt: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageParameters {
+	pub sample_count: u32,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub aspect_ratio: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImagePredictResponse {
+	#[serde(default)]
+	pub predictions: Vec<ImagePrediction>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImagePrediction {
+	#[serde(default)]
+	pub bytes_base64_encoded: Option<String>,
+}
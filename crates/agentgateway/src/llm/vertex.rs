@@ -99,7 +99,7 @@ impl Provider {
 					location
 				)
 			},
-			(RouteType::Embeddings, _) => {
+			(RouteType::Embeddings | RouteType::ImageGeneration, _) => {
 				let model = self.configured_model(request_model).unwrap_or_default();
 				strng::format!(
 					"/v1/projects/{}/locations/{}/publishers/google/models/{}:predict",
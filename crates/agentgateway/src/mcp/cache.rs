@@ -0,0 +1,322 @@
+//! Caching of MCP list results and resource reads.
+//!
+//! `tools/list`, `prompts/list` and `resources/list` results are cached per target as they are
+//! returned by the upstream server, and `resources/read` results per target and URI. Entries are
+//! shared by all sessions using the policy, so authorization still runs on every request: cached
+//! lists are filtered like fresh ones, and cached resources are only served once the read is
+//! authorized.
+
+use std::time::Instant;
+
+use quick_cache::sync::Cache;
+use rmcp::model::{ClientRequest, ServerJsonRpcMessage, ServerNotification, ServerResult};
+
+use crate::llm::policy::response_cache::ResponseCacheStatus;
+use crate::telemetry::metrics::{McpCacheLabels, Metrics};
+use crate::*;
+
+const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(with = "McpCacheSpec"))]
+pub struct McpCache {
+	#[serde(skip_serializing)]
+	store: Arc<Cache<CacheKey, Arc<Entry>>>,
+	#[serde(flatten)]
+	pub spec: McpCacheSpec,
+}
+
+impl<'de> serde::Deserialize<'de> for McpCache {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		McpCacheSpec::deserialize(deserializer).map(McpCache::from)
+	}
+}
+
+#[apply(schema!)]
+pub struct McpCacheSpec {
+	/// How long the `tools/list`, `prompts/list` and `resources/list` results of each target are
+	/// cached. Cached lists of a target are dropped when it sends a list changed notification.
+	#[serde(with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub list_ttl: Duration,
+	/// How long `resources/read` results are cached, by resource URI. Cached resources are dropped
+	/// when the target sends a resource updated notification. If unset, resource reads are not cached.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "serde_dur_option"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub resource_ttl: Option<Duration>,
+	/// CEL expression partitioning the cache, such as `jwt.sub`, for targets that return different
+	/// results to different clients. If unset, cached results are shared by all sessions. If the
+	/// expression fails to evaluate, the request skips the cache.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub key: Option<Arc<cel::Expression>>,
+	/// Maximum number of cached results. When exceeded, rarely used results are evicted first.
+	#[serde(default = "default_max_entries")]
+	pub max_entries: usize,
+}
+
+fn default_max_entries() -> usize {
+	DEFAULT_MAX_ENTRIES
+}
+
+impl From<McpCacheSpec> for McpCache {
+	fn from(spec: McpCacheSpec) -> Self {
+		McpCache {
+			store: Arc::new(Cache::new(spec.max_entries.max(1))),
+			spec,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Method {
+	ListTools,
+	ListPrompts,
+	ListResources,
+	ReadResource,
+}
+
+impl Method {
+	fn as_str(self) -> &'static str {
+		match self {
+			Method::ListTools => "tools/list",
+			Method::ListPrompts => "prompts/list",
+			Method::ListResources => "resources/list",
+			Method::ReadResource => "resources/read",
+		}
+	}
+}
+
+/// Identifies a cacheable request to a single target.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct CacheKey {
+	method: Method,
+	target: Strng,
+	// Upstream URI, for resource reads.
+	uri: Option<String>,
+	partition: Option<String>,
+}
+
+#[derive(Debug)]
+struct Entry {
+	result: ServerResult,
+	expires_at: Instant,
+}
+
+impl McpCache {
+	/// Returns the cache key of a request to a target, or `None` if the request is not cached.
+	/// Only the first page of a list is cached.
+	pub(crate) fn key(
+		&self,
+		target: &str,
+		request: &ClientRequest,
+		exec: &cel::Executor,
+	) -> Option<CacheKey> {
+		let (method, uri, cursor) = match request {
+			ClientRequest::ListToolsRequest(r) => (Method::ListTools, None, r.params.as_ref()),
+			ClientRequest::ListPromptsRequest(r) => (Method::ListPrompts, None, r.params.as_ref()),
+			ClientRequest::ListResourcesRequest(r) => (Method::ListResources, None, r.params.as_ref()),
+			ClientRequest::ReadResourceRequest(r) if self.spec.resource_ttl.is_some() => {
+				(Method::ReadResource, Some(r.params.uri.clone()), None)
+			},
+			_ => return None,
+		};
+		if cursor.is_some_and(|p| p.cursor.is_some()) {
+			return None;
+		}
+		let partition = match self.spec.key.as_deref() {
+			Some(expr) => match exec
+				.eval(expr)
+				.and_then(|v| v.as_string().map_err(Into::into))
+			{
+				Ok(partition) => Some(partition.to_string()),
+				Err(e) => {
+					trace!(
+						"mcp cache key evaluation failed, skipping: expr={:?}, error={}",
+						expr, e
+					);
+					return None;
+				},
+			},
+			None => None,
+		};
+		Some(CacheKey {
+			method,
+			target: strng::new(target),
+			uri,
+			partition,
+		})
+	}
+
+	/// Returns the cached result for a request, if it has not expired.
+	pub(crate) fn get(&self, key: &CacheKey, metrics: &Metrics) -> Option<ServerResult> {
+		let result = match self.store.get(key) {
+			Some(entry) if entry.expires_at > Instant::now() => Some(entry.result.clone()),
+			Some(_) => {
+				self.store.remove(key);
+				None
+			},
+			None => None,
+		};
+		metrics
+			.mcp_cache_lookups
+			.get_or_create(&McpCacheLabels {
+				server: key.target.clone().into(),
+				method: strng::new(key.method.as_str()).into(),
+				result: if result.is_some() {
+					ResponseCacheStatus::Hit
+				} else {
+					ResponseCacheStatus::Miss
+				},
+			})
+			.inc();
+		result
+	}
+
+	/// Returns a function to apply to the upstream messages of a request, which stores the response
+	/// in the cache.
+	pub(crate) fn store(
+		&self,
+		key: CacheKey,
+	) -> impl FnMut(ServerJsonRpcMessage) -> ServerJsonRpcMessage + Send + 'static {
+		let store = self.store.clone();
+		let ttl = match key.method {
+			Method::ReadResource => self.spec.resource_ttl.unwrap_or_default(),
+			_ => self.spec.list_ttl,
+		};
+		let mut key = Some(key);
+		move |message| {
+			if let ServerJsonRpcMessage::Response(resp) = &message
+				&& let Some(key) = key.take()
+			{
+				store.insert(
+					key,
+					Arc::new(Entry {
+						result: resp.result.clone(),
+						expires_at: Instant::now() + ttl,
+					}),
+				);
+			}
+			message
+		}
+	}
+
+	/// Drops the cached results of a target that a notification from it reports as changed.
+	pub(crate) fn invalidate(&self, target: &str, message: &ServerJsonRpcMessage) {
+		let ServerJsonRpcMessage::Notification(notification) = message else {
+			return;
+		};
+		let (method, uri) = match &notification.notification {
+			ServerNotification::ToolListChangedNotification(_) => (Method::ListTools, None),
+			ServerNotification::PromptListChangedNotification(_) => (Method::ListPrompts, None),
+			ServerNotification::ResourceListChangedNotification(_) => (Method::ListResources, None),
+			ServerNotification::ResourceUpdatedNotification(n) => {
+				(Method::ReadResource, Some(n.params.uri.as_str()))
+			},
+			_ => return,
+		};
+		self.store.retain(|k, _| {
+			k.method != method
+				|| k.target.as_str() != target
+				|| uri.is_some_and(|uri| k.uri.as_deref() != Some(uri))
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::{Value, json};
+
+	use super::*;
+
+	fn cache(value: Value) -> McpCache {
+		serde_json::from_value(value).unwrap()
+	}
+
+	fn metrics() -> Metrics {
+		Metrics::new(
+			&mut prometheus_client::registry::Registry::default(),
+			Default::default(),
+		)
+	}
+
+	fn request(value: Value) -> ClientRequest {
+		serde_json::from_value(value).unwrap()
+	}
+
+	fn message(value: Value) -> ServerJsonRpcMessage {
+		serde_json::from_value(value).unwrap()
+	}
+
+	fn key(cache: &McpCache, target: &str, request: &ClientRequest, user: &str) -> Option<CacheKey> {
+		let req = ::http::Request::builder()
+			.header("x-user", user)
+			.body(())
+			.unwrap();
+		let mcp = crate::mcp::MCPInfo::default();
+		cache.key(target, request, &cel::Executor::new_mcp_request(&req, &mcp))
+	}
+
+	fn respond(cache: &McpCache, key: CacheKey, result: Value) {
+		let mut store = cache.store(key);
+		store(message(
+			json!({"jsonrpc": "2.0", "id": 1, "result": result}),
+		));
+	}
+
+	#[test]
+	fn lists_are_cached_until_changed() {
+		let cache = cache(json!({"listTtl": "1h"}));
+		let metrics = metrics();
+		let list = request(json!({"method": "tools/list"}));
+		let next_page = request(json!({"method": "tools/list", "params": {"cursor": "2"}}));
+		let read = request(json!({"method": "resources/read", "params": {"uri": "file:///a"}}));
+		assert!(key(&cache, "weather", &next_page, "a").is_none());
+		assert!(key(&cache, "weather", &read, "a").is_none());
+
+		let k = key(&cache, "weather", &list, "a").unwrap();
+		assert!(cache.get(&k, &metrics).is_none());
+		respond(&cache, k.clone(), json!({"tools": []}));
+		assert!(cache.get(&k, &metrics).is_some());
+
+		// Notifications only drop the lists of the target that sent them.
+		let changed = message(json!({"jsonrpc": "2.0", "method": "notifications/tools/list_changed"}));
+		cache.invalidate("search", &changed);
+		assert!(cache.get(&k, &metrics).is_some());
+		cache.invalidate("weather", &changed);
+		assert!(cache.get(&k, &metrics).is_none());
+	}
+
+	#[test]
+	fn resources_are_cached_by_uri_and_partition() {
+		let cache = cache(json!({
+			"listTtl": "1h",
+			"resourceTtl": "1h",
+			"key": "request.headers['x-user']"
+		}));
+		let metrics = metrics();
+		let read = |uri: &str| request(json!({"method": "resources/read", "params": {"uri": uri}}));
+		let a = key(&cache, "docs", &read("file:///a"), "alice").unwrap();
+		let b = key(&cache, "docs", &read("file:///b"), "alice").unwrap();
+		respond(&cache, a.clone(), json!({"contents": []}));
+		respond(&cache, b.clone(), json!({"contents": []}));
+		let other_user = key(&cache, "docs", &read("file:///a"), "bob").unwrap();
+		assert!(cache.get(&other_user, &metrics).is_none());
+
+		let updated = message(json!({
+			"jsonrpc": "2.0",
+			"method": "notifications/resources/updated",
+			"params": {"uri": "file:///a"}
+		}));
+		cache.invalidate("docs", &updated);
+		assert!(cache.get(&a, &metrics).is_none());
+		assert!(cache.get(&b, &metrics).is_some());
+	}
+}
@@ -0,0 +1,320 @@
+//! Paging and searching the tools of all targets.
+//!
+//! A `tools/list` page is built from the next upstream page of every target that still has tools
+//! to list, so upstream pagination is preserved. The gateway cursor records, for each such target,
+//! the upstream cursor of that page and how many of its tools were already returned, which lets a
+//! page size smaller than the upstream pages split them across several gateway pages.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rmcp::model::{CallToolResult, JsonObject, Tool};
+use serde_json::{Value, json};
+
+use crate::*;
+
+pub(crate) const SEARCH_TOOL: &str = "search_tools";
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+/// Maximum number of upstream `tools/list` pages searched per target.
+pub(crate) const MAX_SEARCH_PAGES: usize = 20;
+
+#[apply(schema!)]
+#[derive(Default)]
+pub struct McpToolCatalog {
+	/// Maximum number of tools in a `tools/list` page. If unset, a page holds the next page of every
+	/// target.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub page_size: Option<usize>,
+	/// Add a `search_tools` tool, which returns the tools matching a keyword query, so clients can
+	/// discover tools without listing them all. Up to 20 pages of tools of each target are searched.
+	/// The tool takes precedence over any target tool of the same name.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub search_tool: bool,
+}
+
+/// Position of a `tools/list` page within the tools of each target.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct ListCursor {
+	// Targets with tools left to list, in target order.
+	#[serde(rename = "p")]
+	pending: Vec<TargetPosition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct TargetPosition {
+	#[serde(rename = "t")]
+	target: String,
+	// Upstream cursor of the page, or none for the first page.
+	#[serde(rename = "c", default, skip_serializing_if = "Option::is_none")]
+	cursor: Option<String>,
+	// Number of tools of the page that were already returned.
+	#[serde(rename = "o", default, skip_serializing_if = "is_zero")]
+	offset: usize,
+}
+
+fn is_zero(v: &usize) -> bool {
+	*v == 0
+}
+
+impl ListCursor {
+	/// The position of the first page, listing every target from the start.
+	pub fn start<S: AsRef<str>>(targets: impl Iterator<Item = S>) -> Self {
+		ListCursor {
+			pending: targets
+				.map(|target| TargetPosition {
+					target: target.as_ref().to_string(),
+					cursor: None,
+					offset: 0,
+				})
+				.collect(),
+		}
+	}
+
+	/// Decodes a cursor returned by the gateway. `known` reports whether a target exists.
+	pub fn decode(cursor: &str, known: impl Fn(&str) -> bool) -> Result<Self, String> {
+		let cursor: ListCursor = URL_SAFE_NO_PAD
+			.decode(cursor)
+			.map_err(|e| e.to_string())
+			.and_then(|b| serde_json::from_slice(&b).map_err(|e| e.to_string()))
+			.map_err(|e| format!("invalid cursor: {e}"))?;
+		if cursor.pending.is_empty() {
+			return Err("invalid cursor: no targets".to_string());
+		}
+		if let Some(p) = cursor.pending.iter().find(|p| !known(&p.target)) {
+			return Err(format!("invalid cursor: unknown target {}", p.target));
+		}
+		Ok(cursor)
+	}
+
+	pub fn encode(&self) -> String {
+		URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).expect("cursor serializes"))
+	}
+
+	/// Returns the upstream cursor to list a target with, or `None` if the target has no tools left
+	/// to list.
+	pub fn upstream_cursor(&self, target: &str) -> Option<Option<&str>> {
+		self
+			.pending
+			.iter()
+			.find(|p| p.target == target)
+			.map(|p| p.cursor.as_deref())
+	}
+
+	/// Builds a page from the items listed by each target, along with their upstream next cursor,
+	/// and returns the position of the following page, if any.
+	///
+	/// Targets missing from `listed`, because they failed, are not listed in later pages.
+	pub fn next_page<T>(
+		self,
+		mut listed: Vec<(Strng, Vec<T>, Option<String>)>,
+		page_size: Option<usize>,
+	) -> (Vec<T>, Option<ListCursor>) {
+		let mut page = Vec::new();
+		let mut pending = Vec::new();
+		for position in self.pending {
+			let Some((_, items, next)) = listed
+				.iter_mut()
+				.find(|(target, _, _)| target.as_str() == position.target)
+			else {
+				continue;
+			};
+			let available = items.len().saturating_sub(position.offset);
+			let taken = match page_size {
+				Some(size) => available.min(size.saturating_sub(page.len())),
+				None => available,
+			};
+			page.extend(items.drain(..).skip(position.offset).take(taken));
+			if taken < available {
+				pending.push(TargetPosition {
+					offset: position.offset + taken,
+					..position
+				});
+			} else if let Some(next) = next.take() {
+				pending.push(TargetPosition {
+					target: position.target,
+					cursor: Some(next),
+					offset: 0,
+				});
+			}
+		}
+		let next = (!pending.is_empty()).then_some(ListCursor { pending });
+		(page, next)
+	}
+}
+
+/// Definition of the `search_tools` tool.
+pub(crate) fn search_tool() -> Tool {
+	let schema = json!({
+		"type": "object",
+		"properties": {
+			"query": {
+				"type": "string",
+				"description": "Keywords to look for in tool names and descriptions."
+			},
+			"limit": {
+				"type": "integer",
+				"minimum": 1,
+				"description": "Maximum number of tools to return."
+			}
+		},
+		"required": ["query"]
+	});
+	let Value::Object(schema) = schema else {
+		unreachable!("schema is an object")
+	};
+	Tool::new(
+		SEARCH_TOOL,
+		"Search the available tools by keyword. Returns the definitions of the best matching tools, which can then be called by name.",
+		Arc::new(schema),
+	)
+}
+
+/// Arguments of a `search_tools` call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SearchQuery {
+	terms: Vec<String>,
+	limit: usize,
+}
+
+impl SearchQuery {
+	pub fn parse(arguments: Option<&JsonObject>) -> Result<Self, String> {
+		let arguments = arguments.ok_or("missing arguments")?;
+		let query = arguments
+			.get("query")
+			.and_then(Value::as_str)
+			.ok_or("'query' must be a string")?;
+		let terms = query
+			.split_whitespace()
+			.map(str::to_lowercase)
+			.collect::<Vec<_>>();
+		if terms.is_empty() {
+			return Err("'query' must not be empty".to_string());
+		}
+		let limit = match arguments.get("limit") {
+			None | Some(Value::Null) => DEFAULT_SEARCH_LIMIT,
+			Some(v) => v
+				.as_u64()
+				.filter(|l| *l > 0)
+				.ok_or("'limit' must be a positive integer")? as usize,
+		};
+		Ok(SearchQuery { terms, limit })
+	}
+
+	/// Returns the tools matching the query, best matches first. Matches in the tool name rank above
+	/// matches in its description.
+	pub fn search(&self, tools: Vec<Tool>) -> CallToolResult {
+		let mut matches = tools
+			.into_iter()
+			.filter_map(|tool| {
+				let name = tool.name.to_lowercase();
+				let description = tool
+					.description
+					.as_deref()
+					.map(str::to_lowercase)
+					.unwrap_or_default();
+				let score: usize = self
+					.terms
+					.iter()
+					.map(|term| {
+						if name.contains(term.as_str()) {
+							2
+						} else if description.contains(term.as_str()) {
+							1
+						} else {
+							0
+						}
+					})
+					.sum();
+				(score > 0).then_some((score, tool))
+			})
+			.collect::<Vec<_>>();
+		// Stable, so equally good matches keep the target order.
+		matches.sort_by(|(a, _), (b, _)| b.cmp(a));
+		let tools = matches
+			.into_iter()
+			.take(self.limit)
+			.map(|(_, tool)| tool)
+			.collect::<Vec<_>>();
+		CallToolResult::structured(json!({ "tools": tools }))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn listed(target: &str, items: &[u32], next: Option<&str>) -> (Strng, Vec<u32>, Option<String>) {
+		(strng::new(target), items.to_vec(), next.map(str::to_string))
+	}
+
+	#[test]
+	fn pages_follow_upstream_cursors() {
+		let start = ListCursor::start(["a", "b"].into_iter());
+		let (page, next) = start.next_page(
+			vec![listed("a", &[1, 2], Some("a2")), listed("b", &[3], None)],
+			None,
+		);
+		assert_eq!(page, vec![1, 2, 3]);
+		let next = next.unwrap();
+		assert_eq!(next.upstream_cursor("a"), Some(Some("a2")));
+		assert_eq!(next.upstream_cursor("b"), None);
+
+		let (page, next) = next.next_page(vec![listed("a", &[4], None)], None);
+		assert_eq!(page, vec![4]);
+		assert!(next.is_none());
+	}
+
+	#[test]
+	fn page_size_splits_upstream_pages() {
+		let cursor = ListCursor::start(["a", "b"].into_iter());
+		let upstream = || vec![listed("a", &[1, 2, 3], None), listed("b", &[4, 5], None)];
+		let (page, next) = cursor.next_page(upstream(), Some(2));
+		assert_eq!(page, vec![1, 2]);
+
+		// The cursor survives the round trip through the client.
+		let next = ListCursor::decode(&next.unwrap().encode(), |t| t == "a" || t == "b").unwrap();
+		assert_eq!(next.upstream_cursor("a"), Some(None));
+		let (page, next) = next.next_page(upstream(), Some(2));
+		assert_eq!(page, vec![3, 4]);
+		let (page, next) = next.unwrap().next_page(upstream(), Some(2));
+		assert_eq!(page, vec![5]);
+		assert!(next.is_none());
+
+		assert!(ListCursor::decode("not a cursor", |_| true).is_err());
+		let cursor = ListCursor::start(["c"].into_iter()).encode();
+		assert!(ListCursor::decode(&cursor, |t| t == "a").is_err());
+	}
+
+	#[test]
+	fn search_ranks_name_matches_first() {
+		let tool = |name: &'static str, description: &'static str| {
+			Tool::new(name, description, Arc::new(JsonObject::new()))
+		};
+		let tools = vec![
+			tool("github_list_issues", "List the issues of a repository."),
+			tool("jira_search", "Search tickets and issues."),
+			tool("weather_forecast", "Get the forecast for a city."),
+			tool("github_create_issue", "Open an issue in a repository."),
+		];
+		let query = |v: Value| SearchQuery::parse(v.as_object());
+		let names = |result: CallToolResult| {
+			result.structured_content.unwrap()["tools"]
+				.as_array()
+				.unwrap()
+				.iter()
+				.map(|t| t["name"].as_str().unwrap().to_string())
+				.collect::<Vec<_>>()
+		};
+
+		let q = query(json!({"query": "Issue"})).unwrap();
+		assert_eq!(
+			names(q.search(tools.clone())),
+			vec!["github_list_issues", "github_create_issue", "jira_search"]
+		);
+		let q = query(json!({"query": "github issue", "limit": 1})).unwrap();
+		assert_eq!(names(q.search(tools)), vec!["github_list_issues"]);
+
+		assert!(query(json!({"query": "  "})).is_err());
+		assert!(query(json!({"query": "issue", "limit": 0})).is_err());
+		assert!(SearchQuery::parse(None).is_err());
+	}
+}
@@ -12,21 +12,26 @@ use rmcp::model::{
 	ClientNotification, ClientRequest, Implementation, JsonRpcNotification, JsonRpcRequest,
 	ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult, ListToolsResult,
 	ProtocolVersion, RequestId, ServerCapabilities, ServerInfo, ServerJsonRpcMessage,
-	ServerNotification, ServerResult,
+	ServerNotification, ServerResult, Tool,
 };
 use tracing::{debug, warn};
 
 use crate::http::Response;
 use crate::http::sessionpersistence::MCPSession;
 use crate::mcp;
+use crate::mcp::catalog::{self, ListCursor, SearchQuery};
 use crate::mcp::mergestream::{MergeFn, Messages};
 use crate::mcp::rbac::{CelExecWrapper, McpAuthorizationSet};
 use crate::mcp::router::McpBackendGroup;
 use crate::mcp::streamablehttp::ServerSseMessage;
 use crate::mcp::upstream::{IncomingRequestContext, UpstreamError};
-use crate::mcp::{ClientError, FailureMode, MCPInfo, mergestream, rbac, upstream};
+use crate::mcp::validation::{self, ToolValidator};
+use crate::mcp::{ClientError, FailureMode, MCPInfo, mergestream, rbac, upstream, virtual_tools};
 use crate::proxy::httpproxy::PolicyClient;
 use crate::telemetry::log::{AsyncLog, SpanWriteOnDrop, SpanWriter};
+use crate::telemetry::metrics::{
+	McpToolValidationLabels, McpToolValidationPhase, McpToolValidationResult, Metrics,
+};
 
 const DELIMITER: &str = "_";
 
@@ -77,6 +82,10 @@ pub struct Relay {
 	pub(crate) upstreams: Arc<upstream::UpstreamGroup>,
 	pub policies: McpAuthorizationSet,
 	pub(crate) mcp_guardrails: Option<Arc<crate::mcp::guardrails::McpGuardrails>>,
+	pub(crate) tool_validator: Option<Arc<ToolValidator>>,
+	pub(crate) rate_limits: Option<Arc<Vec<mcp::McpRateLimit>>>,
+	pub(crate) cache: Option<Arc<mcp::McpCache>>,
+	pub(crate) tool_catalog: Option<mcp::McpToolCatalog>,
 	pub(crate) policy_client: PolicyClient,
 }
 
@@ -84,6 +93,10 @@ pub struct RelayInputs {
 	pub backend: McpBackendGroup,
 	pub policies: McpAuthorizationSet,
 	pub mcp_guardrails: Option<Arc<crate::mcp::guardrails::McpGuardrails>>,
+	pub mcp_tool_validation: Option<mcp::McpToolValidation>,
+	pub mcp_rate_limit: Option<Arc<Vec<mcp::McpRateLimit>>>,
+	pub mcp_cache: Option<Arc<mcp::McpCache>>,
+	pub mcp_tool_catalog: Option<mcp::McpToolCatalog>,
 	pub client: PolicyClient,
 }
 
@@ -92,6 +105,12 @@ impl RelayInputs {
 		let r = Relay::new(self.backend, self.policies, self.client)?;
 		Ok(Relay {
 			mcp_guardrails: self.mcp_guardrails,
+			tool_validator: self
+				.mcp_tool_validation
+				.map(|v| Arc::new(ToolValidator::new(v))),
+			rate_limits: self.mcp_rate_limit,
+			cache: self.mcp_cache,
+			tool_catalog: self.mcp_tool_catalog,
 			..r
 		})
 	}
@@ -107,6 +126,10 @@ impl Relay {
 			upstreams: Arc::new(upstream::UpstreamGroup::new(client.clone(), backend)?),
 			policies,
 			mcp_guardrails: None,
+			tool_validator: None,
+			rate_limits: None,
+			cache: None,
+			tool_catalog: None,
 			policy_client: client,
 		})
 	}
@@ -115,6 +138,10 @@ impl Relay {
 			upstreams: self.upstreams.clone(),
 			policies,
 			mcp_guardrails: self.mcp_guardrails.clone(),
+			tool_validator: self.tool_validator.clone(),
+			rate_limits: self.rate_limits.clone(),
+			cache: self.cache.clone(),
+			tool_catalog: self.tool_catalog.clone(),
 			policy_client: self.policy_client.clone(),
 		}
 	}
@@ -122,11 +149,115 @@ impl Relay {
 	fn rewrite_outbound_server_messages(&self, target: &str, stream: Messages) -> Messages {
 		let target = target.to_string();
 		let default_target_name = self.upstreams.default_target_name.clone();
+		let cache = self.cache.clone();
 		stream.map_server_messages(move |message| {
+			if let Some(cache) = cache.as_ref() {
+				cache.invalidate(&target, &message);
+			}
 			rewrite_resource_update_message(default_target_name.as_ref(), &target, message)
 		})
 	}
 
+	/// Sends a request to a target, unless its result is cached. Results of cacheable requests that
+	/// miss are stored as they are returned.
+	async fn cached_stream(
+		&self,
+		target: &str,
+		con: &upstream::Upstream,
+		r: JsonRpcRequest<ClientRequest>,
+		ctx: &IncomingRequestContext,
+	) -> Result<Messages, UpstreamError> {
+		let key = self.cache.as_ref().and_then(|cache| {
+			let req = ctx.as_request();
+			let key = cache.key(target, &r.request, &crate::cel::Executor::new_request(&req))?;
+			Some((cache, key))
+		});
+		let Some((cache, key)) = key else {
+			return con.generic_stream(r, ctx).await;
+		};
+		if let Some(result) = cache.get(&key, &self.policy_client.inputs.metrics) {
+			return Ok(Messages::from_result(r.id, result));
+		}
+		let stream = con.generic_stream(r, ctx).await?;
+		Ok(stream.map_server_messages(cache.store(key)))
+	}
+
+	/// Lists the tools of a target, following its upstream cursor for up to `MAX_SEARCH_PAGES` pages,
+	/// and returns them as a single `tools/list` result. Listing stops early if a later page fails.
+	async fn all_tools_stream(
+		&self,
+		target: &str,
+		con: &upstream::Upstream,
+		r: JsonRpcRequest<ClientRequest>,
+		ctx: &IncomingRequestContext,
+	) -> Result<Messages, UpstreamError> {
+		use futures_util::StreamExt;
+		let id = r.id.clone();
+		let mut messages = Vec::new();
+		let mut tools = Vec::new();
+		let mut cursor = None;
+		for page in 0..catalog::MAX_SEARCH_PAGES {
+			let mut r = r.clone();
+			if let ClientRequest::ListToolsRequest(ltr) = &mut r.request
+				&& cursor.is_some()
+			{
+				ltr.params.get_or_insert_default().cursor = cursor.take();
+			}
+			let mut stream = match self.cached_stream(target, con, r, ctx).await {
+				Ok(stream) => stream,
+				Err(e) if page == 0 => return Err(e),
+				Err(e) => {
+					warn!("upstream '{target}' failed to list tools page {page}, stopping: {e}");
+					break;
+				},
+			};
+			let mut listed = None;
+			while let Some(message) = stream.next().await {
+				match message {
+					Ok(ServerJsonRpcMessage::Response(resp)) => {
+						listed = Some(resp.result);
+						break;
+					},
+					Ok(ServerJsonRpcMessage::Error(e)) if page > 0 => {
+						warn!(
+							"upstream '{target}' failed to list tools page {page}, stopping: {}",
+							e.error.message
+						);
+						break;
+					},
+					Ok(message) => messages.push(message),
+					Err(e) if page == 0 => return Err(e.into()),
+					Err(e) => {
+						warn!("upstream '{target}' failed to list tools page {page}, stopping: {e}");
+						break;
+					},
+				}
+			}
+			let Some(ServerResult::ListToolsResult(ltr)) = listed else {
+				if page == 0 {
+					// Nothing was listed, forward whatever the target sent.
+					return Ok(messages.into());
+				}
+				break;
+			};
+			tools.extend(ltr.tools);
+			cursor = ltr.next_cursor;
+			if cursor.is_none() {
+				break;
+			}
+		}
+		messages.push(ServerJsonRpcMessage::response(
+			ListToolsResult {
+				tools,
+				next_cursor: None,
+				meta: None,
+			}
+			.into(),
+			id,
+		));
+		Ok(messages.into())
+	}
+
 	pub fn parse_resource_name<'a, 'b: 'a>(
 		&'a self,
 		res: &'b str,
@@ -223,6 +354,116 @@ impl Relay {
 		self.upstreams.is_multiplexing
 	}
 
+	/// Maps a tool called by a client to its virtual tool override, if any.
+	pub(crate) fn resolve_tool(
+		&self,
+		service_name: &str,
+		tool: &str,
+	) -> Result<Option<&mcp::VirtualTool>, UpstreamError> {
+		virtual_tools::resolve(self.upstreams.tools(service_name), tool).map_err(|_| {
+			UpstreamError::Authorization {
+				resource_type: "tool".to_string(),
+				resource_name: resource_name(
+					self.upstreams.default_target_name.as_ref(),
+					service_name,
+					tool,
+				),
+			}
+		})
+	}
+
+	/// Takes a call from the rate limits that apply to the resource.
+	pub(crate) fn check_rate_limits(
+		&self,
+		res: &rbac::ResourceType,
+		cel: &CelExecWrapper,
+	) -> Result<(), UpstreamError> {
+		let Some(limits) = self.rate_limits.as_ref() else {
+			return Ok(());
+		};
+		let info = MCPInfo::from(res);
+		let exec = cel.executor(&info);
+		for limit in limits.iter() {
+			limit.check(res, &exec, &self.policy_client.inputs.metrics)?;
+		}
+		Ok(())
+	}
+
+	/// Validates the arguments of a tool call against the tool's input schema, if tool validation
+	/// is enabled and the tool was listed in this session.
+	pub(crate) fn validate_tool_arguments(
+		&self,
+		service_name: &str,
+		tool: &str,
+		arguments: Option<&rmcp::model::JsonObject>,
+	) -> Result<(), UpstreamError> {
+		let Some(validator) = self.tool_validator.as_ref() else {
+			return Ok(());
+		};
+		let Some(res) = validator.check_arguments(service_name, tool, arguments) else {
+			return Ok(());
+		};
+		record_tool_validation(
+			&self.policy_client.inputs.metrics,
+			service_name,
+			tool,
+			McpToolValidationPhase::Arguments,
+			res.is_ok(),
+		);
+		res.map_err(|e| UpstreamError::InvalidParams(format!("tool {tool}: {e}")))
+	}
+
+	/// Returns a check of the structured content of the tool call result, if the tool declared an
+	/// output schema and output validation is enabled.
+	fn tool_output_validation(
+		&self,
+		service_name: &str,
+		r: &JsonRpcRequest<ClientRequest>,
+	) -> Option<impl FnMut(ServerJsonRpcMessage) -> ServerJsonRpcMessage + Send + 'static> {
+		let ClientRequest::CallToolRequest(ctr) = &r.request else {
+			return None;
+		};
+		let validator = self.tool_validator.as_ref()?;
+		let tool = ctr.params.name.to_string();
+		let schema = validator.output_schema(service_name, &tool)?;
+		let mode = validator.policy.output;
+		let service_name = service_name.to_string();
+		let metrics = self.policy_client.inputs.metrics.clone();
+		Some(move |message: ServerJsonRpcMessage| {
+			let ServerJsonRpcMessage::Response(resp) = &message else {
+				return message;
+			};
+			let ServerResult::CallToolResult(result) = &resp.result else {
+				return message;
+			};
+			let res = validation::check_result(&schema, result);
+			record_tool_validation(
+				&metrics,
+				&service_name,
+				&tool,
+				McpToolValidationPhase::StructuredContent,
+				res.is_ok(),
+			);
+			match res {
+				Ok(()) => message,
+				Err(e) => {
+					warn!(server=%service_name, %tool, "tool result does not match output schema: {e}");
+					if mode == mcp::OutputValidation::Reject {
+						ServerJsonRpcMessage::error(
+							ErrorData::internal_error(
+								format!("tool {tool} returned invalid structured content: {e}"),
+								None,
+							),
+							resp.id.clone(),
+						)
+					} else {
+						message
+					}
+				},
+			}
+		})
+	}
+
 	fn build_guardrails_ctx(
 		&self,
 		r: &JsonRpcRequest<ClientRequest>,
@@ -311,45 +552,79 @@ impl Relay {
 		Ok(())
 	}
 
-	pub fn merge_tools(&self) -> Box<MergeFn> {
+	/// Returns a function filtering and rewriting the tools listed by a target as they are exposed to
+	/// the client.
+	fn expose_tools(
+		&self,
+	) -> impl Fn(&str, Vec<Tool>, &CelExecWrapper) -> Vec<Tool> + Send + Sync + 'static {
 		let policies = self.policies.clone();
+		let upstreams = self.upstreams.clone();
+		let tool_validator = self.tool_validator.clone();
 		let default_target_name = self.upstreams.default_target_name.clone();
+		move |server_name, tools, cel| {
+			tools
+				.into_iter()
+				// Apply authorization policies, filtering tools that are not allowed.
+				.filter(|t| {
+					policies.validate(
+						&rbac::ResourceType::Tool(rbac::ResourceId::new(
+							server_name.to_string(),
+							t.name.to_string(),
+						)),
+						cel,
+					)
+				})
+				// Apply virtual tool overrides, which may hide the tool.
+				.filter_map(|t| {
+					let upstream_name = t.name.clone();
+					let t = match virtual_tools::for_upstream(upstreams.tools(server_name), &t.name) {
+						Some(vt) => vt.list(t)?,
+						None => t,
+					};
+					// Remember the schemas as the client sees them, to validate calls later.
+					if let Some(v) = tool_validator.as_ref() {
+						v.learn(server_name, &upstream_name, &t);
+					}
+					Some(t)
+				})
+				// Rename to handle multiplexing
+				.map(|mut t| {
+					t.name = Cow::Owned(resource_name(
+						default_target_name.as_ref(),
+						server_name,
+						&t.name,
+					));
+					t
+				})
+				.collect_vec()
+		}
+	}
+
+	/// Merges a `tools/list` page. `cursor` is the position of the page; `first_page` is set when
+	/// the client did not send a cursor.
+	fn merge_tools(&self, cursor: ListCursor, first_page: bool) -> Box<MergeFn> {
+		let expose = self.expose_tools();
+		let catalog = self.tool_catalog.clone().unwrap_or_default();
 		Box::new(move |streams, cel| {
-			let tools = streams
+			let listed = streams
 				.into_iter()
-				.flat_map(|(server_name, s)| {
-					let tools = match s {
-						ServerResult::ListToolsResult(ltr) => ltr.tools,
-						_ => vec![],
+				.map(|(server_name, s)| {
+					let (tools, next_cursor) = match s {
+						ServerResult::ListToolsResult(ltr) => (ltr.tools, ltr.next_cursor),
+						_ => (vec![], None),
 					};
-					tools
-						.into_iter()
-						// Apply authorization policies, filtering tools that are not allowed.
-						.filter(|t| {
-							policies.validate(
-								&rbac::ResourceType::Tool(rbac::ResourceId::new(
-									server_name.to_string(),
-									t.name.to_string(),
-								)),
-								cel,
-							)
-						})
-						// Rename to handle multiplexing
-						.map(|mut t| {
-							t.name = Cow::Owned(resource_name(
-								default_target_name.as_ref(),
-								server_name.as_str(),
-								&t.name,
-							));
-							t
-						})
-						.collect_vec()
+					let tools = expose(server_name.as_str(), tools, cel);
+					(server_name, tools, next_cursor)
 				})
 				.collect_vec();
+			let (mut tools, next_cursor) = cursor.next_page(listed, catalog.page_size);
+			if first_page && catalog.search_tool {
+				tools.insert(0, catalog::search_tool());
+			}
 			Ok(
 				ListToolsResult {
 					tools,
-					next_cursor: None,
+					next_cursor: next_cursor.map(|c| c.encode()),
 					meta: None,
 				}
 				.into(),
@@ -357,6 +632,71 @@ impl Relay {
 		})
 	}
 
+	fn merge_search_tools(&self, query: SearchQuery) -> Box<MergeFn> {
+		let expose = self.expose_tools();
+		Box::new(move |streams, cel| {
+			let tools = streams
+				.into_iter()
+				.flat_map(|(server_name, s)| match s {
+					ServerResult::ListToolsResult(ltr) => expose(server_name.as_str(), ltr.tools, cel),
+					_ => vec![],
+				})
+				.collect_vec();
+			Ok(query.search(tools).into())
+		})
+	}
+
+	/// Lists a page of the tools of all targets, following the cursor sent by the client.
+	pub async fn list_tools(
+		&self,
+		r: JsonRpcRequest<ClientRequest>,
+		ctx: IncomingRequestContext,
+	) -> Result<Response, UpstreamError> {
+		let cursor = match &r.request {
+			ClientRequest::ListToolsRequest(ltr) => ltr.params.as_ref().and_then(|p| p.cursor.as_deref()),
+			_ => None,
+		};
+		let first_page = cursor.is_none();
+		let cursor = match cursor {
+			Some(cursor) => ListCursor::decode(cursor, |t| self.upstreams.get_name(t).is_some())
+				.map_err(UpstreamError::InvalidParams)?,
+			None => ListCursor::start(self.upstreams.iter_named().map(|(name, _)| name)),
+		};
+		let merge = self.merge_tools(cursor.clone(), first_page);
+		self
+			.send_fanout_with(r, ctx, merge, false, |target, r| {
+				// Targets with no tools left are skipped; the others continue from their own cursor.
+				let upstream_cursor = cursor.upstream_cursor(target)?;
+				let mut r = r.clone();
+				if let ClientRequest::ListToolsRequest(ltr) = &mut r.request
+					&& (upstream_cursor.is_some() || ltr.params.is_some())
+				{
+					ltr.params.get_or_insert_default().cursor = upstream_cursor.map(str::to_string);
+				}
+				Some(r)
+			})
+			.await
+	}
+
+	pub fn is_search_tool(&self, name: &str) -> bool {
+		name == catalog::SEARCH_TOOL && self.tool_catalog.as_ref().is_some_and(|c| c.search_tool)
+	}
+
+	/// Answers a `search_tools` call with the matching tools of all targets.
+	pub(crate) async fn search_tools(
+		&self,
+		mut r: JsonRpcRequest<ClientRequest>,
+		ctx: IncomingRequestContext,
+		query: SearchQuery,
+	) -> Result<Response, UpstreamError> {
+		r.request = ClientRequest::ListToolsRequest(Default::default());
+		self
+			.send_fanout_with(r, ctx, self.merge_search_tools(query), true, |_, r| {
+				Some(r.clone())
+			})
+			.await
+	}
+
 	pub fn merge_initialize(&self, pv: ProtocolVersion, multiplexing: bool) -> Box<MergeFn> {
 		let resource_subscribe = self.upstreams.stateful();
 		Box::new(move |s, _cel| {
@@ -536,8 +876,12 @@ impl Relay {
 			)));
 		};
 		let guardrails = self.build_guardrails_ctx(&r, &ctx, vec![service_name.to_string()]);
-		let stream =
-			self.rewrite_outbound_server_messages(service_name, us.generic_stream(r, &ctx).await?);
+		let output_validation = self.tool_output_validation(service_name, &r);
+		let mut stream = self.cached_stream(service_name, us, r, &ctx).await?;
+		if let Some(validate) = output_validation {
+			stream = stream.map_server_messages(validate);
+		}
+		let stream = self.rewrite_outbound_server_messages(service_name, stream);
 
 		match guardrails {
 			Some(guardrails) => {
@@ -635,10 +979,26 @@ impl Relay {
 	}
 
 	pub async fn send_fanout(
+		&self,
+		r: JsonRpcRequest<ClientRequest>,
+		ctx: IncomingRequestContext,
+		merge: Box<MergeFn>,
+	) -> Result<Response, UpstreamError> {
+		self
+			.send_fanout_with(r, ctx, merge, false, |_, r| Some(r.clone()))
+			.await
+	}
+
+	/// Like `send_fanout`, but `request_for` builds the request sent to each target, or skips the
+	/// target by returning `None`. With `all_tool_pages`, the `tools/list` request is repeated to
+	/// list every page of each target.
+	async fn send_fanout_with(
 		&self,
 		r: JsonRpcRequest<ClientRequest>,
 		mut ctx: IncomingRequestContext,
 		merge: Box<MergeFn>,
+		all_tool_pages: bool,
+		request_for: impl Fn(&str, &JsonRpcRequest<ClientRequest>) -> Option<JsonRpcRequest<ClientRequest>>,
 	) -> Result<Response, UpstreamError> {
 		let id = r.id.clone();
 		let mut streams = Vec::new();
@@ -680,10 +1040,17 @@ impl Relay {
 		let futs: Vec<_> = self
 			.upstreams
 			.iter_named()
-			.map(|(name, con)| {
-				let r = r.clone();
+			.filter_map(|(name, con)| {
+				let r = request_for(name.as_str(), &r)?;
 				let ctx = &ctx;
-				async move { (name, con.generic_stream(r, ctx).await) }
+				Some(async move {
+					let result = if all_tool_pages {
+						self.all_tools_stream(name.as_str(), &con, r, ctx).await
+					} else {
+						self.cached_stream(name.as_str(), &con, r, ctx).await
+					};
+					(name, result)
+				})
 			})
 			.collect();
 
@@ -817,6 +1184,28 @@ impl Relay {
 	}
 }
 
+fn record_tool_validation(
+	metrics: &Metrics,
+	server: &str,
+	tool: &str,
+	phase: McpToolValidationPhase,
+	valid: bool,
+) {
+	metrics
+		.mcp_tool_validations
+		.get_or_create(&McpToolValidationLabels {
+			server: strng::new(server).into(),
+			tool: strng::new(tool).into(),
+			phase,
+			result: if valid {
+				McpToolValidationResult::Valid
+			} else {
+				McpToolValidationResult::Invalid
+			},
+		})
+		.inc();
+}
+
 pub fn setup_request_log(
 	http: Parts,
 	span_name: &str,
@@ -1625,7 +1625,6 @@ async fn mock_streamable_http_server_inner(
 			.with_json_response(false),
 	);
 
-	let (tx, rx) = tokio::sync::oneshot::channel();
 	let mut router = axum::Router::new().nest_service("/mcp", service);
 	if let Some(cap) = capture {
 		router = router.layer(axum::middleware::from_fn(
@@ -1638,6 +1637,31 @@ async fn mock_streamable_http_server_inner(
 			},
 		));
 	}
+	serve_mock_router(router, init_counter).await
+}
+
+// Serves `tools` over streamable HTTP, `page_size` tools per `tools/list` page.
+async fn mock_paged_tools_server(tools: usize, page_size: usize) -> MockServer {
+	use rmcp::transport::streamable_http_server::StreamableHttpService;
+	use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+	agent_core::telemetry::testing::setup_test_logging();
+	let service = StreamableHttpService::new(
+		move || Ok(mockserver::PagedTools::new(tools, page_size)),
+		LocalSessionManager::default().into(),
+		StreamableHttpServerConfig::default()
+			.with_sse_retry(None)
+			.with_sse_keep_alive(None)
+			.with_json_response(false),
+	);
+	let router = axum::Router::new().nest_service("/mcp", service);
+	serve_mock_router(router, Default::default()).await
+}
+
+async fn serve_mock_router(
+	router: axum::Router,
+	init_counter: std::sync::Arc<tokio::sync::Mutex<i32>>,
+) -> MockServer {
+	let (tx, rx) = tokio::sync::oneshot::channel();
 	let tcp_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
 	let addr = tcp_listener.local_addr().unwrap();
 	tokio::spawn(async move {
@@ -1985,6 +2009,53 @@ mod mockserver {
 			Ok(self.get_info())
 		}
 	}
+
+	/// Lists numbered tools over several `tools/list` pages.
+	#[derive(Clone)]
+	pub struct PagedTools {
+		tools: usize,
+		page_size: usize,
+	}
+
+	impl PagedTools {
+		pub fn new(tools: usize, page_size: usize) -> Self {
+			Self { tools, page_size }
+		}
+	}
+
+	impl ServerHandler for PagedTools {
+		fn get_info(&self) -> ServerInfo {
+			ServerInfo::new(ServerCapabilities::builder().enable_tools().build())
+				.with_protocol_version(ProtocolVersion::V_2025_06_18)
+		}
+
+		async fn list_tools(
+			&self,
+			request: Option<PaginatedRequestParams>,
+			_: RequestContext<RoleServer>,
+		) -> Result<ListToolsResult, McpError> {
+			let start = match request.and_then(|r| r.cursor) {
+				Some(cursor) => cursor
+					.parse::<usize>()
+					.map_err(|_| McpError::invalid_params("invalid cursor", None))?,
+				None => 0,
+			};
+			let end = (start + self.page_size).min(self.tools);
+			Ok(ListToolsResult {
+				tools: (start..end)
+					.map(|i| {
+						Tool::new(
+							format!("tool_{i}"),
+							format!("Paged tool number {i}."),
+							Arc::new(JsonObject::new()),
+						)
+					})
+					.collect(),
+				next_cursor: (end < self.tools).then(|| end.to_string()),
+				meta: None,
+			})
+		}
+	}
 }
 
 mod legacymockserver {
@@ -2279,6 +2350,7 @@ async fn test_setup_partial_success_fail_open() {
 				backend_policies: Default::default(),
 				backend: None,
 				always_use_prefix: false,
+				tools: Vec::new(),
 			}),
 			Arc::new(McpTarget {
 				name: "ok".into(),
@@ -2291,6 +2363,7 @@ async fn test_setup_partial_success_fail_open() {
 				backend_policies: Default::default(),
 				backend: None,
 				always_use_prefix: false,
+				tools: Vec::new(),
 			}),
 		],
 		stateful: false,
@@ -2317,6 +2390,7 @@ async fn test_all_targets_fail_open_still_errors() {
 				backend_policies: Default::default(),
 				backend: None,
 				always_use_prefix: false,
+				tools: Vec::new(),
 			}),
 			Arc::new(McpTarget {
 				name: "bad-2".into(),
@@ -2329,6 +2403,7 @@ async fn test_all_targets_fail_open_still_errors() {
 				backend_policies: Default::default(),
 				backend: None,
 				always_use_prefix: false,
+				tools: Vec::new(),
 			}),
 		],
 		stateful: false,
@@ -2355,6 +2430,7 @@ fn fake_streamable_target(name: &str, addr: SocketAddr) -> Arc<McpTarget> {
 			crate::types::agent::Target::Address(addr),
 		)),
 		always_use_prefix: false,
+		tools: Vec::new(),
 	})
 }
 
@@ -2373,6 +2449,7 @@ fn fake_sse_target(name: &str, addr: SocketAddr) -> Arc<McpTarget> {
 			crate::types::agent::Target::Address(addr),
 		)),
 		always_use_prefix: false,
+		tools: Vec::new(),
 	})
 }
 
@@ -2401,6 +2478,7 @@ fn fake_openapi_target(name: &str, addr: SocketAddr) -> Arc<McpTarget> {
 			crate::types::agent::Target::Address(addr),
 		)),
 		always_use_prefix: false,
+		tools: Vec::new(),
 	})
 }
 
@@ -2416,6 +2494,7 @@ fn fake_stdio_target(name: &str) -> Arc<McpTarget> {
 		backend_policies: Default::default(),
 		backend: None,
 		always_use_prefix: false,
+		tools: Vec::new(),
 	})
 }
 
@@ -4250,3 +4329,229 @@ async fn mcp_guardrails_mutated_resource_read_reaches_upstream() {
 		.expect("resource should return text");
 	assert!(text.contains("Business Intelligence Memo"));
 }
+
+#[tokio::test]
+async fn mcp_tool_validation_rejects_invalid_arguments() {
+	let mock = mock_streamable_http_server(true).await;
+	let (_bind, io) = setup_proxy_policies(
+		&mock,
+		true,
+		false,
+		vec![BackendTrafficPolicy::McpToolValidation(
+			crate::mcp::McpToolValidation::default(),
+		)],
+	)
+	.await;
+	let client = mcp_streamable_client(io).await;
+	let call = |args: serde_json::Value| {
+		rmcp::model::CallToolRequestParams::new("sum")
+			.with_arguments(args.as_object().cloned().unwrap())
+	};
+
+	// Schemas are only known once the tools were listed in the session.
+	client
+		.call_tool(call(serde_json::json!({"a": 1, "b": "two"})))
+		.await
+		.expect_err("upstream rejects the arguments itself");
+	client.list_tools(None).await.unwrap();
+
+	let err = client
+		.call_tool(call(serde_json::json!({"a": 1, "b": "two"})))
+		.await
+		.expect_err("tool call should fail validation");
+	let rmcp::ServiceError::McpError(e) = &err else {
+		panic!("expected McpError, got {err:?}");
+	};
+	assert_eq!(e.code.0, -32602, "expected INVALID_PARAMS");
+	assert!(e.message.contains("/b"), "{}", e.message);
+
+	let result = client
+		.call_tool(call(serde_json::json!({"a": 1, "b": 2})))
+		.await
+		.expect("valid arguments are forwarded");
+	assert!(!result.content.is_empty());
+}
+
+#[tokio::test]
+async fn mcp_rate_limit_returns_jsonrpc_error() {
+	let mock = mock_streamable_http_server(true).await;
+	let limits: Vec<crate::mcp::McpRateLimit> = serde_json::from_value(serde_json::json!([{
+		"names": ["echo"],
+		"maxTokens": 1,
+		"tokensPerFill": 1,
+		"fillInterval": "1h"
+	}]))
+	.unwrap();
+	let (_bind, io) = setup_proxy_policies(
+		&mock,
+		true,
+		false,
+		vec![BackendTrafficPolicy::McpRateLimit(Arc::new(limits))],
+	)
+	.await;
+	let client = mcp_streamable_client(io).await;
+	let echo = || {
+		rmcp::model::CallToolRequestParams::new("echo").with_arguments(
+			serde_json::json!({"hi": "world"})
+				.as_object()
+				.cloned()
+				.unwrap(),
+		)
+	};
+
+	client
+		.call_tool(echo())
+		.await
+		.expect("first call is within the limit");
+	let err = client
+		.call_tool(echo())
+		.await
+		.expect_err("second call should be rate limited");
+	let rmcp::ServiceError::McpError(e) = &err else {
+		panic!("expected McpError, got {err:?}");
+	};
+	assert_eq!(e.code.0, -32003, "expected resource exhausted");
+	assert_eq!(e.message.as_ref(), "rate limit exceeded for tool echo");
+
+	// Other tools have their own budget.
+	client
+		.call_tool(rmcp::model::CallToolRequestParams::new("say_hello"))
+		.await
+		.expect("other tools are not limited");
+}
+
+#[tokio::test]
+async fn mcp_cache_serves_lists_across_sessions() {
+	let mock = mock_streamable_http_server(true).await;
+	let cache: crate::mcp::McpCache =
+		serde_json::from_value(serde_json::json!({"listTtl": "1h"})).unwrap();
+	let (bind, io) = setup_proxy_policies(
+		&mock,
+		true,
+		false,
+		vec![BackendTrafficPolicy::McpCache(Arc::new(cache))],
+	)
+	.await;
+	let lookups = |result| {
+		bind
+			.pi
+			.metrics
+			.mcp_cache_lookups
+			.get_or_create(&crate::telemetry::metrics::McpCacheLabels {
+				server: strng::new("mcp").into(),
+				method: strng::new("tools/list").into(),
+				result,
+			})
+			.get()
+	};
+
+	let first = mcp_streamable_client(io).await;
+	let tools = first.list_tools(None).await.unwrap();
+	let second = mcp_streamable_client(io).await;
+	let cached = second.list_tools(None).await.unwrap();
+	assert_eq!(tools.tools, cached.tools);
+	assert_eq!(
+		lookups(crate::llm::policy::response_cache::ResponseCacheStatus::Miss),
+		1
+	);
+	assert_eq!(
+		lookups(crate::llm::policy::response_cache::ResponseCacheStatus::Hit),
+		1
+	);
+}
+
+#[tokio::test]
+async fn mcp_tool_catalog_paginates_and_searches() {
+	let mock = mock_streamable_http_server(true).await;
+	let catalog: crate::mcp::McpToolCatalog =
+		serde_json::from_value(serde_json::json!({"pageSize": 3, "searchTool": true})).unwrap();
+	let (_bind, io) = setup_proxy_policies(
+		&mock,
+		true,
+		false,
+		vec![BackendTrafficPolicy::McpToolCatalog(catalog)],
+	)
+	.await;
+	let client = mcp_streamable_client(io).await;
+
+	let first = client.list_tools(None).await.unwrap();
+	assert_eq!(first.tools.len(), 4, "search tool plus one page");
+	assert_eq!(first.tools[0].name, "search_tools");
+	assert!(first.next_cursor.is_some());
+
+	let names = client
+		.list_all_tools()
+		.await
+		.unwrap()
+		.into_iter()
+		.map(|t| t.name.to_string())
+		.collect_vec();
+	assert_eq!(names.len(), 9);
+	assert_eq!(names.iter().unique().count(), names.len());
+	assert!(names.contains(&"sum".to_string()));
+
+	let result = client
+		.call_tool(
+			rmcp::model::CallToolRequestParams::new("search_tools").with_arguments(
+				serde_json::json!({"query": "counter"})
+					.as_object()
+					.cloned()
+					.unwrap(),
+			),
+		)
+		.await
+		.unwrap();
+	let found = result.structured_content.unwrap()["tools"]
+		.as_array()
+		.unwrap()
+		.iter()
+		.map(|t| t["name"].as_str().unwrap().to_string())
+		.sorted()
+		.collect_vec();
+	assert_eq!(found, vec!["decrement", "get_value", "increment"]);
+}
+
+#[tokio::test]
+async fn mcp_tool_catalog_searches_every_upstream_page() {
+	let mock = mock_paged_tools_server(7, 2).await;
+	let catalog: crate::mcp::McpToolCatalog =
+		serde_json::from_value(serde_json::json!({"searchTool": true})).unwrap();
+	let (_bind, io) = setup_proxy_policies(
+		&mock,
+		true,
+		false,
+		vec![BackendTrafficPolicy::McpToolCatalog(catalog)],
+	)
+	.await;
+	let client = mcp_streamable_client(io).await;
+
+	let first = client.list_tools(None).await.unwrap();
+	assert_eq!(
+		first.tools.len(),
+		3,
+		"search tool plus the first upstream page"
+	);
+
+	let result = client
+		.call_tool(
+			rmcp::model::CallToolRequestParams::new("search_tools").with_arguments(
+				serde_json::json!({"query": "paged", "limit": 10})
+					.as_object()
+					.cloned()
+					.unwrap(),
+			),
+		)
+		.await
+		.unwrap();
+	let found = result.structured_content.unwrap()["tools"]
+		.as_array()
+		.unwrap()
+		.iter()
+		.map(|t| t["name"].as_str().unwrap().to_string())
+		.collect_vec();
+	assert_eq!(
+		found,
+		(0..7).map(|i| format!("tool_{i}")).collect_vec(),
+		"tools of later upstream pages are searched"
+	);
+}
@@ -56,6 +56,12 @@ impl From<ServerJsonRpcMessage> for Messages {
 	}
 }
 
+impl From<Vec<ServerJsonRpcMessage>> for Messages {
+	fn from(value: Vec<ServerJsonRpcMessage>) -> Self {
+		Messages(futures::stream::iter(value.into_iter().map(Ok)).boxed())
+	}
+}
+
 impl From<Result<ServerJsonRpcMessage, ClientError>> for Messages {
 	fn from(value: Result<ServerJsonRpcMessage, ClientError>) -> Self {
 		Messages(futures::stream::once(async { value }).boxed())
@@ -1,13 +1,18 @@
 pub(crate) mod auth;
+mod cache;
+mod catalog;
 pub(crate) mod guardrails;
 mod handler;
 mod mergestream;
+mod ratelimit;
 mod rbac;
 mod router;
 mod session;
 mod sse;
 mod streamablehttp;
 mod upstream;
+mod validation;
+mod virtual_tools;
 
 use std::fmt::{Display, Write};
 use std::io;
@@ -15,12 +20,17 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use axum_core::BoxError;
+pub use cache::{McpCache, McpCacheSpec};
+pub use catalog::McpToolCatalog;
 use prometheus_client::encoding::{EncodeLabelValue, LabelValueEncoder};
+pub use ratelimit::{McpRateLimit, McpRateLimitKind, McpRateLimitSpec};
 pub use rbac::{McpAuthorization, McpAuthorizationSet, ResourceId, ResourceType};
 use rmcp::model::RequestId;
 pub use router::App;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+pub use validation::{McpToolValidation, OutputValidation};
+pub use virtual_tools::{PinnedArgument, VirtualTool};
 
 #[cfg(feature = "schema")]
 use crate::JsonSchema;
@@ -83,6 +93,10 @@ pub enum Error {
 	Authorization(RequestId, String, String),
 	#[error("mcpGuardrails rejected: {}", .1.message)]
 	McpGuardrails(RequestId, rmcp::ErrorData),
+	#[error("invalid params: {1}")]
+	InvalidParams(RequestId, String),
+	#[error("rate limited: {}", .1.message)]
+	RateLimited(RequestId, rmcp::ErrorData),
 	#[error("failed to process session_id query parameter")]
 	InvalidSessionIdQuery,
 	#[error("failed to establish get stream: {0}")]
@@ -0,0 +1,238 @@
+//! Rate limits for MCP tools, prompts and resources.
+//!
+//! Unlike route level rate limits, these are applied per target and tool, prompt or resource name,
+//! and are reported to the client as JSON-RPC errors inside the session.
+
+use rmcp::ErrorData;
+use rmcp::model::ErrorCode;
+use serde::de::Error;
+use serde_json::json;
+
+use crate::http::localratelimit::{self, RateLimitSpec, RateLimitType};
+use crate::mcp::ResourceType;
+use crate::mcp::upstream::UpstreamError;
+use crate::proxy::ProxyError;
+use crate::telemetry::metrics::Metrics;
+use crate::*;
+
+// Matches the code used for mcpGuardrails ResourceExhausted rejections.
+const RESOURCE_EXHAUSTED: ErrorCode = ErrorCode(-32003);
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(with = "McpRateLimitSpec"))]
+pub struct McpRateLimit {
+	#[serde(skip_serializing)]
+	limit: localratelimit::RateLimit,
+	#[serde(flatten)]
+	pub spec: McpRateLimitSpec,
+}
+
+impl<'de> serde::Deserialize<'de> for McpRateLimit {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		let spec = McpRateLimitSpec::deserialize(deserializer)?;
+		McpRateLimit::try_from(spec).map_err(D::Error::custom)
+	}
+}
+
+#[apply(schema!)]
+pub struct McpRateLimitSpec {
+	/// Target the limit applies to. If unset, the limit applies to every target.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub target: Option<String>,
+	/// Kind of MCP operation the limit applies to.
+	#[serde(default)]
+	pub kind: McpRateLimitKind,
+	/// Names of the tools, prompts or resources the limit applies to. If empty, the limit applies to
+	/// all of them. Each target and name has its own bucket.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub names: Vec<String>,
+	/// Maximum number of calls that can accumulate in a bucket.
+	pub max_tokens: u64,
+	/// Number of calls added to a bucket each fill interval.
+	pub tokens_per_fill: u64,
+	/// How often buckets are refilled.
+	#[serde(with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub fill_interval: Duration,
+	/// CEL expression identifying the client, such as `jwt.sub`, to limit each client separately.
+	/// If the expression fails to evaluate, the limit is skipped for the call.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub key: Option<Arc<cel::Expression>>,
+	/// Maximum number of buckets to keep. When exceeded, rarely used buckets are evicted first; an
+	/// evicted bucket starts again full. Buckets that are idle and have refilled completely are
+	/// dropped regardless.
+	#[serde(default = "default_max_keys")]
+	pub max_keys: usize,
+}
+
+fn default_max_keys() -> usize {
+	localratelimit::DEFAULT_MAX_KEYS
+}
+
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq)]
+pub enum McpRateLimitKind {
+	/// Tool calls.
+	#[default]
+	Tool,
+	/// Prompt requests.
+	Prompt,
+	/// Resource reads.
+	Resource,
+}
+
+impl McpRateLimitKind {
+	fn as_str(&self) -> &'static str {
+		match self {
+			McpRateLimitKind::Tool => "tool",
+			McpRateLimitKind::Prompt => "prompt",
+			McpRateLimitKind::Resource => "resource",
+		}
+	}
+}
+
+impl TryFrom<McpRateLimitSpec> for McpRateLimit {
+	type Error = anyhow::Error;
+	fn try_from(spec: McpRateLimitSpec) -> Result<Self, Self::Error> {
+		let limit = localratelimit::RateLimit::new_keyed(RateLimitSpec {
+			max_tokens: spec.max_tokens,
+			tokens_per_fill: spec.tokens_per_fill,
+			fill_interval: spec.fill_interval,
+			limit_type: RateLimitType::Requests,
+			key: None,
+			max_keys: spec.max_keys,
+		})?;
+		Ok(McpRateLimit { limit, spec })
+	}
+}
+
+impl McpRateLimit {
+	/// Takes a call from the bucket of the resource, if the limit applies to it.
+	pub(crate) fn check(
+		&self,
+		resource: &ResourceType,
+		exec: &cel::Executor,
+		metrics: &Metrics,
+	) -> Result<(), UpstreamError> {
+		let (kind, id) = match resource {
+			ResourceType::Tool(id) => (McpRateLimitKind::Tool, id),
+			ResourceType::Prompt(id) => (McpRateLimitKind::Prompt, id),
+			ResourceType::Resource(id) => (McpRateLimitKind::Resource, id),
+		};
+		if kind != self.spec.kind
+			|| self
+				.spec
+				.target
+				.as_deref()
+				.is_some_and(|t| t != id.target())
+			|| (!self.spec.names.is_empty() && !self.spec.names.iter().any(|n| n == id.name()))
+		{
+			return Ok(());
+		}
+		let mut key = format!("{}\0{}", id.target(), id.name());
+		if let Some(expr) = self.spec.key.as_deref() {
+			match exec
+				.eval(expr)
+				.and_then(|v| v.as_string().map_err(Into::into))
+			{
+				Ok(client) => {
+					key.push('\0');
+					key.push_str(client.as_str());
+				},
+				Err(e) => {
+					trace!(
+						"mcp rate limit key evaluation failed, skipping: expr={:?}, error={}",
+						expr, e
+					);
+					return Ok(());
+				},
+			}
+		}
+		let Some(bucket) = self.limit.bucket_for_key(&key, metrics) else {
+			return Ok(());
+		};
+		bucket.check_request().map_err(|e| match e {
+			ProxyError::RateLimitExceeded {
+				limit,
+				remaining,
+				reset_seconds,
+			} => UpstreamError::RateLimited(ErrorData::new(
+				RESOURCE_EXHAUSTED,
+				format!("rate limit exceeded for {} {}", kind.as_str(), id.name()),
+				Some(json!({
+					"limit": limit,
+					"remaining": remaining,
+					"retryAfterSeconds": reset_seconds,
+				})),
+			)),
+			e => UpstreamError::Proxy(e),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mcp::ResourceId;
+
+	fn limit(value: serde_json::Value) -> McpRateLimit {
+		serde_json::from_value(value).unwrap()
+	}
+
+	fn tool(target: &str, name: &str) -> ResourceType {
+		ResourceType::Tool(ResourceId::new(target.to_string(), name.to_string()))
+	}
+
+	fn check(limit: &McpRateLimit, resource: &ResourceType, user: &str) -> bool {
+		let req = ::http::Request::builder()
+			.header("x-user", user)
+			.body(())
+			.unwrap();
+		let mcp = crate::mcp::MCPInfo::from(resource);
+		let exec = cel::Executor::new_mcp_request(&req, &mcp);
+		let metrics = Metrics::new(
+			&mut prometheus_client::registry::Registry::default(),
+			Default::default(),
+		);
+		limit.check(resource, &exec, &metrics).is_ok()
+	}
+
+	#[test]
+	fn tools_are_limited_separately() {
+		let limit = limit(json!({
+			"target": "search",
+			"maxTokens": 1,
+			"tokensPerFill": 1,
+			"fillInterval": "1h"
+		}));
+		assert!(check(&limit, &tool("search", "web_search"), "a"));
+		assert!(!check(&limit, &tool("search", "web_search"), "a"));
+		assert!(check(&limit, &tool("search", "news_search"), "a"));
+		// Other targets and operations are not limited.
+		assert!(check(&limit, &tool("other", "web_search"), "a"));
+		let prompt = ResourceType::Prompt(ResourceId::new(
+			"search".to_string(),
+			"web_search".to_string(),
+		));
+		assert!(check(&limit, &prompt, "a"));
+	}
+
+	#[test]
+	fn clients_are_limited_separately() {
+		let limit = limit(json!({
+			"names": ["web_search"],
+			"maxTokens": 1,
+			"tokensPerFill": 1,
+			"fillInterval": "1h",
+			"key": "request.headers['x-user']"
+		}));
+		assert!(check(&limit, &tool("search", "web_search"), "a"));
+		assert!(check(&limit, &tool("search", "web_search"), "b"));
+		assert!(!check(&limit, &tool("search", "web_search"), "a"));
+		assert!(check(&limit, &tool("search", "get_page"), "a"));
+	}
+}
@@ -33,6 +33,10 @@ impl CelExecWrapper {
 	pub fn new(req: ::http::Request<()>) -> CelExecWrapper {
 		CelExecWrapper(Arc::new(req))
 	}
+
+	pub fn executor<'a>(&'a self, mcp: &'a crate::mcp::MCPInfo) -> crate::cel::Executor<'a> {
+		crate::cel::Executor::new_mcp_request(self.0.as_ref(), mcp)
+	}
 }
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -48,8 +52,7 @@ impl McpAuthorizationSet {
 		}
 		tracing::debug!("Checking RBAC for resource: {:?}", res);
 		let mcp = crate::mcp::MCPInfo::from(res);
-		let exec = crate::cel::Executor::new_mcp_request(cel.0.as_ref(), &mcp);
-		self.0.validate(&exec)
+		self.0.validate(&cel.executor(&mcp))
 	}
 
 	pub fn register(&self, cel: &mut ContextBuilder) {
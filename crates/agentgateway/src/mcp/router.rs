@@ -93,6 +93,7 @@ impl App {
 						backend: be.map(|b| b.backend),
 						backend_policies,
 						always_use_prefix: backend.always_use_prefix,
+						tools: t.tools.clone(),
 					}))
 				})
 				.collect::<Result<Vec<_>, _>>()?;
@@ -112,6 +113,10 @@ impl App {
 			.unwrap_or_else(|| McpAuthorizationSet::new(RuleSets::from(Vec::new())));
 		let authn = backend_policies.mcp_authentication;
 		let mcp_guardrails = backend_policies.mcp_guardrails.clone();
+		let mcp_tool_validation = backend_policies.mcp_tool_validation.clone();
+		let mcp_rate_limit = backend_policies.mcp_rate_limit.clone();
+		let mcp_cache = backend_policies.mcp_cache.clone();
+		let mcp_tool_catalog = backend_policies.mcp_tool_catalog.clone();
 
 		// Store an empty value, we will populate each field async
 		let logy = log.mcp_status.clone();
@@ -154,6 +159,10 @@ impl App {
 					backend: backends.clone(),
 					policies: authorization_policies.clone(),
 					mcp_guardrails: mcp_guardrails.clone(),
+					mcp_tool_validation: mcp_tool_validation.clone(),
+					mcp_rate_limit: mcp_rate_limit.clone(),
+					mcp_cache: mcp_cache.clone(),
+					mcp_tool_catalog: mcp_tool_catalog.clone(),
 					client: client.clone(),
 				},
 			))
@@ -171,6 +180,10 @@ impl App {
 					backend: backends.clone(),
 					policies: authorization_policies.clone(),
 					mcp_guardrails: mcp_guardrails.clone(),
+					mcp_tool_validation: mcp_tool_validation.clone(),
+					mcp_rate_limit: mcp_rate_limit.clone(),
+					mcp_cache: mcp_cache.clone(),
+					mcp_tool_catalog: mcp_tool_catalog.clone(),
 					client: client.clone(),
 				},
 			))
@@ -232,4 +245,5 @@ pub struct McpTarget {
 	pub backend_policies: BackendPolicies,
 	pub backend: Option<SimpleBackend>,
 	pub always_use_prefix: bool,
+	pub tools: Vec<crate::mcp::VirtualTool>,
 }
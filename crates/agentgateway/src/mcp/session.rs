@@ -75,9 +75,16 @@ impl Session {
 		let is_init = request_type.is_some_and(|r| matches!(r, ClientRequest::InitializeRequest(_)));
 		if !is_init {
 			let init_request = rmcp::model::InitializeRequest::new(get_client_info());
+			// The search tool lists the tools of every target, so it is initialized like a fanout.
+			let search_tool = matches!(
+				request_type,
+				Some(ClientRequest::CallToolRequest(ctr)) if self.relay.is_search_tool(&ctr.params.name)
+			);
 			// first, determine how widely to send the initialize
 			match request_type {
-				Some(ClientRequest::CallToolRequest(_)) | Some(ClientRequest::GetPromptRequest(_)) => {
+				Some(ClientRequest::CallToolRequest(_)) | Some(ClientRequest::GetPromptRequest(_))
+					if !search_tool =>
+				{
 					// Single-target methods only hit one backend, so initialize/initialized should be scoped
 					// to that backend rather than fanning out.
 					let name = match request_type {
@@ -214,14 +221,13 @@ impl Session {
 			.maybe_run_guardrails_call_request(backend, method, params, ctx)
 			.await?;
 		let cel = rbac::CelExecWrapper::new(ctx.as_request().map(|_| ()));
-		if self.relay.policies.validate(&res, &cel) {
-			Ok(())
-		} else {
-			Err(UpstreamError::Authorization {
+		if !self.relay.policies.validate(&res, &cel) {
+			return Err(UpstreamError::Authorization {
 				resource_type: resource_type.to_string(),
 				resource_name: resource_name.to_string(),
-			})
+			});
 		}
+		self.relay.check_rate_limits(&res, &cel)
 	}
 
 	/// delete any active sessions
@@ -315,6 +321,12 @@ impl Session {
 			Err(UpstreamError::McpGuardrails(rej)) if req_id.is_some() => {
 				Err(mcp::Error::McpGuardrails(req_id.unwrap(), rej).into())
 			},
+			Err(UpstreamError::InvalidParams(msg)) if req_id.is_some() => {
+				Err(mcp::Error::InvalidParams(req_id.unwrap(), msg).into())
+			},
+			Err(UpstreamError::RateLimited(rej)) if req_id.is_some() => {
+				Err(mcp::Error::RateLimited(req_id.unwrap(), rej).into())
+			},
 			// TODO: this is too broad. We have a big tangle of errors to untangle though
 			Err(e) => Err(mcp::Error::SendError(req_id, e.to_string()).into()),
 		}
@@ -418,12 +430,7 @@ impl Session {
 						}
 						res
 					},
-					ClientRequest::ListToolsRequest(_) => {
-						self
-							.relay
-							.send_fanout(r, ctx, self.relay.merge_tools())
-							.await
-					},
+					ClientRequest::ListToolsRequest(_) => self.relay.list_tools(r, ctx).await,
 					// TODO(keithmattix): should we forward pings or should we do our own independent pings
 					// as heuristic for the connection pool (and handle client pings as a local reply from agentgateway)?
 					ClientRequest::PingRequest(_) | ClientRequest::SetLevelRequest(_) => {
@@ -450,9 +457,17 @@ impl Session {
 							.send_fanout(r, ctx, self.relay.merge_resource_templates())
 							.await
 					},
+					ClientRequest::CallToolRequest(ctr) if self.relay.is_search_tool(&ctr.params.name) => {
+						let query = mcp::catalog::SearchQuery::parse(ctr.params.arguments.as_ref())
+							.map_err(UpstreamError::InvalidParams)?;
+						self.relay.search_tools(r, ctx, query).await
+					},
 					ClientRequest::CallToolRequest(ctr) => {
 						let name = ctr.params.name.clone();
 						let (service_name, tool) = self.relay.parse_resource_name(&name)?;
+						// Virtual tools may expose the upstream tool under another name.
+						let virtual_tool = self.relay.resolve_tool(service_name, tool)?;
+						let tool = virtual_tool.map(|vt| vt.name.as_str()).unwrap_or(tool);
 						span.rename_span(format!("{method} {service_name}"));
 						let call_arguments = ctr.params.arguments.clone();
 						log.non_atomic_mutate(|l| {
@@ -475,6 +490,20 @@ impl Session {
 								&name,
 							)
 							.await?;
+						self.relay.validate_tool_arguments(
+							service_name,
+							tool,
+							ctr.params.arguments.as_ref(),
+						)?;
+						if let Some(vt) = virtual_tool {
+							let cel = rbac::CelExecWrapper::new(ctx.as_request().map(|_| ()));
+							let info = mcp::MCPInfo::from(&rbac::ResourceType::Tool(rbac::ResourceId::new(
+								service_name.to_string(),
+								tool.to_string(),
+							)));
+							vt.forward_arguments(&mut ctr.params.arguments, &cel.executor(&info))
+								.map_err(UpstreamError::InvalidRequest)?;
+						}
 						self
 							.relay
 							.send_single(r, ctx, service_name, Some(log.clone()))
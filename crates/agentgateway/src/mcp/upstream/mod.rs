@@ -117,6 +117,10 @@ pub enum UpstreamError {
 	McpGuardrails(rmcp::ErrorData),
 	#[error("invalid request: {0}")]
 	InvalidRequest(String),
+	#[error("invalid params: {0}")]
+	InvalidParams(String),
+	#[error("rate limited: {}", .0.message)]
+	RateLimited(rmcp::ErrorData),
 	#[error("unsupported method: {0}")]
 	InvalidMethod(String),
 	#[error("stdio upstream error: {0}")]
@@ -340,6 +344,17 @@ impl UpstreamGroup {
 		self.backend.stateful
 	}
 
+	/// Returns the virtual tool overrides configured for a target.
+	pub(crate) fn tools(&self, name: &str) -> &[mcp::VirtualTool] {
+		self
+			.backend
+			.targets
+			.iter()
+			.find(|t| t.name.as_str() == name)
+			.map(|t| t.tools.as_slice())
+			.unwrap_or_default()
+	}
+
 	fn setup_upstream(&self, target: &McpTarget) -> Result<upstream::Upstream, mcp::Error> {
 		trace!("connecting to target: {}", target.name);
 		let target = match &target.spec {
@@ -1471,6 +1471,7 @@ async fn test_openapi_from_url() {
 			name: "users-api".into(),
 			spec: local_target_spec,
 			policies: None,
+			tools: Vec::new(),
 		})],
 		stateful_mode: McpStatefulMode::Stateful,
 		prefix_mode: None,
@@ -0,0 +1,208 @@
+//! Tool schema validation.
+//!
+//! Tool schemas are learned from `tools/list` responses and cached for the lifetime of the session,
+//! so tool calls can be checked against them before they reach the upstream server. Calls to tools
+//! that were not listed in the session are forwarded without validation.
+
+use std::collections::HashMap;
+
+use rmcp::model::{CallToolResult, JsonObject, Tool};
+use serde_json::Value;
+
+use crate::*;
+
+#[apply(schema!)]
+#[derive(Default)]
+pub struct McpToolValidation {
+	/// How tool results whose `structuredContent` does not match the tool's `outputSchema` are
+	/// handled. Tool call arguments are always validated against the tool's `inputSchema`.
+	#[serde(default, skip_serializing_if = "OutputValidation::is_default")]
+	pub output: OutputValidation,
+}
+
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq)]
+pub enum OutputValidation {
+	/// Tool results are not validated.
+	#[default]
+	Disabled,
+	/// Invalid tool results are logged and counted, but returned to the client.
+	Warn,
+	/// Invalid tool results are replaced with an error.
+	Reject,
+}
+
+impl OutputValidation {
+	pub fn is_default(&self) -> bool {
+		*self == OutputValidation::Disabled
+	}
+}
+
+#[derive(Debug, Clone)]
+struct Schemas {
+	input: Arc<Value>,
+	output: Option<Arc<Value>>,
+}
+
+/// A validation policy along with the tool schemas seen so far in the session.
+#[derive(Debug)]
+pub(crate) struct ToolValidator {
+	pub policy: McpToolValidation,
+	// Keyed by target and upstream tool name.
+	schemas: Mutex<HashMap<(String, String), Schemas>>,
+}
+
+impl ToolValidator {
+	pub fn new(policy: McpToolValidation) -> Self {
+		Self {
+			policy,
+			schemas: Default::default(),
+		}
+	}
+
+	/// Records the schemas of a tool, as it is listed to clients.
+	pub fn learn(&self, target: &str, upstream_name: &str, tool: &Tool) {
+		let schemas = Schemas {
+			input: Arc::new(Value::Object(tool.input_schema.as_ref().clone())),
+			output: tool
+				.output_schema
+				.as_ref()
+				.map(|s| Arc::new(Value::Object(s.as_ref().clone()))),
+		};
+		self
+			.schemas
+			.lock()
+			.unwrap()
+			.insert((target.to_string(), upstream_name.to_string()), schemas);
+	}
+
+	fn schemas(&self, target: &str, tool: &str) -> Option<Schemas> {
+		self
+			.schemas
+			.lock()
+			.unwrap()
+			.get(&(target.to_string(), tool.to_string()))
+			.cloned()
+	}
+
+	/// Checks tool call arguments against the tool's input schema. Returns `None` if the schema of
+	/// the tool is not known.
+	pub fn check_arguments(
+		&self,
+		target: &str,
+		tool: &str,
+		arguments: Option<&JsonObject>,
+	) -> Option<Result<(), String>> {
+		let schemas = self.schemas(target, tool)?;
+		let arguments = Value::Object(arguments.cloned().unwrap_or_default());
+		Some(json::validate_schema(&schemas.input, &arguments).map_err(|e| format!("{e:#}")))
+	}
+
+	/// Returns the output schema of a tool, if results of the tool should be validated.
+	pub fn output_schema(&self, target: &str, tool: &str) -> Option<Arc<Value>> {
+		if self.policy.output == OutputValidation::Disabled {
+			return None;
+		}
+		self.schemas(target, tool)?.output
+	}
+}
+
+/// Checks the structured content of a tool result against the tool's output schema. Error results
+/// are not checked.
+pub(crate) fn check_result(schema: &Value, result: &CallToolResult) -> Result<(), String> {
+	if result.is_error == Some(true) {
+		return Ok(());
+	}
+	let Some(content) = result.structured_content.as_ref() else {
+		return Err("tool result has no structuredContent".to_string());
+	};
+	json::validate_schema(schema, content).map_err(|e| format!("{e:#}"))
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+
+	fn tool() -> Tool {
+		serde_json::from_value(json!({
+			"name": "get_weather",
+			"inputSchema": {
+				"type": "object",
+				"properties": {"city": {"type": "string"}, "days": {"type": "integer"}},
+				"required": ["city"]
+			},
+			"outputSchema": {
+				"type": "object",
+				"properties": {"temperature": {"type": "number"}},
+				"required": ["temperature"]
+			}
+		}))
+		.unwrap()
+	}
+
+	#[test]
+	fn arguments_are_checked_against_learned_schema() {
+		let validator = ToolValidator::new(McpToolValidation::default());
+		let args = |v: Value| v.as_object().cloned();
+		assert_eq!(
+			validator.check_arguments("weather", "get_weather", None),
+			None
+		);
+
+		validator.learn("weather", "get_weather", &tool());
+		assert_eq!(
+			validator.check_arguments(
+				"weather",
+				"get_weather",
+				args(json!({"city": "Paris", "days": 3})).as_ref()
+			),
+			Some(Ok(()))
+		);
+		assert!(matches!(
+			validator.check_arguments(
+				"weather",
+				"get_weather",
+				args(json!({"city": "Paris", "days": "three"})).as_ref()
+			),
+			Some(Err(_))
+		));
+		assert!(matches!(
+			validator.check_arguments("weather", "get_weather", None),
+			Some(Err(_))
+		));
+		assert_eq!(
+			validator.check_arguments("other", "get_weather", None),
+			None
+		);
+	}
+
+	#[test]
+	fn results_are_checked_against_output_schema() {
+		let disabled = ToolValidator::new(McpToolValidation::default());
+		disabled.learn("weather", "get_weather", &tool());
+		assert!(disabled.output_schema("weather", "get_weather").is_none());
+
+		let validator = ToolValidator::new(McpToolValidation {
+			output: OutputValidation::Reject,
+		});
+		validator.learn("weather", "get_weather", &tool());
+		let schema = validator.output_schema("weather", "get_weather").unwrap();
+		assert!(
+			check_result(
+				&schema,
+				&CallToolResult::structured(json!({"temperature": 21.5}))
+			)
+			.is_ok()
+		);
+		assert!(
+			check_result(
+				&schema,
+				&CallToolResult::structured(json!({"temp": "warm"}))
+			)
+			.is_err()
+		);
+		assert!(check_result(&schema, &CallToolResult::structured_error(json!({}))).is_ok());
+	}
+}
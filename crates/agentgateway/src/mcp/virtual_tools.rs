@@ -0,0 +1,242 @@
+//! Virtual tools.
+//!
+//! Per-target overrides that curate the tools an upstream MCP server exposes: tools can be renamed,
+//! redescribed, hidden, or have parameters removed and pinned to values computed by the gateway.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use rmcp::model::{JsonObject, Tool};
+use serde_json::Value;
+
+use crate::*;
+
+#[apply(schema!)]
+pub struct VirtualTool {
+	/// Name of the tool on the upstream server.
+	pub name: String,
+	/// Name the tool is exposed under. Once renamed, the upstream name can no longer be called.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub rename: Option<String>,
+	/// Replaces the description of the tool.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+	/// Annotations merged over the tool's own, such as `readOnlyHint`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub annotations: Option<JsonObject>,
+	/// Hide the tool from clients. Calls to it are rejected as calls to an unknown tool.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub hidden: bool,
+	/// Parameters removed from the input schema. Values sent by clients are dropped.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub hide_parameters: Vec<String>,
+	/// Arguments set by the gateway when the call is forwarded. These are removed from the input
+	/// schema and replace any value sent by the client.
+	#[serde(default, skip_serializing_if = "HashMap::is_empty")]
+	pub arguments: HashMap<String, PinnedArgument>,
+}
+
+#[apply(schema!)]
+pub enum PinnedArgument {
+	/// A fixed value.
+	Value(Value),
+	/// A CEL expression evaluated against the request, such as `jwt.tenant`.
+	Expression(Arc<cel::Expression>),
+}
+
+impl VirtualTool {
+	fn exposed_name(&self) -> &str {
+		self.rename.as_deref().unwrap_or(&self.name)
+	}
+
+	fn is_hidden_parameter(&self, name: &str) -> bool {
+		self.arguments.contains_key(name) || self.hide_parameters.iter().any(|p| p == name)
+	}
+
+	/// Rewrites an upstream tool as it is listed to clients, or returns `None` if it is hidden.
+	pub(crate) fn list(&self, mut tool: Tool) -> Option<Tool> {
+		if self.hidden {
+			return None;
+		}
+		tool.name = Cow::Owned(self.exposed_name().to_string());
+		if let Some(description) = &self.description {
+			tool.description = Some(Cow::Owned(description.clone()));
+		}
+		if let Some(annotations) = &self.annotations {
+			let mut merged = tool
+				.annotations
+				.as_ref()
+				.and_then(|a| serde_json::to_value(a).ok())
+				.and_then(|v| match v {
+					Value::Object(o) => Some(o),
+					_ => None,
+				})
+				.unwrap_or_default();
+			merged.extend(annotations.clone());
+			match serde_json::from_value(Value::Object(merged)) {
+				Ok(a) => tool.annotations = Some(a),
+				Err(e) => warn!(tool=%self.name, "ignoring invalid tool annotations: {e}"),
+			}
+		}
+		if !self.arguments.is_empty() || !self.hide_parameters.is_empty() {
+			let mut schema = tool.input_schema.as_ref().clone();
+			if let Some(Value::Object(properties)) = schema.get_mut("properties") {
+				properties.retain(|k, _| !self.is_hidden_parameter(k));
+			}
+			if let Some(Value::Array(required)) = schema.get_mut("required") {
+				required.retain(|r| !r.as_str().is_some_and(|r| self.is_hidden_parameter(r)));
+			}
+			tool.input_schema = Arc::new(schema);
+		}
+		Some(tool)
+	}
+
+	/// Rewrites the arguments of a call before it is forwarded upstream: hidden parameters are
+	/// dropped and pinned arguments are set.
+	pub(crate) fn forward_arguments(
+		&self,
+		arguments: &mut Option<JsonObject>,
+		exec: &cel::Executor,
+	) -> Result<(), String> {
+		if let Some(args) = arguments.as_mut() {
+			args.retain(|k, _| !self.hide_parameters.iter().any(|p| p == k));
+		}
+		if self.arguments.is_empty() {
+			return Ok(());
+		}
+		let args = arguments.get_or_insert_default();
+		for (name, pinned) in &self.arguments {
+			let value = match pinned {
+				PinnedArgument::Value(v) => v.clone(),
+				PinnedArgument::Expression(expr) => exec
+					.eval(expr)
+					.map_err(|e| e.to_string())
+					.and_then(|v| v.json().map_err(|e| e.to_string()))
+					.map_err(|e| format!("argument '{name}' could not be evaluated: {e}"))?,
+			};
+			args.insert(name.clone(), value);
+		}
+		Ok(())
+	}
+}
+
+/// Finds the override for a tool by its upstream name.
+pub(crate) fn for_upstream<'a>(tools: &'a [VirtualTool], name: &str) -> Option<&'a VirtualTool> {
+	tools.iter().find(|t| t.name == name)
+}
+
+/// Maps the name a client called back to the tool's override.
+///
+/// Returns `Err` when the name does not refer to a callable tool: the tool is hidden, or it was
+/// renamed and the client used the upstream name.
+pub(crate) fn resolve<'a>(
+	tools: &'a [VirtualTool],
+	name: &str,
+) -> Result<Option<&'a VirtualTool>, ()> {
+	if let Some(t) = tools.iter().find(|t| t.exposed_name() == name) {
+		return if t.hidden { Err(()) } else { Ok(Some(t)) };
+	}
+	if tools.iter().any(|t| t.name == name) {
+		return Err(());
+	}
+	Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+
+	fn virtual_tool(value: Value) -> VirtualTool {
+		serde_json::from_value(value).unwrap()
+	}
+
+	fn upstream_tool() -> Tool {
+		serde_json::from_value(json!({
+			"name": "search_issues",
+			"description": "Search issues in any organization.",
+			"inputSchema": {
+				"type": "object",
+				"properties": {
+					"org": {"type": "string"},
+					"query": {"type": "string"},
+					"debug": {"type": "boolean"}
+				},
+				"required": ["org", "query"]
+			},
+			"annotations": {"destructiveHint": false}
+		}))
+		.unwrap()
+	}
+
+	#[test]
+	fn list_rewrites_tool() {
+		let vt = virtual_tool(json!({
+			"name": "search_issues",
+			"rename": "search",
+			"description": "Search issues of your organization.",
+			"annotations": {"readOnlyHint": true},
+			"hideParameters": ["debug"],
+			"arguments": {"org": {"expression": "jwt.org"}}
+		}));
+		let tool = serde_json::to_value(vt.list(upstream_tool()).unwrap()).unwrap();
+		assert_eq!(
+			tool,
+			json!({
+				"name": "search",
+				"description": "Search issues of your organization.",
+				"inputSchema": {
+					"type": "object",
+					"properties": {"query": {"type": "string"}},
+					"required": ["query"]
+				},
+				"annotations": {"destructiveHint": false, "readOnlyHint": true}
+			})
+		);
+
+		let hidden = virtual_tool(json!({"name": "search_issues", "hidden": true}));
+		assert!(hidden.list(upstream_tool()).is_none());
+	}
+
+	#[test]
+	fn resolve_maps_exposed_names() {
+		let tools = vec![
+			virtual_tool(json!({"name": "search_issues", "rename": "search"})),
+			virtual_tool(json!({"name": "delete_repo", "hidden": true})),
+		];
+		assert_eq!(
+			resolve(&tools, "search").unwrap().map(|t| t.name.as_str()),
+			Some("search_issues")
+		);
+		assert!(resolve(&tools, "search_issues").is_err());
+		assert!(resolve(&tools, "delete_repo").is_err());
+		assert!(resolve(&tools, "get_issue").unwrap().is_none());
+	}
+
+	#[test]
+	fn forward_arguments_pins_values() {
+		let vt = virtual_tool(json!({
+			"name": "search_issues",
+			"hideParameters": ["debug"],
+			"arguments": {
+				"org": {"expression": "request.headers['x-org']"},
+				"limit": {"value": 10}
+			}
+		}));
+		let req = ::http::Request::builder()
+			.header("x-org", "acme")
+			.body(())
+			.unwrap();
+		let mcp = crate::mcp::MCPInfo::default();
+		let exec = cel::Executor::new_mcp_request(&req, &mcp);
+		let mut args = json!({"org": "other", "query": "bug", "debug": true})
+			.as_object()
+			.cloned();
+		vt.forward_arguments(&mut args, &exec).unwrap();
+		assert_eq!(
+			Value::Object(args.unwrap()),
+			json!({"org": "acme", "query": "bug", "limit": 10})
+		);
+	}
+}
@@ -1,30 +1,176 @@
+use std::collections::HashMap;
 use std::io::{Error, IoSlice};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll, ready};
 
-use async_openai::types::realtime::{
-	RealtimeClientEvent, RealtimeResponseUsage, RealtimeServerEvent, UserMessageContent,
-};
+use agent_core::strng;
 use bytes::{Bytes, BytesMut};
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
 use websocket_sans_io::{FrameInfo, Opcode, WebsocketFrameEncoder, WebsocketFrameEvent};
 
-use crate::llm::policy::PromptGuard;
-use crate::llm::{LLMInfo, LLMResponse};
+use crate::cel::RequestSnapshot;
+use crate::http::localratelimit;
+use crate::llm::policy::{PromptGuard, RealtimeBlockAction, RealtimeGuardOutcome};
+use crate::llm::{AmendOnDrop, LLMInfo, cost};
 use crate::proxy::httpproxy::PolicyClient;
+use crate::store::LLMResponsePolicies;
 use crate::telemetry::log::AsyncLog;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ResponseDoneEvent {
-	/// The response resource.
-	pub response: ResponseResource,
+/// The subset of realtime server events the gateway inspects. Everything else is forwarded as-is.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum ServerEvent {
+	#[serde(rename = "session.created", alias = "session.updated")]
+	Session { session: SessionResource },
+	#[serde(
+		rename = "response.output_text.delta",
+		alias = "response.text.delta",
+		alias = "response.output_audio_transcript.delta",
+		alias = "response.audio_transcript.delta"
+	)]
+	OutputDelta { delta: String },
+	#[serde(
+		rename = "response.output_text.done",
+		alias = "response.text.done",
+		alias = "response.output_audio_transcript.done",
+		alias = "response.audio_transcript.done"
+	)]
+	OutputDone,
+	#[serde(rename = "conversation.item.input_audio_transcription.delta")]
+	InputTranscriptDelta { item_id: String },
+	#[serde(rename = "conversation.item.input_audio_transcription.completed")]
+	InputTranscript {
+		item_id: String,
+		transcript: String,
+		usage: Option<Usage>,
+	},
+	#[serde(rename = "conversation.item.input_audio_transcription.failed")]
+	InputTranscriptFailed { item_id: String },
+	#[serde(rename = "response.done")]
+	ResponseDone { response: ResponseResource },
+	#[serde(other)]
+	Other,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ResponseResource {
+impl ServerEvent {
+	fn parse(payload: &[u8]) -> Option<Self> {
+		serde_json::from_slice(payload).ok()
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionResource {
+	model: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseResource {
 	/// Usage statistics for the response.
-	pub usage: Option<RealtimeResponseUsage>,
+	usage: Option<Usage>,
+}
+
+/// Usage reported on `response.done` and input audio transcription events.
+#[derive(Debug, Default, Deserialize)]
+struct Usage {
+	input_tokens: Option<u64>,
+	output_tokens: Option<u64>,
+	total_tokens: Option<u64>,
+	input_token_details: Option<TokenDetails>,
+	output_token_details: Option<TokenDetails>,
+	/// Set instead of token counts when transcription is billed by duration.
+	seconds: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TokenDetails {
+	text_tokens: Option<u64>,
+	audio_tokens: Option<u64>,
+	image_tokens: Option<u64>,
+	cached_tokens: Option<u64>,
+}
+
+fn accumulate(total: &mut Option<u64>, value: Option<u64>) {
+	if let Some(value) = value {
+		*total = Some(total.unwrap_or_default() + value);
+	}
+}
+
+/// Accounts for the usage of a realtime session.
+///
+/// A session spans many responses, so usage is summed into the request's LLM response as each one
+/// completes. Local token rate limits are charged per response so concurrent sessions see the
+/// consumption right away; remote rate limits and budgets are settled once the session ends.
+pub struct RealtimeUsage {
+	log: AsyncLog<LLMInfo>,
+	local_rate_limit: Vec<localratelimit::Bucket>,
+	_settle: AmendOnDrop,
+}
+
+impl RealtimeUsage {
+	pub fn new(
+		log: AsyncLog<LLMInfo>,
+		mut policies: LLMResponsePolicies,
+		req: Option<Arc<RequestSnapshot>>,
+		catalog: Option<Arc<cost::ModelCatalog>>,
+	) -> Self {
+		let local_rate_limit = std::mem::take(&mut policies.local_rate_limit);
+		Self {
+			_settle: AmendOnDrop::new(log.clone(), policies, req, catalog),
+			log,
+			local_rate_limit,
+		}
+	}
+
+	fn record(&self, event: &ServerEvent) {
+		match event {
+			ServerEvent::Session { session } => {
+				if let Some(model) = &session.model {
+					self
+						.log
+						.non_atomic_mutate(|r| r.response.provider_model = Some(strng::new(model)));
+				}
+			},
+			ServerEvent::ResponseDone { response } => {
+				if let Some(usage) = &response.usage {
+					self.record_usage(usage);
+				}
+			},
+			ServerEvent::InputTranscript {
+				usage: Some(usage), ..
+			} => self.record_usage(usage),
+			_ => {},
+		}
+	}
+
+	fn record_usage(&self, usage: &Usage) {
+		self.log.non_atomic_mutate(|r| {
+			let resp = &mut r.response;
+			accumulate(&mut resp.input_tokens, usage.input_tokens);
+			accumulate(&mut resp.output_tokens, usage.output_tokens);
+			accumulate(&mut resp.total_tokens, usage.total_tokens);
+			if let Some(details) = &usage.input_token_details {
+				accumulate(&mut resp.input_text_tokens, details.text_tokens);
+				accumulate(&mut resp.input_audio_tokens, details.audio_tokens);
+				accumulate(&mut resp.input_image_tokens, details.image_tokens);
+				accumulate(&mut resp.cached_input_tokens, details.cached_tokens);
+			}
+			if let Some(details) = &usage.output_token_details {
+				accumulate(&mut resp.output_text_tokens, details.text_tokens);
+				accumulate(&mut resp.output_audio_tokens, details.audio_tokens);
+				accumulate(&mut resp.output_image_tokens, details.image_tokens);
+			}
+			accumulate(
+				&mut resp.input_audio_seconds,
+				usage.seconds.map(|s| s.ceil() as u64),
+			);
+		});
+		let tokens = usage.input_tokens.unwrap_or_default() + usage.output_tokens.unwrap_or_default();
+		for lrl in &self.local_rate_limit {
+			lrl.amend_tokens(tokens as i64);
+		}
+	}
 }
 
 struct Parser<IO> {
@@ -33,7 +179,7 @@ struct Parser<IO> {
 	text_buffer: BytesMut,
 	buffer_limit: usize,
 	disabled: bool,
-	log: AsyncLog<LLMInfo>,
+	usage: RealtimeUsage,
 }
 
 impl<IO> Parser<IO> {
@@ -48,43 +194,8 @@ impl<IO> Parser<IO> {
 	}
 
 	fn emit(&self, data: Bytes) {
-		let Ok(data) = str::from_utf8(&data) else {
-			return;
-		};
-		if data.contains("response.done") {
-			let Ok(typed) = serde_json::from_str::<ResponseDoneEvent>(data) else {
-				return;
-			};
-			if let Some(usage) = typed.response.usage {
-				// TODO: do we need to parse the request side to get the request model?
-				// it seems like we get an event from the server with the same thing.
-				// also, the model can change... so what do we report??
-				self.log.non_atomic_mutate(|r| {
-					r.response = LLMResponse {
-						input_tokens: Some(usage.input_tokens as u64),
-						input_image_tokens: None,
-						input_text_tokens: None,
-						input_audio_tokens: None,
-						output_tokens: Some(usage.output_tokens as u64),
-						output_image_tokens: None,
-						output_text_tokens: None,
-						output_audio_tokens: None,
-						total_tokens: Some(usage.total_tokens as u64),
-						service_tier: None,
-						provider_model: None,
-						completion: None,
-						first_token: None,
-						count_tokens: None,
-						reasoning_tokens: None,
-						cache_creation_input_tokens: None,
-						cached_input_tokens: usage
-							.input_token_details
-							.as_ref()
-							.and_then(|d| d.cached_tokens)
-							.map(|x| x as u64),
-					}
-				});
-			}
+		if let Some(event) = ServerEvent::parse(&data) {
+			self.usage.record(&event);
 		}
 	}
 }
@@ -150,17 +261,17 @@ impl<IO: AsyncRead + Unpin + 'static> AsyncRead for Parser<IO> {
 
 pub async fn parser<IO>(
 	body: IO,
-	log: AsyncLog<LLMInfo>,
+	usage: RealtimeUsage,
 ) -> impl AsyncRead + AsyncWrite + Unpin + 'static
 where
 	IO: AsyncRead + AsyncWrite + Unpin + 'static,
 {
-	parser_with_limit(body, log, crate::defaults::max_buffer_size())
+	parser_with_limit(body, usage, crate::defaults::max_buffer_size())
 }
 
 fn parser_with_limit<IO>(
 	body: IO,
-	log: AsyncLog<LLMInfo>,
+	usage: RealtimeUsage,
 	buffer_limit: usize,
 ) -> impl AsyncRead + AsyncWrite + Unpin + 'static
 where
@@ -172,7 +283,7 @@ where
 		text_buffer: Default::default(),
 		buffer_limit,
 		disabled: false,
-		log,
+		usage,
 	}
 }
 
@@ -180,40 +291,45 @@ where
 // Guarded WebSocket realtime proxy
 // ---------------------------------------------------------------------------
 
-/// Encode a WebSocket text frame (server-side, unmasked) containing `payload`.
-fn encode_ws_text_frame(payload: &[u8]) -> Bytes {
+/// Encode a single, final WebSocket frame. Client→server frames must carry a `mask`.
+fn encode_ws_frame(opcode: Opcode, payload: &[u8], mask: Option<[u8; 4]>) -> Bytes {
 	let mut encoder = WebsocketFrameEncoder::new();
 	let frame_info = FrameInfo {
-		opcode: Opcode::Text,
+		opcode,
 		payload_length: payload.len() as u64,
-		mask: None,
+		mask,
 		fin: true,
 		reserved: 0,
 	};
 	let header = encoder.start_frame(&frame_info);
 	let mut out = BytesMut::with_capacity(header.len() + payload.len());
 	out.extend_from_slice(&header);
-	out.extend_from_slice(payload);
+	if mask.is_some() {
+		let mut payload_copy = payload.to_vec();
+		encoder.transform_frame_payload(&mut payload_copy);
+		out.extend_from_slice(&payload_copy);
+	} else {
+		out.extend_from_slice(payload);
+	}
 	out.freeze()
 }
 
+/// Encode a WebSocket text frame (server-side, unmasked) containing `payload`.
+fn encode_ws_text_frame(payload: &[u8]) -> Bytes {
+	encode_ws_frame(Opcode::Text, payload, None)
+}
+
 /// Encode a WebSocket text frame with `mask` (client-side, masked).
 fn encode_ws_text_frame_masked(payload: &[u8], mask: [u8; 4]) -> Bytes {
-	let mut encoder = WebsocketFrameEncoder::new();
-	let frame_info = FrameInfo {
-		opcode: Opcode::Text,
-		payload_length: payload.len() as u64,
-		mask: Some(mask),
-		fin: true,
-		reserved: 0,
-	};
-	let header = encoder.start_frame(&frame_info);
-	let mut out = BytesMut::with_capacity(header.len() + payload.len());
-	out.extend_from_slice(&header);
-	let mut payload_copy = payload.to_vec();
-	encoder.transform_frame_payload(&mut payload_copy);
-	out.extend_from_slice(&payload_copy);
-	out.freeze()
+	encode_ws_frame(Opcode::Text, payload, Some(mask))
+}
+
+/// Close frame with status 1008 (policy violation), sent to both peers when a guard blocks and the
+/// policy closes the session.
+fn policy_violation_close_frame(mask: Option<[u8; 4]>) -> Bytes {
+	let mut payload = 1008u16.to_be_bytes().to_vec();
+	payload.extend_from_slice(b"guardrail_intervention");
+	encode_ws_frame(Opcode::ConnectionClose, &payload, mask)
 }
 
 /// Synthetic WebSocket error event sent when a guardrail blocks content.
@@ -336,23 +452,91 @@ impl WsFrameAccumulator {
 // Guarded realtime proxy
 // ---------------------------------------------------------------------------
 
+/// Applies request guards to the user text of a `conversation.item.create` event.
+///
+/// Returns the re-encoded event when a guard redacted the text, or the rejection body when a guard
+/// blocked it. Other events are left untouched.
+async fn guard_client_event(
+	guard: &PromptGuard,
+	policy_client: &PolicyClient,
+	payload: &[u8],
+) -> Result<Option<Vec<u8>>, Bytes> {
+	let Ok(mut event) = serde_json::from_slice::<serde_json::Value>(payload) else {
+		return Ok(None);
+	};
+	if event["type"] != "conversation.item.create" || event["item"]["role"] != "user" {
+		return Ok(None);
+	}
+	let Some(content) = event["item"]["content"].as_array_mut() else {
+		return Ok(None);
+	};
+	let mut redacted = false;
+	for part in content.iter_mut() {
+		if part["type"] != "input_text" {
+			continue;
+		}
+		let Some(text) = part["text"].as_str() else {
+			continue;
+		};
+		match guard
+			.apply_realtime_request_guards(text, policy_client)
+			.await
+		{
+			RealtimeGuardOutcome::Allow => {},
+			RealtimeGuardOutcome::Masked(text) => {
+				part["text"] = text.into();
+				redacted = true;
+			},
+			RealtimeGuardOutcome::Rejected(body) => return Err(body),
+		}
+	}
+	if !redacted {
+		return Ok(None);
+	}
+	Ok(serde_json::to_vec(&event).ok())
+}
+
+/// Re-encodes an input audio transcription event with a redacted transcript.
+fn redact_transcript(payload: &[u8], transcript: String) -> Option<Bytes> {
+	let mut event = serde_json::from_slice::<serde_json::Value>(payload).ok()?;
+	event["transcript"] = transcript.into();
+	Some(encode_ws_text_frame(&serde_json::to_vec(&event).ok()?))
+}
+
+/// Sends a policy-violation close frame to both peers, ending the session.
+async fn close_session(
+	client_tx: &tokio::sync::mpsc::Sender<Bytes>,
+	server_tx: &tokio::sync::mpsc::Sender<Bytes>,
+) {
+	let _ = client_tx.send(policy_violation_close_frame(None)).await;
+	let _ = server_tx
+		.send(policy_violation_close_frame(Some([0, 0, 0, 0])))
+		.await;
+}
+
 /// A bidirectional guarded WebSocket proxy for the OpenAI Realtime API.
 ///
-/// - **Client→Server:** applies request guards to `conversation.item.create` events.
-/// - **Server→Client:** windowed evaluation of `response.output_text.delta` events —
+/// - **Client→Server:** applies request guards to the user text of `conversation.item.create`
+///   events. Masked text is rewritten in the forwarded event; blocked events are dropped and a
+///   synthetic error event is sent to the client.
+/// - **Server→Client:** windowed evaluation of output text and audio transcript deltas —
 ///   deltas are held until ~`DEFAULT_EVAL_THRESHOLD` bytes of text accumulate, then
 ///   evaluated (with an overlap tail from previously evaluated text) and flushed on
 ///   pass. On block, the held (never-forwarded) deltas are discarded, a synthetic
 ///   error event is sent to the client, and `response.cancel` is sent to the server;
 ///   subsequent deltas for that response are dropped.
-/// - Preserves existing telemetry on `response.done`.
+/// - Input audio transcripts are held until transcription completes and run through the request
+///   guards, so the client only sees redacted transcripts. The model has already heard the audio,
+///   so a blocked transcript also cancels the in-flight response.
+/// - When the guard's `realtimeBlock` is `close`, any block closes the session with both peers.
+/// - Usage is accounted through `usage` as it is reported.
 /// - All non-text frames (audio, control, etc.) are forwarded immediately.
 pub async fn guarded_realtime_proxy<C, S>(
 	client: C,
 	server: S,
 	guard: PromptGuard,
 	policy_client: PolicyClient,
-	log: AsyncLog<LLMInfo>,
+	usage: RealtimeUsage,
 	req_headers: ::http::HeaderMap,
 ) where
 	C: AsyncRead + AsyncWrite + Unpin + Send + 'static,
@@ -368,9 +552,9 @@ pub async fn guarded_realtime_proxy<C, S>(
 	let (client_tx, mut client_rx) = tokio::sync::mpsc::channel::<Bytes>(256);
 	let (server_tx, mut server_rx) = tokio::sync::mpsc::channel::<Bytes>(256);
 
+	let close = guard.realtime_block == RealtimeBlockAction::Close;
 	let guard_clone = guard.clone();
 	let policy_client_clone = policy_client.clone();
-	let log_clone = log.clone();
 
 	let client_to_server = {
 		let server_tx = server_tx.clone();
@@ -379,7 +563,7 @@ pub async fn guarded_realtime_proxy<C, S>(
 			let mut accum = WsFrameAccumulator::new();
 			let mut read_buf = [0u8; 4096];
 
-			loop {
+			'read: loop {
 				let n = match client_reader.read(&mut read_buf).await {
 					Ok(0) | Err(_) => break,
 					Ok(n) => n,
@@ -389,35 +573,25 @@ pub async fn guarded_realtime_proxy<C, S>(
 				for frame in accum.drain_frames() {
 					match frame {
 						WsCompletedFrame::Text { raw, payload } => {
-							if let Ok(text) = std::str::from_utf8(&payload)
-								&& let Ok(RealtimeClientEvent::ConversationItemCreate(create)) =
-									serde_json::from_str::<RealtimeClientEvent>(text)
-							{
-								let mut input_text = String::new();
-								if let async_openai::types::realtime::RealtimeConversationItem::Message(
-									async_openai::types::realtime::RealtimeConversationItemMessage::User(user_msg),
-								) = &create.item
-								{
-									for content in &user_msg.content {
-										if let UserMessageContent::InputText(t) = content {
-											input_text.push_str(&t.text);
-											input_text.push(' ');
-										}
-									}
-								}
-
-								if !input_text.is_empty()
-									&& let Some(blocked_body) = guard
-										.apply_realtime_request_guards(input_text.trim(), &policy_client)
-										.await
-								{
+							match guard_client_event(&guard, &policy_client, &payload).await {
+								Ok(None) => {
+									let _ = server_tx.send(raw).await;
+								},
+								Ok(Some(redacted)) => {
+									let _ = server_tx
+										.send(encode_ws_text_frame_masked(&redacted, [0, 0, 0, 0]))
+										.await;
+								},
+								Err(blocked_body) => {
 									let _ = client_tx_err
 										.send(guardrail_blocked_ws_event_bytes(blocked_body))
 										.await;
-									continue;
-								}
+									if close {
+										close_session(&client_tx_err, &server_tx).await;
+										break 'read;
+									}
+								},
 							}
-							let _ = server_tx.send(raw).await;
 						},
 						WsCompletedFrame::Other { raw } => {
 							let _ = server_tx.send(raw).await;
@@ -434,13 +608,15 @@ pub async fn guarded_realtime_proxy<C, S>(
 			let mut accum = WsFrameAccumulator::new();
 			let mut read_buf = [0u8; 4096];
 			let mut evaluators =
-				guard_clone.begin_streaming_response_guard(&policy_client_clone, &req_headers);
+				guard_clone.begin_streaming_response_guard(&policy_client_clone, &req_headers, None);
 			let mut delta_hold: Vec<Bytes> = Vec::new();
 			let mut pending_text = String::new();
 			let mut overlap_tail = String::new();
 			let mut response_blocked = false;
+			// Input transcription deltas, held per conversation item until the full transcript is guarded.
+			let mut transcript_hold: HashMap<String, Vec<Bytes>> = HashMap::new();
 
-			loop {
+			'read: loop {
 				let n = match server_reader.read(&mut read_buf).await {
 					Ok(0) | Err(_) => break,
 					Ok(n) => n,
@@ -448,115 +624,139 @@ pub async fn guarded_realtime_proxy<C, S>(
 				accum.push(&read_buf[..n]);
 
 				for frame in accum.drain_frames() {
-					match frame {
-						WsCompletedFrame::Text { raw, payload } => {
-							let event = std::str::from_utf8(&payload)
-								.ok()
-								.and_then(|s| serde_json::from_str::<RealtimeServerEvent>(s).ok());
-
-							match event {
-								Some(RealtimeServerEvent::ResponseOutputTextDelta(ref delta_event)) => {
-									if response_blocked {
-										continue;
-									}
-									pending_text.push_str(&delta_event.delta);
-									delta_hold.push(raw);
-
-									if pending_text.len() >= DEFAULT_EVAL_THRESHOLD {
-										let batch = std::mem::take(&mut pending_text);
-										let window = format!("{overlap_tail}{batch}");
-										overlap_tail = tail_chars(&window, OVERLAP_BYTES).to_string();
-
-										if let Some(blocked_body) = evaluate_window(&mut evaluators, &window).await {
-											delta_hold.clear();
-											// Clear text-state so a blocked response's content does not
-											// bleed into the next response's evaluation window.
-											overlap_tail.clear();
-											pending_text.clear();
-											response_blocked = true;
-											let _ = client_tx
-												.send(guardrail_blocked_ws_event_bytes(blocked_body))
-												.await;
-											let _ = server_tx
-												.send(response_cancel_event_bytes([0, 0, 0, 0]))
-												.await;
-										} else {
-											for f in delta_hold.drain(..) {
-												let _ = client_tx.send(f).await;
-											}
-										}
-									}
-								},
-								Some(RealtimeServerEvent::ResponseOutputTextDone(_)) => {
-									if response_blocked {
-										response_blocked = false;
-										overlap_tail.clear();
-										pending_text.clear();
-										delta_hold.clear();
-										continue;
+					let (raw, payload) = match frame {
+						WsCompletedFrame::Text { raw, payload } => (raw, payload),
+						WsCompletedFrame::Other { raw } => {
+							let _ = client_tx.send(raw).await;
+							continue;
+						},
+					};
+					let Some(event) = ServerEvent::parse(&payload) else {
+						let _ = client_tx.send(raw).await;
+						continue;
+					};
+					usage.record(&event);
+
+					match event {
+						ServerEvent::OutputDelta { delta } => {
+							if response_blocked {
+								continue;
+							}
+							pending_text.push_str(&delta);
+							delta_hold.push(raw);
+
+							if pending_text.len() >= DEFAULT_EVAL_THRESHOLD {
+								let batch = std::mem::take(&mut pending_text);
+								let window = format!("{overlap_tail}{batch}");
+								overlap_tail = tail_chars(&window, OVERLAP_BYTES).to_string();
+
+								if let Some(blocked_body) = evaluate_window(&mut evaluators, &window).await {
+									delta_hold.clear();
+									// Clear text-state so a blocked response's content does not
+									// bleed into the next response's evaluation window.
+									overlap_tail.clear();
+									pending_text.clear();
+									response_blocked = true;
+									let _ = client_tx
+										.send(guardrail_blocked_ws_event_bytes(blocked_body))
+										.await;
+									let _ = server_tx
+										.send(response_cancel_event_bytes([0, 0, 0, 0]))
+										.await;
+									if close {
+										close_session(&client_tx, &server_tx).await;
+										break 'read;
 									}
-
-									let mut blocked_body = None;
-									if !pending_text.is_empty() {
-										let batch = std::mem::take(&mut pending_text);
-										let window = format!("{overlap_tail}{batch}");
-										blocked_body = evaluate_window(&mut evaluators, &window).await;
+								} else {
+									for f in delta_hold.drain(..) {
+										let _ = client_tx.send(f).await;
 									}
-									overlap_tail.clear();
+								}
+							}
+						},
+						ServerEvent::OutputDone => {
+							if response_blocked {
+								response_blocked = false;
+								overlap_tail.clear();
+								pending_text.clear();
+								delta_hold.clear();
+								continue;
+							}
 
-									if let Some(blocked_body) = blocked_body {
-										delta_hold.clear();
-										response_blocked = true;
-										let _ = client_tx
-											.send(guardrail_blocked_ws_event_bytes(blocked_body))
-											.await;
-										let _ = server_tx
-											.send(response_cancel_event_bytes([0, 0, 0, 0]))
-											.await;
-									} else {
-										for f in delta_hold.drain(..) {
-											let _ = client_tx.send(f).await;
-										}
-										let _ = client_tx.send(raw).await;
+							let mut blocked_body = None;
+							if !pending_text.is_empty() {
+								let batch = std::mem::take(&mut pending_text);
+								let window = format!("{overlap_tail}{batch}");
+								blocked_body = evaluate_window(&mut evaluators, &window).await;
+							}
+							overlap_tail.clear();
+
+							if let Some(blocked_body) = blocked_body {
+								delta_hold.clear();
+								response_blocked = true;
+								let _ = client_tx
+									.send(guardrail_blocked_ws_event_bytes(blocked_body))
+									.await;
+								let _ = server_tx
+									.send(response_cancel_event_bytes([0, 0, 0, 0]))
+									.await;
+								if close {
+									close_session(&client_tx, &server_tx).await;
+									break 'read;
+								}
+							} else {
+								for f in delta_hold.drain(..) {
+									let _ = client_tx.send(f).await;
+								}
+								let _ = client_tx.send(raw).await;
+							}
+						},
+						ServerEvent::InputTranscriptDelta { item_id } => {
+							transcript_hold.entry(item_id).or_default().push(raw);
+						},
+						ServerEvent::InputTranscriptFailed { item_id } => {
+							for f in transcript_hold.remove(&item_id).into_iter().flatten() {
+								let _ = client_tx.send(f).await;
+							}
+							let _ = client_tx.send(raw).await;
+						},
+						ServerEvent::InputTranscript {
+							item_id,
+							transcript,
+							..
+						} => {
+							let held = transcript_hold.remove(&item_id).unwrap_or_default();
+							match guard_clone
+								.apply_realtime_request_guards(&transcript, &policy_client_clone)
+								.await
+							{
+								RealtimeGuardOutcome::Allow => {
+									for f in held {
+										let _ = client_tx.send(f).await;
 									}
+									let _ = client_tx.send(raw).await;
 								},
-								Some(RealtimeServerEvent::ResponseDone(ref done_event)) => {
-									if let Some(usage) = done_event.response.usage.as_ref() {
-										let usage_clone = usage.clone();
-										log_clone.non_atomic_mutate(|r| {
-											r.response = LLMResponse {
-												input_tokens: Some(usage_clone.input_tokens as u64),
-												input_image_tokens: None,
-												input_text_tokens: None,
-												input_audio_tokens: None,
-												output_tokens: Some(usage_clone.output_tokens as u64),
-												output_image_tokens: None,
-												output_text_tokens: None,
-												output_audio_tokens: None,
-												total_tokens: Some(usage_clone.total_tokens as u64),
-												service_tier: None,
-												provider_model: None,
-												completion: None,
-												first_token: None,
-												count_tokens: None,
-												reasoning_tokens: None,
-												cache_creation_input_tokens: None,
-												cached_input_tokens: usage_clone
-													.input_token_details
-													.as_ref()
-													.and_then(|d| d.cached_tokens)
-													.map(|x| x as u64),
-											}
-										});
+								// The held deltas carry the unredacted text, so only the rewritten event is sent.
+								RealtimeGuardOutcome::Masked(redacted) => {
+									if let Some(f) = redact_transcript(&payload, redacted) {
+										let _ = client_tx.send(f).await;
 									}
-									let _ = client_tx.send(raw).await;
 								},
-								_ => {
-									let _ = client_tx.send(raw).await;
+								RealtimeGuardOutcome::Rejected(blocked_body) => {
+									let _ = client_tx
+										.send(guardrail_blocked_ws_event_bytes(blocked_body))
+										.await;
+									let _ = server_tx
+										.send(response_cancel_event_bytes([0, 0, 0, 0]))
+										.await;
+									if close {
+										close_session(&client_tx, &server_tx).await;
+										break 'read;
+									}
 								},
 							}
 						},
-						WsCompletedFrame::Other { raw } => {
+						_ => {
 							let _ = client_tx.send(raw).await;
 						},
 					}
@@ -606,7 +806,7 @@ mod tests {
 			text_buffer: Default::default(),
 			buffer_limit: 4,
 			disabled: false,
-			log: AsyncLog::default(),
+			usage: RealtimeUsage::new(AsyncLog::default(), Default::default(), None, None),
 		};
 
 		assert!(parser.record_text_payload(b"abc"));
@@ -617,4 +817,65 @@ mod tests {
 		assert!(parser.text_buffer.is_empty());
 		assert_eq!(parser.text_buffer.capacity(), 0);
 	}
+
+	#[test]
+	fn usage_accumulates_across_responses() {
+		let log = AsyncLog::default();
+		log.store(Some(LLMInfo::new(
+			crate::llm::LLMRequest {
+				input_tokens: None,
+				input_format: crate::llm::InputFormat::Realtime,
+				native_format: None,
+				cache_convention: crate::llm::CacheTokenConvention::pending(),
+				request_model: "gpt-realtime".into(),
+				provider: "openai".into(),
+				streaming: true,
+				params: Default::default(),
+				prompt: None,
+			},
+			Default::default(),
+		)));
+		let usage = RealtimeUsage::new(log.clone(), Default::default(), None, None);
+		let events = [
+			r#"{"type":"session.created","session":{"model":"gpt-realtime-2025-08-28"}}"#,
+			r#"{"type":"response.done","response":{"usage":{"total_tokens":30,"input_tokens":20,"output_tokens":10,"input_token_details":{"text_tokens":5,"audio_tokens":15,"cached_tokens":4},"output_token_details":{"text_tokens":2,"audio_tokens":8}}}}"#,
+			r#"{"type":"response.done","response":{"usage":{"total_tokens":7,"input_tokens":5,"output_tokens":2}}}"#,
+			r#"{"type":"conversation.item.input_audio_transcription.completed","item_id":"item_1","transcript":"hi","usage":{"type":"duration","seconds":1.2}}"#,
+			r#"{"type":"response.output_text.delta","delta":"hi"}"#,
+		];
+		for event in events {
+			usage.record(&ServerEvent::parse(event.as_bytes()).unwrap());
+		}
+
+		let resp = log.load_clone().unwrap().response;
+		assert_eq!(
+			resp.provider_model.as_deref(),
+			Some("gpt-realtime-2025-08-28")
+		);
+		assert_eq!(resp.input_tokens, Some(25));
+		assert_eq!(resp.output_tokens, Some(12));
+		assert_eq!(resp.total_tokens, Some(37));
+		assert_eq!(resp.input_audio_tokens, Some(15));
+		assert_eq!(resp.cached_input_tokens, Some(4));
+		assert_eq!(resp.output_audio_tokens, Some(8));
+		assert_eq!(resp.input_audio_seconds, Some(2));
+	}
+
+	#[test]
+	fn audio_transcript_deltas_are_guarded_like_text() {
+		for kind in [
+			"response.output_text.delta",
+			"response.output_audio_transcript.delta",
+		] {
+			let event = format!(r#"{{"type":"{kind}","delta":"hello","item_id":"item_1"}}"#);
+			assert!(matches!(
+				ServerEvent::parse(event.as_bytes()),
+				Some(ServerEvent::OutputDelta { delta }) if delta == "hello"
+			));
+		}
+		assert!(matches!(
+			ServerEvent::parse(br#"{"type":"response.created","response":{}}"#),
+			Some(ServerEvent::Other)
+		));
+	}
 }
@@ -1332,6 +1332,7 @@ async fn llm_detect_mode_passthrough_without_rewrite() {
 		path_override: None,
 		path_prefix: None,
 		tokenize: false,
+		tokenizers: Default::default(),
 		policies: serde_json::from_value(json!({
 			"ai": {
 				"routes": {
@@ -1397,6 +1398,7 @@ async fn llm_detect_mode_respects_model_rewrite() {
 		path_override: None,
 		path_prefix: None,
 		tokenize: false,
+		tokenizers: Default::default(),
 		policies: serde_json::from_value(json!({
 			"ai": {
 				"routes": {
@@ -1765,6 +1767,7 @@ async fn llm_custom_rerank() {
 		path_override: None,
 		path_prefix: None,
 		tokenize: false,
+		tokenizers: Default::default(),
 		policies: serde_json::from_value(json!({
 			"ai": {"routes": {"/v1/rerank": "rerank"}}
 		}))
@@ -2080,6 +2083,55 @@ async fn llm_streaming_remote_rate_limit_cost_amends_response_tokens() {
 	.await;
 }
 
+#[tokio::test]
+async fn llm_response_cache_hits_are_subject_to_budgets() {
+	let mock = body_mock(include_bytes!(
+		"../llm/tests/response/completions/basic.json"
+	))
+	.await;
+	let mut provider = llm_named_provider(
+		&mock,
+		AIProvider::OpenAI(openai::Provider { model: None }),
+		false,
+	);
+	// A budget with no allowance, which only applies to requests with a tenant header.
+	provider.policies = serde_json::from_value(json!({
+		"ai": {
+			"responseCache": {"ttl": "1h"},
+			"budgets": [{
+				"name": "tenant",
+				"limit": 0.0,
+				"window": "daily",
+				"key": "request.headers['x-tenant']"
+			}]
+		}
+	}))
+	.unwrap();
+	let (mock, _bind, io) = setup_llm_named_provider_mock(mock, provider, "{}");
+	let body = include_bytes!("../llm/tests/requests/completions/basic.json");
+	let send = |tenant: Option<&str>| {
+		let mut rb = RequestBuilder::new(Method::POST, "http://lo/v1/chat/completions")
+			.header(header::CONTENT_TYPE, "application/json");
+		if let Some(tenant) = tenant {
+			rb = rb.header("x-tenant", tenant);
+		}
+		rb.body(Body::from(body.to_vec())).send(io.clone())
+	};
+
+	let res = send(None).await.unwrap();
+	assert_eq!(res.status(), StatusCode::OK);
+	let _ = read_body_raw(res.into_body()).await;
+	let res = send(None).await.unwrap();
+	assert_eq!(res.status(), StatusCode::OK);
+	let _ = read_body_raw(res.into_body()).await;
+	assert_eq!(mock.received_requests().await.unwrap().len(), 1);
+
+	// The response is cached, but the budget still rejects the request.
+	let res = send(Some("acme")).await.unwrap();
+	assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+	assert_eq!(mock.received_requests().await.unwrap().len(), 1);
+}
+
 #[rstest::rstest]
 #[case::preserves_path(None, None, "/v1/messages?trace=repro")]
 #[case::path_override(Some("/custom/chat/completions"), None, "/custom/chat/completions")]
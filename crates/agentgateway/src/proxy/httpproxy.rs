@@ -29,6 +29,7 @@ use crate::http::{
 	Authority, HeaderName, HeaderValue, Request, Response, Scheme, StatusCode, Uri, auth, filters,
 	merge_in_headers, retry,
 };
+use crate::llm::policy::{response_cache, structured_output};
 use crate::llm::{
 	InputFormat, LLMInfo, LLMRequest, LLMResponse, RequestResult, RouteType, model_router,
 };
@@ -42,7 +43,10 @@ use crate::store::{
 };
 use crate::telemetry::log;
 use crate::telemetry::log::{AsyncLog, DropOnLog, LogBody, RequestLog, TraceSampler};
-use crate::telemetry::metrics::{OutboundCallKind, OutboundCallLabels, OutboundCallSubtype};
+use crate::telemetry::metrics::{
+	OutboundCallKind, OutboundCallLabels, OutboundCallSubtype, StructuredOutputLabels,
+	StructuredOutputResult,
+};
 use crate::telemetry::trc::TraceParent;
 use crate::transport::stream::{Extension, Socket, TCPConnectionInfo, TLSConnectionInfo};
 use crate::types::local::InternalBackend;
@@ -272,6 +276,14 @@ async fn apply_backend_policies(
 		mcp_authentication: _,
 		// Applied elsewhere (in mcp/handler.rs + mcp/session.rs)
 		mcp_guardrails: _,
+		// Applied elsewhere (in mcp/handler.rs + mcp/session.rs)
+		mcp_tool_validation: _,
+		// Applied elsewhere (in mcp/session.rs)
+		mcp_rate_limit: _,
+		// Applied elsewhere (in mcp/handler.rs)
+		mcp_cache: _,
+		// Applied elsewhere (in mcp/handler.rs)
+		mcp_tool_catalog: _,
 		// Applied elsewhere
 		inference_routing: _,
 		ext_authz,
@@ -426,16 +438,11 @@ async fn apply_llm_request_policies(
 	llm_req: &LLMRequest,
 	response_headers: &mut HeaderMap,
 ) -> Result<store::LLMResponsePolicies, ProxyResponse> {
-	let local_rate_limit = policies
-		.local_rate_limit
-		.as_deref()
-		.into_iter()
-		.flatten()
-		.filter(|rate_limit| rate_limit.spec.limit_type == http::localratelimit::RateLimitType::Tokens)
-		.cloned()
-		.collect::<Vec<_>>();
-	for lrl in &local_rate_limit {
-		lrl.check_llm_request(llm_req)?;
+	let mut local_rate_limit = Vec::new();
+	for lrl in policies.local_rate_limit.as_deref().into_iter().flatten() {
+		if let Some(bucket) = lrl.check_llm_request(req, llm_req, &client.inputs.metrics)? {
+			local_rate_limit.push(bucket);
+		}
 	}
 	let (rl_resp, response) = if let Some(rrl) = &policies.remote_rate_limit {
 		// For the LLM request side, request either the count of the input tokens (if tokenization was done)
@@ -448,6 +455,13 @@ async fn apply_llm_request_policies(
 		(http::PolicyResponse::default(), None)
 	};
 	rl_resp.apply(response_headers)?;
+	let budgets = match policies.llm.as_deref() {
+		Some(llm) => {
+			llm::policy::budget::charge_all(&llm.budgets, req, llm_req, &client.inputs.model_catalog)
+				.await?
+		},
+		None => Vec::new(),
+	};
 	let prompt_guard = policies
 		.llm
 		.as_deref()
@@ -458,6 +472,19 @@ async fn apply_llm_request_policies(
 		request_traceparent: req.headers().get(TRACEPARENT).cloned(),
 		prompt_guard: prompt_guard.map(|g| g.response.clone()).unwrap_or_default(),
 		streaming_prompt_guard_enabled: prompt_guard.is_some_and(|g| g.streaming.is_enabled()),
+		budgets,
+		pii_tokens: req
+			.extensions()
+			.get::<Arc<llm::policy::tokenize::TokenVault>>()
+			.cloned(),
+		declared_tools: req
+			.extensions()
+			.get::<Arc<llm::policy::tools::DeclaredTools>>()
+			.cloned(),
+		structured_output: req
+			.extensions()
+			.get::<Arc<llm::policy::structured_output::Expected>>()
+			.cloned(),
 	})
 }
 
@@ -623,9 +650,16 @@ impl HTTPProxy {
 				return ProxyError::UpgradeFailed(None, None).into_response_with_grpc(is_grpc_request);
 			};
 			let realtime_guard_context = resp.extensions_mut().remove::<RealtimeGuardContext>();
-			handle_upgrade(req_upgrade, resp, log, realtime_guard_context)
-				.await
-				.unwrap_or_else(|e| e.into_response_with_grpc(is_grpc_request))
+			let realtime_policies = resp.extensions_mut().remove::<RealtimePolicies>();
+			handle_upgrade(
+				req_upgrade,
+				resp,
+				log,
+				realtime_guard_context,
+				realtime_policies,
+			)
+			.await
+			.unwrap_or_else(|e| e.into_response_with_grpc(is_grpc_request))
 		} else {
 			resp.map(move |b| http::Body::new(LogBody::new(b, log)))
 		}
@@ -799,11 +833,14 @@ impl HTTPProxy {
 		debug!(bind=%bind_name, listener=%selected_listener.key, route=%selected_route.key, "selected route");
 
 		let selected_llm_backend = if let Some(router) = &selected_route.llm_router {
-			match router.resolve(&mut req).await {
+			match router.resolve(&mut req, &self.inputs.model_catalog).await {
 				model_router::ResolveResult::DirectResponse(resp) => {
 					return Err(ProxyResponse::DirectResponse(Box::new(resp))).snapshot_on_err(log, &mut req);
 				},
-				model_router::ResolveResult::Backend(backend) => Some(backend),
+				model_router::ResolveResult::Backend(backend) => {
+					log.llm_latency_observer = backend.latency_observer.clone();
+					Some(backend)
+				},
 			}
 		} else {
 			None
@@ -923,6 +960,7 @@ impl HTTPProxy {
 
 		let route_request_mirrors = route_policies.request_mirror.select("request mirror", &req);
 		let route_llm = route_policies.llm.select("llm", &req);
+		let request_buffer_limit = http::buffer_limit(&req);
 		let (head, body) = req.into_parts();
 		for mirror in route_request_mirrors
 			.iter()
@@ -958,16 +996,30 @@ impl HTTPProxy {
 		llm_request_policies.llm = route_llm;
 		let llm_request_policies = Arc::new(llm_request_policies);
 
+		// AI backends with failover enabled move failed attempts on to the next provider.
+		let failover = match &selected_backend.backend.backend {
+			Backend::AI(_, ai) => ai.failover.as_ref().map(|f| (ai, f)),
+			_ => None,
+		};
 		// attempts is the total number of attempts, not the retries
-		let attempts = retries.as_ref().map(|r| r.attempts.get() + 1).unwrap_or(1);
+		let retry_attempts = retries.as_ref().map(|r| r.attempts.get() + 1).unwrap_or(1);
+		let failover_attempts = failover
+			.map(|(_, f)| f.max_attempts.map(|m| m.get()).unwrap_or(u8::MAX))
+			.unwrap_or(1);
+		let attempts = retry_attempts.max(failover_attempts);
 		let retry_backoff = retries.as_ref().and_then(|r| r.backoff);
 		let request_timeout = response_policies
 			.timeout
 			.as_ref()
 			.and_then(|t| t.request_timeout);
+		// LLM requests routinely exceed the retry buffer, so failover buffers up to the request limit.
+		let max_buffered_bytes = match failover {
+			Some(_) => request_buffer_limit.max(MAX_BUFFERED_BYTES),
+			None => MAX_BUFFERED_BYTES,
+		};
 		let body = if attempts > 1 {
 			// If we are going to attempt a retry we will need to track the incoming bytes for replay
-			let body = http::retry::ReplayBody::try_new(body, MAX_BUFFERED_BYTES);
+			let body = http::retry::ReplayBody::try_new(body, max_buffered_bytes);
 			if body.is_err() {
 				debug!("initial body is too large to retry, disabling retries")
 			}
@@ -1028,12 +1080,18 @@ impl HTTPProxy {
 					req,
 				)
 				.await;
-			if last
-				|| !should_retry(
-					&res,
-					retries.as_ref().unwrap(),
-					log.request_snapshot.as_deref(),
-				) {
+			let retry_next = n + 1 < retry_attempts
+				&& retries
+					.as_ref()
+					.is_some_and(|r| should_retry(&res, r, log.request_snapshot.as_deref()));
+			// Failover decisions only look at the response head, so streaming responses never fail
+			// over once the first byte has been sent to the client.
+			let failover_next = failover.is_some_and(|(ai, f)| {
+				n + 1 < failover_attempts
+					&& should_failover(&res, f)
+					&& ai.has_untried_provider(&log.llm_failover)
+			});
+			if last || !(retry_next || failover_next) {
 				if !last {
 					debug!("response not retry-able");
 				}
@@ -1047,7 +1105,7 @@ impl HTTPProxy {
 			);
 			finalize_attempt_for_retry(log, &mut res);
 			last_res = Some(res);
-			if let Some(bo) = retry_backoff {
+			if retry_next && let Some(bo) = retry_backoff {
 				let fut = if let Some(request_timeout) = request_timeout {
 					let deadline = tokio::time::Instant::from_std(log.start.as_instant() + request_timeout);
 					tokio::time::timeout_at(deadline, tokio::time::sleep(bo)).await
@@ -1296,6 +1354,15 @@ impl HTTPProxy {
 				.insert(BackendRequestTimeout(backend_timeout));
 		}
 		let upgrade_req_headers = req.headers().clone();
+		// AI backends may re-issue the request to repair structured output, which needs the head.
+		let repair = if matches!(selected_backend.backend.backend, Backend::AI(_, _)) {
+			let (head, body) = req.into_parts();
+			let repair = (head.clone(), backend_policies.clone());
+			req = Request::from_parts(head, body);
+			Some(repair)
+		} else {
+			None
+		};
 		let mut req_opt = Some(req);
 		let timeout = response_policies
 			.timeout
@@ -1340,6 +1407,20 @@ impl HTTPProxy {
 					.maybe_snapshot_on_err(log, &mut req_opt)?;
 			},
 		};
+		if let Some((head, backend_policies)) = repair {
+			resp = self
+				.repair_structured_output(
+					log,
+					route_policies.clone(),
+					selected_backend,
+					backend_policies,
+					response_policies,
+					head,
+					timeout,
+					resp,
+				)
+				.await?;
+		}
 		if resp.status() == StatusCode::SWITCHING_PROTOCOLS {
 			let Some(upgrade) = req_upgrade.take() else {
 				return Err(ProxyResponse::Error(ProxyError::UpgradeFailed(None, None)))
@@ -1367,6 +1448,74 @@ impl HTTPProxy {
 		Ok(resp)
 	}
 
+	/// Re-issue a request to an AI backend while its output does not match the JSON schema the
+	/// client asked for, up to the policy's repair limit. The last response is returned either way.
+	#[allow(clippy::too_many_arguments)]
+	async fn repair_structured_output(
+		&self,
+		log: &mut RequestLog,
+		route_policies: Arc<store::LLMRequestPolicies>,
+		selected_backend: &RouteBackend,
+		backend_policies: BackendPolicies,
+		response_policies: &mut ResponsePolicies,
+		head: ::http::request::Parts,
+		timeout: Option<Duration>,
+		mut resp: Response,
+	) -> Result<Response, SnapshottedProxyResponse> {
+		let mut repairs = 0;
+		loop {
+			let Some(check) = resp.extensions_mut().remove::<structured_output::Check>() else {
+				return Ok(resp);
+			};
+			let Some(body) = check.repair.filter(|_| repairs < check.max_repairs) else {
+				let result = match (check.valid, repairs) {
+					(false, _) => StructuredOutputResult::Invalid,
+					(true, 0) => StructuredOutputResult::Valid,
+					(true, _) => StructuredOutputResult::Repaired,
+				};
+				self
+					.inputs
+					.metrics
+					.structured_output_checks
+					.get_or_create(&StructuredOutputLabels {
+						gen_ai_request_model: check.model.into(),
+						result,
+					})
+					.inc();
+				return Ok(resp);
+			};
+			repairs += 1;
+			debug!(
+				"structured output did not match the schema, repair {repairs}/{}",
+				check.max_repairs
+			);
+			let mut res = Ok(resp);
+			finalize_attempt_for_retry(log, &mut res);
+			let mut head = head.clone();
+			head.headers.remove(header::CONTENT_LENGTH);
+			let mut req_opt = Some(Request::from_parts(head, http::Body::from(body)));
+			let call = make_backend_call(
+				self.inputs.clone(),
+				route_policies.clone(),
+				&selected_backend.backend.backend,
+				backend_policies.clone().into(),
+				MustSnapshot::new(&mut req_opt),
+				Some(log),
+				response_policies,
+			);
+			let res = match timeout {
+				Some(timeout) => {
+					let deadline = tokio::time::Instant::from_std(log.start.as_instant() + timeout);
+					tokio::time::timeout_at(deadline, call)
+						.await
+						.unwrap_or(Err(ProxyResponse::Error(ProxyError::RequestTimeout)))
+				},
+				None => call.await,
+			};
+			resp = res.maybe_snapshot_on_err(log, &mut req_opt)?;
+		}
+	}
+
 	fn policy_client(&self) -> PolicyClient {
 		PolicyClient::new(self.inputs.clone())
 	}
@@ -1390,6 +1539,7 @@ async fn handle_upgrade(
 	mut resp: Response,
 	log: DropOnLog,
 	realtime_guard_context: Option<RealtimeGuardContext>,
+	realtime_policies: Option<RealtimePolicies>,
 ) -> Result<Response, ProxyError> {
 	let RequestUpgrade {
 		upgrade_type,
@@ -1428,19 +1578,28 @@ async fn handle_upgrade(
 			let llm = log.llm_response.clone();
 			let llm_info = LLMInfo::new(llm_req.clone(), LLMResponse::default());
 			llm.store(Some(llm_info));
+			let policies = realtime_policies
+				.and_then(|p| p.0.lock().expect("realtime policies lock").take())
+				.unwrap_or_default();
+			let usage = parse::websocket::RealtimeUsage::new(
+				llm,
+				policies,
+				log.request_snapshot.clone(),
+				Some(log.model_catalog.clone()),
+			);
 			if let Some(guard_context) = realtime_guard_context {
 				parse::websocket::guarded_realtime_proxy(
 					TokioIo::new(req),
 					server,
 					guard_context.prompt_guard,
 					guard_context.policy_client,
-					llm,
+					usage,
 					guard_context.req_headers,
 				)
 				.await;
 				return;
 			}
-			let mut server = parse::websocket::parser(server, llm).await;
+			let mut server = parse::websocket::parser(server, usage).await;
 			let _ = agent_core::copy::copy_bidirectional(
 				&mut TokioIo::new(req),
 				&mut server,
@@ -1946,7 +2105,19 @@ async fn make_backend_call(
 
 	let (mut backend_call, mut maybe_inference) = match backend {
 		Backend::AI(n, ai) => {
-			let (provider, handle) = ai.select_provider().ok_or(ProxyError::NoHealthyEndpoints)?;
+			let selected = match &ai.failover {
+				Some(_) => ai.select_failover_provider(
+					log
+						.as_deref()
+						.map(|l| l.llm_failover.as_slice())
+						.unwrap_or_default(),
+				),
+				None => ai.select_provider(),
+			};
+			let (provider, handle) = selected.ok_or(ProxyError::NoHealthyEndpoints)?;
+			if ai.failover.is_some() {
+				log.add(|l| l.llm_failover.push(provider.name.clone()));
+			}
 			log.add(move |l| l.request_handle = Some(handle));
 			let sub_backend_name = BackendTargetRef::Backend {
 				name: n.name.as_ref(),
@@ -2159,6 +2330,8 @@ async fn make_backend_call(
 
 	set_backend_cel_context(&mut req, log.as_ref());
 
+	let mut response_cache_miss = None;
+	let mut realtime_policies = None;
 	let (mut req, llm_response_policies, llm_request) =
 		if let Some(llm) = &backend_call.backend_policies.llm_provider {
 			// LLM requires CEL execution after the snapshot so we do not clear extensions
@@ -2183,7 +2356,13 @@ async fn make_backend_call(
 				| RouteType::AnthropicTokenCount
 				| RouteType::Embeddings
 				| RouteType::Rerank
-				| RouteType::Detect => {
+				| RouteType::GenerateContent
+				| RouteType::Detect
+				| RouteType::ImageGeneration
+				| RouteType::AudioTranscription
+				| RouteType::AudioSpeech
+				| RouteType::Batches
+				| RouteType::Files => {
 					let request_body_limit = crate::http::buffer_limit(&req);
 					let req = req.map(|b| {
 						dtrace::TracingBody::maybe_wrap("llm request before translation", b, request_body_limit)
@@ -2193,7 +2372,7 @@ async fn make_backend_call(
 							&backend_info,
 							llm_request_policies.llm.as_deref(),
 							req,
-							llm.tokenize,
+							llm.tokenizers(),
 							&mut log,
 						))
 						.await
@@ -2202,7 +2381,7 @@ async fn make_backend_call(
 							&backend_info,
 							llm_request_policies.llm.as_deref(),
 							req,
-							llm.tokenize,
+							llm.tokenizers(),
 							&mut log,
 						))
 						.await
@@ -2211,7 +2390,7 @@ async fn make_backend_call(
 							&backend_info,
 							llm_request_policies.llm.as_deref(),
 							req,
-							llm.tokenize,
+							llm.tokenizers(),
 							&mut log,
 						))
 						.await
@@ -2220,7 +2399,7 @@ async fn make_backend_call(
 							&backend_info,
 							llm_request_policies.llm.as_deref(),
 							req,
-							llm.tokenize,
+							llm.tokenizers(),
 							&mut log,
 						))
 						.await
@@ -2229,7 +2408,16 @@ async fn make_backend_call(
 							&backend_info,
 							llm_request_policies.llm.as_deref(),
 							req,
-							llm.tokenize,
+							llm.tokenizers(),
+							&mut log,
+						))
+						.await
+						.map_err(|e| ProxyError::Processing(e.into()))?,
+						RouteType::GenerateContent => Box::pin(llm.provider.process_generate_content_request(
+							&backend_info,
+							llm_request_policies.llm.as_deref(),
+							req,
+							llm.tokenizers(),
 							&mut log,
 						))
 						.await
@@ -2250,6 +2438,39 @@ async fn make_backend_call(
 						))
 						.await
 						.map_err(|e| ProxyError::Processing(e.into()))?,
+						RouteType::ImageGeneration => Box::pin(llm.provider.process_image_generation_request(
+							&backend_info,
+							llm_request_policies.llm.as_deref(),
+							req,
+							llm.tokenizers(),
+							&mut log,
+						))
+						.await
+						.map_err(|e| ProxyError::Processing(e.into()))?,
+						RouteType::AudioTranscription => Box::pin(llm.provider.process_transcription_request(
+							&backend_info,
+							llm_request_policies.llm.as_deref(),
+							req,
+							&mut log,
+						))
+						.await
+						.map_err(|e| ProxyError::Processing(e.into()))?,
+						RouteType::AudioSpeech => Box::pin(llm.provider.process_speech_request(
+							&backend_info,
+							llm_request_policies.llm.as_deref(),
+							req,
+							llm.tokenizers(),
+							&mut log,
+						))
+						.await
+						.map_err(|e| ProxyError::Processing(e.into()))?,
+						RouteType::Batches | RouteType::Files => Box::pin(
+							llm
+								.provider
+								.process_batch_request(llm_request_policies.llm.as_deref(), req),
+						)
+						.await
+						.map_err(|e| ProxyError::Processing(e.into()))?,
 						_ => unreachable!(),
 					};
 					let (mut req, llm_request) = match r {
@@ -2293,6 +2514,49 @@ async fn make_backend_call(
 						)
 						.await?
 					};
+
+					// Look up cached responses only once the request has passed rate limits, budgets and
+					// prompt guards, so a cached response is never served to a request those would reject.
+					// On a hit, the budget reservations are released as the response costs nothing.
+					// Tokenized requests are not cached: the response is restored with values that are only
+					// valid for this request.
+					let tokenized = req
+						.extensions()
+						.get::<Arc<llm::policy::tokenize::TokenVault>>()
+						.is_some();
+					let cache_lookup = match llm_request_policies
+						.llm
+						.as_deref()
+						.and_then(|llm| llm.response_cache.as_ref())
+					{
+						Some(cache)
+							if !matches!(
+								route_type,
+								RouteType::AnthropicTokenCount
+									| RouteType::ImageGeneration
+									| RouteType::AudioTranscription
+									| RouteType::AudioSpeech
+									| RouteType::Batches
+									| RouteType::Files
+							) && !tokenized =>
+						{
+							cache.lookup(&mut req, &llm_request, &policy_client).await?
+						},
+						_ => None,
+					};
+					match cache_lookup {
+						Some(response_cache::Lookup::Hit(hit)) => {
+							let (resp, info) = hit.into_response(llm_request.clone(), &inputs.model_catalog);
+							log.add(|l| {
+								l.llm_request = Some(llm_request);
+								l.llm_response.store(Some(info));
+							});
+							return Ok(resp);
+						},
+						Some(response_cache::Lookup::Miss(pending)) => response_cache_miss = Some(pending),
+						None => {},
+					}
+
 					log.add(|l| l.llm_request = Some(llm_request.clone()));
 					(req, response_policies, Some(llm_request))
 				},
@@ -2310,7 +2574,8 @@ async fn make_backend_call(
 				RouteType::Passthrough | RouteType::Realtime => {
 					// For passthrough, we only need to setup the response so we get default TLS, hostname, etc set.
 					// We do not need LLM policies nor token-based rate limits, etc.
-					// For realtime we do the same and handle everything in the Websocket handler
+					// For realtime, token rate limits and budgets are checked when the session is opened and
+					// settled from the usage the Websocket handler observes; guards run in the Websocket handler.
 					llm
 						.provider
 						.setup_request(
@@ -2329,19 +2594,28 @@ async fn make_backend_call(
 							.find(|(k, _v)| k == "model")
 							.map(|(_, v)| strng::new(v))
 							.unwrap_or_default();
-						log.add(|l| {
-							l.llm_request = Some(LLMRequest {
-								input_format: InputFormat::Realtime,
-								native_format: Some(llm::custom::ProviderFormat::Realtime),
-								cache_convention: llm::CacheTokenConvention::pending(),
-								request_model,
-								streaming: true,
-								provider: llm.provider.provider(),
-								input_tokens: None,
-								params: Default::default(),
-								prompt: Default::default(),
-							})
-						});
+						let llm_request = LLMRequest {
+							input_format: InputFormat::Realtime,
+							native_format: Some(llm::custom::ProviderFormat::Realtime),
+							cache_convention: llm::CacheTokenConvention::pending(),
+							request_model,
+							streaming: true,
+							provider: llm.provider.provider(),
+							input_tokens: None,
+							params: Default::default(),
+							prompt: Default::default(),
+						};
+						realtime_policies = Some(
+							apply_llm_request_policies(
+								&llm_request_policies,
+								policy_client.clone(),
+								&mut req,
+								&llm_request,
+								&mut response_policies.response_headers,
+							)
+							.await?,
+						);
+						log.add(|l| l.llm_request = Some(llm_request));
 					}
 					(req, LLMResponsePolicies::default(), None)
 				},
@@ -2404,7 +2678,7 @@ async fn make_backend_call(
 	let llm_response_log = log.as_ref().map(|l| l.llm_response.clone());
 	let include_completion_in_log = log
 		.as_ref()
-		.map(|l| l.cel.cel_context.needs_llm_completion())
+		.map(|l| l.cel.cel_context.needs_llm_completion() || l.llm_shadow.is_some())
 		.unwrap_or_default();
 	let a2a_type = response_policies.a2a_type.clone();
 
@@ -2433,6 +2707,19 @@ async fn make_backend_call(
 		if resp.is_ok() {
 			l.response_processing_start = Some(outbound_end);
 		}
+		// Steer provider selection away from a provider before it starts returning 429s.
+		if let (Ok(resp), Some(llm), Some(rh)) = (
+			&resp,
+			&backend_call.backend_policies.llm_provider,
+			&l.request_handle,
+		) && let Some(convention) = llm.provider.rate_limit_headers()
+			&& let Some(headroom) = http::outlierdetection::rate_limit_headroom(
+				resp.headers(),
+				convention,
+				std::time::SystemTime::now(),
+			) {
+			rh.record_rate_limit(headroom.remaining, headroom.reset);
+		}
 	});
 	dtrace::trace(|trace| match &resp {
 		Ok(resp) => trace.backend_call_completed(
@@ -2449,6 +2736,11 @@ async fn make_backend_call(
 		),
 	});
 	let mut resp = resp?;
+	if let Some(policies) = realtime_policies {
+		resp
+			.extensions_mut()
+			.insert(RealtimePolicies(Arc::new(Mutex::new(Some(policies)))));
+	}
 	if let Some(log) = log.as_ref() {
 		resp
 			.extensions_mut()
@@ -2470,20 +2762,25 @@ async fn make_backend_call(
 		backend_call.backend_policies.llm_provider.clone(),
 		llm_request,
 	) {
-		llm
+		let llm_response_log = llm_response_log.expect("must be set");
+		let resp = llm
 			.provider
 			.process_response(
 				policy_client.clone(),
 				llm_request,
 				llm_response_policies,
 				log.as_ref().expect("must be set").request_snapshot.clone(),
-				llm_response_log.expect("must be set"),
+				llm_response_log.clone(),
 				include_completion_in_log,
 				Some(&inputs.model_catalog),
 				resp,
 			)
 			.await
-			.map_err(|e| ProxyError::Processing(e.into()))?
+			.map_err(|e| ProxyError::Processing(e.into()))?;
+		match response_cache_miss {
+			Some(pending) => pending.capture(resp, llm_response_log),
+			None => resp,
+		}
 	} else {
 		resp
 	};
@@ -2959,6 +3256,14 @@ fn should_retry(
 	}
 }
 
+fn should_failover(res: &Result<Response, SnapshottedProxyResponse>, pol: &llm::Failover) -> bool {
+	match res {
+		Ok(resp) => pol.matches(resp.status()),
+		Err(SnapshottedProxyResponse(ProxyResponse::Error(e))) => e.is_retryable(),
+		Err(SnapshottedProxyResponse(ProxyResponse::DirectResponse(_))) => false,
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use std::collections::{HashMap, HashSet};
@@ -3033,6 +3338,7 @@ mod tests {
 				streaming,
 				request: vec![],
 				response: vec![response_regex_guard()],
+				realtime_block: Default::default(),
 			}),
 			..Default::default()
 		};
@@ -3419,6 +3725,113 @@ mod tests {
 			Some("1")
 		);
 	}
+
+	#[tokio::test]
+	async fn llm_failover_translates_request_for_next_provider() {
+		let primary = wiremock::MockServer::start().await;
+		Mock::given(wiremock::matchers::any())
+			.respond_with(ResponseTemplate::new(529))
+			.mount(&primary)
+			.await;
+
+		let fallback = wiremock::MockServer::start().await;
+		Mock::given(wiremock::matchers::any())
+			.respond_with(ResponseTemplate::new(200).set_body_raw(
+				include_bytes!("../llm/tests/response/completions/basic.json").to_vec(),
+				"application/json",
+			))
+			.mount(&fallback)
+			.await;
+
+		let mut bind = proxymock::setup_proxy_test("{}").expect("proxy test harness");
+		let local_backend: LocalAIBackend = serde_json::from_value(json!({
+			"groups": [
+				{
+					"providers": [{
+						"name": "primary",
+						"hostOverride": primary.address().to_string(),
+						"provider": {
+							"anthropic": {}
+						}
+					}]
+				},
+				{
+					"providers": [{
+						"name": "fallback",
+						"hostOverride": fallback.address().to_string(),
+						"provider": {
+							"openAI": {}
+						}
+					}]
+				}
+			],
+			"failover": {}
+		}))
+		.expect("local AI backend");
+		let backend = Backend::AI(
+			ResourceName::new("llm".into(), "".into()),
+			local_backend.translate().expect("translated backend"),
+		);
+		bind
+			.pi
+			.stores
+			.binds
+			.write()
+			.insert_backend(backend.name(), backend.into());
+		bind = bind
+			.with_bind(proxymock::simple_bind())
+			.with_route(proxymock::basic_named_route("/llm".into()));
+		bind
+			.attach_route_policy(json!({
+				"ai": {
+					"routes": {
+						"/v1/chat/completions": "completions"
+					}
+				}
+			}))
+			.await;
+		let io = bind.serve_http(proxymock::BIND_KEY);
+
+		let res = proxymock::send_request_body(
+			io,
+			Method::POST,
+			"http://lo/v1/chat/completions",
+			include_bytes!("../llm/tests/requests/completions/basic.json"),
+		)
+		.await;
+
+		assert_eq!(res.status(), 200);
+
+		// Each provider receives the request in its own format.
+		let primary_requests = primary
+			.received_requests()
+			.await
+			.expect("primary request recording");
+		assert_eq!(primary_requests.len(), 1);
+		assert_eq!(primary_requests[0].url.path(), "/v1/messages");
+
+		let fallback_requests = fallback
+			.received_requests()
+			.await
+			.expect("fallback request recording");
+		assert_eq!(fallback_requests.len(), 1);
+		assert_eq!(fallback_requests[0].url.path(), "/v1/chat/completions");
+	}
+
+	#[test]
+	fn failover_defaults_to_rate_limits_and_server_errors() {
+		let pol = crate::llm::Failover::default();
+		for code in [429, 500, 503, 529] {
+			assert!(pol.matches(::http::StatusCode::from_u16(code).unwrap()));
+		}
+		for code in [200, 400, 404] {
+			assert!(!pol.matches(::http::StatusCode::from_u16(code).unwrap()));
+		}
+
+		let pol: crate::llm::Failover = serde_json::from_value(json!({"codes": [404]})).unwrap();
+		assert!(pol.matches(::http::StatusCode::NOT_FOUND));
+		assert!(!pol.matches(::http::StatusCode::SERVICE_UNAVAILABLE));
+	}
 }
 
 pub fn maybe_set_grpc_status(status: &AsyncLog<u8>, headers: &HeaderMap) {
@@ -3491,6 +3904,11 @@ struct ConnectTunnel {
 	upstream: Arc<Mutex<Option<Socket>>>,
 }
 
+/// Token rate limits and budgets charged when a realtime session is opened, settled from the usage
+/// reported over the websocket.
+#[derive(Clone)]
+struct RealtimePolicies(Arc<Mutex<Option<LLMResponsePolicies>>>);
+
 #[derive(Clone)]
 struct RealtimeGuardContext {
 	prompt_guard: crate::llm::policy::PromptGuard,
@@ -3892,6 +4310,28 @@ impl PolicyClient {
 		res
 	}
 
+	/// Like `call_with_explicit_policies_list`, but the policies are layered over a set of defaults,
+	/// such as the connection policies an LLM provider requires.
+	pub async fn call_with_default_policies(
+		&self,
+		req: Request,
+		backend: Backend,
+		defaults: BackendPolicies,
+		policies: &[BackendTrafficPolicy],
+	) -> Result<Response, ProxyError> {
+		let start = std::time::Instant::now();
+		let pols = defaults.merge(
+			self
+				.inputs
+				.stores
+				.read_binds()
+				.inline_backend_policies(policies),
+		);
+		let res = self.internal_call_with_policies(req, backend, pols).await;
+		self.observe_outbound(start);
+		res
+	}
+
 	fn internal_call_with_policies<'a>(
 		&'a self,
 		req: Request,
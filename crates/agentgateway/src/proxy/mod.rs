@@ -77,9 +77,9 @@ impl ProxyResponse {
 			| ProxyError::UpstreamTCPProxy(_) => ProxyResponseReason::UpstreamFailure,
 			ProxyError::RequestTimeout | ProxyError::UpstreamCallTimeout => ProxyResponseReason::Timeout,
 			ProxyError::ExtProc(_) => ProxyResponseReason::ExtProc,
-			ProxyError::RateLimitFailed | ProxyError::RateLimitExceeded { .. } => {
-				ProxyResponseReason::RateLimit
-			},
+			ProxyError::RateLimitFailed
+			| ProxyError::RateLimitExceeded { .. }
+			| ProxyError::BudgetExceeded { .. } => ProxyResponseReason::RateLimit,
 		}
 	}
 	pub fn downcast(self) -> ProxyError {
@@ -208,6 +208,8 @@ pub enum ProxyError {
 	},
 	#[error("rate limit failed")]
 	RateLimitFailed,
+	#[error("budget exceeded: {budget}")]
+	BudgetExceeded { budget: Strng, reset_seconds: u64 },
 	#[error("invalid request")]
 	InvalidRequest,
 	#[error("method not allowed")]
@@ -291,6 +293,7 @@ impl ProxyError {
 			// Rate limit service communication failure is a server error (500), not a rate limit (429).
 			// This matches Envoy's behavior (status_on_error defaults to 500).
 			ProxyError::RateLimitFailed => StatusCode::INTERNAL_SERVER_ERROR,
+			ProxyError::BudgetExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
 
 			// Shouldn't happen on this path
 			ProxyError::UpstreamTCPCallFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
@@ -318,6 +321,8 @@ impl ProxyError {
 			// Note: we do not return a 401/403 here, as the obscure that it was rejected due to auth
 			ProxyError::MCP(mcp::Error::Authorization(_, _, _)) => StatusCode::BAD_REQUEST,
 			ProxyError::MCP(mcp::Error::McpGuardrails(_, _)) => StatusCode::BAD_REQUEST,
+			ProxyError::MCP(mcp::Error::InvalidParams(_, _)) => StatusCode::BAD_REQUEST,
+			ProxyError::MCP(mcp::Error::RateLimited(_, _)) => StatusCode::BAD_REQUEST,
 		};
 		let grpc_status = is_grpc_request.then(|| proxy_error_to_grpc_status(&self, code));
 		let mut rb = ::http::Response::builder().status(code);
@@ -339,6 +344,11 @@ impl ProxyError {
 				rb = rb.header(http::x_headers::X_RATELIMIT_RESET, hv)
 			}
 		}
+		if let ProxyError::BudgetExceeded { reset_seconds, .. } = self
+			&& let Ok(hv) = HeaderValue::try_from(reset_seconds.to_string())
+		{
+			rb = rb.header(hyper::header::RETRY_AFTER, hv)
+		}
 
 		// Add WWW-Authenticate header for basic auth failures
 		if let ProxyError::BasicAuthenticationFailure(err) = &self {
@@ -414,7 +424,26 @@ impl ProxyError {
 				.body(http::Body::from(msg))
 				.unwrap();
 		}
-		if let ProxyError::MCP(mcp::Error::McpGuardrails(req_id, rej)) = self {
+		if let ProxyError::MCP(mcp::Error::InvalidParams(req_id, reason)) = self {
+			let msg = serde_json::to_string(&JsonRpcError {
+				jsonrpc: Default::default(),
+				id: req_id.clone(),
+				error: ErrorData {
+					code: ErrorCode::INVALID_PARAMS,
+					message: format!("invalid params: {reason}").into(),
+					data: None,
+				},
+			})
+			.unwrap_or_default();
+			return rb
+				.header("content-type", "application/json")
+				.body(http::Body::from(msg))
+				.unwrap();
+		}
+		if let ProxyError::MCP(
+			mcp::Error::McpGuardrails(req_id, rej) | mcp::Error::RateLimited(req_id, rej),
+		) = self
+		{
 			let msg = serde_json::to_string(&JsonRpcError {
 				jsonrpc: Default::default(),
 				id: req_id.clone(),
@@ -231,6 +231,10 @@ pub struct BackendPolicies {
 	pub mcp_authorization: Option<McpAuthorizationSet>,
 	pub mcp_authentication: Option<McpAuthentication>,
 	pub mcp_guardrails: Option<Arc<crate::mcp::guardrails::McpGuardrails>>,
+	pub mcp_tool_validation: Option<crate::mcp::McpToolValidation>,
+	pub mcp_rate_limit: Option<Arc<Vec<crate::mcp::McpRateLimit>>>,
+	pub mcp_cache: Option<Arc<crate::mcp::McpCache>>,
+	pub mcp_tool_catalog: Option<crate::mcp::McpToolCatalog>,
 
 	pub http: Option<types::backend::HTTP>,
 	pub tcp: Option<types::backend::TCP>,
@@ -265,6 +269,10 @@ impl BackendPolicies {
 			mcp_authorization: other.mcp_authorization.or(self.mcp_authorization),
 			mcp_authentication: other.mcp_authentication.or(self.mcp_authentication),
 			mcp_guardrails: other.mcp_guardrails.or(self.mcp_guardrails),
+			mcp_tool_validation: other.mcp_tool_validation.or(self.mcp_tool_validation),
+			mcp_rate_limit: other.mcp_rate_limit.or(self.mcp_rate_limit),
+			mcp_cache: other.mcp_cache.or(self.mcp_cache),
+			mcp_tool_catalog: other.mcp_tool_catalog.or(self.mcp_tool_catalog),
 			inference_routing: other.inference_routing.or(self.inference_routing),
 			ext_authz: other.ext_authz.or(self.ext_authz),
 			http: other.http.or(self.http),
@@ -467,28 +475,62 @@ impl LLMRequestPolicies {
 				.prompts
 				.clone()
 				.or_else(|| fallback.prompts.clone()),
+			prompt_templates: preferred
+				.prompt_templates
+				.clone()
+				.or_else(|| fallback.prompt_templates.clone()),
 			model_aliases: merged_aliases,
 			wildcard_patterns: merged_wildcard_patterns,
+			allowed_models: if preferred.allowed_models.is_empty() {
+				fallback.allowed_models.clone()
+			} else {
+				preferred.allowed_models.clone()
+			},
 			prompt_caching: preferred
 				.prompt_caching
 				.clone()
 				.or_else(|| fallback.prompt_caching.clone()),
+			response_cache: preferred
+				.response_cache
+				.clone()
+				.or_else(|| fallback.response_cache.clone()),
 			routes: if preferred.routes.is_empty() {
 				fallback.routes.clone()
 			} else {
 				preferred.routes.clone()
 			},
+			budgets: if preferred.budgets.is_empty() {
+				fallback.budgets.clone()
+			} else {
+				preferred.budgets.clone()
+			},
+			structured_output: preferred
+				.structured_output
+				.clone()
+				.or_else(|| fallback.structured_output.clone()),
+			context_window: preferred
+				.context_window
+				.clone()
+				.or_else(|| fallback.context_window.clone()),
+			shadow: preferred.shadow.clone().or_else(|| fallback.shadow.clone()),
 		})
 	}
 }
 
 #[derive(Debug, Default)]
 pub struct LLMResponsePolicies {
-	pub local_rate_limit: Vec<http::localratelimit::RateLimit>,
+	pub local_rate_limit: Vec<http::localratelimit::Bucket>,
 	pub remote_rate_limit: Option<http::remoteratelimit::LLMResponseAmend>,
 	pub request_traceparent: Option<HeaderValue>,
 	pub prompt_guard: Vec<ResponseGuard>,
 	pub streaming_prompt_guard_enabled: bool,
+	pub budgets: Vec<llm::policy::budget::Charge>,
+	/// Surrogate tokens substituted into the request, restored in the response.
+	pub pii_tokens: Option<Arc<llm::policy::tokenize::TokenVault>>,
+	/// Tools the request declared, for validating tool-call arguments.
+	pub declared_tools: Option<Arc<llm::policy::tools::DeclaredTools>>,
+	/// The schema the output must match, when structured output is enforced.
+	pub structured_output: Option<Arc<llm::policy::structured_output::Expected>>,
 }
 
 impl Default for Store {
@@ -1162,6 +1204,18 @@ impl Store {
 				BackendTrafficPolicy::McpGuardrails(p) => {
 					pol.mcp_guardrails.get_or_insert_with(|| p.clone());
 				},
+				BackendTrafficPolicy::McpToolValidation(p) => {
+					pol.mcp_tool_validation.get_or_insert_with(|| p.clone());
+				},
+				BackendTrafficPolicy::McpRateLimit(p) => {
+					pol.mcp_rate_limit.get_or_insert_with(|| p.clone());
+				},
+				BackendTrafficPolicy::McpCache(p) => {
+					pol.mcp_cache.get_or_insert_with(|| p.clone());
+				},
+				BackendTrafficPolicy::McpToolCatalog(p) => {
+					pol.mcp_tool_catalog.get_or_insert_with(|| p.clone());
+				},
 			}
 		}
 		if !mcp_authz.is_empty() {
@@ -2885,6 +2939,7 @@ mod tests {
 					}),
 				}],
 				response: vec![],
+				realtime_block: Default::default(),
 			}),
 			..Default::default()
 		}));
@@ -840,6 +840,7 @@ impl RequestLog {
 			retry_backoff: None,
 			jwt_sub: None,
 			retry_attempt: None,
+			llm_failover: Vec::new(),
 			error: None,
 			grpc_status: Default::default(),
 			mcp_status: Default::default(),
@@ -847,6 +848,9 @@ impl RequestLog {
 			outgoing_span: None,
 			llm_request: None,
 			llm_response: Default::default(),
+			llm_latency_observer: None,
+			llm_shadow: None,
+			llm_prompt_template: None,
 			a2a_method: None,
 			inference_pool: None,
 			request_handle: None,
@@ -989,6 +993,8 @@ pub struct RequestLog {
 	pub jwt_sub: Option<String>,
 
 	pub retry_attempt: Option<u8>,
+	/// AI providers already attempted for this request when the AI backend has failover enabled.
+	pub llm_failover: Vec<Strng>,
 	pub error: Option<String>,
 
 	pub grpc_status: AsyncLog<u8>,
@@ -999,6 +1005,12 @@ pub struct RequestLog {
 
 	pub llm_request: Option<llm::LLMRequest>,
 	pub llm_response: AsyncLog<llm::LLMInfo>,
+	/// Receives the observed latency when a lowest-latency virtual model selected the backend.
+	pub llm_latency_observer: Option<llm::model_router::LatencyObserver>,
+	/// Set when the request is shadowed, to report its outcome for comparison.
+	pub llm_shadow: Option<llm::policy::shadow::PrimaryObserver>,
+	/// The prompt template rendered into the request, if any.
+	pub llm_prompt_template: Option<llm::policy::prompt_template::Rendered>,
 
 	pub a2a_method: Option<Strng>,
 
@@ -1071,6 +1083,28 @@ impl Drop for DropOnLog {
 			if let Some(llm_response) = llm_response.as_mut() {
 				llm_response.set_token_timing(log.start.as_instant(), end_time.as_instant());
 			}
+			if let Some(observer) = log.llm_latency_observer.take() {
+				let ttft = llm_response
+					.as_ref()
+					.and_then(|r| r.time_to_first_token)
+					.and_then(|d| d.0.to_std().ok());
+				observer.observe(log.status, ttft, duration);
+			}
+			if let Some(observer) = log.llm_shadow.take() {
+				observer.observe(llm::policy::shadow::Primary {
+					status: log.status,
+					duration,
+					trace_id: log
+						.outgoing_span
+						.as_ref()
+						.map(|id| id.trace_id().to_string()),
+					span_id: log
+						.outgoing_span
+						.as_ref()
+						.map(|id| id.span_id().to_string()),
+					llm: llm_response.clone(),
+				});
+			}
 
 			let mcp = log.mcp_status.take();
 			let request_handle = log.request_handle.take();
@@ -1207,6 +1241,11 @@ impl Drop for DropOnLog {
 						("agw.ai.usage.cost.reasoning", b.reasoning.to_string()),
 						("agw.ai.usage.cost.input_audio", b.input_audio.to_string()),
 						("agw.ai.usage.cost.output_audio", b.output_audio.to_string()),
+						("agw.ai.usage.cost.images", b.images.to_string()),
+						(
+							"agw.ai.usage.cost.audio_seconds",
+							b.audio_seconds.to_string(),
+						),
 					]
 				})
 			} else {
@@ -1369,6 +1408,29 @@ impl Drop for DropOnLog {
 					"agw.ai.usage.cost.total",
 					usage_cost_total.as_deref().map(Into::into),
 				),
+				(
+					"agw.ai.prompt_template.id",
+					log.llm_prompt_template.as_ref().map(|t| display(&t.id)),
+				),
+				(
+					"agw.ai.prompt_template.version",
+					log
+						.llm_prompt_template
+						.as_ref()
+						.map(|t| display(&t.version)),
+				),
+				(
+					"agw.ai.response_cache",
+					llm_response
+						.as_ref()
+						.and_then(|l| l.response_cache.display()),
+				),
+				(
+					"agw.ai.response_cache.similarity",
+					llm_response
+						.as_ref()
+						.and_then(|l| l.response_cache_similarity.display()),
+				),
 				// Not part of official semconv
 				(
 					"gen_ai.usage.output_image_tokens",
@@ -1385,6 +1447,22 @@ impl Drop for DropOnLog {
 						.and_then(|l| l.output_audio_tokens)
 						.map(Into::into),
 				),
+				// Not part of official semconv
+				(
+					"gen_ai.usage.output_images",
+					llm_response
+						.as_ref()
+						.and_then(|l| l.output_images)
+						.map(Into::into),
+				),
+				// Not part of official semconv
+				(
+					"agw.ai.usage.input_audio_seconds",
+					llm_response
+						.as_ref()
+						.and_then(|l| l.input_audio_seconds)
+						.map(Into::into),
+				),
 				(
 					"gen_ai.request.temperature",
 					log
@@ -1448,6 +1526,14 @@ impl Drop for DropOnLog {
 						.and_then(|l| l.params.seed)
 						.map(Into::into),
 				),
+				(
+					"agw.ai.batch.requests",
+					log
+						.llm_request
+						.as_ref()
+						.and_then(|l| l.params.batch_requests)
+						.map(Into::into),
+				),
 				("retry.attempt", log.retry_attempt.display()),
 				("error", log.error.quoted()),
 				("reason", reason.display()),
@@ -1551,6 +1637,8 @@ impl Drop for DropOnLog {
 							("agw.ai.usage.cost.reasoning", cost.reasoning),
 							("agw.ai.usage.cost.inputAudio", cost.input_audio),
 							("agw.ai.usage.cost.outputAudio", cost.output_audio),
+							("agw.ai.usage.cost.images", cost.images),
+							("agw.ai.usage.cost.audioSeconds", cost.audio_seconds),
 						];
 						db_kv.reserve(cost_raws.len());
 						for (k, v) in &cost_raws {
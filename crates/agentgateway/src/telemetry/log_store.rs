@@ -153,6 +153,26 @@ pub async fn analytics_summary(
 		.await
 }
 
+/// Persist spend against a budget. Recorded spend is added to any existing spend for the same
+/// budget, key, and window.
+pub fn record_spend(record: SpendRecord) {
+	if let Some(store) = REQUEST_LOG_STORE.get()
+		&& let Err(err) = store.tx.send(LogStoreMsg::Spend(record))
+	{
+		warn!(target: "request", ?err, "failed to enqueue budget spend record");
+	}
+}
+
+/// Load the persisted spend for a budget, key, and window. Returns 0 if nothing was recorded.
+pub async fn load_spend(request: LoadSpendRequest) -> anyhow::Result<f64> {
+	let store = REQUEST_LOG_STORE
+		.get()
+		.ok_or_else(|| anyhow::anyhow!("request log database is not configured"))?;
+	store
+		.request(|tx| LogStoreMsg::LoadSpend { request, tx })
+		.await
+}
+
 pub async fn tail(request: TailRequest) -> anyhow::Result<TailResponse> {
 	let store = REQUEST_LOG_STORE
 		.get()
@@ -193,6 +213,11 @@ const LOG_STORE_BATCH_SIZE: usize = 64;
 #[allow(clippy::large_enum_variant)] // The StoredRequestLog, which is used 99.9% of the time, is the large one
 enum LogStoreMsg {
 	Record(StoredRequestLog),
+	Spend(SpendRecord),
+	LoadSpend {
+		request: LoadSpendRequest,
+		tx: QueryResponse<f64>,
+	},
 	Search {
 		request: SearchRequest,
 		tx: QueryResponse<SearchResponse>,
@@ -336,6 +361,16 @@ async fn process_log_store_msg(
 			batch.push(record);
 			false
 		},
+		LogStoreMsg::Spend(record) => {
+			if let Err(err) = backend.add_spend(&record).await {
+				warn!(target: "request", ?err, budget = %record.budget, "failed to persist budget spend");
+			}
+			false
+		},
+		LogStoreMsg::LoadSpend { request, tx } => {
+			let _ = tx.send(backend.load_spend(request).await);
+			false
+		},
 		LogStoreMsg::Search { request, tx } => {
 			flush_log_store_batch(backend, batch).await;
 			let _ = tx.send(backend.search(request).await);
@@ -402,6 +437,21 @@ pub struct StoredRequestLogPayload {
 	pub response_completion_json: Option<Value>,
 }
 
+#[derive(Clone, Debug)]
+pub struct SpendRecord {
+	pub budget: String,
+	pub key: String,
+	pub window_start: DateTime<Utc>,
+	pub cost: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct LoadSpendRequest {
+	pub budget: String,
+	pub key: String,
+	pub window_start: DateTime<Utc>,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct TimeRange {
@@ -699,6 +749,20 @@ impl Backend {
 		}
 	}
 
+	async fn add_spend(&self, record: &SpendRecord) -> anyhow::Result<()> {
+		match self {
+			Self::Sqlite(store) => store.add_spend(record).await,
+			Self::Postgres(store) => store.add_spend(record).await,
+		}
+	}
+
+	async fn load_spend(&self, request: LoadSpendRequest) -> anyhow::Result<f64> {
+		match self {
+			Self::Sqlite(store) => store.load_spend(request).await,
+			Self::Postgres(store) => store.load_spend(request).await,
+		}
+	}
+
 	async fn search(&self, request: SearchRequest) -> anyhow::Result<SearchResponse> {
 		match self {
 			Self::Sqlite(store) => store.search(request).await,
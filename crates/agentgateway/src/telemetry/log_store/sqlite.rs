@@ -9,9 +9,10 @@ use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool};
 use super::{
 	AnalyticsGroup, AnalyticsSummaryRequest, AnalyticsSummaryResponse, AnalyticsTimeBucket,
 	GenAiEntry, GetRequest, GetResponse, GroupBy, GroupByField, INSERT_LOG_PREFIX,
-	INSERT_PAYLOAD_PREFIX, LogEntry, LogFilters, PayloadEntry, SearchRequest, SearchResponse,
-	StoredRequestLog, TailRequest, TailResponse, TimeRange, UsageEntry, analytics_window,
-	attr_filter_values, decode_cursor, encode_cursor, limit, promoted_attribute_column,
+	INSERT_PAYLOAD_PREFIX, LoadSpendRequest, LogEntry, LogFilters, PayloadEntry, SearchRequest,
+	SearchResponse, SpendRecord, StoredRequestLog, TailRequest, TailResponse, TimeRange, UsageEntry,
+	analytics_window, attr_filter_values, decode_cursor, encode_cursor, limit,
+	promoted_attribute_column,
 };
 
 pub struct SqliteLogStore {
@@ -65,6 +66,27 @@ impl SqliteLogStore {
 		Ok(())
 	}
 
+	pub async fn add_spend(&self, record: &SpendRecord) -> anyhow::Result<()> {
+		sqlx::query(UPSERT_BUDGET_SPEND)
+			.bind(&record.budget)
+			.bind(&record.key)
+			.bind(record.window_start)
+			.bind(record.cost)
+			.execute(&self.pool)
+			.await?;
+		Ok(())
+	}
+
+	pub async fn load_spend(&self, request: LoadSpendRequest) -> anyhow::Result<f64> {
+		let spent: Option<f64> = sqlx::query_scalar(SELECT_BUDGET_SPEND)
+			.bind(request.budget)
+			.bind(request.key)
+			.bind(request.window_start)
+			.fetch_optional(&self.pool)
+			.await?;
+		Ok(spent.unwrap_or_default())
+	}
+
 	pub async fn search(&self, request: SearchRequest) -> anyhow::Result<SearchResponse> {
 		let limit = limit(request.limit);
 		let mut qb = QueryBuilder::<Sqlite>::new(format!("{SELECT_LOGS} WHERE 1=1"));
@@ -561,6 +583,14 @@ CREATE TABLE IF NOT EXISTS request_log_payloads (
 	response_completion_json TEXT CHECK (response_completion_json IS NULL OR json_valid(response_completion_json))
 );
 
+CREATE TABLE IF NOT EXISTS budget_spend (
+	budget TEXT NOT NULL,
+	budget_key TEXT NOT NULL,
+	window_start TEXT NOT NULL,
+	spent REAL NOT NULL,
+	PRIMARY KEY (budget, budget_key, window_start)
+);
+
 CREATE INDEX IF NOT EXISTS idx_request_logs_completed_at ON request_logs(completed_at DESC, id DESC);
 CREATE INDEX IF NOT EXISTS idx_request_logs_usage_completed_at ON request_logs(completed_at DESC, total_tokens, cost);
 CREATE INDEX IF NOT EXISTS idx_request_logs_http_status_completed_at ON request_logs(http_status, completed_at DESC);
@@ -595,3 +625,12 @@ FROM request_logs
 LEFT JOIN request_log_payloads ON request_logs.id = request_log_payloads.log_id
 WHERE request_logs.id = ?
 "#;
+
+const UPSERT_BUDGET_SPEND: &str = r#"
+INSERT INTO budget_spend (budget, budget_key, window_start, spent) VALUES (?, ?, ?, ?)
+ON CONFLICT (budget, budget_key, window_start) DO UPDATE SET spent = budget_spend.spent + excluded.spent
+"#;
+
+const SELECT_BUDGET_SPEND: &str = r#"
+SELECT spent FROM budget_spend WHERE budget = ? AND budget_key = ? AND window_start = ?
+"#;
@@ -7,6 +7,7 @@ use frozen_collections::FzHashSet;
 use prometheus_client::encoding::EncodeLabelSet;
 use prometheus_client::metrics::counter;
 use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
 use prometheus_client::metrics::histogram::Histogram as PromHistogram;
 use prometheus_client::metrics::info::Info;
 use prometheus_client::registry::{Metric, Registry, Unit};
@@ -51,6 +52,66 @@ pub struct GuardrailLabels {
 	pub action: GuardrailAction,
 }
 
+#[derive(
+	Copy, Clone, Hash, Debug, PartialEq, Eq, prometheus_client::encoding::EncodeLabelValue, Default,
+)]
+pub enum StructuredOutputResult {
+	/// The first response matched the schema.
+	#[default]
+	Valid,
+	/// A response matched the schema after one or more repair attempts.
+	Repaired,
+	/// No response matched the schema within the allowed repair attempts.
+	Invalid,
+}
+
+#[derive(Clone, Hash, Default, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct StructuredOutputLabels {
+	pub gen_ai_request_model: DefaultedUnknown<RichStrng>,
+	pub result: StructuredOutputResult,
+}
+
+#[derive(
+	Copy, Clone, Hash, Debug, PartialEq, Eq, prometheus_client::encoding::EncodeLabelValue, Default,
+)]
+pub enum McpToolValidationPhase {
+	/// Tool call arguments, checked against the tool's input schema.
+	#[default]
+	Arguments,
+	/// Structured content of tool results, checked against the tool's output schema.
+	StructuredContent,
+}
+
+#[derive(
+	Copy, Clone, Hash, Debug, PartialEq, Eq, prometheus_client::encoding::EncodeLabelValue, Default,
+)]
+pub enum McpToolValidationResult {
+	#[default]
+	Valid,
+	Invalid,
+}
+
+#[derive(Clone, Hash, Default, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct McpToolValidationLabels {
+	pub server: DefaultedUnknown<RichStrng>,
+	pub tool: DefaultedUnknown<RichStrng>,
+	pub phase: McpToolValidationPhase,
+	pub result: McpToolValidationResult,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct McpCacheLabels {
+	pub server: DefaultedUnknown<RichStrng>,
+	pub method: DefaultedUnknown<RichStrng>,
+	pub result: crate::llm::policy::response_cache::ResponseCacheStatus,
+}
+
+#[derive(Clone, Hash, Default, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct PromptTemplateLabels {
+	pub template: DefaultedUnknown<RichStrng>,
+	pub version: DefaultedUnknown<RichStrng>,
+}
+
 #[derive(Clone, Hash, Default, Debug, PartialEq, Eq, EncodeLabelSet)]
 pub struct MinimalHTTPLabels {
 	pub backend: DefaultedUnknown<RichStrng>,
@@ -118,6 +179,11 @@ pub struct CostCatalogLookupLabels {
 	pub common: EncodeArc<GenAILabels>,
 }
 
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct SemanticCacheLabels {
+	pub result: crate::llm::policy::response_cache::ResponseCacheStatus,
+}
+
 #[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
 pub struct MCPCall {
 	pub method: DefaultedUnknown<RichStrng>,
@@ -175,6 +241,7 @@ pub enum OutboundCallSubtype {
 	Guardrail,
 	RateLimit,
 	Oidc,
+	Embeddings,
 }
 
 #[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
@@ -201,6 +268,10 @@ pub struct Metrics {
 	pub response_bytes: Family<HTTPLabels, counter::Counter>,
 
 	pub mcp_requests: Family<MCPCall, counter::Counter>,
+	// tool calls and results checked against the tool's declared schemas (valid/invalid)
+	pub mcp_tool_validations: Family<McpToolValidationLabels, counter::Counter>,
+	// MCP list and resource read lookups in the mcpCache policy (hit/miss)
+	pub mcp_cache_lookups: Family<McpCacheLabels, counter::Counter>,
 
 	pub gen_ai_token_usage: Histogram<GenAILabelsTokenUsage>,
 	pub gen_ai_cost: Family<GenAILabels, counter::Counter<f64>>,
@@ -220,8 +291,20 @@ pub struct Metrics {
 	// metrics for guardrail checks (allow/mask/reject) for request/response
 	pub guardrail_checks: Family<GuardrailLabels, counter::Counter>,
 
+	// structured output checks per model, once per client request (valid/repaired/invalid)
+	pub structured_output_checks: Family<StructuredOutputLabels, counter::Counter>,
+
 	pub cost_catalog_lookups: Family<CostCatalogLookupLabels, counter::Counter>,
 
+	// prompt templates rendered into requests, by template id and version
+	pub prompt_template_renders: Family<PromptTemplateLabels, counter::Counter>,
+
+	// similarity of the closest cached request for each semantic response cache lookup
+	pub semantic_cache_similarity: Histogram<SemanticCacheLabels>,
+
+	// number of live per-key buckets across all keyed local rate limits
+	pub local_rate_limit_keys: Gauge,
+
 	// metrics for request retries
 	pub retries: Counter,
 }
@@ -364,6 +447,15 @@ impl Metrics {
 				);
 				m
 			},
+			structured_output_checks: {
+				let m = Family::<StructuredOutputLabels, _>::default();
+				registry.register(
+					"structured_output_checks",
+					"Total number of responses checked against a requested output schema, by result",
+					m.clone(),
+				);
+				m
+			},
 			cost_catalog_lookups: {
 				let m = Family::<CostCatalogLookupLabels, _>::default();
 				registry.register(
@@ -373,6 +465,35 @@ impl Metrics {
 				);
 				m
 			},
+			prompt_template_renders: {
+				let m = Family::<PromptTemplateLabels, _>::default();
+				registry.register(
+					"prompt_template_renders",
+					"Total number of prompt templates rendered into requests",
+					m.clone(),
+				);
+				m
+			},
+			semantic_cache_similarity: {
+				let m = Family::<SemanticCacheLabels, _>::new_with_constructor(move || {
+					PromHistogram::new(SIMILARITY_BUCKETS)
+				});
+				registry.register(
+					"semantic_cache_similarity",
+					"Cosine similarity of the closest cached request for semantic response cache lookups",
+					m.clone(),
+				);
+				m
+			},
+			local_rate_limit_keys: {
+				let m = Gauge::default();
+				registry.register(
+					"local_rate_limit_keys",
+					"Number of live per-key buckets held by keyed local rate limits",
+					m.clone(),
+				);
+				m
+			},
 			downstream_connection: build(
 				&mut registry,
 				"downstream_connections",
@@ -384,6 +505,24 @@ impl Metrics {
 				"mcp_requests",
 				"Total number of MCP tool calls",
 			),
+			mcp_tool_validations: {
+				let m = Family::<McpToolValidationLabels, _>::default();
+				registry.register(
+					"mcp_tool_validations",
+					"Total number of MCP tool call arguments and results checked against the tool's declared schema, by result",
+					m.clone(),
+				);
+				m
+			},
+			mcp_cache_lookups: {
+				let m = Family::<McpCacheLabels, _>::default();
+				registry.register(
+					"mcp_cache_lookups",
+					"Total number of MCP list and resource read lookups in the MCP cache, by result",
+					m.clone(),
+				);
+				m
+			},
 
 			gen_ai_token_usage,
 			gen_ai_cost,
@@ -555,6 +694,11 @@ const PROCESSING_DURATION_BUCKETS: [f64; 10] = [
 	0.25,    // 250ms
 ];
 
+// Cosine similarity, concentrated near the top of the range where cache thresholds are set.
+const SIMILARITY_BUCKETS: [f64; 12] = [
+	0.5, 0.6, 0.7, 0.75, 0.8, 0.85, 0.9, 0.925, 0.95, 0.975, 0.99, 1.0,
+];
+
 // https://opentelemetry.io/docs/specs/semconv/gen-ai/gen-ai-metrics/#metric-gen_aiservertime_per_output_token
 // NOTE: the spec has SHOULD, but is not smart enough to handle the faster LLMs.
 // We have added 0.001 (1000 TPS)
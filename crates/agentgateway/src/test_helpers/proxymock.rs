@@ -174,6 +174,7 @@ pub fn llm_named_provider(
 		path_override: None,
 		path_prefix: None,
 		tokenize,
+		tokenizers: Default::default(),
 		policies: None,
 	}
 }
@@ -210,12 +211,16 @@ pub fn custom_llm_backend_with_formats(
 		path_override: None,
 		path_prefix: None,
 		tokenize: false,
+		tokenizers: Default::default(),
 		inline_policies: vec![],
 	};
 	let providers = EndpointSet::new(vec![vec![(provider.name.clone(), provider)]]);
 	Backend::AI(
 		ResourceName::new(name.into(), "".into()),
-		AIBackend { providers },
+		AIBackend {
+			providers,
+			failover: None,
+		},
 	)
 	.into()
 }
@@ -715,6 +720,7 @@ impl TestBind {
 							path: "/sse".to_string(),
 						})
 					},
+					tools: Vec::new(),
 				})],
 				stateful,
 				always_use_prefix: false,
@@ -778,6 +784,7 @@ impl TestBind {
 									path: "/sse".to_string(),
 								})
 							},
+							tools: Vec::new(),
 						})
 					})
 					.collect_vec(),
@@ -1525,6 +1525,9 @@ pub struct McpTarget {
 	pub name: McpTargetName,
 	#[serde(flatten)]
 	pub spec: McpTargetSpec,
+	/// Overrides that curate the tools exposed by this target.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub tools: Vec<crate::mcp::VirtualTool>,
 }
 
 pub type McpTargetName = Strng;
@@ -2486,6 +2489,10 @@ pub enum BackendTrafficPolicy {
 	McpAuthorization(McpAuthorization),
 	McpAuthentication(McpAuthentication),
 	McpGuardrails(Arc<crate::mcp::guardrails::McpGuardrails>),
+	McpToolValidation(crate::mcp::McpToolValidation),
+	McpRateLimit(Arc<Vec<crate::mcp::McpRateLimit>>),
+	McpCache(Arc<crate::mcp::McpCache>),
+	McpToolCatalog(crate::mcp::McpToolCatalog),
 	A2a(A2aPolicy),
 	#[serde(rename = "http")]
 	HTTP(backend::HTTP),
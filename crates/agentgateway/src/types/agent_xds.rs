@@ -547,6 +547,9 @@ fn convert_route_type(proto_rt: i32, diagnostics: &mut Diagnostics) -> llm::Rout
 		Ok(ProtoRT::Embeddings) => llm::RouteType::Embeddings,
 		Ok(ProtoRT::Realtime) => llm::RouteType::Realtime,
 		Ok(ProtoRT::Rerank) => llm::RouteType::Rerank,
+		Ok(ProtoRT::ImageGeneration) => llm::RouteType::ImageGeneration,
+		Ok(ProtoRT::AudioTranscription) => llm::RouteType::AudioTranscription,
+		Ok(ProtoRT::AudioSpeech) => llm::RouteType::AudioSpeech,
 		Err(_) => {
 			diagnostics.add_warning(format!(
 				"unknown proto RouteType value {}, defaulting to Completions",
@@ -694,6 +697,9 @@ fn convert_provider_format(
 		Ok(ProtoFormat::AnthropicTokenCount) => Ok(llm::custom::ProviderFormat::AnthropicTokenCount),
 		Ok(ProtoFormat::Realtime) => Ok(llm::custom::ProviderFormat::Realtime),
 		Ok(ProtoFormat::Rerank) => Ok(llm::custom::ProviderFormat::Rerank),
+		Ok(ProtoFormat::ImageGeneration) => Ok(llm::custom::ProviderFormat::ImageGeneration),
+		Ok(ProtoFormat::AudioTranscription) => Ok(llm::custom::ProviderFormat::AudioTranscription),
+		Ok(ProtoFormat::AudioSpeech) => Ok(llm::custom::ProviderFormat::AudioSpeech),
 		Err(_) => Err(ProtoError::Generic(format!(
 			"AI backend custom provider at index {provider_idx} has unknown supported format value {proto_format}"
 		))),
@@ -903,6 +909,7 @@ fn convert_backend_ai_policy(
 			streaming,
 			request,
 			response: response.collect_vec(),
+			realtime_block: Default::default(),
 		})
 	});
 
@@ -938,18 +945,25 @@ fn convert_backend_ai_policy(
 			)
 		},
 		prompts: ai.prompts.as_ref().map(convert_prompt_enrichment),
+		prompt_templates: None,
 		model_aliases: ai
 			.model_aliases
 			.iter()
 			.map(|(k, v)| (strng::new(k), strng::new(v)))
 			.collect(),
 		wildcard_patterns: Arc::new(Vec::new()), // Will be populated by compile_model_alias_patterns()
+		allowed_models: Vec::new(),
 		prompt_caching: ai.prompt_caching.as_ref().map(convert_prompt_caching),
+		response_cache: None,
 		routes: ai
 			.routes
 			.iter()
 			.map(|(k, v)| (strng::new(k), convert_route_type(*v, diagnostics)))
 			.collect(),
+		budgets: Vec::new(),
+		structured_output: None,
+		context_window: None,
+		shadow: None,
 	};
 
 	// Compile wildcard patterns from model_aliases
@@ -1419,6 +1433,7 @@ pub(crate) fn backend_with_policies_from_proto(
 						name: provider_name.clone(),
 						provider,
 						tokenize: false,
+						tokenizers: Default::default(),
 						provider_backend,
 						host_override,
 						path_override: provider_config.path_override.as_ref().map(strng::new),
@@ -1440,7 +1455,13 @@ pub(crate) fn backend_with_policies_from_proto(
 			}
 
 			let es = crate::types::loadbalancer::EndpointSet::new(provider_groups);
-			Backend::AI(name.into(), AIBackend { providers: es })
+			Backend::AI(
+				name.into(),
+				AIBackend {
+					providers: es,
+					failover: None,
+				},
+			)
 		},
 		Some(proto::agent::backend::Kind::Mcp(m)) => Backend::MCP(
 			name.into(),
@@ -1509,6 +1530,7 @@ fn mcp_target_from_proto(
 				})
 			},
 		},
+		tools: Vec::new(),
 	})
 }
 
@@ -1924,6 +1946,8 @@ fn traffic_policy_from_proto(
 					tps::local_rate_limit::Type::Request => http::localratelimit::RateLimitType::Requests,
 					tps::local_rate_limit::Type::Token => http::localratelimit::RateLimitType::Tokens,
 				},
+				key: None,
+				max_keys: http::localratelimit::DEFAULT_MAX_KEYS,
 			};
 			// Yes, its single with a vec, because we originally supported multiple rate limit policies before
 			// we added the generic multiple support.
@@ -3292,6 +3316,7 @@ fn convert_regex_rules(
 			llm::policy::Action::Mask
 		},
 		Some(ActionKind::Reject) => llm::policy::Action::Reject,
+		Some(ActionKind::Tokenize) => llm::policy::Action::Tokenize,
 	};
 	let rules = rr
 		.rules
@@ -3316,6 +3341,30 @@ fn convert_regex_rules(
 							proto::agent::backend_policy_spec::ai::BuiltinRegexRule::CaSin => {
 								llm::policy::Builtin::CaSin
 							},
+							proto::agent::backend_policy_spec::ai::BuiltinRegexRule::Iban => {
+								llm::policy::Builtin::Iban
+							},
+							proto::agent::backend_policy_spec::ai::BuiltinRegexRule::SwiftCode => {
+								llm::policy::Builtin::SwiftCode
+							},
+							proto::agent::backend_policy_spec::ai::BuiltinRegexRule::IpAddress => {
+								llm::policy::Builtin::IpAddress
+							},
+							proto::agent::backend_policy_spec::ai::BuiltinRegexRule::UkNino => {
+								llm::policy::Builtin::UkNino
+							},
+							proto::agent::backend_policy_spec::ai::BuiltinRegexRule::Passport => {
+								llm::policy::Builtin::Passport
+							},
+							proto::agent::backend_policy_spec::ai::BuiltinRegexRule::ApiKey => {
+								llm::policy::Builtin::ApiKey
+							},
+							proto::agent::backend_policy_spec::ai::BuiltinRegexRule::PrivateKey => {
+								llm::policy::Builtin::PrivateKey
+							},
+							proto::agent::backend_policy_spec::ai::BuiltinRegexRule::Jwt => {
+								llm::policy::Builtin::Jwt
+							},
 							_ => {
 								diagnostics.add_warning(format!("unknown builtin regex rule value {b}; skipping"));
 								return None;
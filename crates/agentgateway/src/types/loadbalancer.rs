@@ -772,6 +772,8 @@ impl<T: Clone + Sync + Send + 'static> EndpointSet<T> {
 						return false;
 					}
 					ep.info.evicted_until.store(None);
+					// The limit that got the endpoint evicted has reset by now.
+					ep.info.rate_limit.store(None);
 					if let Some(h) = restore_health {
 						// Health scoring assumes normalized values in [0.0, 1.0].
 						ep.info.health.set(h.clamp(0.0, 1.0));
@@ -858,6 +860,29 @@ pub struct EndpointInfo {
 	#[serde(with = "serde_instant_option")]
 	/// evicted_until is the time at which the endpoint will be evicted.
 	evicted_until: AtomicOption<Instant>,
+	/// rate_limit is the rate limit headroom last reported by the endpoint, until it resets.
+	#[serde(serialize_with = "serialize_rate_limit")]
+	rate_limit: AtomicOption<RateLimit>,
+}
+
+/// Rate limit headroom reported by an endpoint, valid until the limit resets.
+#[derive(Debug)]
+struct RateLimit {
+	remaining: f64,
+	until: Instant,
+}
+
+/// How long reported rate limit headroom is trusted when the endpoint does not say when it resets.
+const RATE_LIMIT_DEFAULT_RESET: Duration = Duration::from_secs(60);
+
+fn serialize_rate_limit<S: Serializer>(
+	t: &AtomicOption<RateLimit>,
+	serializer: S,
+) -> Result<S::Ok, S::Error> {
+	match t.load().as_deref() {
+		Some(rl) if rl.until > Instant::now() => serializer.serialize_some(&rl.remaining),
+		_ => serializer.serialize_none(),
+	}
 }
 
 impl Default for EndpointInfo {
@@ -871,6 +896,7 @@ impl Default for EndpointInfo {
 			consecutive_failures: Default::default(),
 			times_ejected: Default::default(),
 			evicted_until: Arc::new(Default::default()),
+			rate_limit: Arc::new(Default::default()),
 		}
 	}
 }
@@ -889,11 +915,19 @@ impl EndpointInfo {
 	pub fn times_ejected(&self) -> u64 {
 		self.times_ejected.load(AtomicOrdering::Relaxed)
 	}
+	/// Fraction (0.0–1.0) of its rate limit the endpoint last reported remaining, until the limit
+	/// resets.
+	pub fn rate_limit_remaining(&self) -> f64 {
+		match self.rate_limit.load().as_deref() {
+			Some(rl) if rl.until > Instant::now() => rl.remaining,
+			_ => 1.0,
+		}
+	}
 	// Todo: fine-tune the algorithm here
 	pub fn score(&self) -> f64 {
 		let latency_penalty =
 			self.request_latency.load() * (1.0 + self.pending_requests.countf() * 0.1);
-		self.health.load() / (1.0 + latency_penalty)
+		self.health.load() * self.rate_limit_remaining() / (1.0 + latency_penalty)
 	}
 	fn start_request(
 		self: &Arc<Self>,
@@ -994,36 +1028,60 @@ impl ActiveHandle {
 				.consecutive_failures
 				.fetch_add(1, AtomicOrdering::Relaxed);
 		};
-		if let Some(eviction_time) = eviction_time {
-			let time = Instant::now() + eviction_time;
-			let prev = self
+		// Only count an ejection when this request actually starts a new
+		// eviction window. Failures of in-flight requests during an existing
+		// window are no-ops here, so bumping the counter would inflate the
+		// ejection-duration multiplier without extending the eviction.
+		if let Some(eviction_time) = eviction_time
+			&& self.evict(eviction_time, restore_health)
+		{
+			self
 				.info
-				.evicted_until
-				.compare_and_swap(&None::<Arc<_>>, Some(Arc::new(time)));
-			if prev.is_none() {
-				// Only count an ejection when this request actually starts a new
-				// eviction window. Failures of in-flight requests during an existing
-				// window are no-ops here, so bumping the counter would inflate the
-				// ejection-duration multiplier without extending the eviction.
-				self
-					.info
-					.times_ejected
-					.fetch_add(1, AtomicOrdering::Relaxed);
-				self.eviction_starter.start();
-				let mut tx = self.tx.clone();
-				let key = self.key.clone();
-				tokio::spawn(async move {
-					let _ = tx
-						.send(EvictionEvent::Evict {
-							key,
-							until: time,
-							restore_health,
-						})
-						.await;
-				});
-			}
+				.times_ejected
+				.fetch_add(1, AtomicOrdering::Relaxed);
 		}
 	}
+
+	/// Record the rate limit headroom the endpoint reported on a response. The endpoint's score is
+	/// scaled by the fraction remaining until the limit resets, and an endpoint with none left is
+	/// evicted until then, so traffic moves elsewhere before it starts returning 429s.
+	pub fn record_rate_limit(&self, remaining: f64, reset: Option<Duration>) {
+		let remaining = remaining.clamp(0.0, 1.0);
+		let reset = reset.unwrap_or(RATE_LIMIT_DEFAULT_RESET);
+		self.info.rate_limit.store(Some(Arc::new(RateLimit {
+			remaining,
+			until: Instant::now() + reset,
+		})));
+		if remaining == 0.0 && !reset.is_zero() {
+			self.evict(reset, None);
+		}
+	}
+
+	/// Evict the endpoint for `eviction_time`, unless it is already evicted. Returns whether a new
+	/// eviction window was started.
+	fn evict(&self, eviction_time: Duration, restore_health: Option<f64>) -> bool {
+		let time = Instant::now() + eviction_time;
+		let prev = self
+			.info
+			.evicted_until
+			.compare_and_swap(&None::<Arc<_>>, Some(Arc::new(time)));
+		if prev.is_some() {
+			return false;
+		}
+		self.eviction_starter.start();
+		let mut tx = self.tx.clone();
+		let key = self.key.clone();
+		tokio::spawn(async move {
+			let _ = tx
+				.send(EvictionEvent::Evict {
+					key,
+					until: time,
+					restore_health,
+				})
+				.await;
+		});
+		true
+	}
 }
 
 impl ActiveCounter {
@@ -1202,6 +1260,23 @@ mod tests {
 		assert_eq!(info.times_ejected(), 0);
 	}
 
+	#[tokio::test]
+	async fn rate_limit_headroom_scales_score() {
+		let key: Strng = "ep1".into();
+		let eps = EndpointSet::new(vec![vec![(key.clone(), "backend1")]]);
+		let info = eps.best_bucket().active.get(&key).unwrap().info.clone();
+		let full = info.score();
+
+		let handle = eps.start_request(key.clone(), &info);
+		handle.record_rate_limit(0.25, Some(Duration::from_secs(60)));
+		assert!((info.score() - full * 0.25).abs() < 1e-10);
+		assert!(info.evicted_until.load().is_none());
+
+		// Headroom is no longer trusted once the limit has reset.
+		handle.record_rate_limit(0.25, Some(Duration::ZERO));
+		assert_eq!(info.rate_limit_remaining(), 1.0);
+	}
+
 	// --- EndpointSet eviction integration ---
 
 	#[tokio::test]
@@ -1377,6 +1452,30 @@ mod tests {
 		assert_eq!(*group.active.get(&key).unwrap().endpoint, "backend2");
 	}
 
+	#[tokio::test]
+	async fn endpoint_set_rate_limit_exhausted_evicts_until_reset() {
+		tokio::time::pause();
+		let key: Strng = "ep1".into();
+		let eps = EndpointSet::new(vec![vec![(key.clone(), "backend1")]]);
+		let info = eps.best_bucket().active.get(&key).unwrap().info.clone();
+
+		let handle = eps.start_request(key.clone(), &info);
+		handle.record_rate_limit(0.0, Some(Duration::from_millis(100)));
+
+		yield_until(|| eps.best_bucket().rejected.contains_key(&key))
+			.await
+			.expect("endpoint should be evicted");
+		// Running out of rate limit is not a failure.
+		assert_eq!(info.times_ejected(), 0);
+		assert_eq!(info.consecutive_failures(), 0);
+
+		tokio::time::advance(Duration::from_millis(150)).await;
+		yield_until(|| eps.best_bucket().active.contains_key(&key))
+			.await
+			.expect("endpoint should be unevicted");
+		assert_eq!(info.rate_limit_remaining(), 1.0);
+	}
+
 	async fn yield_until(mut f: impl FnMut() -> bool) -> Result<(), ()> {
 		for _ in 0..100 {
 			if f() {
@@ -19,6 +19,7 @@ use crate::http::backendtls::LocalBackendTLS;
 use crate::http::transformation_cel::{LocalTransformationConfig, Transformation};
 use crate::http::{filters, health, retry, timeout, transformation_cel};
 use crate::llm::policy::{PromptCachingConfig, PromptGuard};
+use crate::llm::tokenizer::Tokenizers;
 use crate::llm::{AIBackend, AIProvider, NamedAIProvider, anthropic, copilot, custom, openai};
 use crate::mcp::{FailureMode, McpAuthorization};
 use crate::store::{LocalWorkload, RequestPolicy};
@@ -387,6 +388,16 @@ pub struct LocalLLMVirtualModelRouting {
 	/// in order until the best match is found.
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	conditional: Option<LocalLLMConditionalRouting>,
+	/// lowestCost selects the cheapest target model for the estimated request size, priced from the
+	/// model catalog. Targets the catalog cannot price are only selected if no target can be priced.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	lowest_cost: Option<LocalLLMRankedRouting>,
+	/// lowestLatency selects the target model with the lowest observed time to first token, or request
+	/// duration for non-streaming requests. Unsuccessful responses, including rate limits, count as
+	/// slow, so failing targets are avoided. A small share of requests is sent to other targets to keep
+	/// their latency up to date.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	lowest_latency: Option<LocalLLMRankedRouting>,
 }
 
 #[apply(schema_de!)]
@@ -417,6 +428,24 @@ pub struct LocalLLMFailoverTarget {
 	priority: usize,
 }
 
+#[apply(schema_de!)]
+pub struct LocalLLMRankedRouting {
+	/// targets are existing model names or names matched by wildcard model entries.
+	targets: Vec<LocalLLMRankedTarget>,
+	/// minTier is the lowest quality tier a target may have to be selected.
+	#[serde(default)]
+	min_tier: u32,
+}
+
+#[apply(schema_de!)]
+pub struct LocalLLMRankedTarget {
+	/// model is resolved against llm.models using the same wildcard matching as client requests.
+	model: String,
+	/// tier is the quality tier of the model. Higher values are better.
+	#[serde(default)]
+	tier: u32,
+}
+
 #[apply(schema_de!)]
 pub struct LocalLLMConditionalRouting {
 	/// targets are evaluated in order. The first matching condition selects the model.
@@ -795,6 +824,10 @@ pub struct LocalLLMParams {
 	/// Whether to tokenize the request before forwarding it upstream.
 	#[serde(default)]
 	tokenize: bool,
+	/// How request tokens are counted for each model, when `tokenize` is set. Models matching no
+	/// rule use the built-in tokenizer for their model family.
+	#[serde(default)]
+	tokenizers: Tokenizers,
 }
 
 impl LocalLLMModels {
@@ -815,6 +848,7 @@ impl LocalLLMModels {
 			path_override: None,
 			path_prefix: None,
 			tokenize: false,
+			tokenizers,
 		} = std::mem::take(&mut self.params)
 		else {
 			bail!(
@@ -823,6 +857,13 @@ impl LocalLLMModels {
 				provider.name
 			);
 		};
+		if !tokenizers.is_empty() {
+			bail!(
+				"model {} references provider {} and can only set params.model",
+				self.name,
+				provider.name
+			);
+		}
 		if matches!(&provider.provider, LocalModelAIProvider::Reference(_)) {
 			bail!(
 				"llm.providers.{} cannot reference another provider",
@@ -1233,7 +1274,12 @@ pub enum LocalBackend {
 #[allow(clippy::large_enum_variant)] // Size is not sensitive for local config
 pub enum LocalAIBackend {
 	Provider(LocalNamedAIProvider),
-	Groups { groups: Vec<LocalAIProviders> },
+	Groups {
+		groups: Vec<LocalAIProviders>,
+		/// Retry failed requests against the next provider, in priority order.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		failover: Option<llm::Failover>,
+	},
 }
 
 // Custom impl to avoid terrible 'not match any variant of untagged' errors.
@@ -1247,11 +1293,12 @@ impl<'de> Deserialize<'de> for LocalAIBackend {
 				let v: serde_json::Value = map.deserialize()?;
 
 				if let serde_json::Value::Object(m) = &v
-					&& m.len() == 1
-					&& let Some(g) = m.get("groups")
+					&& m.contains_key("groups")
 				{
+					let g = LocalAIGroups::deserialize(&v).map_err(serde::de::Error::custom)?;
 					Ok(LocalAIBackend::Groups {
-						groups: Vec::<LocalAIProviders>::deserialize(g).map_err(serde::de::Error::custom)?,
+						groups: g.groups,
+						failover: g.failover,
 					})
 				} else {
 					Ok(LocalAIBackend::Provider(
@@ -1263,6 +1310,14 @@ impl<'de> Deserialize<'de> for LocalAIBackend {
 	}
 }
 
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct LocalAIGroups {
+	groups: Vec<LocalAIProviders>,
+	#[serde(default)]
+	failover: Option<llm::Failover>,
+}
+
 #[apply(schema_de!)]
 pub struct LocalAIProviders {
 	providers: Vec<LocalNamedAIProvider>,
@@ -1283,17 +1338,22 @@ pub struct LocalNamedAIProvider {
 	/// This comes with the cost of an expensive operation.
 	#[serde(default)]
 	pub tokenize: bool,
+	/// How request tokens are counted for each model, when `tokenize` is set. Models matching no
+	/// rule use the built-in tokenizer for their model family.
+	#[serde(default)]
+	pub tokenizers: Tokenizers,
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	pub policies: Option<LocalBackendPolicies>,
 }
 
 impl LocalAIBackend {
 	pub fn translate(self) -> anyhow::Result<AIBackend> {
-		let providers = match self {
-			LocalAIBackend::Provider(p) => {
-				vec![vec![p]]
-			},
-			LocalAIBackend::Groups { groups } => groups.into_iter().map(|g| g.providers).collect_vec(),
+		let (providers, failover) = match self {
+			LocalAIBackend::Provider(p) => (vec![vec![p]], None),
+			LocalAIBackend::Groups { groups, failover } => (
+				groups.into_iter().map(|g| g.providers).collect_vec(),
+				failover,
+			),
 		};
 		let mut ep_groups = vec![];
 		for g in providers {
@@ -1318,6 +1378,7 @@ impl LocalAIBackend {
 						path_override: p.path_override,
 						path_prefix: p.path_prefix,
 						tokenize: p.tokenize,
+						tokenizers: p.tokenizers,
 						inline_policies: policies,
 					},
 				));
@@ -1325,7 +1386,10 @@ impl LocalAIBackend {
 			ep_groups.push(group);
 		}
 		let es = types::loadbalancer::EndpointSet::new(ep_groups);
-		Ok(AIBackend { providers: es })
+		Ok(AIBackend {
+			providers: es,
+			failover,
+		})
 	}
 }
 
@@ -1340,6 +1404,10 @@ impl LocalBackend {
 				simple: p.simple,
 				mcp_authorization: p.mcp_authorization,
 				mcp_guardrails: p.mcp_guardrails,
+				mcp_tool_validation: p.mcp_tool_validation,
+				mcp_rate_limit: p.mcp_rate_limit,
+				mcp_cache: p.mcp_cache,
+				mcp_tool_catalog: p.mcp_tool_catalog,
 				a2a: None,
 				inference_routing: None,
 				ai: None,
@@ -1437,6 +1505,7 @@ impl LocalBackend {
 					let t = McpTarget {
 						name: t.name.clone(),
 						spec,
+						tools: t.tools.clone(),
 					};
 					targets.push(Arc::new(t));
 				}
@@ -1533,6 +1602,9 @@ pub struct LocalMcpTarget {
 	pub spec: LocalMcpTargetSpec,
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	pub policies: Option<MCPLocalBackendPolicies>,
+	/// Overrides that curate the tools exposed by this target.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub tools: Vec<crate::mcp::VirtualTool>,
 }
 
 #[derive(Debug, Clone)]
@@ -1956,6 +2028,18 @@ pub struct MCPLocalBackendPolicies {
 	/// External MCP policy processors.
 	#[serde(default)]
 	pub mcp_guardrails: Option<LocalMcpGuardrails>,
+	/// Validate MCP tool calls against the schemas declared by the tools.
+	#[serde(default)]
+	pub mcp_tool_validation: Option<crate::mcp::McpToolValidation>,
+	/// Rate limits for MCP tools, prompts and resources.
+	#[serde(default)]
+	pub mcp_rate_limit: Option<Vec<crate::mcp::McpRateLimit>>,
+	/// Cache MCP list results and resource reads.
+	#[serde(default)]
+	pub mcp_cache: Option<crate::mcp::McpCache>,
+	/// Paginate and search the MCP tools of all targets.
+	#[serde(default)]
+	pub mcp_tool_catalog: Option<crate::mcp::McpToolCatalog>,
 }
 
 #[apply(schema_de!)]
@@ -1986,6 +2070,18 @@ pub struct LocalBackendPolicies {
 	/// External MCP policy processors.
 	#[serde(default)]
 	pub mcp_guardrails: Option<LocalMcpGuardrails>,
+	/// Validate MCP tool calls against the schemas declared by the tools.
+	#[serde(default)]
+	pub mcp_tool_validation: Option<crate::mcp::McpToolValidation>,
+	/// Rate limits for MCP tools, prompts and resources.
+	#[serde(default)]
+	pub mcp_rate_limit: Option<Vec<crate::mcp::McpRateLimit>>,
+	/// Cache MCP list results and resource reads.
+	#[serde(default)]
+	pub mcp_cache: Option<crate::mcp::McpCache>,
+	/// Paginate and search the MCP tools of all targets.
+	#[serde(default)]
+	pub mcp_tool_catalog: Option<crate::mcp::McpToolCatalog>,
 	/// Mark this traffic as A2A to enable A2A processing and telemetry.
 	#[serde(default)]
 	pub a2a: Option<A2aPolicy>,
@@ -2042,6 +2138,10 @@ impl LocalBackendPolicies {
 				},
 			mcp_authorization,
 			mcp_guardrails,
+			mcp_tool_validation,
+			mcp_rate_limit,
+			mcp_cache,
+			mcp_tool_catalog,
 			a2a,
 			inference_routing,
 			ai,
@@ -2081,6 +2181,18 @@ impl LocalBackendPolicies {
 			}
 			pols.push(BackendTrafficPolicy::McpGuardrails(Arc::new(p)))
 		}
+		if let Some(p) = mcp_tool_validation {
+			pols.push(BackendTrafficPolicy::McpToolValidation(p))
+		}
+		if let Some(p) = mcp_rate_limit {
+			pols.push(BackendTrafficPolicy::McpRateLimit(Arc::new(p)))
+		}
+		if let Some(p) = mcp_cache {
+			pols.push(BackendTrafficPolicy::McpCache(Arc::new(p)))
+		}
+		if let Some(p) = mcp_tool_catalog {
+			pols.push(BackendTrafficPolicy::McpToolCatalog(p))
+		}
 		if let Some(p) = a2a {
 			pols.push(BackendTrafficPolicy::A2a(p))
 		}
@@ -2208,6 +2320,18 @@ pub struct FilterOrPolicy {
 	/// External MCP policy processors.
 	#[serde(default)]
 	mcp_guardrails: Option<LocalMcpGuardrails>,
+	/// Validate MCP tool calls against the schemas declared by the tools.
+	#[serde(default)]
+	mcp_tool_validation: Option<crate::mcp::McpToolValidation>,
+	/// Rate limits for MCP tools, prompts and resources.
+	#[serde(default)]
+	mcp_rate_limit: Option<Vec<crate::mcp::McpRateLimit>>,
+	/// Cache MCP list results and resource reads.
+	#[serde(default)]
+	mcp_cache: Option<crate::mcp::McpCache>,
+	/// Paginate and search the MCP tools of all targets.
+	#[serde(default)]
+	mcp_tool_catalog: Option<crate::mcp::McpToolCatalog>,
 	/// Authorization rules for incoming HTTP requests.
 	#[serde(default)]
 	authorization: Option<Authorization>,
@@ -2553,6 +2677,8 @@ enum LocalLLMVirtualRoutingStrategy<'a> {
 	Weighted(&'a LocalLLMWeightedRouting),
 	Failover(&'a LocalLLMFailoverRouting),
 	Conditional(&'a LocalLLMConditionalRouting),
+	LowestCost(&'a LocalLLMRankedRouting),
+	LowestLatency(&'a LocalLLMRankedRouting),
 }
 
 fn llm_model_matches(pattern: &str, model: &str) -> anyhow::Result<bool> {
@@ -2584,6 +2710,9 @@ impl<'a> LocalLLMVirtualRoutingStrategy<'a> {
 					.iter()
 					.map(|target| target.model.as_str()),
 			),
+			Self::LowestCost(ranked) | Self::LowestLatency(ranked) => {
+				Box::new(ranked.targets.iter().map(|target| target.model.as_str()))
+			},
 		}
 	}
 }
@@ -2592,7 +2721,9 @@ impl LocalLLMVirtualModel {
 	fn routing_strategy(&self) -> anyhow::Result<LocalLLMVirtualRoutingStrategy<'_>> {
 		let strategy_count = usize::from(self.routing.weighted.is_some())
 			+ usize::from(self.routing.failover.is_some())
-			+ usize::from(self.routing.conditional.is_some());
+			+ usize::from(self.routing.conditional.is_some())
+			+ usize::from(self.routing.lowest_cost.is_some())
+			+ usize::from(self.routing.lowest_latency.is_some());
 		if strategy_count != 1 {
 			bail!(
 				"virtual model {} must specify exactly one routing strategy",
@@ -2628,6 +2759,14 @@ impl LocalLLMVirtualModel {
 			}
 			return Ok(LocalLLMVirtualRoutingStrategy::Weighted(weighted));
 		}
+		if let Some(ranked) = self.routing.lowest_cost.as_ref() {
+			self.validate_ranked(ranked)?;
+			return Ok(LocalLLMVirtualRoutingStrategy::LowestCost(ranked));
+		}
+		if let Some(ranked) = self.routing.lowest_latency.as_ref() {
+			self.validate_ranked(ranked)?;
+			return Ok(LocalLLMVirtualRoutingStrategy::LowestLatency(ranked));
+		}
 		let failover = self
 			.routing
 			.failover
@@ -2641,6 +2780,21 @@ impl LocalLLMVirtualModel {
 		}
 		Ok(LocalLLMVirtualRoutingStrategy::Failover(failover))
 	}
+
+	fn validate_ranked(&self, ranked: &LocalLLMRankedRouting) -> anyhow::Result<()> {
+		if !ranked
+			.targets
+			.iter()
+			.any(|target| target.tier >= ranked.min_tier)
+		{
+			bail!(
+				"virtual model {} must specify at least one target with a tier of at least {}",
+				self.name,
+				ranked.min_tier
+			);
+		}
+		Ok(())
+	}
 }
 
 impl LocalLLMModelRegistry {
@@ -2756,9 +2910,17 @@ fn llm_route_types(
 			strng::new("/v1/responses"),
 			crate::llm::RouteType::Responses,
 		),
+		(
+			strng::new(":generateContent"),
+			crate::llm::RouteType::GenerateContent,
+		),
+		(
+			strng::new(":streamGenerateContent"),
+			crate::llm::RouteType::GenerateContent,
+		),
 		(
 			strng::new("/v1/images/generations"),
-			crate::llm::RouteType::Detect,
+			crate::llm::RouteType::ImageGeneration,
 		),
 		(
 			strng::new("/v1/images/edits"),
@@ -2776,12 +2938,58 @@ fn llm_route_types(
 			strng::new("/v1/embeddings"),
 			crate::llm::RouteType::Embeddings,
 		),
+		(
+			strng::new("/v1/audio/transcriptions"),
+			crate::llm::RouteType::AudioTranscription,
+		),
+		(
+			strng::new("/v1/audio/speech"),
+			crate::llm::RouteType::AudioSpeech,
+		),
 		(strng::new("/v1/rerank"), crate::llm::RouteType::Rerank),
 		(strng::new("/v2/rerank"), crate::llm::RouteType::Rerank),
+		(strng::new("/v1/batches"), crate::llm::RouteType::Batches),
+		(strng::new("/v1/batches/*"), crate::llm::RouteType::Batches),
+		(
+			strng::new("/v1/batches/*/cancel"),
+			crate::llm::RouteType::Batches,
+		),
+		(strng::new("/v1/files"), crate::llm::RouteType::Files),
+		(strng::new("/v1/files/*"), crate::llm::RouteType::Files),
+		(
+			strng::new("/v1/files/*/content"),
+			crate::llm::RouteType::Files,
+		),
 		(strng::new("*"), crate::llm::RouteType::Passthrough),
 	]
 }
 
+fn ranked_routing(
+	ranked: &LocalLLMRankedRouting,
+	resolved_models: &ResolvedLLMModelRegistry,
+) -> anyhow::Result<llm::model_router::RankedRouting> {
+	let targets = ranked
+		.targets
+		.iter()
+		.map(|target| {
+			let resolved = resolved_models.resolve(&target.model)?;
+			let provider = &resolved.provider.provider;
+			Ok(llm::model_router::RankedTarget::new(
+				target.model.clone(),
+				target.tier,
+				provider.provider(),
+				provider
+					.override_model()
+					.unwrap_or_else(|| strng::new(&target.model)),
+			))
+		})
+		.collect::<anyhow::Result<Vec<_>>>()?;
+	Ok(llm::model_router::RankedRouting {
+		targets,
+		min_tier: ranked.min_tier,
+	})
+}
+
 fn ensure_ai_provider_model(provider: &mut AIProvider, model: &str) {
 	let model = || Some(strng::new(model));
 	match provider {
@@ -3111,6 +3319,7 @@ async fn convert_llm_config(
 			path_override: p.path_override,
 			path_prefix: p.path_prefix,
 			tokenize: p.tokenize,
+			tokenizers: p.tokenizers,
 			inline_policies: pols,
 		};
 		let resolved_provider = named_provider.clone();
@@ -3120,6 +3329,7 @@ async fn convert_llm_config(
 				model_name.clone(),
 				named_provider,
 			)]]),
+			failover: None,
 		};
 
 		let mut pols = vec![];
@@ -3151,10 +3361,17 @@ async fn convert_llm_config(
 			transformations: model_config.transformation.clone(),
 			prompt_guard,
 			prompts: None,
+			prompt_templates: None,
 			model_aliases: Default::default(),
 			wildcard_patterns: Arc::new(vec![]),
+			allowed_models: Vec::new(),
 			prompt_caching: model_config.prompt_caching.clone(),
+			response_cache: None,
 			routes: Default::default(),
+			budgets: Default::default(),
+			structured_output: None,
+			context_window: None,
+			shadow: None,
 		})));
 		let resolved_inline_policies = pols.clone();
 		let backend_with_policies = BackendWithPolicies {
@@ -3228,6 +3445,18 @@ async fn convert_llm_config(
 						.collect(),
 				)
 			},
+			LocalLLMVirtualRoutingStrategy::LowestCost(ranked) => {
+				llm::model_router::VirtualModelRouting::LowestCost(ranked_routing(
+					ranked,
+					&resolved_models,
+				)?)
+			},
+			LocalLLMVirtualRoutingStrategy::LowestLatency(ranked) => {
+				llm::model_router::VirtualModelRouting::LowestLatency(ranked_routing(
+					ranked,
+					&resolved_models,
+				)?)
+			},
 			LocalLLMVirtualRoutingStrategy::Failover(failover) => {
 				let provider_groups = failover
 					.targets
@@ -3256,6 +3485,7 @@ async fn convert_llm_config(
 						local_name(backend_key.clone()),
 						AIBackend {
 							providers: crate::types::loadbalancer::EndpointSet::new(provider_groups),
+							failover: None,
 						},
 					),
 					inline_policies: vec![],
@@ -3810,6 +4040,10 @@ pub(crate) async fn split_policies(
 		cors,
 		mcp_authorization,
 		mcp_guardrails,
+		mcp_tool_validation,
+		mcp_rate_limit,
+		mcp_cache,
+		mcp_tool_catalog,
 		mcp_authentication,
 		a2a,
 		ai,
@@ -3869,6 +4103,18 @@ pub(crate) async fn split_policies(
 		}
 		backend_policies.push(BackendTrafficPolicy::McpGuardrails(Arc::new(p)))
 	}
+	if let Some(p) = mcp_tool_validation {
+		backend_policies.push(BackendTrafficPolicy::McpToolValidation(p))
+	}
+	if let Some(p) = mcp_rate_limit {
+		backend_policies.push(BackendTrafficPolicy::McpRateLimit(Arc::new(p)))
+	}
+	if let Some(p) = mcp_cache {
+		backend_policies.push(BackendTrafficPolicy::McpCache(Arc::new(p)))
+	}
+	if let Some(p) = mcp_tool_catalog {
+		backend_policies.push(BackendTrafficPolicy::McpToolCatalog(p))
+	}
 	if let Some(p) = mcp_authentication {
 		let authn: McpAuthentication = p.translate(client.clone()).await?;
 		route_policies.push(TrafficPolicy::JwtAuth(RequestPolicy::single(
@@ -312,6 +312,34 @@ llm:
 	);
 }
 
+#[tokio::test]
+async fn test_llm_lowest_cost_virtual_model_requires_target_meeting_min_tier() {
+	let err = normalize_test_config(
+		r#"
+llm:
+  models:
+  - name: cheap
+    provider: openAI
+  virtualModels:
+  - name: smart
+    routing:
+      lowestCost:
+        minTier: 2
+        targets:
+        - model: cheap
+          tier: 1
+"#,
+	)
+	.await
+	.expect_err("no target meets the minimum tier");
+	assert!(
+		err
+			.to_string()
+			.contains("virtual model smart must specify at least one target with a tier of at least 2"),
+		"{err:?}"
+	);
+}
+
 #[tokio::test]
 async fn test_llm_conditional_virtual_model_rejects_unknown_target_before_expression_compile() {
 	let err = normalize_test_config(
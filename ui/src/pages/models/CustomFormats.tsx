@@ -16,6 +16,9 @@ const formats: ProviderFormat[] = [
   "anthropicTokenCount",
   "realtime",
   "rerank",
+  "imageGeneration",
+  "audioTranscription",
+  "audioSpeech",
 ];
 
 const formatLabels: Record<ProviderFormat, string> = {
@@ -26,6 +29,9 @@ const formatLabels: Record<ProviderFormat, string> = {
   anthropicTokenCount: "Anthropic token count (/v1/messages/count_tokens)",
   realtime: "Realtime (/v1/realtime)",
   rerank: "Rerank (/v2/rerank)",
+  imageGeneration: "Image generation (/v1/images/generations)",
+  audioTranscription: "Audio transcription (/v1/audio/transcriptions)",
+  audioSpeech: "Audio speech (/v1/audio/speech)",
 };
 
 export function CustomFormats(props: {